@@ -1,7 +1,6 @@
 use futures::stream::StreamExt;
-use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use vkclient::{longpoll::LongPollRequest, VkApi, VkApiWrapper};
+use vkclient::VkApi;
 
 fn main() {
     let access_token = std::env::var("SERVICE_TOKEN").unwrap();
@@ -15,43 +14,11 @@ fn main() {
     runtime.block_on(async move {
         let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
 
-        let BotLongPollResponse { key, server, ts } = client
-            .send_request_with_wrapper(BotLongPollRequest { group_id })
-            .await
-            .unwrap();
-
         client
             .longpoll()
-            .subscribe::<_, Value>(LongPollRequest {
-                key,
-                server,
-                ts,
-                wait: 25,
-                additional_params: (),
-            })
+            .subscribe_group::<Value>(&client, group_id)
             .take(1)
             .for_each(|r| async move { println!("{:?}", r) })
             .await;
     });
 }
-
-#[derive(Serialize, Debug)]
-struct BotLongPollRequest {
-    group_id: usize,
-}
-
-#[allow(dead_code)]
-#[derive(Deserialize, Debug)]
-struct BotLongPollResponse {
-    key: String,
-    server: String,
-    ts: String,
-}
-
-impl VkApiWrapper for BotLongPollRequest {
-    type Response = BotLongPollResponse;
-
-    fn get_method_name() -> &'static str {
-        "groups.getLongPollServer"
-    }
-}
@@ -22,7 +22,7 @@ fn main() {
 
         let client: VkApi = vkclient::VkApiBuilder::new(access_token.clone())
             .with_encoding(Encoding::Msgpack)
-            .with_compression(Compression::Zstd)
+            .with_compression(Compression::ZSTD)
             .into();
 
         let i = Instant::now();
@@ -31,7 +31,7 @@ fn main() {
 
         let client: VkApi = vkclient::VkApiBuilder::new(access_token.clone())
             .with_encoding(Encoding::Json)
-            .with_compression(Compression::Zstd)
+            .with_compression(Compression::ZSTD)
             .into();
 
         let i = Instant::now();
@@ -64,6 +64,16 @@ fn main() {
         let i = Instant::now();
         assert!(get_users_info(&client).await.is_ok());
         println!("msgpack+none {} micros", i.elapsed().as_micros());
+
+        let client: VkApi = vkclient::VkApiBuilder::new(access_token.clone())
+            .with_encoding(Encoding::Auto)
+            .with_compression(Compression::ZSTD)
+            .into();
+
+        while client.encoding_stats().selected.is_none() {
+            assert!(get_users_info(&client).await.is_ok());
+        }
+        println!("auto+zstd {:?}", client.encoding_stats());
     });
 }
 async fn get_users_info(client: &VkApi) -> VkApiResult<Vec<UsersGetResponse>> {
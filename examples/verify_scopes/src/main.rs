@@ -0,0 +1,41 @@
+use std::process::ExitCode;
+use vkclient::{Scope, VkApi};
+
+fn main() -> ExitCode {
+    let access_token = std::env::var("ACCESS_TOKEN").unwrap();
+
+    let mut required = Scope::NONE;
+    for name in std::env::args().skip(1) {
+        match Scope::from_name(&name) {
+            Some(scope) => required |= scope,
+            None => {
+                eprintln!("unknown scope {name}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    runtime.block_on(async move {
+        let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+
+        match client.verify_scopes(required).await.map(|r| r.ensure()) {
+            Ok(Ok(report)) => {
+                println!("{report}");
+                ExitCode::SUCCESS
+            }
+            Ok(Err(report)) => {
+                eprintln!("{report}");
+                ExitCode::FAILURE
+            }
+            Err(e) => {
+                eprintln!("token verification failed: {e}");
+                ExitCode::FAILURE
+            }
+        }
+    })
+}
@@ -0,0 +1,144 @@
+use crate::vkapi::Encoding;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Responses count, which must be decoded by each encoding before the choice.
+const SAMPLES: u32 = 3;
+
+/// Chooses the faster encoding for `Encoding::Auto` by the first responses of the client.
+#[derive(Debug, Default)]
+pub struct AutoEncoding {
+    stats: Mutex<EncodingStats>,
+}
+
+impl AutoEncoding {
+    /// Returns encoding for the next request.
+    /// Encodings are probed in turns until the choice is made.
+    pub fn next(&self) -> Encoding {
+        let stats = self
+            .stats
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        match stats.selected {
+            Some(selected) => selected,
+            None if stats.json.responses <= stats.msgpack.responses => Encoding::Json,
+            None => Encoding::Msgpack,
+        }
+    }
+
+    /// Saves measurements of the decoded response.
+    pub fn record(&self, encoding: Encoding, bytes: usize, decode_time: Duration) {
+        let mut stats = self
+            .stats
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        if stats.selected.is_some() {
+            return;
+        }
+
+        match encoding {
+            Encoding::Json => stats.json.add(bytes, decode_time),
+            Encoding::Msgpack => stats.msgpack.add(bytes, decode_time),
+            _ => return,
+        }
+
+        if stats.json.responses >= SAMPLES && stats.msgpack.responses >= SAMPLES {
+            let json = (stats.json.avg_decode_time(), stats.json.avg_bytes());
+            let msgpack = (stats.msgpack.avg_decode_time(), stats.msgpack.avg_bytes());
+
+            stats.selected = Some(if msgpack < json {
+                Encoding::Msgpack
+            } else {
+                Encoding::Json
+            });
+        }
+    }
+
+    pub fn stats(&self) -> EncodingStats {
+        *self
+            .stats
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// Measurements of `Encoding::Auto` probes.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EncodingStats {
+    /// Responses decoded from json
+    pub json: EncodingSamples,
+    /// Responses decoded from msgpack
+    pub msgpack: EncodingSamples,
+    /// Encoding locked in for the client. `None` while probing is in progress.
+    pub selected: Option<Encoding>,
+}
+
+/// Accumulated measurements of responses decoded with one encoding.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EncodingSamples {
+    /// Decoded responses count
+    pub responses: u32,
+    /// Total size of the response bodies before decompression
+    pub bytes: u64,
+    /// Total time spent on decompression and decoding
+    pub decode_time: Duration,
+}
+
+impl EncodingSamples {
+    fn add(&mut self, bytes: usize, decode_time: Duration) {
+        self.responses += 1;
+        self.bytes += bytes as u64;
+        self.decode_time += decode_time;
+    }
+
+    /// Average size of the response body
+    #[must_use]
+    pub fn avg_bytes(&self) -> u64 {
+        self.bytes
+            .checked_div(u64::from(self.responses))
+            .unwrap_or_default()
+    }
+
+    /// Average time of the response decoding
+    #[must_use]
+    pub fn avg_decode_time(&self) -> Duration {
+        self.decode_time
+            .checked_div(self.responses)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::auto::{AutoEncoding, SAMPLES};
+    use crate::Encoding;
+    use std::time::Duration;
+
+    #[test]
+    fn probes_in_turns() {
+        let auto = AutoEncoding::default();
+
+        assert_eq!(auto.next(), Encoding::Json);
+        auto.record(Encoding::Json, 100, Duration::from_micros(10));
+        assert_eq!(auto.next(), Encoding::Msgpack);
+        auto.record(Encoding::Msgpack, 80, Duration::from_micros(5));
+        assert_eq!(auto.next(), Encoding::Json);
+        assert_eq!(auto.stats().selected, None);
+    }
+
+    #[test]
+    fn selects_faster_encoding() {
+        let auto = AutoEncoding::default();
+
+        for _ in 0..SAMPLES {
+            auto.record(Encoding::Json, 100, Duration::from_micros(10));
+            auto.record(Encoding::Msgpack, 80, Duration::from_micros(5));
+        }
+
+        assert_eq!(auto.stats().selected, Some(Encoding::Msgpack));
+        assert_eq!(auto.next(), Encoding::Msgpack);
+        assert_eq!(auto.stats().msgpack.avg_bytes(), 80);
+    }
+}
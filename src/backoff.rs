@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+/// Exponential backoff between the retries of the requests failed with transport errors,
+/// like the connection reset or the timeout. The delay is doubled after each failure in a row
+/// up to the `max` delay, and the error is returned when the retries run out.
+///
+/// ## Usage
+/// ```rust
+/// use std::time::Duration;
+/// use vkclient::Backoff;
+///
+/// let backoff = Backoff::new(Duration::from_millis(500), Duration::from_secs(30))
+///     .with_max_retries(5);
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    max_retries: u32,
+}
+
+impl Backoff {
+    /// Creates backoff with the delay of the first retry and the maximum delay, with 10 retries in a row.
+    #[must_use]
+    pub const fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            max_retries: 10,
+        }
+    }
+
+    /// Pass the count of the retries in a row, after which the error is returned.
+    #[must_use]
+    pub const fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Returns the count of the retries in a row.
+    #[must_use]
+    pub const fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Returns the delay before the retry after the given count of failures in a row,
+    /// or `None` if the retries run out.
+    pub(crate) fn delay(&self, failures: u32) -> Option<Duration> {
+        if failures == 0 || failures > self.max_retries {
+            return None;
+        }
+
+        let delay = self
+            .initial
+            .checked_mul(2_u32.saturating_pow(failures - 1))
+            .unwrap_or(self.max);
+        Some(delay.min(self.max))
+    }
+}
+
+impl Default for Backoff {
+    /// Starts from 1 second delay up to 1 minute.
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1), Duration::from_mins(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Backoff;
+    use std::time::Duration;
+
+    #[test]
+    fn double_delays() {
+        let backoff =
+            Backoff::new(Duration::from_secs(1), Duration::from_secs(5)).with_max_retries(4);
+
+        assert_eq!(backoff.delay(1), Some(Duration::from_secs(1)));
+        assert_eq!(backoff.delay(2), Some(Duration::from_secs(2)));
+        assert_eq!(backoff.delay(3), Some(Duration::from_secs(4)));
+        assert_eq!(backoff.delay(4), Some(Duration::from_secs(5)));
+        assert_eq!(backoff.delay(5), None);
+
+        let backoff = Backoff::default().with_max_retries(u32::MAX);
+        assert_eq!(backoff.delay(100), Some(Duration::from_secs(60)));
+    }
+}
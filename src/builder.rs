@@ -1,52 +1,184 @@
-use crate::inner::VkApiInner;
+use crate::inner::{base_url, ClientConfig, VkApiInner};
+use crate::open::VkOpenApi;
 use crate::structs::Version;
-use crate::vkapi::{Compression, Encoding, VkApi};
+use crate::vkapi::{Auth, Compression, DecodeStrictness, Encoding, VkApi};
+use reqwest::Certificate;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+use std::time::Duration;
 
 /// API Client builder struct.
-/// Use `VkApi::from` or `into` to make `VkApi` struct.
+/// Use [`VkApiBuilder::build`] to make `VkApi` struct.
+/// `VkApi::from` or `into` are kept for compatibility and panic if the http client can't be built.
+///
+/// The builder created by [`VkApiBuilder::unauthenticated`] has no access token and builds [`VkOpenApi`],
+/// so calling the methods, which require a token, without it is a compile error.
+/// ```rust,compile_fail
+/// let client = vkclient::VkApiBuilder::unauthenticated().build().unwrap();
+/// client.messages();
+/// ```
 #[derive(Clone, Debug)]
-pub struct VkApiBuilder {
+pub struct VkApiBuilder<S = WithToken> {
     inner: VkApiInner,
+    state: PhantomData<S>,
 }
 
-impl VkApiBuilder {
+/// State of the [`VkApiBuilder`] without an access token.
+#[derive(Clone, Copy, Debug)]
+pub struct NoToken;
+
+/// State of the [`VkApiBuilder`] with an access token.
+#[derive(Clone, Copy, Debug)]
+pub struct WithToken;
+
+/// Builder of [`VkApiBuilder::from_auth`], split by the authorization.
+#[derive(Clone, Debug)]
+pub enum AuthBuilder {
+    /// Builder of [`VkApi`] with the access token
+    Token(VkApiBuilder<WithToken>),
+    /// Builder of [`VkOpenApi`] for the open methods
+    Open(VkApiBuilder<NoToken>),
+}
+
+impl VkApiBuilder<NoToken> {
+    /// Creates the builder without access token for the open methods.
+    /// Use [`VkApiBuilder::with_access_token`] to pass the token later.
+    #[must_use]
+    pub fn unauthenticated() -> Self {
+        Self::with_auth(Auth::None)
+    }
+
+    /// Creates the builder with the authorization known at runtime, like an optional token from the config.
+    /// Returns the builder of [`VkApi`] with the token, or the builder of [`VkOpenApi`] without it.
+    ///
+    /// ## Usage:
+    /// ```rust,no_run
+    /// use vkclient::{Auth, AuthBuilder, VkApiBuilder};
+    /// # let token: Option<String> = None;
+    ///
+    /// match VkApiBuilder::from_auth(token.map_or(Auth::None, Auth::Token)) {
+    ///     AuthBuilder::Token(builder) => {
+    ///         let client = builder.build().unwrap();
+    ///     }
+    ///     AuthBuilder::Open(builder) => {
+    ///         let open_client = builder.build().unwrap();
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn from_auth(auth: Auth) -> AuthBuilder {
+        match auth {
+            Auth::Token(access_token) => AuthBuilder::Token(VkApiBuilder::new(access_token)),
+            Auth::None => AuthBuilder::Open(Self::unauthenticated()),
+        }
+    }
+
+    /// Builds the client for the open methods.
+    /// Returns an error if the http client can't be built.
+    pub fn build(self) -> Result<VkOpenApi, BuildError> {
+        VkApi::from_inner(self.inner).map(VkOpenApi::from)
+    }
+}
+
+impl VkApiBuilder<WithToken> {
     /// Creates the builder from access key with default values.
+    #[must_use]
     pub fn new(access_token: String) -> Self {
+        Self::with_auth(Auth::Token(access_token))
+    }
+
+    /// Creates the builder from the pool of the access tokens, which are rotated between the requests.
+    #[must_use]
+    pub fn with_token_pool(pool: crate::TokenPool) -> Self {
+        let mut builder = Self::new(pool.first().to_owned());
+        builder.inner.token_pool = Some(std::sync::Arc::new(pool));
+        builder
+    }
+
+    /// Creates the builder with the cheapest to initialize settings: json encoding and gzip compression if available.
+    /// Use it with the `minimal` feature profile for short-living processes like serverless functions.
+    #[must_use]
+    pub fn minimal(access_token: String) -> Self {
         cfg_if::cfg_if! {
-            if #[cfg(features = "compression_zstd")] {
-                let encoding = Compression::Zstd;
-            } else if #[cfg(features = "compression_gzip")] {
+            if #[cfg(feature = "compression_gzip")] {
                 let encoding = Compression::Gzip;
             } else {
                 let encoding = Compression::None;
             }
         }
         cfg_if::cfg_if! {
-            if #[cfg(features = "encode_msgpack")] {
+            if #[cfg(feature = "encode_json")] {
+                let format = Encoding::Json;
+            } else if #[cfg(feature = "encode_msgpack")] {
+                let format = Encoding::Msgpack;
+            } else {
+                #[allow(deprecated)]
+                let format = Encoding::None;
+            }
+        }
+
+        Self::new(access_token)
+            .with_compression(encoding)
+            .with_encoding(format)
+    }
+}
+
+impl<S> VkApiBuilder<S> {
+    fn with_auth(auth: Auth) -> Self {
+        let encoding = Compression::default();
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "encode_msgpack")] {
                 let format = Encoding::Msgpack;
-            } else if #[cfg(features = "encode_json")] {
+            } else if #[cfg(feature = "encode_json")] {
                 let format = Encoding::Json;
             } else {
+                #[allow(deprecated)]
                 let format = Encoding::None;
             }
         }
 
         Self {
+            state: PhantomData,
             inner: VkApiInner {
-                access_token,
+                auth,
                 version: Version::default(),
                 domain: "api.vk.com".to_owned(),
+                base_url: base_url("api.vk.com"),
                 format,
                 encoding,
+                buffer_pool_size: 16,
+                buffer_max_capacity: 1 << 20,
+                client_config: ClientConfig::default(),
+                strictness: DecodeStrictness::Lenient,
+                #[cfg(feature = "rate_limit")]
+                rate_limiter: None,
+                captcha_solver: None,
+                #[cfg(feature = "encode_json")]
+                transformers: crate::transform::Transformers::default(),
+                wrapper_registry: None,
+                token_pool: None,
+                #[cfg(feature = "resilience")]
+                policies: crate::resilience::Policies::default(),
+                #[cfg(feature = "vk_id")]
+                refresher: None,
+                error_stats: None,
+                method_stats: None,
+                metrics: None,
+                #[cfg(feature = "chaos")]
+                chaos: None,
             },
         }
     }
 
     /// Pass new access token to builder
     #[must_use]
-    pub fn with_access_token(mut self, access_token: String) -> Self {
-        self.inner.access_token = access_token;
-        self
+    pub fn with_access_token(mut self, access_token: String) -> VkApiBuilder<WithToken> {
+        self.inner.auth = Auth::Token(access_token);
+        VkApiBuilder {
+            inner: self.inner,
+            state: PhantomData,
+        }
     }
 
     /// Pass new version to builder. Default is 5.131
@@ -59,27 +191,310 @@ impl VkApiBuilder {
     /// Pass new API domain to builder. Default is api.vk.com
     #[must_use]
     pub fn with_domain(mut self, domain: String) -> Self {
+        self.inner.base_url = base_url(&domain);
         self.inner.domain = domain;
         self
     }
 
-    /// Pass new compression to builder. Default is `Compression::Zstd`
+    /// Pass new compression to builder. Default is `Compression::ZSTD`
     #[must_use]
     pub const fn with_compression(mut self, compression: Compression) -> Self {
         self.inner.encoding = compression;
         self
     }
 
+    /// Pass size of the response buffers pool and the maximum capacity of pooled buffer.
+    /// Default is 16 buffers up to 1 MiB. Pass zero size to disable buffers reuse.
+    #[must_use]
+    pub const fn with_buffer_pool(mut self, size: usize, max_capacity: usize) -> Self {
+        self.inner.buffer_pool_size = size;
+        self.inner.buffer_max_capacity = max_capacity;
+        self
+    }
+
+    /// Pass root certificates, which replace the built-in root set of the client.
+    /// Required with the `manual_roots` feature, which disables the built-in root set.
+    #[must_use]
+    pub fn with_root_certificates(mut self, root_certificates: Vec<Certificate>) -> Self {
+        self.inner.client_config.root_certificates = root_certificates;
+        self
+    }
+
+    /// Pass the pinned public keys of VK hosts, see [`TlsPins`](crate::TlsPins).
+    /// The long poll clients of [`VkApi::longpoll`](crate::VkApi::longpoll) share the pins.
+    #[cfg(feature = "tls_pinning")]
+    #[must_use]
+    pub fn with_tls_pins(mut self, pins: crate::TlsPins) -> Self {
+        self.inner.client_config.tls_pins = Some(pins);
+        self
+    }
+
     /// Pass new encoding to builder. Default is `Encoding::Msgpack`
     #[must_use]
     pub const fn with_encoding(mut self, encoding: Encoding) -> Self {
         self.inner.format = encoding;
         self
     }
+
+    /// Pass the timeout of establishing the connection.
+    /// The timeout is shared by all resolved addresses of the host.
+    #[must_use]
+    pub const fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.inner.client_config.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Pass the preferred address family of the connections. Default is `AddressFamily::Any`
+    #[cfg(feature = "address_family")]
+    #[must_use]
+    pub const fn with_address_family(mut self, family: crate::AddressFamily) -> Self {
+        self.inner.client_config.address_family = family;
+        self
+    }
+
+    /// Pass handling of the unknown response fields. Default is `DecodeStrictness::Lenient`
+    #[must_use]
+    pub const fn with_decode_strictness(mut self, strictness: DecodeStrictness) -> Self {
+        self.inner.strictness = strictness;
+        self
+    }
+
+    /// Pass the solver of the captchas, which VK requires with the error 14.
+    /// Requests are repeated with the solved captcha up to 3 times.
+    #[must_use]
+    pub fn with_captcha_solver<C: crate::CaptchaSolver + 'static>(mut self, solver: C) -> Self {
+        self.inner.captcha_solver = Some(crate::captcha::SolverHandle(std::sync::Arc::new(solver)));
+        self
+    }
+
+    /// Registers the transformer of the responses of the methods matched by the pattern:
+    /// the exact name, the prefix ending with `*` like `messages.*`, or `*` for all methods.
+    /// Matched transformers are applied in the order of registration before the response is deserialized.
+    #[cfg(feature = "encode_json")]
+    #[must_use]
+    pub fn with_response_transformer<P, R>(mut self, pattern: P, transformer: R) -> Self
+    where
+        P: Into<String>,
+        R: crate::ResponseTransformer + 'static,
+    {
+        self.inner
+            .transformers
+            .push(pattern.into(), std::sync::Arc::new(transformer));
+        self
+    }
+
+    /// Pass the registry of the wrappers for [`VkApi::check_compatibility`].
+    #[must_use]
+    pub fn with_wrapper_registry(mut self, registry: crate::WrapperRegistry) -> Self {
+        self.inner.wrapper_registry = Some(registry);
+        self
+    }
+
+    /// Pass the timeout, retries and circuit breaker of the requests of the kind.
+    /// Default policies have none of them. Use [`VkApi::send_request_with_policy`] to override it per request.
+    #[cfg(feature = "resilience")]
+    #[must_use]
+    pub fn with_resilience_policy(
+        mut self,
+        kind: crate::RequestKind,
+        policy: crate::ResiliencePolicy,
+    ) -> Self {
+        self.inner.policies.set(kind, policy);
+        self
+    }
+
+    /// Pass the faults, which are injected into the requests for testing the retries and the circuit breakers.
+    /// Long poll clients of [`VkApi::longpoll`](crate::VkApi::longpoll) inject them too.
+    #[cfg(feature = "chaos")]
+    #[must_use]
+    pub fn with_chaos(mut self, chaos: crate::chaos::Chaos) -> Self {
+        self.inner.chaos = Some(chaos);
+        self
+    }
+
+    /// Enables counting of the VK errors by their codes over the sliding `window`,
+    /// see [`VkApi::error_stats`]. The window moves by 1/60 of its duration.
+    #[must_use]
+    pub fn with_error_stats(mut self, window: Duration) -> Self {
+        self.inner.error_stats = Some(match self.inner.error_stats.take() {
+            Some(stats) => stats.with_window(window),
+            None => crate::stats::ErrorStats::new(window),
+        });
+        self
+    }
+
+    /// Pass the log of the VK errors, which is called for the first error of each code and then
+    /// for each `every` one with the count of the code in the window, like `spike of error 5: 120 per minute`.
+    /// Enables the error stats with 1 minute window, if they aren't enabled.
+    ///
+    /// ## Usage:
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// let client = vkclient::VkApiBuilder::new(String::new())
+    ///     .with_error_stats(Duration::from_secs(60))
+    ///     .with_error_log(100, |error: &vkclient::VkError, count: u64| {
+    ///         eprintln!("error {}: {} per minute", error.code(), count)
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    #[must_use]
+    pub fn with_error_log<F>(mut self, every: u64, log: F) -> Self
+    where
+        F: Fn(&crate::VkError, u64) + Send + Sync + 'static,
+    {
+        let stats = self
+            .inner
+            .error_stats
+            .take()
+            .unwrap_or_else(|| crate::stats::ErrorStats::new(Duration::from_mins(1)));
+        self.inner.error_stats = Some(stats.with_log(every, std::sync::Arc::new(log)));
+        self
+    }
+
+    /// Enables tracking of the response sizes and durations per method, see [`VkApi::method_stats`](crate::VkApi::method_stats).
+    /// It shows the methods, which benefit from msgpack, zstd or execute batching the most.
+    #[must_use]
+    pub fn with_method_stats(mut self) -> Self {
+        if self.inner.method_stats.is_none() {
+            self.inner.method_stats = Some(crate::method_stats::MethodStats::new());
+        }
+        self
+    }
+
+    /// Pass the recorder of the requests metrics, like the counters and histograms of your metrics system.
+    /// The method stats are recorded from the same metrics.
+    /// The long poll and upload clients of [`VkApi::longpoll`](crate::VkApi::longpoll) and
+    /// [`VkApi::uploader`](crate::VkApi::uploader) share the recorder.
+    ///
+    /// ## Usage:
+    /// ```rust
+    /// let client = vkclient::VkApiBuilder::new(String::new())
+    ///     .with_metrics(|metrics: &vkclient::RequestMetrics<'_>| println!("{metrics:?}"))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    #[must_use]
+    pub fn with_metrics<M: crate::MetricsRecorder + 'static>(mut self, metrics: M) -> Self {
+        self.inner.metrics = Some(crate::metrics::RecorderHandle(std::sync::Arc::new(metrics)));
+        self
+    }
+
+    /// Pass the log of the requests, which take the `threshold` or longer with decoding the response.
+    /// Enables the method stats, if they aren't enabled.
+    ///
+    /// ## Usage:
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// let client = vkclient::VkApiBuilder::new(String::new())
+    ///     .with_slow_request_log(Duration::from_secs(1), |request: &vkclient::SlowRequest<'_>| {
+    ///         eprintln!(
+    ///             "slow {}: {:?}, {} bytes of {:?}",
+    ///             request.method, request.duration, request.bytes, request.encoding
+    ///         )
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    #[must_use]
+    pub fn with_slow_request_log<F>(mut self, threshold: Duration, log: F) -> Self
+    where
+        F: Fn(&crate::SlowRequest<'_>) + Send + Sync + 'static,
+    {
+        let stats = self
+            .inner
+            .method_stats
+            .take()
+            .unwrap_or_else(crate::method_stats::MethodStats::new);
+        self.inner.method_stats = Some(stats.with_slow_log(threshold, std::sync::Arc::new(log)));
+        self
+    }
+
+    /// Pass the requests rate limit of the token. Default is unlimited.
+    /// Exceeding requests wait for their turn instead of failing with the error 6.
+    /// The limit is shared by all clones of the built client.
+    #[cfg(feature = "rate_limit")]
+    #[must_use]
+    pub const fn with_rate_limit(mut self, limit: crate::RateLimit) -> Self {
+        self.inner.rate_limiter = Some(crate::rate::RateLimiter::new(limit));
+        self
+    }
+
+    /// Pass the kind of the token to apply its documented rate limit.
+    /// Use [`VkApiBuilder::with_rate_limit`] for custom limits.
+    #[cfg(feature = "rate_limit")]
+    #[must_use]
+    pub fn with_token_scope(self, scope: crate::TokenScope) -> Self {
+        self.with_rate_limit(scope.rate_limit())
+    }
+}
+
+impl VkApiBuilder<WithToken> {
+    /// Pass the VK ID tokens to refresh the access token automatically,
+    /// when it expires or VK rejects it with the error 5. The access token of the builder is replaced with them.
+    /// The refreshed tokens are available with [`VkApi::vk_id_tokens`] to store them.
+    #[cfg(feature = "vk_id")]
+    #[must_use]
+    pub fn with_token_refresh<D: Into<String>>(
+        mut self,
+        vk_id: crate::vkid::VkId,
+        device_id: D,
+        tokens: crate::vkid::Tokens,
+    ) -> Self {
+        self.inner.auth = Auth::Token(tokens.access_token.clone());
+        self.inner.token_pool = None;
+        self.inner.refresher = Some(std::sync::Arc::new(crate::vkid::TokenRefresher::new(
+            vk_id,
+            device_id.into(),
+            tokens,
+        )));
+        self
+    }
+
+    /// Builds the client. Returns an error if the http client can't be built,
+    /// like when the TLS backend can't be initialized.
+    pub fn build(self) -> Result<VkApi, BuildError> {
+        VkApi::from_inner(self.inner)
+    }
+}
+
+impl From<VkApiBuilder<NoToken>> for VkOpenApi {
+    /// # Panics
+    /// Panics if the http client can't be built, use [`VkApiBuilder::build`] to handle it.
+    fn from(builder: VkApiBuilder<NoToken>) -> Self {
+        builder.build().expect("vk api client can't be built")
+    }
 }
 
 impl From<VkApiBuilder> for VkApi {
+    /// # Panics
+    /// Panics if the http client can't be built, use [`VkApiBuilder::build`] to handle it.
     fn from(builder: VkApiBuilder) -> Self {
-        Self::from_inner(builder.inner)
+        builder.build().expect("vk api client can't be built")
+    }
+}
+
+/// Client construction errors.
+#[derive(Debug)]
+pub enum BuildError {
+    /// Http client can't be built
+    Client(reqwest::Error),
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Client(e) => write!(f, "http client can't be built: {e}"),
+        }
+    }
+}
+
+impl Error for BuildError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Client(e) => Some(e),
+        }
     }
 }
@@ -0,0 +1,105 @@
+use crate::{VkApiError, VkApiResult};
+#[cfg(any(feature = "messages", feature = "search", feature = "uploader"))]
+use std::future::{poll_fn, Future};
+#[cfg(any(feature = "messages", feature = "search", feature = "uploader"))]
+use std::pin::{pin, Pin};
+#[cfg(any(feature = "messages", feature = "search", feature = "uploader"))]
+use std::task::Poll;
+
+/// Result of the bulk operation, which may continue after failures or stop at the cancellation.
+///
+/// Results completed before the cancellation are kept, and the failed and the pending inputs
+/// are returned, so only them can be retried.
+#[derive(Debug)]
+pub struct BulkResult<T, I = ()> {
+    pub succeeded: Vec<T>,
+    /// Failed inputs with their errors
+    pub failed: Vec<(I, VkApiError)>,
+    /// Inputs, which weren't processed, because the operation was cancelled or stopped after the failure
+    pub pending: Vec<I>,
+    pub cancelled: bool,
+}
+
+impl<T, I> BulkResult<T, I> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            succeeded: Vec::new(),
+            failed: Vec::new(),
+            pending: Vec::new(),
+            cancelled: false,
+        }
+    }
+
+    /// Returns true if no input failed.
+    #[must_use]
+    pub const fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Returns true if every input succeeded.
+    #[must_use]
+    pub const fn is_complete(&self) -> bool {
+        !self.cancelled && self.failed.is_empty() && self.pending.is_empty()
+    }
+
+    /// Returns the failed and the pending inputs for the retry.
+    #[must_use]
+    pub fn failed_inputs(self) -> Vec<I> {
+        self.failed
+            .into_iter()
+            .map(|(input, _)| input)
+            .chain(self.pending)
+            .collect()
+    }
+
+    /// Returns the first error if any input failed, otherwise the succeeded results.
+    pub fn into_result(self) -> VkApiResult<Vec<T>> {
+        match self.failed.into_iter().next() {
+            Some((_, error)) => Err(error),
+            None => Ok(self.succeeded),
+        }
+    }
+}
+
+impl<T, I> Default for BulkResult<T, I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns `None` if the `cancel` future completes before the `future`.
+#[cfg(any(feature = "messages", feature = "search", feature = "uploader"))]
+pub async fn or_cancel<C: Future, F: Future>(
+    mut cancel: Pin<&mut C>,
+    future: F,
+) -> Option<F::Output> {
+    let mut future = pin!(future);
+
+    poll_fn(|cx| {
+        if cancel.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(None);
+        }
+        future.as_mut().poll(cx).map(Some)
+    })
+    .await
+}
+
+#[cfg(all(
+    test,
+    any(feature = "messages", feature = "search", feature = "uploader")
+))]
+mod tests {
+    use crate::bulk::or_cancel;
+    use std::future::{pending, ready};
+    use std::pin::pin;
+
+    #[tokio::test]
+    async fn cancel_future() {
+        let mut cancel = pin!(ready(()));
+        assert_eq!(or_cancel(cancel.as_mut(), pending::<()>()).await, None);
+
+        let mut cancel = pin!(pending::<()>());
+        assert_eq!(or_cancel(cancel.as_mut(), ready(1)).await, Some(1));
+    }
+}
@@ -0,0 +1,272 @@
+use crate::{ResponseDeserialize, VkApiError, VkApiResult};
+use futures_util::Stream;
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+#[cfg(feature = "integration_actix")]
+pub mod actix;
+#[cfg(feature = "integration_axum")]
+pub mod axum;
+mod reconcile;
+
+pub use reconcile::{reconcile, EventId, Identified};
+
+/// Count of the events, which can wait for handling in the stream of [`router`].
+#[cfg(feature = "integration_axum")]
+const ROUTER_CAPACITY: usize = 1024;
+
+/// # Callback API bridge
+/// Converts incoming [Callback API](https://dev.vk.com/api/callback/getting-started) requests
+/// into the events stream, like the [long poll stream](crate::longpoll::VkLongPoll::subscribe) does.
+/// So the application code can handle events without knowing the delivery mechanism.
+///
+/// Pass headers and body of each request to [`CallbackBridge::handle`] from any web framework
+/// and send returned response back to VK.
+///
+/// ## Usage:
+/// ```rust
+/// use futures_util::StreamExt;
+/// use vkclient::callback::CallbackBridge;
+///
+/// let (bridge, events) = CallbackBridge::<serde_json::Value>::new("a1b2c3d4".to_owned(), 1024);
+/// let bridge = bridge.with_secret("secret".to_owned());
+///
+/// let response = bridge.handle(
+///     &Default::default(),
+///     br#"{"type":"confirmation","group_id":1,"secret":"secret"}"#,
+/// );
+/// assert_eq!(response.body, "a1b2c3d4");
+///
+/// async move {
+///     events.for_each(|event| async move { println!("{:?}", event) }).await;
+/// };
+/// ```
+#[derive(Debug)]
+pub struct CallbackBridge<I> {
+    confirmation: Arc<str>,
+    secret: Option<Arc<str>>,
+    group_id: Option<i64>,
+    sender: mpsc::Sender<VkApiResult<I>>,
+}
+
+impl<I: DeserializeOwned> CallbackBridge<I> {
+    /// Creates the bridge and the events stream.
+    /// * `confirmation` is the string, which server must return to confirm the address.
+    /// * `capacity` is the count of events, which can wait for handling in the stream.
+    ///   Requests over it are answered with an error status, so VK will deliver them again later.
+    #[must_use]
+    pub fn new(confirmation: String, capacity: usize) -> (Self, CallbackEvents<I>) {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+
+        (
+            Self {
+                confirmation: confirmation.into(),
+                secret: None,
+                group_id: None,
+                sender,
+            },
+            CallbackEvents { receiver },
+        )
+    }
+
+    /// Pass the secret key from the Callback API settings. Requests with other secret are rejected.
+    #[must_use]
+    pub fn with_secret(mut self, secret: String) -> Self {
+        self.secret = Some(secret.into());
+        self
+    }
+
+    /// Pass the community id. Requests for other communities are rejected.
+    #[must_use]
+    pub const fn with_group_id(mut self, group_id: i64) -> Self {
+        self.group_id = Some(group_id);
+        self
+    }
+
+    /// Handles one Callback API request and returns the response for VK.
+    /// Events are forwarded to the stream, undecodable events are forwarded as errors.
+    pub fn handle(&self, headers: &HeaderMap, body: &[u8]) -> CallbackResponse {
+        let is_json = headers
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_none_or(|v| v.starts_with("application/json"));
+        if !is_json {
+            return CallbackResponse::new(415, "unsupported media type");
+        }
+
+        let envelope: CallbackEnvelope = match serde_json::from_slice(body) {
+            Ok(envelope) => envelope,
+            Err(_) => return CallbackResponse::new(400, "bad request"),
+        };
+
+        if let Some(secret) = &self.secret {
+            if envelope.secret.as_deref() != Some(secret) {
+                return CallbackResponse::new(403, "forbidden");
+            }
+        }
+
+        if let (Some(group_id), Some(received)) = (self.group_id, envelope.group_id) {
+            if group_id != received {
+                return CallbackResponse::new(403, "forbidden");
+            }
+        }
+
+        if envelope.event_type == "confirmation" {
+            return CallbackResponse::new(200, self.confirmation.as_ref());
+        }
+
+        let event = serde_json::from_slice(body)
+            .map_err(|e| VkApiError::ResponseDeserialize(ResponseDeserialize::Json(e)));
+
+        match self.sender.try_send(event) {
+            Ok(()) => CallbackResponse::new(200, "ok"),
+            Err(_) => CallbackResponse::new(503, "unavailable"),
+        }
+    }
+}
+
+impl<I> Clone for CallbackBridge<I> {
+    fn clone(&self) -> Self {
+        Self {
+            confirmation: self.confirmation.clone(),
+            secret: self.secret.clone(),
+            group_id: self.group_id,
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+/// Returns the axum router, which handles Callback API requests at the root path,
+/// and the stream of the received events. Nest the router to any path of your application,
+/// or turn it into the `tower::Service` with `Router::into_service`.
+/// Up to 1024 events wait for handling, use [`CallbackBridge`] with [`axum::router`] to pass another capacity.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use futures_util::StreamExt;
+///
+/// let (router, events) = vkclient::callback::router::<serde_json::Value, ()>(
+///     "secret".to_owned(),
+///     "a1b2c3d4".to_owned(),
+/// );
+/// let app: axum::Router = axum::Router::new().nest("/vk", router);
+///
+/// async move {
+///     events.for_each(|event| async move { println!("{:?}", event) }).await;
+/// };
+/// ```
+#[cfg(feature = "integration_axum")]
+pub fn router<I, S>(secret: String, confirmation: String) -> (::axum::Router<S>, CallbackEvents<I>)
+where
+    I: DeserializeOwned + Send + 'static,
+    S: Clone + Send + Sync + 'static,
+{
+    let (bridge, events) = CallbackBridge::new(confirmation, ROUTER_CAPACITY);
+    (self::axum::router(bridge.with_secret(secret)), events)
+}
+
+/// Response for the Callback API request.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CallbackResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+impl CallbackResponse {
+    fn new<B: Into<String>>(status: u16, body: B) -> Self {
+        Self {
+            status,
+            body: body.into(),
+        }
+    }
+}
+
+/// Stream of the events received by [`CallbackBridge`].
+/// Ends when all bridge copies are dropped.
+#[derive(Debug)]
+pub struct CallbackEvents<I> {
+    receiver: mpsc::Receiver<VkApiResult<I>>,
+}
+
+impl<I> Stream for CallbackEvents<I> {
+    type Item = VkApiResult<I>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+#[derive(Deserialize)]
+struct CallbackEnvelope {
+    #[serde(rename = "type")]
+    event_type: String,
+    #[serde(default)]
+    group_id: Option<i64>,
+    #[serde(default)]
+    secret: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::callback::{CallbackBridge, CallbackResponse};
+    use futures_util::StreamExt;
+    use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+    use serde::Deserialize;
+
+    #[derive(Deserialize, Debug)]
+    struct Event {
+        #[serde(rename = "type")]
+        event_type: String,
+    }
+
+    #[tokio::test]
+    async fn forward_events() {
+        let (bridge, events) = CallbackBridge::<Event>::new("code".to_owned(), 1);
+        let bridge = bridge.with_secret("secret".to_owned()).with_group_id(1);
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+        assert_eq!(
+            bridge.handle(
+                &headers,
+                br#"{"type":"confirmation","group_id":1,"secret":"secret"}"#
+            ),
+            CallbackResponse::new(200, "code")
+        );
+        assert_eq!(
+            bridge
+                .handle(
+                    &headers,
+                    br#"{"type":"message_new","group_id":1,"secret":"wrong"}"#
+                )
+                .status,
+            403
+        );
+        assert_eq!(
+            bridge.handle(
+                &headers,
+                br#"{"type":"message_new","group_id":1,"secret":"secret"}"#
+            ),
+            CallbackResponse::new(200, "ok")
+        );
+        assert_eq!(
+            bridge
+                .handle(
+                    &headers,
+                    br#"{"type":"message_new","group_id":1,"secret":"secret"}"#
+                )
+                .status,
+            503
+        );
+        drop(bridge);
+
+        let events: Vec<_> = events.collect().await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].as_ref().unwrap().event_type, "message_new");
+    }
+}
@@ -0,0 +1,51 @@
+use crate::callback::CallbackBridge;
+use actix_web::http::StatusCode;
+use actix_web::web::{self, Bytes, Data};
+use actix_web::{HttpRequest, HttpResponse, Resource};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::de::DeserializeOwned;
+
+/// Returns the resource, which handles Callback API requests at the given path with the given bridge.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use actix_web::App;
+/// use vkclient::callback::CallbackBridge;
+///
+/// let (bridge, events) = CallbackBridge::<serde_json::Value>::new("a1b2c3d4".to_owned(), 1024);
+///
+/// let app = App::new().service(vkclient::callback::actix::resource("/vk", bridge));
+/// ```
+pub fn resource<I>(path: &str, bridge: CallbackBridge<I>) -> Resource
+where
+    I: DeserializeOwned + 'static,
+{
+    web::resource(path)
+        .app_data(Data::new(bridge))
+        .route(web::post().to(handler::<I>))
+}
+
+/// Handler of Callback API requests. Use it with your own routes and `Data<CallbackBridge>` as app data.
+pub async fn handler<I: DeserializeOwned>(
+    bridge: Data<CallbackBridge<I>>,
+    request: HttpRequest,
+    body: Bytes,
+) -> HttpResponse {
+    let headers = request
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            Some((
+                HeaderName::from_bytes(name.as_str().as_bytes()).ok()?,
+                HeaderValue::from_bytes(value.as_bytes()).ok()?,
+            ))
+        })
+        .collect::<HeaderMap>();
+
+    let response = bridge.handle(&headers, &body);
+
+    HttpResponse::build(
+        StatusCode::from_u16(response.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+    )
+    .body(response.body)
+}
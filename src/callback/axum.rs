@@ -0,0 +1,42 @@
+use crate::callback::CallbackBridge;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use serde::de::DeserializeOwned;
+
+/// Returns the router, which handles Callback API requests at the root path with the given bridge.
+/// Nest it to any path of your application.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use vkclient::callback::CallbackBridge;
+///
+/// let (bridge, events) = CallbackBridge::<serde_json::Value>::new("a1b2c3d4".to_owned(), 1024);
+///
+/// let app: axum::Router = axum::Router::new().nest("/vk", vkclient::callback::axum::router(bridge));
+/// ```
+pub fn router<I, S>(bridge: CallbackBridge<I>) -> Router<S>
+where
+    I: DeserializeOwned + Send + 'static,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", post(handler::<I>))
+        .with_state(bridge)
+}
+
+/// Handler of Callback API requests. Use it with your own routes and `CallbackBridge` as state.
+pub async fn handler<I: DeserializeOwned>(
+    State(bridge): State<CallbackBridge<I>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, String) {
+    let response = bridge.handle(&headers, &body);
+
+    (
+        StatusCode::from_u16(response.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+        response.body,
+    )
+}
@@ -0,0 +1,149 @@
+use crate::VkApiResult;
+use futures_util::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use std::collections::{HashSet, VecDeque};
+
+/// Event with the `event_id`, which VK sends with each event of the Callback API and the Bots Long Poll API.
+pub trait EventId {
+    /// Returns the id of the event, events without it aren't deduplicated.
+    fn event_id(&self) -> Option<&str>;
+}
+
+impl EventId for Value {
+    fn event_id(&self) -> Option<&str> {
+        self.get("event_id")?.as_str()
+    }
+}
+
+/// Event decoded as `I` with its `event_id`, use it with the models, which don't keep the id,
+/// like the [`GroupEvent`](crate::longpoll::GroupEvent).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identified<I> {
+    pub event_id: Option<String>,
+    pub event: I,
+}
+
+impl<I> EventId for Identified<I> {
+    fn event_id(&self) -> Option<&str> {
+        self.event_id.as_deref()
+    }
+}
+
+impl<'de, I: DeserializeOwned> Deserialize<'de> for Identified<I> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        let event_id = value.event_id().map(str::to_owned);
+        let event = I::deserialize(value).map_err(serde::de::Error::custom)?;
+
+        Ok(Self { event_id, event })
+    }
+}
+
+/// Merges the events of the long poll stream and the Callback API stream of the same community
+/// and skips the events, which are already received by the other channel.
+/// Each event is processed at least once while any of the channels delivers it,
+/// the ids of the last `window` events are kept for the deduplication.
+/// Errors of both channels are passed through, the stream ends when both channels end.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use futures_util::StreamExt;
+/// use vkclient::callback::{reconcile, CallbackBridge};
+/// use vkclient::VkApi;
+/// # let access_token = String::new();
+/// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+///
+/// let (bridge, callback_events) = CallbackBridge::<serde_json::Value>::new("a1b2c3d4".to_owned(), 1024);
+/// let long_poll_events = client.longpoll().subscribe_group(&client, 1);
+///
+/// reconcile(long_poll_events, callback_events, 10_000)
+///     .for_each(|event| async move { println!("{:?}", event) });
+/// ```
+pub fn reconcile<I, A, B>(
+    long_poll: A,
+    callback: B,
+    window: usize,
+) -> impl Stream<Item = VkApiResult<I>>
+where
+    I: EventId,
+    A: Stream<Item = VkApiResult<I>>,
+    B: Stream<Item = VkApiResult<I>>,
+{
+    let mut seen = SeenEvents::new(window);
+
+    futures_util::stream::select(long_poll, callback).filter(move |event| {
+        let is_new = match event {
+            Ok(event) => event.event_id().is_none_or(|id| seen.insert(id)),
+            Err(_) => true,
+        };
+        futures_util::future::ready(is_new)
+    })
+}
+
+/// Ids of the last received events.
+struct SeenEvents {
+    ids: HashSet<String>,
+    order: VecDeque<String>,
+    window: usize,
+}
+
+impl SeenEvents {
+    fn new(window: usize) -> Self {
+        let window = window.max(1);
+        Self {
+            ids: HashSet::with_capacity(window),
+            order: VecDeque::with_capacity(window),
+            window,
+        }
+    }
+
+    /// Returns `false` if the id is already seen.
+    fn insert(&mut self, id: &str) -> bool {
+        if self.ids.contains(id) {
+            return false;
+        }
+
+        if self.order.len() == self.window {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+        self.ids.insert(id.to_owned());
+        self.order.push_back(id.to_owned());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::callback::{reconcile, Identified};
+    use futures_util::StreamExt;
+    use serde_json::{json, Value};
+
+    #[tokio::test]
+    async fn deduplicate_events() {
+        let long_poll = futures_util::stream::iter(vec![
+            Ok(json!({"type": "message_new", "event_id": "a"})),
+            Ok(json!({"type": "message_new", "event_id": "b"})),
+        ]);
+        let callback = futures_util::stream::iter(vec![
+            Ok(json!({"type": "message_new", "event_id": "b"})),
+            Ok(json!({"type": "message_new", "event_id": "c"})),
+            Ok(json!({"type": "message_new"})),
+        ]);
+
+        let events: Vec<Value> = reconcile(long_poll, callback, 2)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        let ids: Vec<_> = events.iter().map(|e| e.get("event_id")).collect();
+        assert_eq!(events.len(), 4);
+        assert_eq!(ids.iter().filter(|id| **id == Some(&json!("b"))).count(), 1);
+
+        let event: Identified<Value> =
+            serde_json::from_str(r#"{"type":"like_add","event_id":"d"}"#).unwrap();
+        assert_eq!(event.event_id.as_deref(), Some("d"));
+    }
+}
@@ -0,0 +1,64 @@
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Count of the solved captchas per request, after which the captcha error is returned.
+pub const MAX_CAPTCHA_ATTEMPTS: usize = 3;
+
+/// Captcha, which VK requires to repeat the request with the error 14.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Captcha {
+    pub sid: String,
+    /// Url of the captcha image
+    pub img: String,
+}
+
+/// # Captcha solver
+/// Register it with [`VkApiBuilder::with_captcha_solver`](crate::VkApiBuilder::with_captcha_solver),
+/// so requests failed with the error 14 are repeated with the solved captcha automatically.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use std::future::Future;
+/// use std::pin::Pin;
+/// use vkclient::{Captcha, CaptchaSolver, VkApi};
+///
+/// struct ManualSolver;
+///
+/// impl CaptchaSolver for ManualSolver {
+///     fn solve<'a>(
+///         &'a self,
+///         captcha: &'a Captcha,
+///     ) -> Pin<Box<dyn Future<Output = Option<String>> + Send + 'a>> {
+///         Box::pin(async move {
+///             println!("enter the captcha from {}", captcha.img);
+///             let mut key = String::new();
+///             std::io::stdin().read_line(&mut key).ok()?;
+///             Some(key.trim().to_owned())
+///         })
+///     }
+/// }
+///
+/// # let access_token = String::new();
+/// let client: VkApi = vkclient::VkApiBuilder::new(access_token)
+///     .with_captcha_solver(ManualSolver)
+///     .into();
+/// ```
+pub trait CaptchaSolver: Send + Sync {
+    /// Returns the captcha key, or `None` to return the captcha error to the caller.
+    fn solve<'a>(
+        &'a self,
+        captcha: &'a Captcha,
+    ) -> Pin<Box<dyn Future<Output = Option<String>> + Send + 'a>>;
+}
+
+/// Registered solver, shared by the clients.
+#[derive(Clone)]
+pub struct SolverHandle(pub(crate) Arc<dyn CaptchaSolver>);
+
+impl Debug for SolverHandle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CaptchaSolver")
+    }
+}
@@ -0,0 +1,267 @@
+//! # Failure injection
+//! Injects the latency and the failures into the requests of the clients at the given probabilities,
+//! so the retries, the circuit breakers and the long poll reconnects can be tested without the real outages.
+//! Faults are injected before the request is sent, so they pass through the same retry loops as the real ones.
+
+use crate::inner::decode;
+use crate::{DecodeStrictness, ResponseDeserialize, VkApiError};
+use reqwest::header::HeaderValue;
+use serde::de::IgnoredAny;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Truncated json, which is decoded instead of the response.
+const MALFORMED_PAYLOAD: &[u8] = br#"{"response": [{"id": 1, "#;
+
+/// # Failure injection settings
+/// Each fault is rolled independently with its probability from 0 to 1, the first rolled failure wins.
+/// Clones share the random generator.
+///
+/// ## Usage:
+/// ```rust
+/// use std::time::Duration;
+/// use vkclient::chaos::Chaos;
+/// use vkclient::longpoll::VkLongPoll;
+///
+/// let chaos = Chaos::new()
+///     .with_seed(42)
+///     .with_latency(0.2, Duration::from_millis(300))
+///     .with_drops(0.05)
+///     .with_server_errors(0.05)
+///     .with_longpoll_failures(0.1);
+///
+/// let client = vkclient::VkApiBuilder::new(String::new())
+///     .with_chaos(chaos.clone())
+///     .build()
+///     .unwrap();
+/// let longpoll = VkLongPoll::default().with_chaos(chaos);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Chaos {
+    latency: Option<(f64, Duration)>,
+    drops: f64,
+    server_errors: f64,
+    malformed_payloads: f64,
+    longpoll_failures: f64,
+    state: Arc<AtomicU64>,
+}
+
+impl Chaos {
+    /// Creates the settings without the faults and with the random seed.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            latency: None,
+            drops: 0.0,
+            server_errors: 0.0,
+            malformed_payloads: 0.0,
+            longpoll_failures: 0.0,
+            state: Arc::new(AtomicU64::new(RandomState::new().build_hasher().finish())),
+        }
+    }
+
+    /// Pass the seed of the random generator to repeat the same faults in the same order.
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.state = Arc::new(AtomicU64::new(seed));
+        self
+    }
+
+    /// Pass the delay, which is added to the requests with the probability.
+    #[must_use]
+    pub const fn with_latency(mut self, probability: f64, delay: Duration) -> Self {
+        self.latency = Some((probability, delay));
+        self
+    }
+
+    /// Pass the probability of the dropped connections, which are returned as [`VkApiError::IO`].
+    #[must_use]
+    pub const fn with_drops(mut self, probability: f64) -> Self {
+        self.drops = probability;
+        self
+    }
+
+    /// Pass the probability of the responses with the 500 status.
+    #[must_use]
+    pub const fn with_server_errors(mut self, probability: f64) -> Self {
+        self.server_errors = probability;
+        self
+    }
+
+    /// Pass the probability of the truncated responses, which fail to decode.
+    #[must_use]
+    pub const fn with_malformed_payloads(mut self, probability: f64) -> Self {
+        self.malformed_payloads = probability;
+        self
+    }
+
+    /// Pass the probability of the long poll errors with the random `failed` code from 1 to 3.
+    /// API requests aren't affected.
+    #[must_use]
+    pub const fn with_longpoll_failures(mut self, probability: f64) -> Self {
+        self.longpoll_failures = probability;
+        self
+    }
+
+    /// Waits for the rolled latency and returns the rolled fault of the request.
+    pub(crate) async fn before_request(&self) -> Option<Fault> {
+        if let Some((probability, delay)) = self.latency {
+            if self.roll(probability) {
+                tokio::time::sleep(delay).await;
+            }
+        }
+
+        if self.roll(self.drops) {
+            Some(Fault::Drop)
+        } else if self.roll(self.server_errors) {
+            Some(Fault::ServerError)
+        } else if self.roll(self.malformed_payloads) {
+            Some(Fault::Malformed)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the rolled `failed` code of the long poll request.
+    #[cfg(feature = "longpoll")]
+    pub(crate) fn longpoll_failure(&self) -> Option<usize> {
+        self.roll(self.longpoll_failures)
+            .then(|| (self.next() % 3) as usize + 1)
+    }
+
+    fn roll(&self, probability: f64) -> bool {
+        probability > 0.0 && ((self.next() >> 11) as f64 / (1_u64 << 53) as f64) < probability
+    }
+
+    /// Returns the next number of the `SplitMix64` generator.
+    fn next(&self) -> u64 {
+        let mut z = self
+            .state
+            .fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed)
+            .wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+impl Default for Chaos {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Fault of the request.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum Fault {
+    Drop,
+    ServerError,
+    Malformed,
+}
+
+impl Fault {
+    /// Returns the error of the fault, status errors of the long poll servers are returned as [`VkApiError::LongPollRequest`].
+    pub(crate) fn into_error(self, long_poll: bool) -> VkApiError {
+        match self {
+            Self::Drop => VkApiError::IO(std::io::Error::new(
+                std::io::ErrorKind::ConnectionReset,
+                "connection dropped by chaos",
+            )),
+            Self::ServerError => {
+                let response = http::Response::builder()
+                    .status(http::StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Vec::new())
+                    .expect("response is valid");
+                let error = reqwest::Response::from(response)
+                    .error_for_status()
+                    .expect_err("status is failed");
+
+                #[cfg(feature = "longpoll")]
+                if long_poll {
+                    return VkApiError::LongPollRequest(error);
+                }
+                #[cfg(not(feature = "longpoll"))]
+                let _ = long_poll;
+
+                VkApiError::Request(error)
+            }
+            Self::Malformed => decode::<IgnoredAny, _>(
+                Some(&HeaderValue::from_static("application/json")),
+                MALFORMED_PAYLOAD,
+                DecodeStrictness::Lenient,
+            )
+            .err()
+            .unwrap_or(VkApiError::ResponseDeserialize(
+                ResponseDeserialize::BadEncoding,
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chaos::{Chaos, Fault};
+    use crate::VkApiError;
+
+    #[tokio::test]
+    async fn inject_faults() {
+        let chaos = Chaos::new().with_seed(1);
+        for _ in 0..100 {
+            assert_eq!(chaos.before_request().await, None);
+        }
+
+        let chaos = Chaos::new().with_seed(1).with_server_errors(1.0);
+        let error = chaos.before_request().await.unwrap().into_error(false);
+        assert!(matches!(&error, VkApiError::Request(e) if e.status().is_some_and(|s| s == 500)));
+        assert!(error.is_retryable());
+
+        let chaos = Chaos::new().with_seed(7).with_drops(0.5);
+        let mut drops = 0;
+        for _ in 0..1000 {
+            if chaos.before_request().await == Some(Fault::Drop) {
+                drops += 1;
+            }
+        }
+        assert!((400..600).contains(&drops), "{drops} drops");
+
+        assert!(matches!(
+            Fault::Malformed.into_error(false),
+            VkApiError::ResponseDeserialize(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn inject_into_clients() {
+        let client: crate::VkApi = crate::VkApiBuilder::new(String::new())
+            .with_chaos(Chaos::new().with_drops(1.0))
+            .into();
+        let error = client
+            .send_request::<serde::de::IgnoredAny, _, _>("users.get", ())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, VkApiError::IO(_)));
+
+        #[cfg(feature = "longpoll")]
+        {
+            let error = client
+                .longpoll()
+                .with_chaos(Chaos::new().with_longpoll_failures(1.0))
+                .subscribe_once::<_, serde::de::IgnoredAny>(crate::longpoll::LongPollRequest {
+                    key: String::new(),
+                    server: "lp.vk.com".to_owned(),
+                    ts: "10".to_owned(),
+                    wait: 25,
+                    additional_params: (),
+                })
+                .await
+                .unwrap_err();
+            assert!(matches!(
+                error,
+                VkApiError::LongPoll(e) if e.failed() != 1 || e.ts() == Some("10")
+            ));
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{VkApi, VkApiError, VkApiResult, VkError};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+/// Boxed future of the [`VkApiClient`] response.
+pub type ResponseFuture<'a> = Pin<Box<dyn Future<Output = VkApiResult<Value>> + Send + 'a>>;
+
+/// # Object safe VK API client
+/// Inject `Arc<dyn VkApiClient>` into your services to replace [`VkApi`] with [`MockClient`] in tests
+/// or with your own implementation, like a caching one.
+///
+/// ## Usage:
+/// ```rust
+/// use std::sync::Arc;
+/// use serde::Deserialize;
+/// use vkclient::{MockClient, VkApiClient, VkApiResult};
+///
+/// #[derive(Deserialize)]
+/// struct User {
+///     id: i64,
+/// }
+///
+/// async fn first_user(client: &dyn VkApiClient) -> VkApiResult<Vec<User>> {
+///     client.send("users.get", serde_json::json!({"user_ids": 1})).await
+/// }
+///
+/// let client: Arc<dyn VkApiClient> =
+///     Arc::new(MockClient::new().with_response("users.get", serde_json::json!([{"id": 1}])));
+///
+/// async move {
+///     assert_eq!(first_user(client.as_ref()).await.unwrap()[0].id, 1);
+/// };
+/// ```
+pub trait VkApiClient: Send + Sync {
+    /// Sends the request with the json object body and returns the json response.
+    /// Body fields must be scalars or arrays of scalars, like for [`VkApi::send_request_json`].
+    fn send_json<'a>(&'a self, method: &'a str, body: Value) -> ResponseFuture<'a>;
+}
+
+impl dyn VkApiClient + '_ {
+    /// Sends the request with the typed body and response through the object safe client.
+    pub async fn send<T, B>(&self, method: &str, body: B) -> VkApiResult<T>
+    where
+        T: DeserializeOwned,
+        B: Serialize,
+    {
+        let body = serde_json::to_value(body).map_err(|e| {
+            VkApiError::RequestSerialize(serde_urlencoded::ser::Error::Custom(e.to_string().into()))
+        })?;
+        let response = self.send_json(method, body).await?;
+
+        T::deserialize(response)
+            .map_err(|e| VkApiError::ResponseDeserialize(crate::ResponseDeserialize::Json(e)))
+    }
+}
+
+impl VkApiClient for VkApi {
+    fn send_json<'a>(&'a self, method: &'a str, body: Value) -> ResponseFuture<'a> {
+        Box::pin(self.send_request_json(method, body))
+    }
+}
+
+/// # Mock client
+/// Returns the registered responses by the method names and records the sent requests.
+/// Requests of the methods without responses fail with the VK error 3, like unknown methods.
+#[derive(Debug, Default)]
+pub struct MockClient {
+    responses: HashMap<String, Result<Value, VkError>>,
+    requests: Mutex<Vec<(String, Value)>>,
+}
+
+impl MockClient {
+    /// Creates the client without responses.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pass the response of the method.
+    #[must_use]
+    pub fn with_response<M: Into<String>>(mut self, method: M, response: Value) -> Self {
+        self.responses.insert(method.into(), Ok(response));
+        self
+    }
+
+    /// Pass the error of the method.
+    #[must_use]
+    pub fn with_error<M: Into<String>>(mut self, method: M, error: VkError) -> Self {
+        self.responses.insert(method.into(), Err(error));
+        self
+    }
+
+    /// Returns the sent requests with their bodies in order.
+    pub fn requests(&self) -> Vec<(String, Value)> {
+        self.requests
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+    }
+}
+
+impl VkApiClient for MockClient {
+    fn send_json<'a>(&'a self, method: &'a str, body: Value) -> ResponseFuture<'a> {
+        self.requests
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .push((method.to_owned(), body));
+
+        let response = match self.responses.get(method) {
+            Some(Ok(response)) => Ok(response.clone()),
+            Some(Err(error)) => Err(VkApiError::Vk(error.clone())),
+            None => Err(VkApiError::Vk(VkError::new(
+                VkError::UNKNOWN_METHOD,
+                format!("Unknown method passed: {method}"),
+            ))),
+        };
+
+        Box::pin(std::future::ready(response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{MockClient, VkApiClient, VkApiError, VkError};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn mock_responses() {
+        let mock = Arc::new(
+            MockClient::new()
+                .with_response("users.get", serde_json::json!([{"id": 1}]))
+                .with_error("wall.post", VkError::new(VkError::ACCESS_DENIED, "denied")),
+        );
+        let client: Arc<dyn VkApiClient> = mock.clone();
+
+        let users: Vec<serde_json::Value> = client
+            .send("users.get", serde_json::json!({"user_ids": 1}))
+            .await
+            .unwrap();
+        assert_eq!(users[0]["id"], 1);
+
+        let error = client.send::<(), _>("wall.post", ()).await.unwrap_err();
+        assert!(matches!(error, VkApiError::Vk(e) if e.code() == VkError::ACCESS_DENIED));
+        assert!(client.send::<(), _>("unknown", ()).await.is_err());
+
+        assert_eq!(
+            mock.requests()[0],
+            ("users.get".to_owned(), serde_json::json!({"user_ids": 1}))
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use crate::inner::{create_client, ClientConfig};
+use crate::VkApiError;
+use reqwest::Client;
+use std::error::Error;
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::RwLock;
+
+/// Count of the stale connection errors in a row, after which the client is rebuilt.
+const STALE_ERRORS_LIMIT: u32 = 3;
+
+/// Shared http client, which is rebuilt when its connections become stale,
+/// like after the network change or the laptop sleep, and the server answers with GOAWAY or stream errors.
+#[derive(Debug)]
+pub struct ConnectionPool {
+    client: RwLock<Client>,
+    config: ClientConfig,
+    stale_in_row: AtomicU32,
+    stale_errors: AtomicU64,
+    recycles: AtomicU64,
+}
+
+impl ConnectionPool {
+    pub(crate) fn new(config: ClientConfig) -> reqwest::Result<Self> {
+        Ok(Self {
+            client: RwLock::new(create_client(&config)?),
+            config,
+            stale_in_row: AtomicU32::new(0),
+            stale_errors: AtomicU64::new(0),
+            recycles: AtomicU64::new(0),
+        })
+    }
+
+    /// Returns the current client.
+    pub(crate) fn client(&self) -> Client {
+        self.client
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Replaces the client, so the tests can send requests to the plain http servers.
+    #[cfg(all(
+        test,
+        feature = "encode_json",
+        any(feature = "compression_zstd", feature = "messages")
+    ))]
+    pub(crate) fn set_client(&self, client: Client) {
+        *self
+            .client
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = client;
+    }
+
+    /// Resets the stale errors counter after the successful request.
+    pub(crate) fn succeeded(&self) {
+        self.stale_in_row.store(0, Ordering::Relaxed);
+    }
+
+    /// Counts the request error and rebuilds the client after the stale connection errors in a row.
+    pub(crate) fn failed(&self, error: reqwest::Error) -> VkApiError {
+        if is_stale_connection(&error) {
+            self.stale();
+        }
+
+        VkApiError::Request(error)
+    }
+
+    fn stale(&self) {
+        self.stale_errors.fetch_add(1, Ordering::Relaxed);
+
+        if self.stale_in_row.fetch_add(1, Ordering::Relaxed) + 1 >= STALE_ERRORS_LIMIT {
+            self.stale_in_row.store(0, Ordering::Relaxed);
+            // the old client is kept if the new one can't be built
+            if let Ok(client) = create_client(&self.config) {
+                *self
+                    .client
+                    .write()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner) = client;
+                self.recycles.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub(crate) fn stats(&self) -> ConnectionStats {
+        ConnectionStats {
+            stale_errors: self.stale_errors.load(Ordering::Relaxed),
+            recycles: self.recycles.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Checks the error sources for the errors of the closed connections:
+/// http2 GOAWAY, refused streams and the transport errors of h2, closed hyper connections
+/// and io errors of the reset connections.
+fn is_stale_connection(error: &(dyn Error + 'static)) -> bool {
+    std::iter::successors(Some(error), |e| (*e).source()).any(|e| {
+        if let Some(e) = e.downcast_ref::<h2::Error>() {
+            e.is_go_away() || e.is_io() || e.reason() == Some(h2::Reason::REFUSED_STREAM)
+        } else if let Some(e) = e.downcast_ref::<hyper::Error>() {
+            e.is_closed() || e.is_canceled() || e.is_incomplete_message()
+        } else if let Some(e) = e.downcast_ref::<std::io::Error>() {
+            matches!(
+                e.kind(),
+                ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted | ErrorKind::BrokenPipe
+            )
+        } else {
+            false
+        }
+    })
+}
+
+/// Counters of the stale connections handling.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct ConnectionStats {
+    /// Requests failed with the stale connection errors
+    pub stale_errors: u64,
+    /// Times the client was rebuilt
+    pub recycles: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connection::{is_stale_connection, ConnectionPool, STALE_ERRORS_LIMIT};
+    use crate::inner::ClientConfig;
+    use crate::ConnectionStats;
+    use std::error::Error;
+    use std::fmt::{Display, Formatter};
+    use std::io::ErrorKind;
+
+    /// Error of the request with the cause, like the errors of reqwest.
+    #[derive(Debug)]
+    struct RequestError(Box<dyn Error + Send + Sync>);
+
+    impl Display for RequestError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            f.write_str("error sending request")
+        }
+    }
+
+    impl Error for RequestError {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(self.0.as_ref())
+        }
+    }
+
+    fn stale<E: Error + Send + Sync + 'static>(cause: E) -> bool {
+        is_stale_connection(&RequestError(Box::new(cause)))
+    }
+
+    #[test]
+    fn stale_connection_errors() {
+        assert!(stale(std::io::Error::from(ErrorKind::ConnectionReset)));
+        assert!(stale(std::io::Error::from(ErrorKind::BrokenPipe)));
+        assert!(stale(h2::Error::from(h2::Reason::REFUSED_STREAM)));
+        assert!(stale(std::io::Error::from(ErrorKind::ConnectionAborted)));
+
+        assert!(!stale(std::io::Error::from(ErrorKind::TimedOut)));
+        assert!(!stale(h2::Error::from(h2::Reason::PROTOCOL_ERROR)));
+        // messages aren't matched
+        assert!(!stale(std::io::Error::other(
+            "connection error: broken pipe"
+        )));
+    }
+
+    #[test]
+    fn rebuild_after_errors_in_row() {
+        let pool = ConnectionPool::new(ClientConfig::default()).unwrap();
+
+        for _ in 1..STALE_ERRORS_LIMIT {
+            pool.stale();
+        }
+        pool.succeeded();
+        for _ in 1..STALE_ERRORS_LIMIT {
+            pool.stale();
+        }
+        assert_eq!(
+            pool.stats(),
+            ConnectionStats {
+                stale_errors: u64::from(STALE_ERRORS_LIMIT - 1) * 2,
+                recycles: 0,
+            }
+        );
+
+        pool.stale();
+        assert_eq!(pool.stats().recycles, 1);
+        // the counter starts again after the rebuild
+        pool.stale();
+        assert_eq!(pool.stats().recycles, 1);
+    }
+}
@@ -0,0 +1,267 @@
+use crate::inner::VkApiInner;
+use crate::{Auth, Compression, DecodeStrictness, Encoding, Version};
+use serde::Serialize;
+use std::time::Duration;
+
+/// Features of the crate with their compile time state.
+const FEATURES: &[(&str, bool)] = &[
+    ("compression_zstd", cfg!(feature = "compression_zstd")),
+    ("compression_gzip", cfg!(feature = "compression_gzip")),
+    ("encode_json", cfg!(feature = "encode_json")),
+    ("encode_msgpack", cfg!(feature = "encode_msgpack")),
+    ("uploader", cfg!(feature = "uploader")),
+    ("upload_cookies", cfg!(feature = "upload_cookies")),
+    ("upload_file", cfg!(feature = "upload_file")),
+    ("download", cfg!(feature = "download")),
+    ("strict_decode", cfg!(feature = "strict_decode")),
+    ("address_family", cfg!(feature = "address_family")),
+    ("longpoll", cfg!(feature = "longpoll")),
+    ("longpoll_stream", cfg!(feature = "longpoll_stream")),
+    ("longpoll_replay", cfg!(feature = "longpoll_replay")),
+    ("longpoll_broadcast", cfg!(feature = "longpoll_broadcast")),
+    ("callback", cfg!(feature = "callback")),
+    ("integration_axum", cfg!(feature = "integration_axum")),
+    ("integration_actix", cfg!(feature = "integration_actix")),
+    ("messages", cfg!(feature = "messages")),
+    ("search", cfg!(feature = "search")),
+    ("streaming", cfg!(feature = "streaming")),
+    ("groups", cfg!(feature = "groups")),
+    ("export", cfg!(feature = "export")),
+    ("rate_limit", cfg!(feature = "rate_limit")),
+    ("resilience", cfg!(feature = "resilience")),
+    ("vk_id", cfg!(feature = "vk_id")),
+    ("direct_auth", cfg!(feature = "direct_auth")),
+    ("chaos", cfg!(feature = "chaos")),
+    ("tls_pinning", cfg!(feature = "tls_pinning")),
+    ("manual_roots", cfg!(feature = "manual_roots")),
+];
+
+/// Effective configuration of the client for the bug reports and the health endpoints,
+/// see [`VkApi::describe`](crate::VkApi::describe).
+/// Access tokens and other secrets are never included, only the kind of the authorization.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientDescription {
+    /// Version of the crate
+    pub crate_version: &'static str,
+    pub domain: String,
+    pub version: Version,
+    /// `msgpack`, `json`, `auto` or `none`
+    pub encoding: &'static str,
+    /// `zstd`, `gzip` or `none`
+    pub compression: &'static str,
+    /// `lenient` or `strict`
+    pub strictness: &'static str,
+    /// `token`, `token_pool`, `vk_id` or `none`
+    pub auth: &'static str,
+    pub connect_timeout_ms: Option<u64>,
+    /// Count of the custom root certificates, the built-in roots are used without them
+    pub root_certificates: usize,
+    /// Preferred address family, if the `address_family` feature is enabled
+    pub address_family: Option<String>,
+    /// Hosts with the pinned public keys
+    pub pinned_hosts: Vec<String>,
+    pub rate_limit: Option<RateLimitDescription>,
+    /// Resilience policies of the request kinds, which have any setting
+    pub policies: Vec<PolicyDescription>,
+    pub buffer_pool_size: usize,
+    pub buffer_max_capacity: usize,
+    pub captcha_solver: bool,
+    pub error_stats: bool,
+    pub method_stats: bool,
+    /// Recorder of the requests metrics is set
+    pub metrics: bool,
+    /// Failures are injected into the requests
+    pub chaos: bool,
+    /// Enabled features of the crate
+    pub features: Vec<&'static str>,
+}
+
+/// Rate limit of the [`ClientDescription`].
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct RateLimitDescription {
+    pub interval_ms: f64,
+    pub burst: u32,
+}
+
+/// Resilience policy of the [`ClientDescription`].
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyDescription {
+    /// `read`, `write`, `longpoll` or `upload`
+    pub kind: String,
+    pub timeout_ms: Option<u64>,
+    pub max_retries: Option<u32>,
+    /// Failures in a row, which open the circuit breaker
+    pub breaker_failures: Option<u32>,
+    pub breaker_cooldown_ms: Option<u64>,
+}
+
+impl ClientDescription {
+    pub(crate) fn of(inner: &VkApiInner) -> Self {
+        let auth = match &inner.auth {
+            Auth::Token(_) if inner.token_pool.is_some() => "token_pool",
+            #[cfg(feature = "vk_id")]
+            Auth::Token(_) if inner.refresher.is_some() => "vk_id",
+            Auth::Token(_) => "token",
+            Auth::None => "none",
+        };
+
+        Self {
+            crate_version: env!("CARGO_PKG_VERSION"),
+            domain: inner.domain.clone(),
+            version: inner.version,
+            encoding: encoding_name(inner.format),
+            compression: compression_name(inner.encoding),
+            strictness: match inner.strictness {
+                DecodeStrictness::Lenient => "lenient",
+                #[cfg(feature = "strict_decode")]
+                DecodeStrictness::Strict => "strict",
+            },
+            auth,
+            connect_timeout_ms: inner.client_config.connect_timeout.map(millis),
+            root_certificates: inner.client_config.root_certificates.len(),
+            #[cfg(feature = "address_family")]
+            address_family: Some(format!("{:?}", inner.client_config.address_family)),
+            #[cfg(not(feature = "address_family"))]
+            address_family: None,
+            #[cfg(feature = "tls_pinning")]
+            pinned_hosts: inner
+                .client_config
+                .tls_pins
+                .iter()
+                .flat_map(crate::TlsPins::hosts)
+                .map(str::to_owned)
+                .collect(),
+            #[cfg(not(feature = "tls_pinning"))]
+            pinned_hosts: Vec::new(),
+            #[cfg(feature = "rate_limit")]
+            rate_limit: inner.rate_limiter.as_ref().map(|limiter| {
+                let limit = limiter.quota().limit;
+                RateLimitDescription {
+                    interval_ms: limit.interval().as_secs_f64() * 1000.0,
+                    burst: limit.burst(),
+                }
+            }),
+            #[cfg(not(feature = "rate_limit"))]
+            rate_limit: None,
+            #[cfg(feature = "resilience")]
+            policies: policies(&inner.policies),
+            #[cfg(not(feature = "resilience"))]
+            policies: Vec::new(),
+            buffer_pool_size: inner.buffer_pool_size,
+            buffer_max_capacity: inner.buffer_max_capacity,
+            captcha_solver: inner.captcha_solver.is_some(),
+            error_stats: inner.error_stats.is_some(),
+            method_stats: inner.method_stats.is_some(),
+            metrics: inner.metrics.is_some(),
+            #[cfg(feature = "chaos")]
+            chaos: inner.chaos.is_some(),
+            #[cfg(not(feature = "chaos"))]
+            chaos: false,
+            features: FEATURES
+                .iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(name, _)| *name)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(feature = "resilience")]
+fn policies(policies: &crate::resilience::Policies) -> Vec<PolicyDescription> {
+    use crate::RequestKind;
+
+    [
+        RequestKind::Read,
+        RequestKind::Write,
+        RequestKind::LongPoll,
+        RequestKind::Upload,
+    ]
+    .into_iter()
+    .map(|kind| {
+        let policy = policies.get(kind);
+        PolicyDescription {
+            kind: kind.to_string(),
+            timeout_ms: policy.timeout().map(millis),
+            max_retries: policy.retry().map(|backoff| backoff.max_retries()),
+            breaker_failures: policy.breaker().map(|breaker| breaker.failures()),
+            breaker_cooldown_ms: policy.breaker().map(|breaker| millis(breaker.cooldown())),
+        }
+    })
+    .filter(|policy| {
+        policy.timeout_ms.is_some()
+            || policy.max_retries.is_some()
+            || policy.breaker_failures.is_some()
+    })
+    .collect()
+}
+
+const fn encoding_name(encoding: Encoding) -> &'static str {
+    match encoding {
+        #[cfg(feature = "encode_msgpack")]
+        Encoding::Msgpack => "msgpack",
+        #[cfg(feature = "encode_json")]
+        Encoding::Json => "json",
+        #[cfg(all(feature = "encode_json", feature = "encode_msgpack"))]
+        Encoding::Auto => "auto",
+        #[allow(deprecated)]
+        Encoding::None => "none",
+    }
+}
+
+const fn compression_name(compression: Compression) -> &'static str {
+    match compression {
+        #[cfg(feature = "compression_zstd")]
+        Compression::Zstd { .. } => "zstd",
+        #[cfg(feature = "compression_gzip")]
+        Compression::Gzip => "gzip",
+        Compression::None => "none",
+    }
+}
+
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+#[cfg(all(test, feature = "encode_json"))]
+mod tests {
+    use crate::{Encoding, VkApi, VkApiBuilder};
+
+    #[test]
+    fn describe_without_secrets() {
+        let client: VkApi = VkApiBuilder::new("secret-token".to_owned())
+            .with_encoding(Encoding::Json)
+            .with_connect_timeout(std::time::Duration::from_secs(3))
+            .with_method_stats()
+            .into();
+
+        let description = client.describe();
+        assert_eq!(description.domain, "api.vk.com");
+        assert_eq!(description.encoding, "json");
+        assert_eq!(description.auth, "token");
+        assert_eq!(description.connect_timeout_ms, Some(3000));
+        assert!(description.method_stats && !description.error_stats);
+        assert!(description.features.contains(&"encode_json"));
+
+        let json = serde_json::to_string(&description).unwrap();
+        assert!(!json.contains("secret-token"));
+        assert!(json.contains(r#""version":"5."#));
+    }
+
+    #[test]
+    #[cfg(feature = "resilience")]
+    fn describe_policies() {
+        use crate::{RequestKind, ResiliencePolicy};
+
+        let client: VkApi = VkApiBuilder::new(String::new())
+            .with_resilience_policy(
+                RequestKind::LongPoll,
+                ResiliencePolicy::new().with_timeout(std::time::Duration::from_secs(30)),
+            )
+            .into();
+
+        let policies = client.describe().policies;
+        assert_eq!(policies.len(), 1);
+        assert_eq!(policies[0].kind, RequestKind::LongPoll.to_string());
+        assert_eq!(policies[0].timeout_ms, Some(30_000));
+    }
+}
@@ -0,0 +1,358 @@
+//! # Direct authorization
+//! Password grant of the [direct authorization](https://dev.vk.com/api/direct-auth),
+//! which is available to the official clients only.
+
+use crate::inner::{create_client, ClientConfig};
+use crate::{BuildError, Captcha, Version, VkApiError};
+use reqwest::header::ACCEPT;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+
+/// Token endpoint of the direct authorization.
+const OAUTH_URL: &str = "https://oauth.vk.com/token";
+
+/// Count of the 2FA codes per login, after which the validation error is returned.
+const MAX_CODE_ATTEMPTS: usize = 3;
+
+/// # Direct authorization client
+/// Exchanges the login and the password to the access token.
+/// Codes of the two-factor authentication are requested from the [`TwoFactor`] callback.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use std::future::Future;
+/// use std::pin::Pin;
+/// use vkclient::direct_auth::{DirectAuth, TwoFactor, Validation};
+///
+/// struct StdinCode;
+///
+/// impl TwoFactor for StdinCode {
+///     fn code<'a>(
+///         &'a self,
+///         validation: &'a Validation,
+///     ) -> Pin<Box<dyn Future<Output = Option<String>> + Send + 'a>> {
+///         Box::pin(async move {
+///             println!("enter the code of {}", validation.validation_type);
+///             let mut code = String::new();
+///             std::io::stdin().read_line(&mut code).ok()?;
+///             Some(code.trim().to_owned())
+///         })
+///     }
+/// }
+///
+/// let auth = DirectAuth::new(2274003, "client secret")
+///     .unwrap()
+///     .with_scope("offline,messages");
+///
+/// async move {
+///     let token = auth.login("+79990000000", "password", &StdinCode).await.unwrap();
+///     let client: vkclient::VkApi = vkclient::VkApiBuilder::new(token.access_token).into();
+/// };
+/// ```
+#[derive(Clone)]
+pub struct DirectAuth {
+    client: Client,
+    client_id: u64,
+    client_secret: String,
+    scope: Option<String>,
+}
+
+impl DirectAuth {
+    /// Creates the client of the official application.
+    /// Use [`DirectAuth::with_client`] to pass the built http client.
+    pub fn new<S: Into<String>>(client_id: u64, client_secret: S) -> Result<Self, BuildError> {
+        create_client(&ClientConfig::default())
+            .map(|client| Self::with_client(client_id, client_secret, client))
+            .map_err(BuildError::Client)
+    }
+
+    /// Creates the client of the official application with the http client.
+    pub fn with_client<S: Into<String>>(client_id: u64, client_secret: S, client: Client) -> Self {
+        Self {
+            client,
+            client_id,
+            client_secret: client_secret.into(),
+            scope: None,
+        }
+    }
+
+    /// Pass the requested scopes separated by commas, like `offline,messages`.
+    #[must_use]
+    pub fn with_scope<S: Into<String>>(mut self, scope: S) -> Self {
+        self.scope = Some(scope.into());
+        self
+    }
+
+    /// Exchanges the login and the password to the access token.
+    /// When VK requires the two-factor authentication, the code is requested from `two_factor`
+    /// and the request is repeated with it up to 3 times.
+    pub async fn login<F: TwoFactor + ?Sized>(
+        &self,
+        username: &str,
+        password: &str,
+        two_factor: &F,
+    ) -> Result<DirectToken, DirectAuthError> {
+        let mut code = None;
+
+        for _ in 0..MAX_CODE_ATTEMPTS {
+            let validation = match self.send(username, password, code.as_deref()).await {
+                Err(DirectAuthError::NeedValidation(validation)) => validation,
+                result => return result,
+            };
+
+            code = two_factor.code(&validation).await;
+            if code.is_none() {
+                return Err(DirectAuthError::NeedValidation(validation));
+            }
+        }
+
+        self.send(username, password, code.as_deref()).await
+    }
+
+    async fn send(
+        &self,
+        username: &str,
+        password: &str,
+        code: Option<&str>,
+    ) -> Result<DirectToken, DirectAuthError> {
+        let params = PasswordGrant {
+            grant_type: "password",
+            client_id: self.client_id,
+            client_secret: &self.client_secret,
+            username,
+            password,
+            scope: self.scope.as_deref(),
+            two_factor_supported: 1,
+            code,
+            v: Version::default(),
+        };
+
+        let response = self
+            .client
+            .post(OAUTH_URL)
+            .header(ACCEPT, "application/json")
+            .form(&params)
+            .send()
+            .await
+            .map_err(|e| DirectAuthError::Request(VkApiError::Request(e)))?
+            .bytes()
+            .await
+            .map_err(|e| DirectAuthError::Request(VkApiError::Request(e)))?;
+
+        let response: DirectResponse = serde_json::from_slice(&response).map_err(|e| {
+            DirectAuthError::Request(VkApiError::ResponseDeserialize(
+                crate::ResponseDeserialize::Json(e),
+            ))
+        })?;
+
+        response.into_result()
+    }
+}
+
+impl std::fmt::Debug for DirectAuth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DirectAuth")
+            .field("client_id", &self.client_id)
+            .field("client_secret", &"***")
+            .field("scope", &self.scope)
+            .finish()
+    }
+}
+
+/// Callback of the two-factor authentication codes.
+pub trait TwoFactor: Send + Sync {
+    /// Returns the code from the SMS or the authenticator app,
+    /// or `None` to return the validation error to the caller.
+    fn code<'a>(
+        &'a self,
+        validation: &'a Validation,
+    ) -> Pin<Box<dyn Future<Output = Option<String>> + Send + 'a>>;
+}
+
+/// Access token of the direct authorization.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DirectToken {
+    pub access_token: String,
+    /// Lifetime of the access token in seconds, zero for the `offline` scope.
+    #[serde(default)]
+    pub expires_in: u64,
+    pub user_id: i64,
+}
+
+impl std::fmt::Debug for DirectToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DirectToken")
+            .field("access_token", &"***")
+            .field("expires_in", &self.expires_in)
+            .field("user_id", &self.user_id)
+            .finish()
+    }
+}
+
+/// Details of the required two-factor authentication.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Validation {
+    /// `2fa_sms` or `2fa_app`.
+    #[serde(default)]
+    pub validation_type: String,
+    #[serde(default)]
+    pub validation_sid: Option<String>,
+    /// Masked phone number, which the SMS is sent to.
+    #[serde(default)]
+    pub phone_mask: Option<String>,
+    /// Page of the validation in the browser, when it can't be passed with the code.
+    #[serde(default)]
+    pub redirect_uri: Option<String>,
+}
+
+/// Error of the direct authorization.
+#[derive(Debug)]
+pub enum DirectAuthError {
+    /// Request failed or the response can't be decoded
+    Request(VkApiError),
+    /// Wrong login, password or application credentials
+    InvalidClient(String),
+    /// Two-factor authentication is required and no valid code is passed
+    NeedValidation(Validation),
+    /// VK requires the captcha, repeat the login later or from the browser
+    NeedCaptcha(Captcha),
+    /// Other OAuth errors, like `invalid_request`
+    OAuth {
+        error: String,
+        description: Option<String>,
+    },
+}
+
+impl Display for DirectAuthError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Request(e) => Display::fmt(e, f),
+            Self::InvalidClient(description) => write!(f, "invalid client: {description}"),
+            Self::NeedValidation(validation) => write!(
+                f,
+                "two-factor authentication {} is required",
+                validation.validation_type
+            ),
+            Self::NeedCaptcha(captcha) => write!(f, "captcha {} is required", captcha.sid),
+            Self::OAuth {
+                error,
+                description: Some(description),
+            } => write!(f, "oauth error {error}: {description}"),
+            Self::OAuth { error, .. } => write!(f, "oauth error {error}"),
+        }
+    }
+}
+
+impl Error for DirectAuthError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Request(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PasswordGrant<'a> {
+    grant_type: &'static str,
+    client_id: u64,
+    client_secret: &'a str,
+    username: &'a str,
+    password: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scope: Option<&'a str>,
+    #[serde(rename = "2fa_supported")]
+    two_factor_supported: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<&'a str>,
+    v: Version,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DirectResponse {
+    Error {
+        error: String,
+        #[serde(default)]
+        error_description: Option<String>,
+        #[serde(default)]
+        captcha_sid: Option<String>,
+        #[serde(default)]
+        captcha_img: Option<String>,
+        #[serde(flatten)]
+        validation: Validation,
+    },
+    Token(DirectToken),
+}
+
+impl DirectResponse {
+    fn into_result(self) -> Result<DirectToken, DirectAuthError> {
+        match self {
+            Self::Token(token) => Ok(token),
+            Self::Error {
+                error,
+                error_description,
+                captcha_sid,
+                captcha_img,
+                validation,
+            } => Err(match error.as_str() {
+                "invalid_client" => {
+                    DirectAuthError::InvalidClient(error_description.unwrap_or(error))
+                }
+                "need_validation" => DirectAuthError::NeedValidation(validation),
+                "need_captcha" => DirectAuthError::NeedCaptcha(Captcha {
+                    sid: captcha_sid.unwrap_or_default(),
+                    img: captcha_img.unwrap_or_default(),
+                }),
+                _ => DirectAuthError::OAuth {
+                    error,
+                    description: error_description,
+                },
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::direct_auth::{DirectAuth, DirectAuthError, DirectResponse};
+
+    fn parse(response: &str) -> Result<i64, DirectAuthError> {
+        serde_json::from_str::<DirectResponse>(response)
+            .unwrap()
+            .into_result()
+            .map(|token| token.user_id)
+    }
+
+    #[test]
+    fn typed_errors() {
+        assert_eq!(
+            parse(r#"{"access_token":"token","expires_in":0,"user_id":1}"#).unwrap(),
+            1
+        );
+        assert!(matches!(
+            parse(r#"{"error":"invalid_client","error_description":"Username or password is incorrect"}"#),
+            Err(DirectAuthError::InvalidClient(description)) if description.starts_with("Username")
+        ));
+        assert!(matches!(
+            parse(r#"{"error":"need_validation","validation_type":"2fa_sms","validation_sid":"sid","phone_mask":"+7 *** *** ** 00"}"#),
+            Err(DirectAuthError::NeedValidation(validation)) if validation.validation_type == "2fa_sms"
+        ));
+        assert!(matches!(
+            parse(r#"{"error":"need_captcha","captcha_sid":"1","captcha_img":"https://api.vk.com/captcha.php?sid=1"}"#),
+            Err(DirectAuthError::NeedCaptcha(captcha)) if captcha.sid == "1"
+        ));
+    }
+
+    #[test]
+    fn debug_without_secret() {
+        let auth = DirectAuth::with_client(2274003, "hJUa2k", reqwest::Client::new());
+        let debug = format!("{auth:?}");
+        assert!(debug.contains("2274003"));
+        assert!(!debug.contains("hJUa2k"));
+    }
+}
@@ -0,0 +1,144 @@
+//! # Document uploads
+//! Helpers, which chain the upload server request, the upload and the save of the documents.
+
+use crate::upload::{
+    uploaded_file, Doc, FileKind, FormBuilder, SaveDoc, SavedDoc, UploadError, UploadServer,
+    UploadSource, UploadStep,
+};
+use crate::VkApi;
+use serde::Serialize;
+
+/// Destination of the uploaded document.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DocTarget {
+    /// Messages of the peer, uploaded with [docs.getMessagesUploadServer](https://dev.vk.com/method/docs.getMessagesUploadServer)
+    Peer(i64),
+    /// Wall of the community, uploaded with [docs.getWallUploadServer](https://dev.vk.com/method/docs.getWallUploadServer)
+    Group(u64),
+}
+
+/// Uploads the document and saves it with [docs.save](https://dev.vk.com/method/docs.save).
+///
+/// The file name is used as the title, when the `title` is empty.
+/// The document is checked against the size limit of the documents before the upload server is requested.
+/// Use [`Doc::attachment`] to attach the saved document to the message or the post.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use vkclient::docs::DocTarget;
+/// use vkclient::upload::UploadSource;
+/// use vkclient::VkApi;
+/// # let access_token = String::new();
+/// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+///
+/// async move {
+///     let report = UploadSource::bytes("report.pdf", std::fs::read("report.pdf").unwrap());
+///     let doc = vkclient::docs::upload(&client, DocTarget::Peer(2000000001), report, "Report")
+///         .await
+///         .unwrap();
+///     println!("{}", doc.attachment());
+/// };
+/// ```
+pub async fn upload<T: Into<String>>(
+    api: &VkApi,
+    target: DocTarget,
+    source: UploadSource,
+    title: T,
+) -> Result<Doc, UploadError> {
+    upload_tagged(api, target, source, title.into(), "").await
+}
+
+/// Same as [`upload`], but saves the document with the comma separated `tags`.
+pub(crate) async fn upload_tagged(
+    api: &VkApi,
+    target: DocTarget,
+    source: UploadSource,
+    title: String,
+    tags: &str,
+) -> Result<Doc, UploadError> {
+    let upload_error = |error| UploadError {
+        step: UploadStep::Upload,
+        error,
+    };
+
+    source.check(FileKind::Doc).await.map_err(upload_error)?;
+
+    let server: Result<UploadServer, _> = match target {
+        DocTarget::Peer(peer_id) => {
+            api.send_request(
+                "docs.getMessagesUploadServer",
+                MessagesServer {
+                    peer_id,
+                    doc_type: "doc",
+                },
+            )
+            .await
+        }
+        DocTarget::Group(group_id) => {
+            api.send_request("docs.getWallUploadServer", WallServer { group_id })
+                .await
+        }
+    };
+    let server = server.map_err(|error| UploadError {
+        step: UploadStep::Server,
+        error,
+    })?;
+
+    let form = FormBuilder::new()
+        .with_source("file", source)
+        .build()
+        .await
+        .map_err(upload_error)?;
+    let file = api
+        .uploader()
+        .upload(&server.upload_url, form)
+        .await
+        .and_then(|response| uploaded_file(&response))
+        .map_err(upload_error)?;
+
+    let saved: SavedDoc = api
+        .send_request("docs.save", SaveDoc { file, title, tags })
+        .await
+        .map_err(|error| UploadError {
+            step: UploadStep::Save,
+            error,
+        })?;
+
+    Ok(saved.doc)
+}
+
+#[derive(Serialize)]
+struct MessagesServer {
+    peer_id: i64,
+    #[serde(rename = "type")]
+    doc_type: &'static str,
+}
+
+#[derive(Serialize)]
+struct WallServer {
+    group_id: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::docs::MessagesServer;
+    use crate::upload::SaveDoc;
+
+    #[test]
+    fn encode_doc_params() {
+        let server = serde_urlencoded::to_string(MessagesServer {
+            peer_id: 2000000001,
+            doc_type: "doc",
+        })
+        .unwrap();
+        assert_eq!(server, "peer_id=2000000001&type=doc");
+
+        let save = serde_urlencoded::to_string(SaveDoc {
+            file: "abc".to_owned(),
+            title: String::new(),
+            tags: "",
+        })
+        .unwrap();
+        assert_eq!(save, "file=abc");
+    }
+}
@@ -0,0 +1,519 @@
+mod cache;
+
+use crate::inner::{create_client, ClientConfig};
+use crate::{BuildError, VkApiError, VkApiResult};
+use bytes::Bytes;
+pub use cache::*;
+use cfg_if::cfg_if;
+use reqwest::header::{
+    HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_RANGE, ETAG, IF_RANGE, RANGE,
+};
+use reqwest::{Client, Response, StatusCode};
+use std::ffi::OsString;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::time::Instant;
+
+/// # Download files from VK servers
+/// Downloads the received photos, documents and other attachments by their urls.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use vkclient::VkApi;
+/// # let access_token = String::new();
+/// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+///
+/// let downloader = client.downloader().with_bandwidth_limit(1 << 20);
+///
+/// async {
+///     let size = downloader.download_to_path("https://...", "doc.pdf").await.unwrap();
+/// };
+/// ```
+///
+/// ```rust,no_run
+/// use vkclient::download::{MediaCache, MediaKey, VkDownloader};
+///
+/// let downloader = VkDownloader::new()
+///     .unwrap()
+///     .with_cache(MediaCache::new("media", 1 << 30).unwrap());
+///
+/// async {
+///     let mut photo = Vec::new();
+///     let key = MediaKey::new(1, 456239017).with_access_key("abc");
+///     downloader.download_attachment(&key, "https://...", &mut photo).await.unwrap();
+/// };
+/// ```
+#[derive(Clone, Debug)]
+pub struct VkDownloader {
+    client: Client,
+    bandwidth_limit: Option<u64>,
+    cache: Option<MediaCache>,
+}
+
+impl VkDownloader {
+    /// Creates the downloader with the own http client.
+    /// Use [`VkApi::downloader`](crate::VkApi::downloader) to share the connection pool of the api client.
+    pub fn new() -> Result<Self, BuildError> {
+        create_client(&ClientConfig::default())
+            .map(Self::from)
+            .map_err(BuildError::Client)
+    }
+
+    /// Limits the download speed by the bytes per second.
+    #[must_use]
+    pub const fn with_bandwidth_limit(mut self, bytes_per_second: u64) -> Self {
+        self.bandwidth_limit = Some(bytes_per_second);
+        self
+    }
+
+    /// Pass the cache for [`VkDownloader::download_attachment`].
+    #[must_use]
+    pub fn with_cache(mut self, cache: MediaCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Downloads the attachment to the writer.
+    /// With the [`MediaCache`] the attachment is downloaded once and repeated calls are served from the disk.
+    /// Returns the count of written bytes.
+    pub async fn download_attachment<U: AsRef<str> + Send, W: AsyncWrite + Unpin>(
+        &self,
+        key: &MediaKey,
+        url: U,
+        writer: &mut W,
+    ) -> VkApiResult<u64> {
+        let Some(cache) = &self.cache else {
+            return self.download(url, writer).await;
+        };
+
+        let path = if let Some(path) = cache.get(key).await {
+            path
+        } else {
+            let _download = cache.lock_download(key).await;
+            // the attachment may be cached by the concurrent download, which was waited
+            if let Some(path) = cache.get(key).await {
+                path
+            } else {
+                let partial = cache.partial_path(key);
+                self.download_to_path(url, &partial).await?;
+                cache.insert(key, &partial).await.map_err(VkApiError::IO)?
+            }
+        };
+
+        let mut file = File::open(path).await.map_err(VkApiError::IO)?;
+        let written = tokio::io::copy(&mut file, writer)
+            .await
+            .map_err(VkApiError::IO)?;
+        writer.flush().await.map_err(VkApiError::IO)?;
+        Ok(written)
+    }
+
+    /// Downloads the file to the writer. Compressed responses are decompressed while they are read.
+    /// Returns the count of written bytes.
+    pub async fn download<U: AsRef<str> + Send, W: AsyncWrite + Unpin>(
+        &self,
+        url: U,
+        writer: &mut W,
+    ) -> VkApiResult<u64> {
+        cfg_if! {
+            if #[cfg(all(feature = "compression_zstd", feature = "compression_gzip"))] {
+                let encoding = "zstd, gzip";
+            } else if #[cfg(feature = "compression_zstd")] {
+                let encoding = "zstd";
+            } else if #[cfg(feature = "compression_gzip")] {
+                let encoding = "gzip";
+            } else {
+                let encoding = "identity";
+            }
+        }
+
+        let response = self
+            .client
+            .get(url.as_ref())
+            .header(ACCEPT_ENCODING, encoding)
+            .send()
+            .await
+            .and_then(Response::error_for_status)
+            .map_err(VkApiError::Request)?;
+
+        let mut decoder = Decoder::new(response.headers().get(CONTENT_ENCODING))?;
+        let written = self.read_body(response, &mut decoder, writer).await?;
+        writer.flush().await.map_err(VkApiError::IO)?;
+        Ok(written)
+    }
+
+    /// Downloads the file to the path.
+    /// `ETag` of the file is stored next to it until the download is complete, so the interrupted download
+    /// is resumed from the end of the file with the ranged request. The file is downloaded from the start,
+    /// if it has been changed on the server or it has no `ETag`.
+    /// Returns the size of the file.
+    pub async fn download_to_path<U: AsRef<str> + Send, P: AsRef<Path>>(
+        &self,
+        url: U,
+        path: P,
+    ) -> VkApiResult<u64> {
+        let path = path.as_ref();
+        let etag_path = etag_path(path);
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .map_err(VkApiError::IO)?;
+        let etag = tokio::fs::read_to_string(&etag_path).await.ok();
+        let offset = match etag {
+            Some(_) => file.metadata().await.map_err(VkApiError::IO)?.len(),
+            None => 0,
+        };
+
+        let mut request = self
+            .client
+            .get(url.as_ref())
+            .header(ACCEPT_ENCODING, "identity");
+        if let Some(etag) = etag.as_deref().filter(|_| offset > 0) {
+            request = request
+                .header(RANGE, format!("bytes={offset}-"))
+                .header(IF_RANGE, etag);
+        }
+
+        let response = request.send().await.map_err(VkApiError::Request)?;
+        let offset = match response.status() {
+            // the file was downloaded, but its ETag wasn't removed
+            StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => {
+                remove_etag(&etag_path).await.map_err(VkApiError::IO)?;
+                return Ok(offset);
+            }
+            StatusCode::PARTIAL_CONTENT => {
+                if content_range_start(response.headers().get(CONTENT_RANGE)) != Some(offset) {
+                    return Err(VkApiError::IO(Error::new(
+                        ErrorKind::InvalidData,
+                        "content range doesn't start at the end of the file",
+                    )));
+                }
+                offset
+            }
+            _ => {
+                response
+                    .error_for_status_ref()
+                    .map_err(VkApiError::Request)?;
+                // server ignored the range or the file was changed, so it's downloaded from the start
+                file.set_len(0).await.map_err(VkApiError::IO)?;
+                match strong_etag(response.headers().get(ETAG)) {
+                    Some(etag) => tokio::fs::write(&etag_path, etag).await,
+                    None => remove_etag(&etag_path).await,
+                }
+                .map_err(VkApiError::IO)?;
+                0
+            }
+        };
+
+        let written = self
+            .read_body(response, &mut Decoder::Identity, &mut file)
+            .await?;
+        file.flush().await.map_err(VkApiError::IO)?;
+        remove_etag(&etag_path).await.map_err(VkApiError::IO)?;
+
+        Ok(offset + written)
+    }
+
+    /// Passes the response chunks through the decoder to the writer, keeping the bandwidth limit.
+    /// Returns the count of written bytes.
+    async fn read_body<W: AsyncWrite + Unpin>(
+        &self,
+        mut response: Response,
+        decoder: &mut Decoder,
+        writer: &mut W,
+    ) -> VkApiResult<u64> {
+        let started = Instant::now();
+        let mut read = 0_u64;
+        let mut written = 0_u64;
+
+        while let Some(chunk) = response.chunk().await.map_err(VkApiError::Request)? {
+            read += chunk.len() as u64;
+            let chunk = decoder.decode(chunk).map_err(VkApiError::IO)?;
+            writer.write_all(&chunk).await.map_err(VkApiError::IO)?;
+            written += chunk.len() as u64;
+
+            if let Some(limit) = self.bandwidth_limit.filter(|l| *l > 0) {
+                let expected = Duration::from_secs_f64(read as f64 / limit as f64);
+                if let Some(delay) = expected.checked_sub(started.elapsed()) {
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+
+        let rest = decoder.finish().map_err(VkApiError::IO)?;
+        writer.write_all(&rest).await.map_err(VkApiError::IO)?;
+        Ok(written + rest.len() as u64)
+    }
+}
+
+impl From<Client> for VkDownloader {
+    fn from(client: Client) -> Self {
+        Self {
+            client,
+            bandwidth_limit: None,
+            cache: None,
+        }
+    }
+}
+
+/// Decompresses the response by its chunks, so the body isn't buffered in the memory.
+enum Decoder {
+    #[cfg(feature = "compression_zstd")]
+    Zstd(Box<zstd::stream::write::Decoder<'static, Vec<u8>>>),
+    #[cfg(feature = "compression_gzip")]
+    Gzip(Box<flate2::write::GzDecoder<Vec<u8>>>),
+    Identity,
+}
+
+impl Decoder {
+    fn new(content_encoding: Option<&HeaderValue>) -> VkApiResult<Self> {
+        match content_encoding {
+            #[cfg(feature = "compression_zstd")]
+            Some(v) if v == "zstd" => zstd::stream::write::Decoder::new(Vec::new())
+                .map(|decoder| Self::Zstd(Box::new(decoder)))
+                .map_err(VkApiError::IO),
+            #[cfg(feature = "compression_gzip")]
+            Some(v) if v == "gzip" => Ok(Self::Gzip(Box::new(flate2::write::GzDecoder::new(
+                Vec::new(),
+            )))),
+            _ => Ok(Self::Identity),
+        }
+    }
+
+    /// Returns the decompressed bytes of the chunk.
+    fn decode(&mut self, chunk: Bytes) -> std::io::Result<Bytes> {
+        match self {
+            #[cfg(feature = "compression_zstd")]
+            Self::Zstd(decoder) => {
+                std::io::Write::write_all(decoder, &chunk)?;
+                std::io::Write::flush(decoder)?;
+                Ok(Bytes::from(std::mem::take(decoder.get_mut())))
+            }
+            #[cfg(feature = "compression_gzip")]
+            Self::Gzip(decoder) => {
+                std::io::Write::write_all(decoder, &chunk)?;
+                std::io::Write::flush(decoder)?;
+                Ok(Bytes::from(std::mem::take(decoder.get_mut())))
+            }
+            Self::Identity => Ok(chunk),
+        }
+    }
+
+    /// Returns the rest of the decompressed bytes after the last chunk.
+    fn finish(&mut self) -> std::io::Result<Bytes> {
+        match self {
+            #[cfg(feature = "compression_zstd")]
+            Self::Zstd(decoder) => {
+                std::io::Write::flush(decoder)?;
+                Ok(Bytes::from(std::mem::take(decoder.get_mut())))
+            }
+            #[cfg(feature = "compression_gzip")]
+            Self::Gzip(decoder) => {
+                decoder.try_finish()?;
+                Ok(Bytes::from(std::mem::take(decoder.get_mut())))
+            }
+            Self::Identity => Ok(Bytes::new()),
+        }
+    }
+}
+
+/// Returns the path of the `ETag` of the downloading file.
+fn etag_path(path: &Path) -> PathBuf {
+    let mut etag_path = OsString::from(path.as_os_str());
+    etag_path.push(".etag");
+    PathBuf::from(etag_path)
+}
+
+async fn remove_etag(path: &Path) -> std::io::Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// Returns the `ETag`, which can be passed to the `If-Range`. Weak `ETags` can't be used for the ranges.
+fn strong_etag(etag: Option<&HeaderValue>) -> Option<&str> {
+    etag.and_then(|etag| etag.to_str().ok())
+        .filter(|etag| etag.starts_with('"'))
+}
+
+/// Returns the first byte of the `Content-Range: bytes 100-199/200` header.
+fn content_range_start(content_range: Option<&HeaderValue>) -> Option<u64> {
+    content_range?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes ")?
+        .split_once('-')?
+        .0
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::download::{etag_path, VkDownloader};
+    use std::time::{Duration, Instant};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serves the requests of the test with the responses of the handler, which gets the request head.
+    async fn serve<F: Fn(&str) -> Vec<u8> + Send + 'static>(handler: F) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/file", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut head = Vec::new();
+                while !head.ends_with(b"\r\n\r\n") {
+                    let mut byte = [0; 1];
+                    if socket.read(&mut byte).await.unwrap_or(0) == 0 {
+                        break;
+                    }
+                    head.extend_from_slice(&byte);
+                }
+                let head = String::from_utf8_lossy(&head).to_lowercase();
+                let _ = socket.write_all(&handler(&head)).await;
+            }
+        });
+
+        url
+    }
+
+    fn response(status: &str, headers: &[&str], body: &[u8]) -> Vec<u8> {
+        let mut response = format!(
+            "HTTP/1.1 {status}\r\ncontent-length: {}\r\nconnection: close\r\n",
+            body.len()
+        );
+        for header in headers {
+            response.push_str(header);
+            response.push_str("\r\n");
+        }
+        response.push_str("\r\n");
+
+        let mut response = response.into_bytes();
+        response.extend_from_slice(body);
+        response
+    }
+
+    #[tokio::test]
+    async fn resume_download_with_range() {
+        let url = serve(|head| {
+            match (
+                head.contains("range: bytes=5-"),
+                head.contains(r#"if-range: "v1""#),
+            ) {
+                (true, true) => response(
+                    "206 Partial Content",
+                    &["content-range: bytes 5-10/11", r#"etag: "v1""#],
+                    b" world",
+                ),
+                _ => response("200 OK", &[r#"etag: "v2""#], b"HELLO WORLD"),
+            }
+        })
+        .await;
+
+        let dir = std::env::temp_dir().join(format!("vkclient-download-{}", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let path = dir.join("file.txt");
+        let downloader = VkDownloader::from(reqwest::Client::new());
+
+        tokio::fs::write(&path, "hello").await.unwrap();
+        tokio::fs::write(etag_path(&path), r#""v1""#).await.unwrap();
+        assert_eq!(downloader.download_to_path(&url, &path).await.unwrap(), 11);
+        assert_eq!(tokio::fs::read(&path).await.unwrap(), b"hello world");
+        assert!(!etag_path(&path).exists());
+
+        // the file was changed on the server
+        tokio::fs::write(&path, "hello").await.unwrap();
+        tokio::fs::write(etag_path(&path), r#""v0""#).await.unwrap();
+        assert_eq!(downloader.download_to_path(&url, &path).await.unwrap(), 11);
+        assert_eq!(tokio::fs::read(&path).await.unwrap(), b"HELLO WORLD");
+
+        // the range of the server doesn't continue the file
+        let url = serve(|_| {
+            response(
+                "206 Partial Content",
+                &["content-range: bytes 0-10/11"],
+                b"hello world",
+            )
+        })
+        .await;
+        tokio::fs::write(&path, "hello").await.unwrap();
+        tokio::fs::write(etag_path(&path), r#""v1""#).await.unwrap();
+        assert!(downloader.download_to_path(&url, &path).await.is_err());
+        assert_eq!(tokio::fs::read(&path).await.unwrap(), b"hello");
+
+        tokio::fs::remove_dir_all(dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn keep_bandwidth_limit() {
+        let url = serve(|_| response("200 OK", &[], &[b'a'; 2000])).await;
+        let downloader = VkDownloader::from(reqwest::Client::new()).with_bandwidth_limit(10_000);
+
+        let started = Instant::now();
+        let mut body = Vec::new();
+        assert_eq!(downloader.download(&url, &mut body).await.unwrap(), 2000);
+        assert_eq!(body.len(), 2000);
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[cfg(feature = "compression_gzip")]
+    #[tokio::test]
+    async fn decompress_by_chunks() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        encoder.write_all(&[b'z'; 100_000]).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let url =
+            serve(move |_| response("200 OK", &["content-encoding: gzip"], &compressed)).await;
+        let downloader = VkDownloader::from(reqwest::Client::new());
+
+        let mut body = Vec::new();
+        assert_eq!(downloader.download(&url, &mut body).await.unwrap(), 100_000);
+        assert!(body.iter().all(|byte| *byte == b'z'));
+    }
+
+    #[tokio::test]
+    async fn download_attachment_once() {
+        use crate::download::{MediaCache, MediaKey};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&requests);
+        let url = serve(move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            response("200 OK", &[], &[b'a'; 2000])
+        })
+        .await;
+
+        let dir = std::env::temp_dir().join(format!("vkclient-attachment-{}", std::process::id()));
+        let downloader = VkDownloader::from(reqwest::Client::new())
+            .with_bandwidth_limit(20_000)
+            .with_cache(MediaCache::new(&dir, 1 << 20).unwrap());
+        let key = MediaKey::new(1, 1);
+
+        // the second download waits for the first one and is served from the cache
+        let (mut first, mut second) = (Vec::new(), Vec::new());
+        let (first_written, second_written) = tokio::join!(
+            downloader.download_attachment(&key, &url, &mut first),
+            downloader.download_attachment(&key, &url, &mut second),
+        );
+        assert_eq!(first_written.unwrap(), 2000);
+        assert_eq!(second_written.unwrap(), 2000);
+        assert_eq!(first, [b'a'; 2000]);
+        assert_eq!(second, [b'a'; 2000]);
+        assert_eq!(requests.load(Ordering::Relaxed), 1);
+
+        tokio::fs::remove_dir_all(dir).await.unwrap();
+    }
+}
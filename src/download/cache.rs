@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use std::io::{self, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tokio::sync::OwnedMutexGuard;
+
+/// Directory of the files, which are named by the SHA-256 of their content.
+const OBJECTS_DIR: &str = "objects";
+/// Directory of the keys, which contain the content hash of the attachment.
+const KEYS_DIR: &str = "keys";
+/// Directory of the unfinished downloads.
+const PARTIAL_DIR: &str = "partial";
+
+/// Key of the cached attachment.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct MediaKey {
+    pub owner_id: i64,
+    pub id: i64,
+    pub access_key: Option<String>,
+}
+
+impl MediaKey {
+    #[must_use]
+    pub const fn new(owner_id: i64, id: i64) -> Self {
+        Self {
+            owner_id,
+            id,
+            access_key: None,
+        }
+    }
+
+    /// Pass the access key of the private attachment.
+    #[must_use]
+    pub fn with_access_key<T: Into<String>>(mut self, access_key: T) -> Self {
+        self.access_key = Some(access_key.into());
+        self
+    }
+
+    /// Returns the file name of the attachment key in the cache.
+    /// Access key is hashed to keep it out of the file system.
+    fn file_name(&self) -> String {
+        match &self.access_key {
+            None => format!("{}_{}", self.owner_id, self.id),
+            Some(access_key) => format!("{}_{}_{:016x}", self.owner_id, self.id, fnv1a(access_key)),
+        }
+    }
+}
+
+/// Stable between the builds hash, unlike the std hashers.
+fn fnv1a(value: &str) -> u64 {
+    value.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// # On-disk cache of the downloaded attachments
+/// Files are stored in the directory by the SHA-256 of their content, so the same file of the different
+/// [`MediaKey`]s, like the forwarded photo, is stored once.
+/// When the cache grows over the max size, the least recently used files are removed.
+/// Usage is tracked by the cache and its clones, the files unused since the start are ordered by their download time.
+/// Downloads of the same key by the cache and its clones are serialized, so they don't write the same partial file.
+#[derive(Debug, Clone)]
+pub struct MediaCache {
+    dir: PathBuf,
+    max_size: u64,
+    used: Arc<Mutex<HashMap<String, SystemTime>>>,
+    downloads: Arc<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>>,
+}
+
+impl MediaCache {
+    /// Creates the cache in the directory with the max size in bytes.
+    pub fn new<P: Into<PathBuf>>(dir: P, max_size: u64) -> io::Result<Self> {
+        let dir = dir.into();
+        for subdir in [OBJECTS_DIR, KEYS_DIR, PARTIAL_DIR] {
+            std::fs::create_dir_all(dir.join(subdir))?;
+        }
+        Ok(Self {
+            dir,
+            max_size,
+            used: Arc::default(),
+            downloads: Arc::default(),
+        })
+    }
+
+    /// Returns the path of the cached attachment if it exists and marks it as recently used.
+    pub async fn get(&self, key: &MediaKey) -> Option<PathBuf> {
+        let key_path = self.dir.join(KEYS_DIR).join(key.file_name());
+        let hash = tokio::fs::read_to_string(&key_path).await.ok()?;
+
+        let path = self.dir.join(OBJECTS_DIR).join(&hash);
+        if !tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            // the file was evicted
+            let _ = tokio::fs::remove_file(key_path).await;
+            return None;
+        }
+
+        self.used
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .insert(hash, SystemTime::now());
+        Some(path)
+    }
+
+    /// Waits for the concurrent download of the attachment and locks the key for the own download.
+    pub(crate) async fn lock_download(&self, key: &MediaKey) -> OwnedMutexGuard<()> {
+        let lock = {
+            let mut downloads = self
+                .downloads
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            // locks of the finished downloads are referenced by the map only
+            downloads.retain(|_, lock| Arc::strong_count(lock) > 1);
+            Arc::clone(downloads.entry(key.file_name()).or_default())
+        };
+        lock.lock_owned().await
+    }
+
+    /// Returns the path of the temporary file for the attachment download.
+    pub(crate) fn partial_path(&self, key: &MediaKey) -> PathBuf {
+        self.dir.join(PARTIAL_DIR).join(key.file_name())
+    }
+
+    /// Moves the downloaded file to the cache by the hash of its content and evicts the old files.
+    pub(crate) async fn insert(&self, key: &MediaKey, downloaded: &Path) -> io::Result<PathBuf> {
+        let downloaded = downloaded.to_owned();
+        let hash =
+            tokio::task::spawn_blocking(move || sha256_file(&downloaded).map(|h| (h, downloaded)))
+                .await
+                .map_err(io::Error::other)?;
+        let (hash, downloaded) = hash?;
+
+        let path = self.dir.join(OBJECTS_DIR).join(&hash);
+        if tokio::fs::try_exists(&path).await? {
+            tokio::fs::remove_file(&downloaded).await?;
+        } else {
+            tokio::fs::rename(&downloaded, &path).await?;
+        }
+        tokio::fs::write(self.dir.join(KEYS_DIR).join(key.file_name()), &hash).await?;
+
+        self.used
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .insert(hash, SystemTime::now());
+        self.evict(&path).await?;
+        Ok(path)
+    }
+
+    /// Removes the least recently used files, until the cache fits the max size.
+    /// The `keep` file is never removed.
+    async fn evict(&self, keep: &Path) -> io::Result<()> {
+        let mut files = Vec::new();
+        let mut size = 0;
+
+        let mut entries = tokio::fs::read_dir(self.dir.join(OBJECTS_DIR)).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            if !metadata.is_file() {
+                continue;
+            }
+
+            size += metadata.len();
+            files.push((
+                metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                metadata.len(),
+                entry.path(),
+            ));
+        }
+
+        {
+            let used = self
+                .used
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            for (modified, _, path) in &mut files {
+                let last_use = path.file_name().and_then(|hash| used.get(hash.to_str()?));
+                if let Some(last_use) = last_use {
+                    *modified = *last_use;
+                }
+            }
+        }
+        files.sort_unstable_by_key(|(modified, _, _)| *modified);
+
+        for (_, len, path) in files {
+            if size <= self.max_size {
+                break;
+            }
+            if path == keep {
+                continue;
+            }
+
+            match tokio::fs::remove_file(&path).await {
+                Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+                _ => size -= len,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the hex SHA-256 of the file content.
+fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut context = ring::digest::Context::new(&ring::digest::SHA256);
+    let mut buffer = vec![0; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        match buffer.get(..read) {
+            Some([]) | None => break,
+            Some(chunk) => context.update(chunk),
+        }
+    }
+
+    Ok(context
+        .finish()
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::download::{MediaCache, MediaKey};
+    use std::time::{Duration, SystemTime};
+
+    #[tokio::test]
+    async fn evict_least_recently_used() {
+        let dir = std::env::temp_dir().join(format!("vkclient-cache-{}", std::process::id()));
+        let cache = MediaCache::new(&dir, 8).unwrap();
+
+        let old = MediaKey::new(1, 1);
+        let forwarded = MediaKey::new(2, 1);
+        let new = MediaKey::new(1, 2).with_access_key("secret");
+        assert!(cache.get(&old).await.is_none());
+
+        tokio::fs::write(cache.partial_path(&old), "12345")
+            .await
+            .unwrap();
+        let path = cache.insert(&old, &cache.partial_path(&old)).await.unwrap();
+        assert!(cache.get(&old).await.is_some());
+
+        // the same content is stored once
+        tokio::fs::write(cache.partial_path(&forwarded), "12345")
+            .await
+            .unwrap();
+        let forwarded_path = cache
+            .insert(&forwarded, &cache.partial_path(&forwarded))
+            .await
+            .unwrap();
+        assert_eq!(path, forwarded_path);
+        cache.used.lock().unwrap().insert(
+            path.file_name().unwrap().to_str().unwrap().to_owned(),
+            SystemTime::now() - Duration::from_secs(60),
+        );
+
+        tokio::fs::write(cache.partial_path(&new), "67890")
+            .await
+            .unwrap();
+        cache.insert(&new, &cache.partial_path(&new)).await.unwrap();
+
+        assert!(cache.get(&old).await.is_none());
+        assert!(cache.get(&forwarded).await.is_none());
+        assert!(cache.get(&new).await.is_some());
+
+        tokio::fs::remove_dir_all(dir).await.unwrap();
+    }
+}
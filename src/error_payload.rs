@@ -0,0 +1,99 @@
+//! Decoding of the json VK error payloads, which works regardless of the response encoding,
+//! so the errors are decoded even if the response encoding isn't the one of the client,
+//! like the json error of an intermediary proxy for the msgpack only client.
+//! Without the `encode_json` feature the payloads aren't decoded.
+
+#[cfg(feature = "encode_json")]
+use crate::vkapi::RequestParam;
+use crate::VkError;
+#[cfg(feature = "encode_json")]
+use serde_json::Value;
+
+#[cfg(feature = "encode_json")]
+#[derive(serde::Deserialize)]
+struct Payload {
+    error: Value,
+}
+
+/// Returns the string or the number as string, like VK sends the ids.
+#[cfg(feature = "encode_json")]
+fn to_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Returns the error of the `{"error": {...}}` json payload, or `None` if it's not the VK error.
+#[cfg(feature = "encode_json")]
+pub fn parse_error(body: &[u8]) -> Option<VkError> {
+    let Payload { error } = serde_json::from_slice(body).ok()?;
+    let code = i16::try_from(error.get("error_code")?.as_i64()?).ok()?;
+    let message = error
+        .get("error_msg")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+
+    let text = |field: &str| error.get(field).and_then(to_text);
+    let request_params = match error.get("request_params") {
+        Some(Value::Array(params)) => params
+            .iter()
+            .filter_map(|param| {
+                Some(RequestParam {
+                    key: to_text(param.get("key")?)?,
+                    value: to_text(param.get("value")?)?,
+                })
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Some(VkError {
+        request_params,
+        captcha_sid: text("captcha_sid"),
+        captcha_img: text("captcha_img"),
+        ..VkError::new(code, message)
+    })
+}
+
+#[cfg(not(feature = "encode_json"))]
+pub const fn parse_error(_body: &[u8]) -> Option<VkError> {
+    None
+}
+
+/// Returns the text of the `{"error": "..."}` json payload of the upload servers.
+#[cfg(all(feature = "uploader", feature = "encode_json"))]
+pub fn parse_upload_error(body: &[u8]) -> Option<String> {
+    match serde_json::from_slice::<Payload>(body).ok()?.error {
+        Value::String(error) => Some(error),
+        error => to_text(error.get("error_msg")?),
+    }
+}
+
+#[cfg(all(feature = "uploader", not(feature = "encode_json")))]
+pub const fn parse_upload_error(_body: &[u8]) -> Option<String> {
+    None
+}
+
+#[cfg(all(test, feature = "encode_json"))]
+mod tests {
+    use crate::error_payload::parse_error;
+
+    #[test]
+    fn parse_error_payloads() {
+        let error = parse_error(
+            br#"{"error":{"error_code":14,"error_msg":"Captcha needed \u0441\ud83d\ude00","request_params":[{"key":"method","value":"wall.post"},{"key":"v","value":5.199}],"captcha_sid":"123","captcha_img":"https:\/\/vk.com\/captcha.php"}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(error.code(), 14);
+        assert_eq!(error.message(), "Captcha needed с😀");
+        assert_eq!(error.request_params()[1].value, "5.199");
+        assert_eq!(error.captcha().unwrap().sid, "123");
+
+        assert!(parse_error(br#"{"response":[1]}"#).is_none());
+        assert!(parse_error(br#"{"error":{"error_code":5}"#).is_none());
+        assert!(parse_error(&[b'['; 100]).is_none());
+    }
+}
@@ -0,0 +1,133 @@
+//! # Execute based batch helpers
+//! VK lacks batch variants of some methods, so these helpers pack up to 25 calls
+//! into one [execute](https://dev.vk.com/method/execute) request.
+
+use crate::{BulkResult, VkApi};
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+
+/// Maximum count of the API calls in one execute request.
+pub const MAX_EXECUTE_CALLS: usize = 25;
+
+/// Object found by [utils.resolveScreenName](https://dev.vk.com/method/utils.resolveScreenName).
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ResolvedScreenName {
+    #[serde(rename = "type")]
+    pub kind: ScreenNameType,
+    pub object_id: i64,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenNameType {
+    User,
+    Group,
+    Application,
+    #[serde(other)]
+    Unknown,
+}
+
+/// VK returns an empty array for the unknown screen names.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MaybeResolved {
+    Found(ResolvedScreenName),
+    Missing(IgnoredAny),
+}
+
+#[derive(Serialize)]
+struct Execute {
+    code: String,
+}
+
+impl VkApi {
+    /// Resolves many screen names with the batched
+    /// [utils.resolveScreenName](https://dev.vk.com/method/utils.resolveScreenName) calls.
+    /// Unknown screen names are absent in the result.
+    /// Resolving continues after the failed batch, its screen names are returned in [`BulkResult::failed`].
+    ///
+    /// ## Usage:
+    /// ```rust,no_run
+    /// use vkclient::VkApi;
+    /// # let access_token = String::new();
+    /// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+    ///
+    /// async {
+    ///     let resolved = client.resolve_screen_names(vec!["durov".to_owned(), "apiclub".to_owned()]).await;
+    ///     let resolved: std::collections::HashMap<_, _> = resolved.into_result().unwrap().into_iter().collect();
+    /// };
+    /// ```
+    pub async fn resolve_screen_names(
+        &self,
+        screen_names: Vec<String>,
+    ) -> BulkResult<(String, ResolvedScreenName), Vec<String>> {
+        let mut resolved = BulkResult::new();
+
+        for batch in screen_names.chunks(MAX_EXECUTE_CALLS) {
+            let code = execute_code(batch.iter().map(|screen_name| {
+                format!(
+                    "API.utils.resolveScreenName({{\"screen_name\":{}}})",
+                    quote(screen_name)
+                )
+            }));
+
+            let results: Vec<MaybeResolved> =
+                match self.send_request("execute", Execute { code }).await {
+                    Ok(results) => results,
+                    Err(e) => {
+                        resolved.failed.push((batch.to_vec(), e));
+                        continue;
+                    }
+                };
+
+            for (screen_name, result) in batch.iter().zip(results) {
+                if let MaybeResolved::Found(object) = result {
+                    resolved.succeeded.push((screen_name.clone(), object));
+                }
+            }
+        }
+
+        resolved
+    }
+}
+
+/// Returns the `VKScript`, which returns the array of the calls results.
+fn execute_code<I: IntoIterator<Item = String>>(calls: I) -> String {
+    format!(
+        "return [{}];",
+        calls.into_iter().collect::<Vec<_>>().join(",")
+    )
+}
+
+/// Returns the `VKScript` string literal.
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::execute::{execute_code, quote};
+
+    #[test]
+    fn generate_code() {
+        assert_eq!(quote(r#"a"b\"#), r#""a\"b\\""#);
+        assert_eq!(
+            execute_code(["API.a()".to_owned(), "API.b()".to_owned()]),
+            "return [API.a(),API.b()];"
+        );
+    }
+}
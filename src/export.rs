@@ -0,0 +1,231 @@
+//! # Collections export
+//! Writes the paginated collections, like the community members, the messages history or the wall posts,
+//! to NDJSON with the constant memory. Pages are requested by the client, so they pass its rate limit and retries.
+
+use crate::{VkApi, VkApiError, VkApiResult};
+use futures_util::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// Progress of the export, which is reported after the flush of the written items.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Offset of the next item, pass it to [`pages`] to resume the export
+    pub offset: usize,
+    /// Items written by the export
+    pub items: u64,
+    /// Bytes written by the export
+    pub bytes: u64,
+}
+
+/// Returns the stream of the items of the method with the `offset` and `count` params,
+/// which responds with the `count` and the `items` fields, like
+/// [groups.getMembers](https://dev.vk.com/method/groups.getMembers),
+/// [messages.getHistory](https://dev.vk.com/method/messages.getHistory) or
+/// [wall.get](https://dev.vk.com/method/wall.get).
+/// Pages are requested lazily from the `offset` until the items run out or an error occurs.
+pub fn pages<I, P>(
+    client: &VkApi,
+    method: &'static str,
+    params: P,
+    page_size: usize,
+    offset: usize,
+) -> impl Stream<Item = VkApiResult<I>>
+where
+    I: DeserializeOwned,
+    P: Serialize + Send + Sync,
+{
+    let client = client.clone();
+
+    async_stream::stream! {
+        let mut offset = offset;
+
+        loop {
+            let page: VkApiResult<Page<I>> = client
+                .send_request(
+                    method,
+                    PageParams {
+                        params: &params,
+                        offset,
+                        count: page_size,
+                    },
+                )
+                .await;
+
+            let Page { count: total, items } = match page {
+                Ok(page) => page,
+                Err(e) => {
+                    yield Err(e);
+                    break;
+                }
+            };
+
+            if items.is_empty() {
+                break;
+            }
+            offset += items.len();
+
+            for item in items {
+                yield Ok(item);
+            }
+
+            if offset >= total {
+                break;
+            }
+        }
+    }
+}
+
+/// Writes the items of the stream to the writer as NDJSON, one item per line.
+///
+/// The writer is flushed and the checkpoint is reported after every `every` items, at the end
+/// and before returning the error of the stream, so the export can be resumed from the last checkpoint.
+/// `offset` is the offset of the first item of the stream, it's reported in the checkpoints.
+/// Pass zero `every` to report the checkpoint at the end only.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use vkclient::export::{pages, write_ndjson};
+/// use vkclient::VkApi;
+/// # let access_token = String::new();
+/// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+///
+/// #[derive(serde::Serialize)]
+/// struct Members {
+///     group_id: u64,
+/// }
+///
+/// async move {
+///     let members = pages::<serde_json::Value, _>(&client, "groups.getMembers", Members { group_id: 1 }, 1000, 0);
+///     let mut file = tokio::fs::File::create("members.ndjson").await.unwrap();
+///
+///     let done = write_ndjson(members, &mut file, 0, 10_000, |checkpoint| {
+///         println!("{} members exported", checkpoint.items);
+///     })
+///     .await
+///     .unwrap();
+/// };
+/// ```
+pub async fn write_ndjson<S, I, W, F>(
+    stream: S,
+    writer: &mut W,
+    offset: usize,
+    every: u64,
+    mut on_checkpoint: F,
+) -> VkApiResult<Checkpoint>
+where
+    S: Stream<Item = VkApiResult<I>>,
+    I: Serialize,
+    W: AsyncWrite + Unpin,
+    F: FnMut(Checkpoint),
+{
+    let mut stream = std::pin::pin!(stream);
+    let mut checkpoint = Checkpoint {
+        offset,
+        ..Checkpoint::default()
+    };
+    let mut line = Vec::new();
+
+    while let Some(item) = stream.next().await {
+        let item = match item {
+            Ok(item) => item,
+            Err(e) => {
+                writer.flush().await.map_err(VkApiError::IO)?;
+                on_checkpoint(checkpoint);
+                return Err(e);
+            }
+        };
+
+        line.clear();
+        serde_json::to_writer(&mut line, &item)
+            .map_err(|e| VkApiError::IO(std::io::Error::from(e)))?;
+        line.push(b'\n');
+        writer.write_all(&line).await.map_err(VkApiError::IO)?;
+
+        checkpoint.offset += 1;
+        checkpoint.items += 1;
+        checkpoint.bytes += line.len() as u64;
+
+        if checkpoint.items.is_multiple_of(every) {
+            writer.flush().await.map_err(VkApiError::IO)?;
+            on_checkpoint(checkpoint);
+        }
+    }
+
+    writer.flush().await.map_err(VkApiError::IO)?;
+    on_checkpoint(checkpoint);
+    Ok(checkpoint)
+}
+
+#[derive(Serialize)]
+struct PageParams<'a, P> {
+    #[serde(flatten)]
+    params: &'a P,
+    offset: usize,
+    count: usize,
+}
+
+#[derive(Deserialize)]
+struct Page<I> {
+    count: usize,
+    items: Vec<I>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::{write_ndjson, Checkpoint, PageParams};
+    use crate::VkApiError;
+
+    #[tokio::test]
+    async fn export_ndjson() {
+        let items = futures_util::stream::iter([Ok(1), Ok(2), Ok(3)]);
+        let mut output = Vec::new();
+        let mut checkpoints = Vec::new();
+
+        let done = write_ndjson(items, &mut output, 10, 2, |checkpoint| {
+            checkpoints.push(checkpoint.offset)
+        })
+        .await
+        .unwrap();
+        assert_eq!(output, b"1\n2\n3\n");
+        assert_eq!(
+            done,
+            Checkpoint {
+                offset: 13,
+                items: 3,
+                bytes: 6
+            }
+        );
+        assert_eq!(checkpoints, [12, 13]);
+
+        let items = futures_util::stream::iter([
+            Ok(1),
+            Err(VkApiError::ResponseDeserialize(
+                crate::ResponseDeserialize::BadEncoding,
+            )),
+        ]);
+        let mut output = Vec::new();
+        let mut last = None;
+        let result = write_ndjson(items, &mut output, 0, 5, |checkpoint| {
+            last = Some(checkpoint)
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(output, b"1\n");
+        assert_eq!(last.map(|checkpoint| checkpoint.offset), Some(1));
+
+        #[derive(serde::Serialize)]
+        struct Wall {
+            owner_id: i64,
+        }
+
+        let params = serde_urlencoded::to_string(PageParams {
+            params: &Wall { owner_id: -1 },
+            offset: 100,
+            count: 50,
+        })
+        .unwrap();
+        assert_eq!(params, "owner_id=-1&offset=100&count=50");
+    }
+}
@@ -0,0 +1,111 @@
+//! Compatibility tests of the typed models with the recorded and sanitized VK payloads from the `fixtures` directory.
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Response<T> {
+    response: T,
+}
+
+fn response<T: DeserializeOwned>(fixture: &str) -> T {
+    serde_json::from_str::<Response<T>>(fixture)
+        .unwrap()
+        .response
+}
+
+#[cfg(feature = "messages")]
+#[test]
+fn messages_get_by_id() {
+    use crate::messages::Message;
+
+    #[derive(Deserialize)]
+    struct Items {
+        items: Vec<Message>,
+    }
+
+    let Items { items } = response(include_str!("../fixtures/messages.getById.json"));
+    let message = &items[0];
+
+    assert_eq!(message.id, 52110);
+    assert_eq!(message.conversation_message_id, Some(1205));
+    assert_eq!(message.attachments.len(), 1);
+    assert_eq!(message.reply_message.as_ref().unwrap().id, 52109);
+    assert_eq!(message.forwarded(10).len(), 3);
+}
+
+#[cfg(feature = "search")]
+#[test]
+fn users_and_groups_search() {
+    use crate::search::{GroupType, SearchGroup, SearchPage, SearchUser};
+
+    let users: SearchPage<SearchUser> = response(include_str!("../fixtures/users.search.json"));
+    assert_eq!(users.count, 2);
+    assert_eq!(users.items[1].deactivated.as_deref(), Some("deleted"));
+
+    let groups: SearchPage<SearchGroup> = response(include_str!("../fixtures/groups.search.json"));
+    assert_eq!(groups.items[1].group_type, Some(GroupType::Page));
+}
+
+#[test]
+fn execute_resolve_screen_names() {
+    use crate::execute::{ResolvedScreenName, ScreenNameType};
+
+    let results: Vec<serde_json::Value> =
+        response(include_str!("../fixtures/execute.resolveScreenName.json"));
+    let resolved: ResolvedScreenName = serde_json::from_value(results[0].clone()).unwrap();
+
+    assert_eq!(resolved.kind, ScreenNameType::Group);
+    assert!(serde_json::from_value::<ResolvedScreenName>(results[1].clone()).is_err());
+}
+
+#[cfg(feature = "uploader")]
+#[test]
+fn photo_upload() {
+    use crate::upload::PhotoUploadResponse;
+
+    let uploaded: PhotoUploadResponse =
+        serde_json::from_str(include_str!("../fixtures/photos.upload.json")).unwrap();
+    assert_eq!(uploaded.server, 123456);
+}
+
+#[cfg(all(feature = "longpoll_stream", feature = "messages"))]
+#[tokio::test]
+async fn bots_long_poll_events() {
+    use crate::longpoll::{test_stream, LongPollResponse};
+    use crate::messages::{Activity, MessageRead, TypingState};
+    use futures_util::StreamExt;
+
+    #[derive(Deserialize)]
+    #[serde(tag = "type", content = "object", rename_all = "snake_case")]
+    enum Event {
+        MessageRead(MessageRead),
+        MessageTypingState(TypingState),
+    }
+
+    let responses = vec![
+        serde_json::from_str::<LongPollResponse<Event>>(include_str!(
+            "../fixtures/longpoll.bots.json"
+        ))
+        .unwrap(),
+        serde_json::from_str(include_str!("../fixtures/longpoll.failed.json")).unwrap(),
+    ];
+    let mut events = test_stream(responses);
+
+    assert!(matches!(
+        events.next().await,
+        Some(Ok(Event::MessageRead(MessageRead {
+            read_message_id: 52110,
+            ..
+        })))
+    ));
+    assert!(matches!(
+        events.next().await,
+        Some(Ok(Event::MessageTypingState(TypingState {
+            state: Activity::Typing,
+            ..
+        })))
+    ));
+    assert!(events.next().await.is_none());
+    assert_eq!(events.checkpoint().ts(), "1030");
+}
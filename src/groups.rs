@@ -0,0 +1,522 @@
+//! # Groups management helpers
+//! Typed params of the community management methods and streams of the community lists.
+
+use crate::{VkApi, VkApiResult};
+use futures_util::Stream;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Maximum count of the banned users per one request.
+const BANNED_PAGE_SIZE: usize = 200;
+/// Maximum count of the invited users per one request.
+const INVITED_PAGE_SIZE: usize = 100;
+
+/// # Groups facade
+/// Manages the community settings, roles and bans.
+/// Methods require the token with the community management rights.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use vkclient::groups::{Ban, BanReason, ManagerRole};
+/// use vkclient::VkApi;
+/// # let access_token = String::new();
+/// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+///
+/// let group = client.groups(1);
+///
+/// async move {
+///     group.edit_manager(2, Some(ManagerRole::Moderator)).await.unwrap();
+///     group
+///         .ban(
+///             Ban::new(3)
+///                 .with_reason(BanReason::Spam)
+///                 .with_comment("spam in comments")
+///                 .with_duration(Duration::from_secs(86400)),
+///         )
+///         .await
+///         .unwrap();
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct VkGroups {
+    client: VkApi,
+    group_id: u64,
+}
+
+impl VkGroups {
+    /// Creates the facade for the community.
+    #[must_use]
+    pub const fn new(client: VkApi, group_id: u64) -> Self {
+        Self { client, group_id }
+    }
+
+    /// Edits the community settings with [groups.edit](https://dev.vk.com/method/groups.edit).
+    /// Only the passed settings are changed.
+    pub async fn edit(&self, settings: GroupSettings) -> VkApiResult<()> {
+        self.client
+            .send_request::<u8, _, _>(
+                "groups.edit",
+                GroupParams {
+                    group_id: self.group_id,
+                    params: settings,
+                },
+            )
+            .await
+            .map(drop)
+    }
+
+    /// Sets the role of the user with [groups.editManager](https://dev.vk.com/method/groups.editManager).
+    /// Pass `None` to remove the user from the managers.
+    pub async fn edit_manager(&self, user_id: i64, role: Option<ManagerRole>) -> VkApiResult<()> {
+        self.client
+            .send_request::<u8, _, _>(
+                "groups.editManager",
+                EditManager {
+                    group_id: self.group_id,
+                    user_id,
+                    role,
+                },
+            )
+            .await
+            .map(drop)
+    }
+
+    /// Bans the user or the community with [groups.ban](https://dev.vk.com/method/groups.ban).
+    pub async fn ban(&self, ban: Ban) -> VkApiResult<()> {
+        self.client
+            .send_request::<u8, _, _>(
+                "groups.ban",
+                GroupParams {
+                    group_id: self.group_id,
+                    params: ban,
+                },
+            )
+            .await
+            .map(drop)
+    }
+
+    /// Unbans the user or the community with [groups.unban](https://dev.vk.com/method/groups.unban).
+    pub async fn unban(&self, owner_id: i64) -> VkApiResult<()> {
+        self.client
+            .send_request::<u8, _, _>(
+                "groups.unban",
+                GroupParams {
+                    group_id: self.group_id,
+                    params: Owner { owner_id },
+                },
+            )
+            .await
+            .map(drop)
+    }
+
+    /// Uploads the documents to the community wall and saves them with the tags.
+    /// Files are uploaded and saved one by one with [`crate::docs::upload`], their names are used as the titles.
+    /// Uploading continues after the failed file, the saved documents are returned with the failed files,
+    /// which can be retried with [`crate::BulkResult::failed_inputs`].
+    ///
+    /// ## Usage:
+    /// ```rust,no_run
+    /// use vkclient::upload::UploadSource;
+    /// use vkclient::VkApi;
+    /// # let access_token = String::new();
+    /// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+    ///
+    /// async move {
+    ///     let docs = client
+    ///         .groups(1)
+    ///         .upload_wall_docs(
+    ///             [UploadSource::bytes("report.pdf", std::fs::read("report.pdf").unwrap())],
+    ///             &["reports", "2024"],
+    ///         )
+    ///         .await;
+    ///     for (_, error) in docs.failed {
+    ///         println!("document isn't uploaded: {error}");
+    ///     }
+    /// };
+    /// ```
+    #[cfg(all(feature = "uploader", feature = "encode_json"))]
+    pub async fn upload_wall_docs<F, T>(
+        &self,
+        files: F,
+        tags: &[T],
+    ) -> crate::BulkResult<crate::upload::Doc, crate::upload::UploadSource>
+    where
+        F: IntoIterator<Item = crate::upload::UploadSource> + Send,
+        T: AsRef<str> + Sync,
+    {
+        use crate::docs::{upload_tagged, DocTarget};
+
+        let tags = tags.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(",");
+        let files = files.into_iter().collect::<Vec<_>>();
+        let mut result = crate::BulkResult::new();
+
+        for file in files {
+            let target = DocTarget::Group(self.group_id);
+            match upload_tagged(&self.client, target, file.clone(), String::new(), &tags).await {
+                Ok(doc) => result.succeeded.push(doc),
+                Err(e) => result.failed.push((file, e.error)),
+            }
+        }
+
+        result
+    }
+
+    /// Returns the stream of the banned users and communities
+    /// with [groups.getBanned](https://dev.vk.com/method/groups.getBanned).
+    pub fn banned(&self) -> impl Stream<Item = VkApiResult<BannedItem>> {
+        self.pages("groups.getBanned", BANNED_PAGE_SIZE)
+    }
+
+    /// Returns the stream of the users invited to the community
+    /// with [groups.getInvitedUsers](https://dev.vk.com/method/groups.getInvitedUsers).
+    pub fn invited_users(&self) -> impl Stream<Item = VkApiResult<InvitedUser>> {
+        self.pages("groups.getInvitedUsers", INVITED_PAGE_SIZE)
+    }
+
+    /// Requests the pages lazily until the items run out or an error occurs.
+    fn pages<I: DeserializeOwned>(
+        &self,
+        method: &'static str,
+        count: usize,
+    ) -> impl Stream<Item = VkApiResult<I>> {
+        let client = self.client.clone();
+        let group_id = self.group_id;
+
+        async_stream::stream! {
+            let mut offset = 0;
+
+            loop {
+                let page: VkApiResult<Page<I>> = client
+                    .send_request(
+                        method,
+                        GroupParams {
+                            group_id,
+                            params: PageParams { offset, count },
+                        },
+                    )
+                    .await;
+
+                let Page { count: total, items } = match page {
+                    Ok(page) => page,
+                    Err(e) => {
+                        yield Err(e);
+                        break;
+                    }
+                };
+
+                if items.is_empty() {
+                    break;
+                }
+                offset += items.len();
+
+                for item in items {
+                    yield Ok(item);
+                }
+
+                if offset >= total {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Settings of the community for [groups.edit](https://dev.vk.com/method/groups.edit).
+/// Unset settings are kept as is.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GroupSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub screen_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub website: Option<String>,
+    /// 0 is open, 1 is closed, 2 is private.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access: Option<u8>,
+    /// 0 is off, 1 is open, 2 is limited, 3 is closed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wall: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub messages: Option<u8>,
+    /// 1 is no limits, 2 is 16+, 3 is 18+.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age_limits: Option<u8>,
+}
+
+impl GroupSettings {
+    /// Creates the settings without changes.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pass the community title.
+    #[must_use]
+    pub fn with_title<T: Into<String>>(mut self, title: T) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Pass the community description.
+    #[must_use]
+    pub fn with_description<T: Into<String>>(mut self, description: T) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Enables or disables the community messages.
+    #[must_use]
+    pub const fn with_messages(mut self, enabled: bool) -> Self {
+        self.messages = Some(enabled as u8);
+        self
+    }
+}
+
+/// Role of the community manager.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ManagerRole {
+    Moderator,
+    Editor,
+    Administrator,
+    Advertiser,
+}
+
+/// Params of [groups.ban](https://dev.vk.com/method/groups.ban).
+#[derive(Debug, Clone, Serialize)]
+pub struct Ban {
+    /// User id or negative community id.
+    pub owner_id: i64,
+    /// Unix time of the ban end, the ban is permanent without it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_date: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<BanReason>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment_visible: Option<u8>,
+}
+
+impl Ban {
+    /// Creates the permanent ban of the user or the community.
+    #[must_use]
+    pub const fn new(owner_id: i64) -> Self {
+        Self {
+            owner_id,
+            end_date: None,
+            reason: None,
+            comment: None,
+            comment_visible: None,
+        }
+    }
+
+    /// Pass the ban reason.
+    #[must_use]
+    pub const fn with_reason(mut self, reason: BanReason) -> Self {
+        self.reason = Some(reason);
+        self
+    }
+
+    /// Pass the comment, which is visible to the managers only.
+    #[must_use]
+    pub fn with_comment<C: Into<String>>(mut self, comment: C) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Shows the comment to the banned user.
+    #[must_use]
+    pub const fn with_visible_comment(mut self) -> Self {
+        self.comment_visible = Some(1);
+        self
+    }
+
+    /// Pass the ban duration from now.
+    #[must_use]
+    pub fn with_duration(mut self, duration: std::time::Duration) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        self.end_date = Some((now + duration).as_secs());
+        self
+    }
+}
+
+/// Ban reason.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BanReason {
+    Other,
+    Spam,
+    VerbalAbuse,
+    StrongLanguage,
+    IrrelevantMessages,
+}
+
+impl BanReason {
+    const fn code(self) -> u8 {
+        match self {
+            Self::Other => 0,
+            Self::Spam => 1,
+            Self::VerbalAbuse => 2,
+            Self::StrongLanguage => 3,
+            Self::IrrelevantMessages => 4,
+        }
+    }
+
+    const fn from_code(code: u8) -> Self {
+        match code {
+            1 => Self::Spam,
+            2 => Self::VerbalAbuse,
+            3 => Self::StrongLanguage,
+            4 => Self::IrrelevantMessages,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl Serialize for BanReason {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for BanReason {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u8::deserialize(deserializer).map(Self::from_code)
+    }
+}
+
+/// Banned user or community returned by [groups.getBanned](https://dev.vk.com/method/groups.getBanned).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BannedItem {
+    /// `profile` or `group`.
+    #[serde(rename = "type")]
+    pub item_type: String,
+    #[serde(default)]
+    pub profile: Option<BannedProfile>,
+    #[serde(default)]
+    pub group: Option<BannedGroup>,
+    pub ban_info: BanInfo,
+}
+
+/// Banned user.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BannedProfile {
+    pub id: i64,
+    pub first_name: String,
+    pub last_name: String,
+}
+
+/// Banned community.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BannedGroup {
+    pub id: i64,
+    pub name: String,
+}
+
+/// Details of the ban.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BanInfo {
+    pub admin_id: i64,
+    pub date: u64,
+    #[serde(default = "default_reason")]
+    pub reason: BanReason,
+    #[serde(default)]
+    pub comment: Option<String>,
+    /// Unix time of the ban end, zero for the permanent ban.
+    #[serde(default)]
+    pub end_date: u64,
+}
+
+const fn default_reason() -> BanReason {
+    BanReason::Other
+}
+
+/// User invited to the community.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvitedUser {
+    pub id: i64,
+    pub first_name: String,
+    pub last_name: String,
+}
+
+#[derive(Serialize)]
+struct GroupParams<P> {
+    group_id: u64,
+    #[serde(flatten)]
+    params: P,
+}
+
+#[derive(Serialize)]
+struct EditManager {
+    group_id: u64,
+    user_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<ManagerRole>,
+}
+
+#[derive(Serialize)]
+struct Owner {
+    owner_id: i64,
+}
+
+#[derive(Serialize)]
+struct PageParams {
+    offset: usize,
+    count: usize,
+}
+
+#[derive(Deserialize)]
+struct Page<I> {
+    count: usize,
+    items: Vec<I>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::groups::{Ban, BanReason, GroupParams};
+
+    #[test]
+    fn serialize_ban() {
+        let ban = GroupParams {
+            group_id: 1,
+            params: Ban::new(2)
+                .with_reason(BanReason::Spam)
+                .with_comment("spam")
+                .with_visible_comment(),
+        };
+
+        assert_eq!(
+            serde_urlencoded::to_string(ban).unwrap(),
+            "group_id=1&owner_id=2&reason=1&comment=spam&comment_visible=1"
+        );
+        assert_eq!(
+            serde_urlencoded::to_string(GroupParams {
+                group_id: 1,
+                params: ()
+            })
+            .unwrap(),
+            "group_id=1"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "encode_json")]
+    fn deserialize_banned() {
+        use crate::groups::BannedItem;
+
+        let item: BannedItem = serde_json::from_str(
+            r#"{"type":"profile","profile":{"id":2,"first_name":"Ivan","last_name":"Ivanov"},"ban_info":{"admin_id":1,"date":1700000000,"reason":3,"comment":"","end_date":0}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(item.profile.unwrap().id, 2);
+        assert_eq!(item.ban_info.reason, BanReason::StrongLanguage);
+    }
+}
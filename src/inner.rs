@@ -1,93 +1,236 @@
 use crate::structs::Version;
-use crate::vkapi::{Compression, Encoding};
+use crate::vkapi::{Auth, Compression, DecodeStrictness, Encoding};
 use crate::{ResponseDeserialize, VkApiError, VkApiResult};
 use reqwest::header::HeaderValue;
-use reqwest::Client;
+use reqwest::{Certificate, Client, Url};
 use serde::de::DeserializeOwned;
+use std::collections::HashMap;
 use std::io::{BufReader, IoSliceMut, Read};
+use std::sync::RwLock;
+use std::time::Duration;
 
 #[derive(Clone, Debug)]
 pub struct VkApiInner {
     pub(crate) encoding: Compression,
     pub(crate) format: Encoding,
-    pub(crate) access_token: String,
+    pub(crate) auth: Auth,
     pub(crate) version: Version,
     pub(crate) domain: String,
+    pub(crate) base_url: String,
+    pub(crate) buffer_pool_size: usize,
+    pub(crate) buffer_max_capacity: usize,
+    pub(crate) client_config: ClientConfig,
+    pub(crate) strictness: DecodeStrictness,
+    #[cfg(feature = "rate_limit")]
+    pub(crate) rate_limiter: Option<crate::rate::RateLimiter>,
+    pub(crate) captcha_solver: Option<crate::captcha::SolverHandle>,
+    #[cfg(feature = "encode_json")]
+    pub(crate) transformers: crate::transform::Transformers,
+    pub(crate) wrapper_registry: Option<crate::WrapperRegistry>,
+    pub(crate) token_pool: Option<std::sync::Arc<crate::TokenPool>>,
+    #[cfg(feature = "resilience")]
+    pub(crate) policies: crate::resilience::Policies,
+    #[cfg(feature = "vk_id")]
+    pub(crate) refresher: Option<std::sync::Arc<crate::vkid::TokenRefresher>>,
+    pub(crate) error_stats: Option<crate::stats::ErrorStats>,
+    pub(crate) method_stats: Option<crate::method_stats::MethodStats>,
+    pub(crate) metrics: Option<crate::metrics::RecorderHandle>,
+    #[cfg(feature = "chaos")]
+    pub(crate) chaos: Option<crate::chaos::Chaos>,
 }
 
-pub fn create_client() -> Client {
-    Client::builder()
-        .https_only(true)
-        .use_rustls_tls()
-        .build()
-        .unwrap()
+pub fn base_url(domain: &str) -> String {
+    format!("https://{domain}/method/")
 }
 
-pub enum CompressReader<'a, R>
+/// Maximum count of cached urls per encoding, protects from unbounded growth with generated method names.
+const URL_CACHE_LIMIT: usize = 1024;
+
+/// Parsed method urls, which are reused between requests.
+#[derive(Debug, Default)]
+pub struct UrlCache {
+    plain: RwLock<HashMap<Box<str>, Url>>,
+    msgpack: RwLock<HashMap<Box<str>, Url>>,
+}
+
+impl UrlCache {
+    /// Returns cached url of the method or builds the new one.
+    /// Url, which can't be parsed, is returned as string for reporting by the request builder.
+    pub fn get(&self, base_url: &str, method: &str, msgpack: bool) -> Result<Url, String> {
+        let (urls, suffix) = if msgpack {
+            (&self.msgpack, ".msgpack")
+        } else {
+            (&self.plain, "")
+        };
+
+        if let Some(url) = urls
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(method)
+        {
+            return Ok(url.clone());
+        }
+
+        let mut raw = String::with_capacity(base_url.len() + method.len() + suffix.len());
+        raw.push_str(base_url);
+        raw.push_str(method);
+        raw.push_str(suffix);
+
+        let url = Url::parse(&raw).map_err(|_| raw)?;
+
+        let mut urls = urls
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if urls.len() < URL_CACHE_LIMIT {
+            urls.insert(method.into(), url.clone());
+        }
+
+        Ok(url)
+    }
+}
+
+/// Settings of the http client, which are kept to rebuild it.
+#[derive(Clone, Debug, Default)]
+pub struct ClientConfig {
+    pub(crate) root_certificates: Vec<Certificate>,
+    pub(crate) connect_timeout: Option<Duration>,
+    #[cfg(feature = "address_family")]
+    pub(crate) address_family: crate::AddressFamily,
+    #[cfg(feature = "tls_pinning")]
+    pub(crate) tls_pins: Option<crate::TlsPins>,
+}
+
+pub fn create_client(config: &ClientConfig) -> reqwest::Result<Client> {
+    client_builder(config).build()
+}
+
+/// Returns the builder of the http client with the settings, which can be extended by the client kind.
+pub fn client_builder(config: &ClientConfig) -> reqwest::ClientBuilder {
+    let mut builder = Client::builder().https_only(true).use_rustls_tls();
+
+    if cfg!(feature = "manual_roots") || !config.root_certificates.is_empty() {
+        builder = builder.tls_built_in_root_certs(false);
+        for certificate in &config.root_certificates {
+            builder = builder.add_root_certificate(certificate.clone());
+        }
+    }
+
+    if let Some(timeout) = config.connect_timeout {
+        builder = builder.connect_timeout(timeout);
+    }
+
+    #[cfg(feature = "address_family")]
+    if config.address_family != crate::AddressFamily::Any {
+        builder = builder.dns_resolver(std::sync::Arc::new(crate::network::FamilyResolver(
+            config.address_family,
+        )));
+    }
+
+    #[cfg(feature = "tls_pinning")]
+    if let Some(pins) = &config.tls_pins {
+        builder = builder.use_preconfigured_tls(pins.tls_config(&[b"h2", b"http/1.1"]));
+    }
+
+    builder
+}
+
+/// Returns the builder of the http/1 only client, which is required by the WebSocket connections.
+#[cfg(feature = "streaming")]
+pub fn http1_client_builder(config: &ClientConfig) -> reqwest::ClientBuilder {
+    let builder = client_builder(config).http1_only();
+
+    // http/2 can't be negotiated with the pinned tls settings, which are passed as is
+    #[cfg(feature = "tls_pinning")]
+    if let Some(pins) = &config.tls_pins {
+        return builder.use_preconfigured_tls(pins.tls_config(&[b"http/1.1"]));
+    }
+
+    builder
+}
+
+pub enum CompressReader<R>
 where
-    R: 'static + Read,
+    R: Read,
 {
     #[cfg(feature = "compression_zstd")]
-    Zstd(zstd::Decoder<'a, BufReader<R>>),
+    Zstd(zstd::Decoder<'static, BufReader<R>>),
     #[cfg(feature = "compression_gzip")]
     Gzip(Box<flate2::read::GzDecoder<BufReader<R>>>),
     Skip(BufReader<R>),
 }
 
-impl<'a, R> Read for CompressReader<'a, R>
+impl<R> Read for CompressReader<R>
 where
-    R: 'static + Read,
+    R: Read,
 {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         match self {
-            CompressReader::Zstd(reader) => reader.read(buf),
-            CompressReader::Gzip(reader) => reader.read(buf),
-            CompressReader::Skip(reader) => reader.read(buf),
+            #[cfg(feature = "compression_zstd")]
+            Self::Zstd(reader) => reader.read(buf),
+            #[cfg(feature = "compression_gzip")]
+            Self::Gzip(reader) => reader.read(buf),
+            Self::Skip(reader) => reader.read(buf),
         }
     }
 
     fn read_exact(&mut self, buf: &mut [u8]) -> std::io::Result<()> {
         match self {
-            CompressReader::Zstd(reader) => reader.read_exact(buf),
-            CompressReader::Gzip(reader) => reader.read_exact(buf),
-            CompressReader::Skip(reader) => reader.read_exact(buf),
+            #[cfg(feature = "compression_zstd")]
+            Self::Zstd(reader) => reader.read_exact(buf),
+            #[cfg(feature = "compression_gzip")]
+            Self::Gzip(reader) => reader.read_exact(buf),
+            Self::Skip(reader) => reader.read_exact(buf),
         }
     }
 
     fn read_to_end(&mut self, buf: &mut Vec<u8>) -> std::io::Result<usize> {
         match self {
-            CompressReader::Zstd(reader) => reader.read_to_end(buf),
-            CompressReader::Gzip(reader) => reader.read_to_end(buf),
-            CompressReader::Skip(reader) => reader.read_to_end(buf),
+            #[cfg(feature = "compression_zstd")]
+            Self::Zstd(reader) => reader.read_to_end(buf),
+            #[cfg(feature = "compression_gzip")]
+            Self::Gzip(reader) => reader.read_to_end(buf),
+            Self::Skip(reader) => reader.read_to_end(buf),
         }
     }
 
     fn read_to_string(&mut self, buf: &mut String) -> std::io::Result<usize> {
         match self {
-            CompressReader::Zstd(reader) => reader.read_to_string(buf),
-            CompressReader::Gzip(reader) => reader.read_to_string(buf),
-            CompressReader::Skip(reader) => reader.read_to_string(buf),
+            #[cfg(feature = "compression_zstd")]
+            Self::Zstd(reader) => reader.read_to_string(buf),
+            #[cfg(feature = "compression_gzip")]
+            Self::Gzip(reader) => reader.read_to_string(buf),
+            Self::Skip(reader) => reader.read_to_string(buf),
         }
     }
 
     fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> std::io::Result<usize> {
         match self {
-            CompressReader::Zstd(reader) => reader.read_vectored(bufs),
-            CompressReader::Gzip(reader) => reader.read_vectored(bufs),
-            CompressReader::Skip(reader) => reader.read_vectored(bufs),
+            #[cfg(feature = "compression_zstd")]
+            Self::Zstd(reader) => reader.read_vectored(bufs),
+            #[cfg(feature = "compression_gzip")]
+            Self::Gzip(reader) => reader.read_vectored(bufs),
+            Self::Skip(reader) => reader.read_vectored(bufs),
         }
     }
 }
 
-pub fn uncompress<B: Read + 'static>(
+#[cfg_attr(not(feature = "compression_zstd"), allow(unused_variables))]
+pub fn uncompress<B: Read>(
     encode: Option<&HeaderValue>,
     body: B,
-) -> VkApiResult<CompressReader<'static, B>> {
+    zstd_window_log_max: Option<u32>,
+) -> VkApiResult<CompressReader<B>> {
     match encode {
         #[cfg(feature = "compression_zstd")]
-        Some(v) if v == "zstd" => Ok(CompressReader::Zstd(
-            zstd::Decoder::new(body).map_err(VkApiError::IO)?,
-        )),
+        Some(v) if v == "zstd" => {
+            let mut decoder = zstd::Decoder::new(body).map_err(VkApiError::IO)?;
+            if let Some(window_log_max) = zstd_window_log_max {
+                decoder
+                    .window_log_max(window_log_max)
+                    .map_err(VkApiError::IO)?;
+            }
+            Ok(CompressReader::Zstd(decoder))
+        }
         #[cfg(feature = "compression_gzip")]
         Some(v) if v == "gzip" => Ok(CompressReader::Gzip(Box::new(
             flate2::read::GzDecoder::new(BufReader::new(body)),
@@ -96,21 +239,125 @@ pub fn uncompress<B: Read + 'static>(
     }
 }
 
+#[cfg_attr(
+    not(all(
+        feature = "strict_decode",
+        any(feature = "encode_json", feature = "encode_msgpack")
+    )),
+    allow(unused_variables)
+)]
 pub fn decode<T: DeserializeOwned, B: Read>(
     format: Option<&HeaderValue>,
     body: B,
+    strictness: DecodeStrictness,
 ) -> VkApiResult<T> {
     match format.as_ref().and_then(|f| f.to_str().ok()) {
         #[cfg(feature = "encode_json")]
-        Some(v) if v.starts_with("application/json") => serde_json::from_reader::<B, T>(body)
-            .map_err(|e| VkApiError::ResponseDeserialize(ResponseDeserialize::Json(e))),
+        Some(v) if v.starts_with("application/json") => match strictness {
+            DecodeStrictness::Lenient => serde_json::from_reader::<B, T>(body)
+                .map_err(|e| VkApiError::ResponseDeserialize(ResponseDeserialize::Json(e))),
+            #[cfg(feature = "strict_decode")]
+            DecodeStrictness::Strict => {
+                let mut deserializer = serde_json::Deserializer::from_reader(body);
+                let value = deserialize_strict(&mut deserializer)
+                    .and_then(|value| deserializer.end().map(|()| value))
+                    .map_err(|e| VkApiError::ResponseDeserialize(ResponseDeserialize::Json(e)))?;
+                value.map_err(|fields| {
+                    VkApiError::ResponseDeserialize(ResponseDeserialize::UnknownFields(fields))
+                })
+            }
+        },
         #[cfg(feature = "encode_msgpack")]
-        Some(v) if v.starts_with("application/x-msgpack") => {
-            rmp_serde::decode::from_read::<B, T>(body)
-                .map_err(|e| VkApiError::ResponseDeserialize(ResponseDeserialize::MsgPack(e)))
-        }
+        Some(v) if v.starts_with("application/x-msgpack") => match strictness {
+            DecodeStrictness::Lenient => rmp_serde::decode::from_read::<B, T>(body)
+                .map_err(|e| VkApiError::ResponseDeserialize(ResponseDeserialize::MsgPack(e))),
+            #[cfg(feature = "strict_decode")]
+            DecodeStrictness::Strict => deserialize_strict(&mut rmp_serde::Deserializer::new(body))
+                .map_err(|e| VkApiError::ResponseDeserialize(ResponseDeserialize::MsgPack(e)))?
+                .map_err(|fields| {
+                    VkApiError::ResponseDeserialize(ResponseDeserialize::UnknownFields(fields))
+                }),
+        },
         _ => Err(VkApiError::ResponseDeserialize(
             ResponseDeserialize::BadEncoding,
         )),
     }
 }
+
+/// Deserializes the model from the decoded json value with the strictness of the client.
+#[cfg(feature = "encode_json")]
+#[cfg_attr(not(feature = "strict_decode"), allow(unused_variables))]
+pub fn from_value<T: DeserializeOwned>(
+    value: serde_json::Value,
+    strictness: DecodeStrictness,
+) -> VkApiResult<T> {
+    match strictness {
+        DecodeStrictness::Lenient => T::deserialize(value)
+            .map_err(|e| VkApiError::ResponseDeserialize(ResponseDeserialize::Json(e))),
+        #[cfg(feature = "strict_decode")]
+        DecodeStrictness::Strict => deserialize_strict(value)
+            .map_err(|e| VkApiError::ResponseDeserialize(ResponseDeserialize::Json(e)))?
+            .map_err(|fields| {
+                VkApiError::ResponseDeserialize(ResponseDeserialize::UnknownFields(fields))
+            }),
+    }
+}
+
+/// Deserializes the value and collects paths of the ignored fields.
+#[cfg(feature = "strict_decode")]
+fn deserialize_strict<'de, T: DeserializeOwned, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Result<T, Vec<String>>, D::Error> {
+    let mut unknown = Vec::new();
+    let value = serde_ignored::deserialize(deserializer, |path| unknown.push(path.to_string()))?;
+
+    if unknown.is_empty() {
+        Ok(Ok(value))
+    } else {
+        Ok(Err(unknown))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::inner::{base_url, UrlCache};
+
+    #[test]
+    fn build_method_urls() {
+        let cache = UrlCache::default();
+        let base = base_url("api.vk.com");
+
+        let url = cache.get(&base, "users.get", false).unwrap();
+        assert_eq!(url.as_str(), "https://api.vk.com/method/users.get");
+
+        let url = cache.get(&base, "users.get", true).unwrap();
+        assert_eq!(url.as_str(), "https://api.vk.com/method/users.get.msgpack");
+
+        let url = cache.get(&base, "users.get", false).unwrap();
+        assert_eq!(url.as_str(), "https://api.vk.com/method/users.get");
+    }
+
+    #[test]
+    #[cfg(all(feature = "strict_decode", feature = "encode_json"))]
+    fn decode_strict() {
+        use crate::inner::decode;
+        use crate::{DecodeStrictness, ResponseDeserialize, VkApiError};
+        use reqwest::header::HeaderValue;
+
+        #[derive(Debug, serde::Deserialize)]
+        struct Model {
+            #[allow(dead_code)]
+            known: i32,
+        }
+
+        let json = HeaderValue::from_static("application/json");
+        let body = br#"{"known":1,"added":{"nested":2}}"#;
+
+        assert!(decode::<Model, _>(Some(&json), &body[..], DecodeStrictness::Lenient).is_ok());
+        assert!(matches!(
+            decode::<Model, _>(Some(&json), &body[..], DecodeStrictness::Strict),
+            Err(VkApiError::ResponseDeserialize(ResponseDeserialize::UnknownFields(fields)))
+                if fields == ["added"]
+        ));
+    }
+}
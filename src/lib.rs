@@ -3,17 +3,32 @@
 //!
 //! ## VK API Clients list:
 //! * [API client](crate::VkApi)
+//! * [Object safe client with mock](crate::VkApiClient)
 //! * [Uploader client](crate::upload::VkUploader)
+//! * [Photo upload helpers](crate::photos)
+//! * [Document upload helpers](crate::docs)
+//! * [Download client](crate::download::VkDownloader)
 //! * [Long Poll Client](crate::longpoll::VkLongPoll)
+//! * [Manual Long Poll Client](crate::longpoll::LongPoller)
+//! * [Search facade](crate::search::VkSearch)
+//! * [Groups facade](crate::groups::VkGroups)
+//! * [VK ID client](crate::vkid::VkId)
+//! * [Direct authorization client](crate::direct_auth::DirectAuth)
+//! * [Callback API bridge](crate::callback::CallbackBridge)
+//! * [Execute based batch helpers](crate::execute)
 //!
 //! ## Usage
-//! ```rust
+//! ```rust,no_run
 //! use vkclient::{List, VkApi, VkApiResult};
+//! use serde::{Deserialize, Serialize};
+//! # fn main() {
+//! # let access_token = String::new();
 //! let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
 //!
 //! async {
 //!     let users = get_users_info(&client).await.unwrap();
-//! }
+//! };
+//! # }
 //!
 //! async fn get_users_info(client: &VkApi) -> VkApiResult<Vec<UsersGetResponse>> {
 //!     client.send_request("users.get", UsersGetRequest {
@@ -37,27 +52,178 @@
 //! }
 //! ```
 //!
+//! Common items can be imported at once with the [`prelude`](crate::prelude).
+//!
 //! ## Features
 //! * [`compression_zstd`](crate::Compression) - enabled by default. Adds zstd compression support;
 //! * [`compression_gzip`](crate::Compression) - enabled by default. Adds gzip compression support;
-//! * [`encode_json`](crate::Encoding) - enabled by default. Adds json encoding support;
+//! * [`encode_json`](crate::Encoding) - enabled by default. Adds json encoding support and the decoding of json errors for other encodings;
 //! * [`encode_msgpack`](crate::Encoding) - enabled by default. Adds msgpack encoding support;
 //! * [`uploader`](crate::upload::VkUploader) - enabled by default. Adds file uploads support.
-//! * [`longpoll`](crate::longpoll::VkLongPoll) - enabled by default. Adds longpoll support.
-//! * [`longpoll_stream`](crate::longpoll::VkLongPoll::subscribe) - enabled by default. Adds converter long poll queries to futures stream.
+//! * [`upload_cookies`](crate::upload::VkUploaderBuilder::with_cookie_store) - disabled by default. Adds cookie store of the uploads.
+//! * [`upload_file`](crate::upload::VkUploader::upload_file) - disabled by default. Adds streaming uploads of the files from disk.
+//! * [`download`](crate::download::VkDownloader) - disabled by default. Adds resumable files downloads.
+//! * [`strict_decode`](crate::DecodeStrictness) - disabled by default. Adds failing on unknown response fields.
+//! * [`address_family`](crate::AddressFamily) - disabled by default. Adds IPv4/IPv6 preference of the connections.
+//! * [`longpoll`](crate::longpoll::VkLongPoll) - enabled by default. Adds longpoll support and the [typed community events](crate::longpoll::GroupEvent) with `messages`.
+//! * [`longpoll_stream`](crate::longpoll::VkLongPoll::subscribe) - enabled by default. Adds converter long poll queries to futures stream. Implies `longpoll`.
+//! * [`longpoll_replay`](crate::longpoll::VkLongPoll::replay) - disabled by default. Adds recording of long poll events to file and replaying them back. Implies `longpoll` and `longpoll_stream`.
+//! * [`longpoll_broadcast`](crate::longpoll::LongPollBroadcast) - disabled by default. Adds fan-out of long poll events to multiple receivers.
+//! * [`callback`](crate::callback::CallbackBridge) - disabled by default. Adds converter of Callback API requests to events stream.
+//! * [`integration_axum`](crate::callback::axum) - disabled by default. Adds axum router for Callback API requests.
+//! * [`integration_actix`](crate::callback::actix) - disabled by default. Adds actix-web resource for Callback API requests.
+//! * [`messages`](crate::messages) - disabled by default. Adds typed keyboards and message templates.
+//! * [`search`](crate::search::VkSearch) - disabled by default. Adds paginated search streams for users, groups and audio.
+//! * [`streaming`](crate::streaming::VkStreaming) - disabled by default. Adds Streaming API rules management and events stream over WebSocket.
+//! * [`groups`](crate::groups::VkGroups) - disabled by default. Adds community settings, roles and bans management.
+//! * [`export`](crate::export) - disabled by default. Adds NDJSON export of the paginated collections with checkpoints.
+//! * [`rate_limit`](crate::RateLimit) - disabled by default. Adds queueing of requests over the token rate limit.
+//! * [`resilience`](crate::ResiliencePolicy) - disabled by default. Adds timeouts, retries and circuit breakers per request kind.
+//! * [`vk_id`](crate::vkid::VkId) - disabled by default. Adds VK ID tokens exchange and automatic refresh.
+//! * [`direct_auth`](crate::direct_auth::DirectAuth) - disabled by default. Adds password grant of the official clients with 2FA codes.
+//! * [`chaos`](crate::chaos::Chaos) - disabled by default. Adds failure injection for testing the retries and the reconnects.
+//! * [`tls_pinning`](crate::TlsPins) - disabled by default. Adds pinning of the public keys of VK hosts.
+//! * [`manual_roots`](crate::VkApiBuilder::with_root_certificates) - disabled by default. Disables the built-in Mozilla roots,
+//!   so only the root certificates passed to the builder are trusted.
+//!
+//! ## Minimal profile
+//! For serverless functions and other short-living processes disable default features and enable `minimal`.
+//! It keeps only json encoding and gzip compression
+//! without long poll, uploads, downloads, search, groups management and authorization helpers.
+//! ```toml
+//! vkclient = { version = "5", default-features = false, features = ["minimal"] }
+//! ```
+//!
+//! ```rust,no_run
+//! use vkclient::VkApi;
+//! # let access_token = String::new();
+//! let client: VkApi = vkclient::VkApiBuilder::minimal(access_token).into();
+//! ```
+//!
+//! Pass the root certificates of VK hosts to trust them instead of the built-in Mozilla roots:
+//! ```rust,no_run
+//! use vkclient::{Certificate, VkApi};
+//! # let (access_token, root_pem) = (String::new(), Vec::<u8>::new());
+//! let client: VkApi = vkclient::VkApiBuilder::minimal(access_token)
+//!     .with_root_certificates(vec![Certificate::from_pem(&root_pem).unwrap()])
+//!     .into();
+//! ```
+
+#[cfg(not(any(feature = "encode_json", feature = "encode_msgpack")))]
+compile_error!(
+    "vkclient requires the `encode_json` or the `encode_msgpack` feature to decode VK responses"
+);
 
+#[cfg(all(feature = "encode_json", feature = "encode_msgpack"))]
+mod auto;
+#[cfg(any(
+    all(feature = "longpoll", feature = "longpoll_stream"),
+    feature = "resilience",
+    feature = "uploader"
+))]
+mod backoff;
 mod builder;
+mod bulk;
+#[cfg(feature = "callback")]
+pub mod callback;
+mod captcha;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+#[cfg(feature = "encode_json")]
+mod client;
+mod connection;
+mod describe;
+#[cfg(feature = "direct_auth")]
+pub mod direct_auth;
+#[cfg(all(feature = "uploader", feature = "encode_json"))]
+pub mod docs;
+#[cfg(feature = "download")]
+pub mod download;
+mod error_payload;
+pub mod execute;
+#[cfg(feature = "export")]
+pub mod export;
+#[cfg(all(test, feature = "encode_json"))]
+mod fixtures;
+#[cfg(feature = "groups")]
+pub mod groups;
 mod inner;
+mod method_stats;
+mod metrics;
+mod migration;
+#[cfg(feature = "address_family")]
+mod network;
+mod open;
+#[cfg(all(feature = "uploader", feature = "encode_json"))]
+pub mod photos;
+#[cfg(feature = "tls_pinning")]
+mod pinning;
+mod pool;
+pub mod prelude;
+#[cfg(feature = "rate_limit")]
+mod rate;
+#[cfg(feature = "resilience")]
+mod resilience;
+mod scope;
+#[cfg(any(feature = "longpoll", feature = "uploader"))]
+mod server;
+mod stats;
 mod structs;
+mod tokens;
+#[cfg(feature = "encode_json")]
+mod transform;
 mod vkapi;
+#[cfg(feature = "vk_id")]
+pub mod vkid;
 
 #[cfg(feature = "longpoll")]
 pub mod longpoll;
+#[cfg(feature = "messages")]
+pub mod messages;
+#[cfg(feature = "search")]
+pub mod search;
+#[cfg(feature = "streaming")]
+pub mod streaming;
 #[cfg(feature = "uploader")]
 pub mod upload;
 mod wrapper;
 
-pub use builder::VkApiBuilder;
+#[cfg(all(feature = "encode_json", feature = "encode_msgpack"))]
+pub use auto::{EncodingSamples, EncodingStats};
+#[cfg(any(
+    all(feature = "longpoll", feature = "longpoll_stream"),
+    feature = "resilience",
+    feature = "uploader"
+))]
+pub use backoff::Backoff;
+pub use builder::{AuthBuilder, BuildError, NoToken, VkApiBuilder, WithToken};
+pub use bulk::BulkResult;
+pub use captcha::{Captcha, CaptchaSolver};
+#[cfg(feature = "encode_json")]
+pub use client::{MockClient, ResponseFuture, VkApiClient};
+pub use connection::ConnectionStats;
+pub use describe::{ClientDescription, PolicyDescription, RateLimitDescription};
+pub use method_stats::{MethodSnapshot, SizeHistogram, SlowRequest};
+pub use metrics::{MetricsRecorder, RequestMetrics};
+pub use migration::{
+    BreakingChange, CompatibilityIssue, CompatibilityReport, WrapperInfo, WrapperRegistry,
+};
+#[cfg(feature = "address_family")]
+pub use network::AddressFamily;
+pub use open::VkOpenApi;
+#[cfg(feature = "tls_pinning")]
+pub use pinning::{TlsPin, TlsPins};
+pub use pool::BufferPoolStats;
+#[cfg(feature = "rate_limit")]
+pub use rate::{Quota, RateLimit};
+pub use reqwest::Certificate;
+#[cfg(feature = "resilience")]
+pub use resilience::{Breaker, RequestKind, ResiliencePolicy};
+pub use scope::{Scope, ScopeReport};
+pub use stats::ErrorSnapshot;
 pub use structs::*;
+pub use tokens::TokenPool;
+#[cfg(feature = "encode_json")]
+pub use transform::ResponseTransformer;
 pub use vkapi::*;
-pub use wrapper::VkApiWrapper;
+pub use wrapper::{TokenScope, VkApiWrapper};
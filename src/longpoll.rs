@@ -1,5 +1,6 @@
-use crate::inner::{create_client, decode, uncompress};
-use crate::{VkApiError, VkApiResult};
+use crate::inner::{create_client, decode, uncompress, ClientConfig};
+use crate::server::ServerConfig;
+use crate::{BuildError, Certificate, Compression, DecodeStrictness, VkApiError, VkApiResult};
 use bytes::{Buf, BufMut, BytesMut};
 use cfg_if::cfg_if;
 use reqwest::header::{ACCEPT, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE};
@@ -8,6 +9,43 @@ use serde::de::DeserializeOwned;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "longpoll_broadcast")]
+mod broadcast;
+#[cfg(feature = "encode_json")]
+mod decoded;
+#[cfg(feature = "messages")]
+mod events;
+#[cfg(feature = "encode_json")]
+mod history;
+mod mode;
+#[cfg(feature = "longpoll_stream")]
+mod options;
+mod poller;
+#[cfg(feature = "encode_json")]
+mod updates;
+
+#[cfg(feature = "longpoll_stream")]
+pub use crate::backoff::Backoff;
+#[cfg(feature = "longpoll_broadcast")]
+pub use broadcast::{BroadcastEvent, LongPollBroadcast};
+#[cfg(feature = "encode_json")]
+pub use decoded::{DecodeFailure, Decoded};
+#[cfg(feature = "messages")]
+pub use events::{
+    ClientInfo, GroupEvent, GroupJoin, GroupLeave, MessageAllow, MessageDeny, MessageEvent,
+    MessageNew, WallPost,
+};
+#[cfg(feature = "encode_json")]
+pub use history::{HistoryMessages, LongPollHistory};
+pub use mode::LongPollMode;
+#[cfg(feature = "longpoll_stream")]
+pub use options::{Chunks, Heartbeats, PollItems, SubscribeOptions, Updates};
+pub use poller::{InvalidWait, LongPoller, WAIT_RANGE};
+#[cfg(feature = "encode_json")]
+pub use updates::{UserMessage, UserUpdate};
 
 /// # Client for long poll subscriptions
 /// Use it to subscribe on some VK events, like
@@ -15,33 +53,80 @@ use std::fmt::{Display, Formatter};
 /// or the [Bots Long Poll API](https://dev.vk.com/api/bots-long-poll/getting-started).
 ///
 /// ## Usage:
-/// ```rust
+/// ```rust,no_run
 /// use vkclient::VkApi;
+/// # let access_token = String::new();
 /// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
 ///
 /// let longpoll_client = client.longpoll();
 /// ```
 ///
-/// ```rust
+/// ```rust,no_run
 /// use vkclient::longpoll::VkLongPoll;
 ///
 /// let longpoll_client = VkLongPoll::default();
 /// ```
+///
+/// Use [`VkLongPollBuilder`] to configure the standalone client.
 #[derive(Debug, Clone)]
 pub struct VkLongPoll {
     client: Client,
+    config: ServerConfig,
+    #[cfg(feature = "longpoll_stream")]
+    retry: Option<Backoff>,
 }
 
 impl VkLongPoll {
+    /// Creates the builder of the standalone client.
+    #[must_use]
+    pub fn builder() -> VkLongPollBuilder {
+        VkLongPollBuilder::default()
+    }
+
+    /// Pass the timeout of the requests, which is added to their `wait` time. Default is unlimited.
+    #[must_use]
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = Some(timeout);
+        self
+    }
+
+    /// Pass the recorder of the requests metrics.
+    #[must_use]
+    pub fn with_metrics<M: crate::MetricsRecorder + 'static>(mut self, metrics: M) -> Self {
+        self.config.metrics = Some(Arc::new(metrics));
+        self
+    }
+
+    /// Pass the backoff of the streams, which retries the polls failed with transport errors
+    /// in place and yields the error only when the retries in a row run out.
+    /// Default is no retries, the stream ends with the first transport error.
+    /// Override it for the single subscription with [`SubscribeOptions::with_retry`].
+    #[cfg(feature = "longpoll_stream")]
+    #[must_use]
+    pub const fn with_retry(mut self, backoff: Backoff) -> Self {
+        self.retry = Some(backoff);
+        self
+    }
+
+    /// Pass the faults, which are injected into the polls for testing the reconnects.
+    #[cfg(feature = "chaos")]
+    #[must_use]
+    pub fn with_chaos(mut self, chaos: crate::chaos::Chaos) -> Self {
+        self.config.chaos = Some(chaos);
+        self
+    }
+
     /// Returns an events stream from long poll server.
     ///
     /// ## Usage
-    /// ```rust
+    /// ```rust,no_run
+    /// use futures_util::StreamExt;
     /// use vkclient::longpoll::{VkLongPoll, LongPollRequest};
+    /// # let (key, server, ts) = (String::new(), String::new(), String::new());
     ///
     /// let longpoll_client = VkLongPoll::default();
     ///
-    /// longpoll_client.subscribe(LongPollRequest {
+    /// longpoll_client.subscribe::<_, serde_json::Value>(LongPollRequest {
     ///         key,
     ///         server,
     ///         ts,
@@ -51,43 +136,359 @@ impl VkLongPoll {
     ///     .take(1)
     ///     .for_each(|r| async move { println!("{:?}", r) });
     /// ```
+    ///
+    /// Transport errors end the stream, unless the retries are set by [`VkLongPoll::with_retry`].
+    /// Use [`LongPollStream::checkpoint`] to persist the ts of the processed events on your own schedule.
+    /// The stream ends, when the updates chunk doesn't match `I`; subscribe with the [`Decoded`] updates
+    /// to receive the undecodable ones as the failures and continue.
     #[cfg(feature = "longpoll_stream")]
     pub fn subscribe<T: Serialize + Clone + Send, I: DeserializeOwned>(
         &self,
-        mut request: LongPollRequest<T>,
-    ) -> impl futures_util::Stream<Item = VkApiResult<I>> {
-        let client = self.client.clone();
+        request: LongPollRequest<T>,
+    ) -> LongPollStream<impl futures_util::Stream<Item = VkApiResult<I>>> {
+        self.subscribe_with(request, SubscribeOptions::new())
+    }
 
-        async_stream::stream! {
-            loop {
-                match Self::subscribe_once_with_client(&client, request.clone()).await {
-                    Err(VkApiError::LongPoll(LongPollError { ts: Some(ts), .. })) => {
-                        request.ts = ts;
-                    },
-                    Ok(LongPollSuccess{ ts, updates }) => {
-                        request.ts = ts.clone();
-                        for update in updates {
-                            yield Ok(update);
-                        }
-                    },
+    /// Returns an events stream from long poll server, like the [`VkLongPoll::subscribe`],
+    /// with the [`SubscribeOptions`], which can be combined.
+    #[cfg(feature = "longpoll_stream")]
+    pub fn subscribe_with<T: Serialize + Clone + Send, I: DeserializeOwned, M: PollItems>(
+        &self,
+        request: LongPollRequest<T>,
+        options: SubscribeOptions<T, M>,
+    ) -> LongPollStream<impl futures_util::Stream<Item = VkApiResult<M::Item<I>>>> {
+        let (client, config) = (self.client.clone(), self.config.clone());
+        // server and key of the last refresh, the stream keeps only the ts of the request
+        let refreshed = Arc::new(std::sync::Mutex::new(None::<(String, String)>));
+
+        events_stream(request, options.retry.or(self.retry), move |request| {
+            let (client, config, options, refreshed) = (
+                client.clone(),
+                config.clone(),
+                options.clone(),
+                refreshed.clone(),
+            );
+            async move {
+                #[cfg(feature = "encode_json")]
+                if let Some(types) = &options.types {
+                    return Some(
+                        Self::poll_refreshed::<_, serde_json::Value, _>(
+                            &client, &config, request, &options, &refreshed,
+                        )
+                        .await
+                        .map(|success| M::items(success.filter_types(types))),
+                    );
+                }
+
+                Some(
+                    Self::poll_refreshed(&client, &config, request, &options, &refreshed)
+                        .await
+                        .map(|success| M::items(success.into_results())),
+                )
+            }
+        })
+    }
+
+    /// Returns a merged events stream of the long poll servers of several communities.
+    /// Each request is polled concurrently on the shared client like the [`VkLongPoll::subscribe`],
+    /// and the ready streams take turns, so a busy community doesn't delay the updates of the others.
+    /// Items are paired with the group id of their request. The error ends only the stream of its group.
+    ///
+    /// ## Usage
+    /// ```rust,no_run
+    /// use futures_util::StreamExt;
+    /// use vkclient::longpoll::{LongPollRequest, VkLongPoll};
+    /// # let (key, server, ts) = (String::new(), String::new(), String::new());
+    ///
+    /// let longpoll_client = VkLongPoll::default();
+    /// let request = LongPollRequest {
+    ///     key,
+    ///     server,
+    ///     ts,
+    ///     wait: 25,
+    ///     additional_params: (),
+    /// };
+    ///
+    /// longpoll_client
+    ///     .subscribe_many::<_, serde_json::Value, _>([(1, request.clone()), (2, request)])
+    ///     .for_each(|(group_id, r)| async move { println!("{}: {:?}", group_id, r) });
+    /// ```
+    #[cfg(feature = "longpoll_stream")]
+    pub fn subscribe_many<T, I, R>(
+        &self,
+        requests: R,
+    ) -> impl futures_util::Stream<Item = (i64, VkApiResult<I>)>
+    where
+        T: Serialize + Clone + Send,
+        I: DeserializeOwned,
+        R: IntoIterator<Item = (i64, LongPollRequest<T>)>,
+    {
+        let streams: Vec<_> = requests
+            .into_iter()
+            .map(|(group_id, request)| (group_id, self.subscribe::<T, I>(request)))
+            .collect();
+
+        merge_streams(streams)
+    }
+
+    /// Returns an events stream of the Bots Long Poll API of the community.
+    /// The server is requested with [groups.getLongPollServer](https://dev.vk.com/method/groups.getLongPollServer)
+    /// by the `api` client and requested again, when its key expires or the events history is lost.
+    /// The server is shared by the streams of the `api` client, so the new stream after the reconnect
+    /// reuses it and continues from the last received events.
+    /// The stream ends with the error, if the server can't be requested.
+    ///
+    /// ## Usage
+    /// ```rust,no_run
+    /// use futures_util::StreamExt;
+    /// use vkclient::VkApi;
+    /// # let access_token = String::new();
+    /// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+    ///
+    /// client
+    ///     .longpoll()
+    ///     .subscribe_group::<serde_json::Value>(&client, 1)
+    ///     .for_each(|r| async move { println!("{:?}", r) });
+    /// ```
+    #[cfg(feature = "longpoll_stream")]
+    #[must_use]
+    pub fn subscribe_group<I: DeserializeOwned>(
+        &self,
+        api: &crate::VkApi,
+        group_id: i64,
+    ) -> LongPollStream<impl futures_util::Stream<Item = VkApiResult<I>>> {
+        self.managed_stream(
+            api,
+            "groups.getLongPollServer",
+            GroupServerRequest { group_id },
+            (),
+        )
+    }
+
+    /// Returns an updates stream of the User Long Poll API, like the [`VkLongPoll::subscribe_group`].
+    /// The server is requested with [messages.getLongPollServer](https://dev.vk.com/method/messages.getLongPollServer)
+    /// with the `version` of the updates, `mode` is the flags of the additional updates fields.
+    ///
+    /// ## Usage
+    /// ```rust,no_run
+    /// use futures_util::StreamExt;
+    /// use vkclient::longpoll::{LongPollMode, UserUpdate};
+    /// use vkclient::VkApi;
+    /// # let access_token = String::new();
+    /// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+    ///
+    /// client
+    ///     .longpoll()
+    ///     .subscribe_user(&client, LongPollMode::ATTACHMENTS | LongPollMode::EXTENDED, 3)
+    ///     .for_each(|update| async move {
+    ///         if let Ok(UserUpdate::MessageNew(message)) = update {
+    ///             println!("{}", message.text);
+    ///         }
+    ///     });
+    /// ```
+    #[cfg(all(feature = "longpoll_stream", feature = "encode_json"))]
+    #[must_use]
+    pub fn subscribe_user(
+        &self,
+        api: &crate::VkApi,
+        mode: LongPollMode,
+        version: u32,
+    ) -> LongPollStream<impl futures_util::Stream<Item = VkApiResult<UserUpdate>>> {
+        self.managed_stream(
+            api,
+            "messages.getLongPollServer",
+            UserServerRequest {
+                lp_version: version,
+                need_pts: 1,
+            },
+            UserServerParams { mode, version },
+        )
+    }
+
+    /// Returns an updates stream of the User Long Poll API, like the [`VkLongPoll::subscribe_user`],
+    /// which starts with the updates missed since the stored `ts` and `pts`.
+    /// The missed updates are requested with [messages.getLongPollHistory](https://dev.vk.com/method/messages.getLongPollHistory)
+    /// after the server of the live updates, so no updates are lost between them, but some may be repeated.
+    /// Persist the ts of the [`LongPollStream::checkpoint`] and the pts, which is returned by
+    /// [messages.getLongPollServer](https://dev.vk.com/method/messages.getLongPollServer) with `need_pts`
+    /// and by the history as [`LongPollHistory::new_pts`].
+    /// The [`LongPollStream::stop_handle`] ends the stream after the current page of the history too.
+    ///
+    /// ## Usage
+    /// ```rust,no_run
+    /// use futures_util::StreamExt;
+    /// use vkclient::longpoll::LongPollMode;
+    /// use vkclient::VkApi;
+    /// # let access_token = String::new();
+    /// # let (ts, pts) = (String::new(), 0);
+    /// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+    ///
+    /// client
+    ///     .longpoll()
+    ///     .subscribe_user_since(&client, LongPollMode::ATTACHMENTS | LongPollMode::PTS, 3, ts, pts)
+    ///     .for_each(|update| async move { println!("{:?}", update) });
+    /// ```
+    #[cfg(all(feature = "longpoll_stream", feature = "encode_json"))]
+    #[must_use]
+    pub fn subscribe_user_since(
+        &self,
+        api: &crate::VkApi,
+        mode: LongPollMode,
+        version: u32,
+        ts: String,
+        pts: u64,
+    ) -> LongPollStream<impl futures_util::Stream<Item = VkApiResult<UserUpdate>>> {
+        let params = UserServerRequest {
+            lp_version: version,
+            need_pts: 1,
+        };
+        let key = server_cache_key("messages.getLongPollServer", &params);
+        let live = self.subscribe_user(api, mode, version);
+        let (checkpoint, stop) = (live.checkpoint(), live.stop_handle());
+        let stopped = stop.clone();
+        let api = api.clone();
+
+        let inner = async_stream::stream! {
+            match api
+                .send_request::<ServerInfo, _, _>("messages.getLongPollServer", params)
+                .await
+            {
+                Ok(server) => api.long_poll_servers().set(&key, server),
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            }
+
+            let mut pts = pts;
+            while !stopped.is_stopped() {
+                let history = match api
+                    .send_request::<LongPollHistory, _, _>(
+                        "messages.getLongPollHistory",
+                        HistoryRequest {
+                            ts: ts.clone(),
+                            pts,
+                            lp_version: version,
+                        },
+                    )
+                    .await
+                {
+                    Ok(history) => history,
                     Err(e) => {
                         yield Err(e);
-                        break;
-                    },
+                        return;
+                    }
                 };
+
+                let next = history.new_pts.filter(|_| history.more == 1);
+                for update in history.into_updates() {
+                    yield Ok(update);
+                }
+                match next {
+                    Some(next) => pts = next,
+                    None => break,
+                }
+            }
+
+            for await update in live {
+                yield update;
             }
+        };
+
+        LongPollStream {
+            inner: Box::pin(inner),
+            checkpoint,
+            stop,
         }
     }
 
+    /// Returns an events stream of the server, which is requested by the API `method`
+    /// and requested again after the `failed: 2` and `failed: 3` errors.
+    #[cfg(feature = "longpoll_stream")]
+    fn managed_stream<P, T, I>(
+        &self,
+        api: &crate::VkApi,
+        method: &'static str,
+        params: P,
+        additional_params: T,
+    ) -> LongPollStream<impl futures_util::Stream<Item = VkApiResult<I>>>
+    where
+        P: Serialize + Clone + Send,
+        T: Serialize + Clone + Send,
+        I: DeserializeOwned,
+    {
+        let (client, config, api) = (self.client.clone(), self.config.clone(), api.clone());
+        let key = server_cache_key(method, &params);
+        let request = LongPollRequest {
+            server: String::new(),
+            key: String::new(),
+            ts: String::new(),
+            wait: 25,
+            additional_params,
+        };
+
+        events_stream(request, self.retry, move |mut request| {
+            let (client, config, api, params, key) = (
+                client.clone(),
+                config.clone(),
+                api.clone(),
+                params.clone(),
+                key.clone(),
+            );
+            async move {
+                let servers = api.long_poll_servers();
+                let mut reset_ts = request.ts.is_empty();
+                loop {
+                    let (server, requested) = match servers.get(&key) {
+                        Some(server) => (server, false),
+                        None => match api
+                            .send_request::<ServerInfo, _, _>(method, params.clone())
+                            .await
+                        {
+                            Ok(server) => {
+                                servers.set(&key, server.clone());
+                                (server, true)
+                            }
+                            Err(e) => return Some(Err(e)),
+                        },
+                    };
+
+                    request.server = server.server;
+                    request.key = server.key;
+                    if reset_ts {
+                        request.ts = server.ts;
+                    }
+
+                    match Self::subscribe_once_with_client(&client, &config, request.clone()).await
+                    {
+                        Err(VkApiError::LongPoll(LongPollError {
+                            failed: failed @ (2 | 3),
+                            ..
+                        })) if !requested => {
+                            servers.invalidate(&key, &request.key);
+                            reset_ts = failed == 3;
+                        }
+                        Ok(success) => {
+                            servers.set_ts(&key, &request.key, &success.ts);
+                            return Some(Ok(success.into_results()));
+                        }
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+            }
+        })
+    }
+
     /// Returns first events chunk from long poll server.
     ///
     /// ## Usage
-    /// ```rust
+    /// ```rust,no_run
     /// use vkclient::longpoll::{VkLongPoll, LongPollRequest};
+    /// # let (key, server, ts) = (String::new(), String::new(), String::new());
     ///
     /// let longpoll_client = VkLongPoll::default();
     ///
-    /// longpoll_client.subscribe_once(LongPollRequest {
+    /// longpoll_client.subscribe_once::<_, serde_json::Value>(LongPollRequest {
     ///         key,
     ///         server,
     ///         ts,
@@ -99,13 +500,61 @@ impl VkLongPoll {
         &self,
         request: LongPollRequest<T>,
     ) -> VkApiResult<LongPollSuccess<I>> {
-        Self::subscribe_once_with_client(&self.client, request).await
+        Self::subscribe_once_with_client(&self.client, &self.config, request).await
+    }
+
+    /// Polls the server of the last refresh, if there was one, and refreshes it
+    /// after the `failed: 2` and `failed: 3` errors, when the options have the refresh.
+    #[cfg(feature = "longpoll_stream")]
+    async fn poll_refreshed<T: Serialize + Clone + Send, I: DeserializeOwned, M>(
+        client: &Client,
+        config: &ServerConfig,
+        mut request: LongPollRequest<T>,
+        options: &SubscribeOptions<T, M>,
+        refreshed: &std::sync::Mutex<Option<(String, String)>>,
+    ) -> VkApiResult<LongPollSuccess<I>> {
+        let Some(refresh) = &options.refresh else {
+            return Self::subscribe_once_with_client(client, config, request).await;
+        };
+
+        if let Some((server, key)) = refreshed
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+        {
+            request.server = server;
+            request.key = key;
+        }
+
+        match Self::subscribe_once_with_client(client, config, request.clone()).await {
+            Err(VkApiError::LongPoll(LongPollError {
+                failed: failed @ (2 | 3),
+                ..
+            })) => {
+                let fresh = refresh().await?;
+                *refreshed
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner) =
+                    Some((fresh.server.clone(), fresh.key.clone()));
+
+                let ts = if failed == 3 { fresh.ts } else { request.ts };
+                Self::subscribe_once_with_client(client, config, LongPollRequest { ts, ..fresh })
+                    .await
+            }
+            result => result,
+        }
     }
 
     async fn subscribe_once_with_client<T: Serialize + Send, I: DeserializeOwned>(
         client: &Client,
+        config: &ServerConfig,
         request: LongPollRequest<T>,
     ) -> VkApiResult<LongPollSuccess<I>> {
+        let timeout = config
+            .timeout
+            .map(|timeout| Duration::from_secs(request.wait as u64) + timeout);
+        #[cfg(feature = "chaos")]
+        let ts = request.ts.clone();
         let LongPollInnerRequest(LongPollServer(server), params) =
             LongPollInnerRequest::from(request);
 
@@ -116,17 +565,10 @@ impl VkLongPoll {
         } else {
             format!("https://{server}?act=a_check&{params}")
         };
+        config.check_host(&url)?;
 
         cfg_if! {
-            if #[cfg(features = "compression_gzip")] {
-                let encoding = "gzip";
-            } else {
-                let encoding =  "identity";
-            }
-        }
-
-        cfg_if! {
-            if #[cfg(features = "encode_json")] {
+            if #[cfg(feature = "encode_json")] {
                 let serialisation = "application/json";
             } else {
                 let serialisation =  "text/*";
@@ -134,54 +576,817 @@ impl VkLongPoll {
         }
 
         let request = client
-            .get(url)
-            .header(ACCEPT_ENCODING, encoding)
+            .get(&url)
+            .header(ACCEPT_ENCODING, config.accept_encoding())
             .header(ACCEPT, serialisation);
+        let request = match timeout {
+            Some(timeout) => request.timeout(timeout),
+            None => request,
+        };
+
+        let started = Instant::now();
+        let mut bytes = 0;
+        let result = async {
+            #[cfg(feature = "chaos")]
+            if let Some(chaos) = &config.chaos {
+                if let Some(fault) = chaos.before_request().await {
+                    return Err(fault.into_error(true));
+                }
+                if let Some(failed) = chaos.longpoll_failure() {
+                    return Err(VkApiError::LongPoll(match failed {
+                        1 => LongPollError::new(failed).with_ts(ts),
+                        failed => LongPollError::new(failed),
+                    }));
+                }
+            }
+
+            let mut response = request.send().await.map_err(VkApiError::LongPollRequest)?;
+            let conent_length = response.content_length();
+
+            let mut body = BytesMut::with_capacity(conent_length.unwrap_or_default() as usize);
+            while let Some(buf) = response
+                .chunk()
+                .await
+                .map_err(VkApiError::LongPollRequest)?
+            {
+                body.put(buf);
+            }
+            bytes = body.len();
+
+            let headers = response.headers();
+
+            let content_type = headers.get(CONTENT_TYPE);
+            let content_encoding = headers.get(CONTENT_ENCODING);
+
+            let resp = decode::<LongPollResponse<I>, _>(
+                content_type,
+                uncompress(
+                    content_encoding,
+                    body.reader(),
+                    config.compression.zstd_window_log_max(),
+                )?,
+                DecodeStrictness::Lenient,
+            )?;
+
+            resp.into_result()
+        }
+        .await;
+
+        config.record(&url, started, bytes, &result);
+        result
+    }
+}
+
+#[cfg(feature = "longpoll_replay")]
+impl VkLongPoll {
+    /// Records every update of the stream to the NDJSON file and passes the stream through.
+    /// Each line holds the update and milliseconds passed since the recording start.
+    /// Use [`VkLongPoll::replay`] to feed recorded updates back.
+    ///
+    /// ## Usage
+    /// ```rust,no_run
+    /// use futures_util::StreamExt;
+    /// use vkclient::longpoll::{VkLongPoll, LongPollRequest};
+    /// # let (key, server, ts) = (String::new(), String::new(), String::new());
+    ///
+    /// let longpoll_client = VkLongPoll::default();
+    ///
+    /// let events = longpoll_client.subscribe::<_, serde_json::Value>(LongPollRequest {
+    ///     key,
+    ///     server,
+    ///     ts,
+    ///     wait: 25,
+    ///     additional_params: (),
+    /// });
+    ///
+    /// VkLongPoll::record(events, "events.ndjson")
+    ///     .take(100)
+    ///     .for_each(|r| async move { println!("{:?}", r) });
+    /// ```
+    pub fn record<S, I, P>(stream: S, path: P) -> impl futures_util::Stream<Item = VkApiResult<I>>
+    where
+        S: futures_util::Stream<Item = VkApiResult<I>>,
+        I: Serialize,
+        P: AsRef<std::path::Path>,
+    {
+        use futures_util::StreamExt;
+        use tokio::io::AsyncWriteExt;
+
+        let path = path.as_ref().to_owned();
+        let started = std::time::Instant::now();
+
+        async_stream::stream! {
+            let mut file = match tokio::fs::File::create(path).await {
+                Ok(file) => tokio::io::BufWriter::new(file),
+                Err(e) => {
+                    yield Err(VkApiError::IO(e));
+                    return;
+                }
+            };
+
+            futures_util::pin_mut!(stream);
+            while let Some(update) = stream.next().await {
+                if let Ok(update) = &update {
+                    let record = RecordedUpdate {
+                        at_ms: started.elapsed().as_millis() as u64,
+                        update,
+                    };
+
+                    let mut line = match serde_json::to_vec(&record) {
+                        Ok(line) => line,
+                        Err(e) => {
+                            yield Err(VkApiError::IO(e.into()));
+                            break;
+                        }
+                    };
+                    line.push(b'\n');
+
+                    let written = match file.write_all(&line).await {
+                        Ok(()) => file.flush().await,
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = written {
+                        yield Err(VkApiError::IO(e));
+                        break;
+                    }
+                }
+
+                yield update;
+            }
+        }
+    }
+
+    /// Returns an events stream from the NDJSON file written by [`VkLongPoll::record`].
+    /// Pauses between updates are kept as recorded and divided by `speed`.
+    /// Pass zero or infinite speed to replay updates without pauses.
+    ///
+    /// ## Usage
+    /// ```rust,no_run
+    /// use futures_util::StreamExt;
+    /// use vkclient::longpoll::VkLongPoll;
+    ///
+    /// VkLongPoll::replay::<serde_json::Value, _>("events.ndjson", 10.0)
+    ///     .for_each(|r| async move { println!("{:?}", r) });
+    /// ```
+    pub fn replay<I, P>(path: P, speed: f64) -> impl futures_util::Stream<Item = VkApiResult<I>>
+    where
+        I: DeserializeOwned,
+        P: AsRef<std::path::Path>,
+    {
+        use tokio::io::AsyncBufReadExt;
+
+        let path = path.as_ref().to_owned();
+
+        async_stream::stream! {
+            let mut lines = match tokio::fs::File::open(path).await {
+                Ok(file) => tokio::io::BufReader::new(file).lines(),
+                Err(e) => {
+                    yield Err(VkApiError::IO(e));
+                    return;
+                }
+            };
+
+            let mut previous = 0;
+            loop {
+                let line = match lines.next_line().await {
+                    Ok(Some(line)) if line.trim().is_empty() => continue,
+                    Ok(Some(line)) => line,
+                    Ok(None) => break,
+                    Err(e) => {
+                        yield Err(VkApiError::IO(e));
+                        break;
+                    }
+                };
+
+                let RecordedUpdate { at_ms, update } = match serde_json::from_str(&line) {
+                    Ok(record) => record,
+                    Err(e) => {
+                        yield Err(VkApiError::ResponseDeserialize(crate::ResponseDeserialize::Json(e)));
+                        break;
+                    }
+                };
+
+                let pause = at_ms.saturating_sub(previous) as f64 / speed;
+                if let Ok(pause) = std::time::Duration::try_from_secs_f64(pause / 1000.0) {
+                    if !pause.is_zero() {
+                        tokio::time::sleep(pause).await;
+                    }
+                }
+                previous = at_ms;
+
+                yield Ok(update);
+            }
+        }
+    }
+}
+
+/// One line of the recorded events file.
+#[cfg(feature = "longpoll_replay")]
+#[derive(Serialize, Deserialize)]
+struct RecordedUpdate<U> {
+    at_ms: u64,
+    update: U,
+}
+
+/// Returns an events stream, which handles given responses like the [`VkLongPoll::subscribe`] stream handles server responses.
+///
+/// Use it to test your bot logic against synthetic events and failures without a server.
+/// The stream ends when the responses run out.
+///
+/// ## Usage
+/// ```rust
+/// use futures_util::StreamExt;
+/// use vkclient::longpoll::{test_stream, LongPollError, LongPollResponse, LongPollSuccess};
+///
+/// let events = test_stream(vec![
+///     LongPollResponse::Success(LongPollSuccess::new("1", vec!["first"])),
+///     LongPollResponse::Error(LongPollError::new(1).with_ts("2")),
+///     LongPollResponse::Success(LongPollSuccess::new("3", vec!["second"])),
+///     LongPollResponse::Error(LongPollError::new(2)),
+/// ]);
+///
+/// async {
+///     let events: Vec<_> = events.collect().await;
+///     assert_eq!(events.len(), 3);
+///     assert!(events[2].is_err());
+/// };
+/// ```
+#[cfg(feature = "longpoll_stream")]
+#[must_use]
+pub fn test_stream<I>(
+    responses: Vec<LongPollResponse<I>>,
+) -> LongPollStream<impl futures_util::Stream<Item = VkApiResult<I>>> {
+    let mut responses = responses.into_iter();
+    let request = LongPollRequest {
+        server: String::new(),
+        key: String::new(),
+        ts: String::new(),
+        wait: 0,
+        additional_params: (),
+    };
+
+    events_stream(request, None, move |_| {
+        let response = responses
+            .next()
+            .map(|response| response.into_result().map(LongPollSuccess::into_results));
+        async move { response }
+    })
+}
+
+/// Merges the streams, pairing their items with the group ids. Ready streams are polled in turns.
+#[cfg(feature = "longpoll_stream")]
+fn merge_streams<S, I>(
+    streams: impl IntoIterator<Item = (i64, LongPollStream<S>)>,
+) -> impl futures_util::Stream<Item = (i64, VkApiResult<I>)>
+where
+    S: futures_util::Stream<Item = VkApiResult<I>>,
+{
+    use futures_util::StreamExt;
+
+    futures_util::stream::select_all(
+        streams
+            .into_iter()
+            .map(|(group_id, stream)| stream.map(move |item| (group_id, item))),
+    )
+}
+
+/// Repeats long poll requests with the actual ts and yields received updates.
+/// Failed updates are yielded as errors in place, the stream ends on the first unrecoverable error of the poll
+/// or when `fetch` returns `None`.
+/// Transport errors are retried with the backoff, if it's passed.
+#[cfg(feature = "longpoll_stream")]
+fn events_stream<T, I, F, Fut>(
+    mut request: LongPollRequest<T>,
+    backoff: Option<Backoff>,
+    mut fetch: F,
+) -> LongPollStream<impl futures_util::Stream<Item = VkApiResult<I>>>
+where
+    T: Clone,
+    F: FnMut(LongPollRequest<T>) -> Fut,
+    Fut: std::future::Future<Output = Option<VkApiResult<LongPollSuccess<VkApiResult<I>>>>>,
+{
+    let checkpoint = Checkpoint::new(request.ts.clone());
+    let processed = checkpoint.clone();
+    let stop = StopHandle::default();
+    let stopped = stop.clone();
+
+    let inner = async_stream::stream! {
+        let mut failures = 0;
+
+        while !stopped.is_stopped() {
+            let Some(response) = fetch(request.clone()).await else {
+                break;
+            };
+
+            if !matches!(response, Err(VkApiError::LongPollRequest(_))) {
+                failures = 0;
+            }
+
+            match response {
+                Err(VkApiError::LongPollRequest(e)) => {
+                    failures += 1;
+                    match backoff.and_then(|backoff| backoff.delay(failures)) {
+                        Some(delay) => tokio::time::sleep(delay).await,
+                        None => {
+                            yield Err(VkApiError::LongPollRequest(e));
+                            break;
+                        }
+                    }
+                },
+                Err(VkApiError::LongPoll(LongPollError { ts: Some(ts), .. })) => {
+                    request.ts = ts;
+                    processed.set(&request.ts);
+                },
+                Ok(LongPollSuccess{ ts, updates }) => {
+                    request.ts = ts;
+                    for update in updates {
+                        yield update;
+                    }
+                    // the stream is resumed after the last update of the chunk was taken
+                    processed.set(&request.ts);
+                },
+                Err(e) => {
+                    yield Err(e);
+                    break;
+                },
+            }
+        }
+    };
+
+    LongPollStream {
+        inner: Box::pin(inner),
+        checkpoint,
+        stop,
+    }
+}
+
+/// Item of the stream with the [`SubscribeOptions::with_heartbeats`].
+#[cfg(feature = "longpoll_stream")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Polled<I> {
+    Update(I),
+    /// Poll without updates, `ts` is the ts for the next poll
+    Heartbeat {
+        ts: String,
+        polled_at: std::time::SystemTime,
+    },
+}
 
-        let mut response = request.send().await.map_err(VkApiError::Request)?;
-        let conent_length = response.content_length();
+#[cfg(all(feature = "longpoll_stream", feature = "encode_json"))]
+impl LongPollSuccess<serde_json::Value> {
+    /// Decodes the updates with the `type` in `types`, dropping the other ones.
+    /// Each update is decoded separately, so the undecodable one fails only itself.
+    fn filter_types<I: DeserializeOwned>(
+        self,
+        types: &[String],
+    ) -> LongPollSuccess<VkApiResult<I>> {
+        let updates = self
+            .updates
+            .into_iter()
+            .filter(|update| {
+                update
+                    .get("type")
+                    .and_then(serde_json::Value::as_str)
+                    .is_some_and(|kind| types.iter().any(|t| t == kind))
+            })
+            .map(|update| {
+                serde_json::from_value(update).map_err(|e| {
+                    VkApiError::ResponseDeserialize(crate::ResponseDeserialize::Json(e))
+                })
+            })
+            .collect();
 
-        let mut body = BytesMut::with_capacity(conent_length.unwrap_or_default() as usize);
-        while let Some(buf) = response.chunk().await.map_err(VkApiError::Request)? {
-            body.put(buf)
+        LongPollSuccess {
+            ts: self.ts,
+            updates,
         }
+    }
+}
+
+#[cfg(feature = "longpoll_stream")]
+impl<R> LongPollSuccess<R> {
+    /// Wraps the updates as the decoded ones.
+    fn into_results(self) -> LongPollSuccess<VkApiResult<R>> {
+        LongPollSuccess {
+            ts: self.ts,
+            updates: self.updates.into_iter().map(Ok).collect(),
+        }
+    }
+}
 
-        let headers = response.headers();
+#[cfg(feature = "longpoll_stream")]
+impl<R> LongPollSuccess<VkApiResult<R>> {
+    /// Wraps the decoded updates as the only update of the chunk with the same ts.
+    /// Failed updates are kept as the separate items after the chunk.
+    fn into_chunk(self) -> LongPollSuccess<VkApiResult<LongPollSuccess<R>>> {
+        let (decoded, failed): (Vec<_>, Vec<_>) = self.updates.into_iter().partition(Result::is_ok);
+        let chunk = LongPollSuccess {
+            ts: self.ts.clone(),
+            updates: decoded.into_iter().filter_map(Result::ok).collect(),
+        };
 
-        let content_type = headers.get(CONTENT_TYPE);
-        let content_encoding = headers.get(CONTENT_ENCODING);
+        LongPollSuccess {
+            ts: self.ts,
+            updates: std::iter::once(Ok(chunk))
+                .chain(
+                    failed
+                        .into_iter()
+                        .filter_map(|update| update.err().map(Err)),
+                )
+                .collect(),
+        }
+    }
 
-        let resp = decode::<LongPollResponse<I>, _>(
-            content_type,
-            uncompress(content_encoding, body.reader())?,
-        )?;
+    /// Wraps the updates, replacing no updates with the heartbeat.
+    fn into_polled(self) -> LongPollSuccess<VkApiResult<Polled<R>>> {
+        let updates = if self.updates.is_empty() {
+            vec![Ok(Polled::Heartbeat {
+                ts: self.ts.clone(),
+                polled_at: std::time::SystemTime::now(),
+            })]
+        } else {
+            self.updates
+                .into_iter()
+                .map(|update| update.map(Polled::Update))
+                .collect()
+        };
 
-        match resp {
-            LongPollResponse::Success(r) => Ok(r),
-            LongPollResponse::Error(e) => Err(VkApiError::LongPoll(e)),
+        LongPollSuccess {
+            ts: self.ts,
+            updates,
         }
     }
 }
 
+/// Stream of the long poll events with the ts of the processed events.
+#[cfg(feature = "longpoll_stream")]
+pub struct LongPollStream<S> {
+    inner: std::pin::Pin<Box<S>>,
+    checkpoint: Checkpoint,
+    stop: StopHandle,
+}
+
+#[cfg(feature = "longpoll_stream")]
+impl<S> LongPollStream<S> {
+    /// Returns the handle of the ts, which is updated after all events of the chunk are taken from the stream.
+    /// Persist it and pass as the request ts after restart to continue from the processed events.
+    #[must_use]
+    pub fn checkpoint(&self) -> Checkpoint {
+        self.checkpoint.clone()
+    }
+
+    /// Returns the handle, which ends the stream after the current poll.
+    /// Updates of the poll are yielded before the end, so they aren't lost on shutdown.
+    ///
+    /// ## Usage
+    /// ```rust,no_run
+    /// use futures_util::StreamExt;
+    /// use vkclient::longpoll::{LongPollRequest, VkLongPoll};
+    /// # let (key, server, ts) = (String::new(), String::new(), String::new());
+    ///
+    /// let stream = VkLongPoll::default().subscribe::<_, serde_json::Value>(LongPollRequest {
+    ///     key,
+    ///     server,
+    ///     ts,
+    ///     wait: 25,
+    ///     additional_params: (),
+    /// });
+    /// let stop = stream.stop_handle();
+    ///
+    /// async move {
+    ///     let events = tokio::spawn(stream.for_each(|r| async move { println!("{:?}", r) }));
+    ///     // on shutdown
+    ///     stop.stop();
+    ///     events.await.unwrap();
+    /// };
+    /// ```
+    #[must_use]
+    pub fn stop_handle(&self) -> StopHandle {
+        self.stop.clone()
+    }
+}
+
+#[cfg(feature = "longpoll_stream")]
+impl<S: futures_util::Stream> futures_util::Stream for LongPollStream<S> {
+    type Item = S::Item;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// Shared ts of the processed long poll events.
+#[cfg(feature = "longpoll_stream")]
+#[derive(Debug, Clone)]
+pub struct Checkpoint(std::sync::Arc<std::sync::Mutex<String>>);
+
+#[cfg(feature = "longpoll_stream")]
+impl Checkpoint {
+    fn new(ts: String) -> Self {
+        Self(std::sync::Arc::new(std::sync::Mutex::new(ts)))
+    }
+
+    fn set(&self, ts: &str) {
+        let mut current = self
+            .0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        current.clear();
+        current.push_str(ts);
+    }
+
+    /// Returns the ts of the last processed events.
+    #[must_use]
+    pub fn ts(&self) -> String {
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+    }
+}
+
+/// Handle of the [`LongPollStream`], which ends the stream.
+#[cfg(feature = "longpoll_stream")]
+#[derive(Debug, Clone, Default)]
+pub struct StopHandle(Arc<std::sync::atomic::AtomicBool>);
+
+#[cfg(feature = "longpoll_stream")]
+impl StopHandle {
+    /// Ends the stream after the current poll, the next poll isn't started.
+    pub fn stop(&self) {
+        self.0.store(true, std::sync::atomic::Ordering::Release);
+    }
+
+    /// Returns `true` if the stream is stopped.
+    #[must_use]
+    pub fn is_stopped(&self) -> bool {
+        self.0.load(std::sync::atomic::Ordering::Acquire)
+    }
+}
+
 impl From<Client> for VkLongPoll {
     fn from(client: Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            config: ServerConfig::default(),
+            #[cfg(feature = "longpoll_stream")]
+            retry: None,
+        }
     }
 }
 
 impl Default for VkLongPoll {
+    /// # Panics
+    /// Panics if the http client can't be built, use the client of [`VkApi`](crate::VkApi) to avoid it.
     fn default() -> Self {
-        Self::from(create_client())
+        Self::from(create_client(&ClientConfig::default()).expect("http client can't be built"))
     }
 }
 
+/// # Builder of the long poll client
+/// Configures the standalone [`VkLongPoll`], the one of [`VkApi::longpoll`](crate::VkApi::longpoll)
+/// shares the connection pool and the timeout of the API client.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use vkclient::longpoll::VkLongPoll;
+///
+/// let longpoll_client = VkLongPoll::builder()
+///     .with_timeout(Duration::from_secs(5))
+///     .with_allowed_hosts(["vk.com"])
+///     .with_metrics(|metrics: &vkclient::RequestMetrics<'_>| println!("{metrics:?}"))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct VkLongPollBuilder {
+    client: Option<Client>,
+    client_config: ClientConfig,
+    config: ServerConfig,
+    #[cfg(feature = "longpoll_stream")]
+    retry: Option<Backoff>,
+}
+
+impl VkLongPollBuilder {
+    /// Pass the http client, which replaces the one built from the connection settings.
+    #[must_use]
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Pass root certificates, which replace the built-in root set of the client.
+    #[must_use]
+    pub fn with_root_certificates(mut self, root_certificates: Vec<Certificate>) -> Self {
+        self.client_config.root_certificates = root_certificates;
+        self
+    }
+
+    /// Pass the pinned public keys of the long poll hosts, see [`TlsPins`](crate::TlsPins).
+    #[cfg(feature = "tls_pinning")]
+    #[must_use]
+    pub fn with_tls_pins(mut self, pins: crate::TlsPins) -> Self {
+        self.client_config.tls_pins = Some(pins);
+        self
+    }
+
+    /// Pass the timeout of establishing the connection.
+    #[must_use]
+    pub const fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.client_config.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Pass the timeout of the requests, which is added to their `wait` time. Default is unlimited.
+    #[must_use]
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = Some(timeout);
+        self
+    }
+
+    /// Pass the compression of the responses. Default is `Compression::Gzip` if it's enabled.
+    #[must_use]
+    pub const fn with_compression(mut self, compression: Compression) -> Self {
+        self.config.compression = compression;
+        self
+    }
+
+    /// Pass the hosts of the long poll servers, which the requests are allowed to.
+    /// Subdomains of the hosts are allowed too. Default is any host.
+    #[must_use]
+    pub fn with_allowed_hosts<I, S>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.allowed_hosts = Some(hosts.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Pass the backoff of the streams, see [`VkLongPoll::with_retry`].
+    #[cfg(feature = "longpoll_stream")]
+    #[must_use]
+    pub const fn with_retry(mut self, backoff: Backoff) -> Self {
+        self.retry = Some(backoff);
+        self
+    }
+
+    /// Pass the recorder of the requests metrics.
+    #[must_use]
+    pub fn with_metrics<M: crate::MetricsRecorder + 'static>(mut self, metrics: M) -> Self {
+        self.config.metrics = Some(Arc::new(metrics));
+        self
+    }
+
+    /// Pass the faults of the polls, see [`VkLongPoll::with_chaos`].
+    #[cfg(feature = "chaos")]
+    #[must_use]
+    pub fn with_chaos(mut self, chaos: crate::chaos::Chaos) -> Self {
+        self.config.chaos = Some(chaos);
+        self
+    }
+
+    /// Builds the client.
+    pub fn build(self) -> Result<VkLongPoll, BuildError> {
+        let client = match self.client {
+            Some(client) => client,
+            None => create_client(&self.client_config).map_err(BuildError::Client)?,
+        };
+
+        Ok(VkLongPoll {
+            client,
+            config: self.config,
+            #[cfg(feature = "longpoll_stream")]
+            retry: self.retry,
+        })
+    }
+}
+
+/// Long poll server returned by the API.
+#[cfg(feature = "longpoll_stream")]
+#[derive(Debug, Clone, Deserialize)]
+struct ServerInfo {
+    key: String,
+    server: String,
+    #[serde(deserialize_with = "deserialize_usize_or_string")]
+    ts: String,
+}
+
+/// Returns the key of the server in the [`ServerCache`] by the API method and params, which request it.
+#[cfg(feature = "longpoll_stream")]
+fn server_cache_key<P: Serialize>(method: &str, params: &P) -> String {
+    format!(
+        "{method}?{}",
+        serde_urlencoded::to_string(params).unwrap_or_default()
+    )
+}
+
+/// Long poll servers of the managed streams by their API method and params.
+/// Servers are reused by the new streams with the ts of the last received events,
+/// until the key expires or the events history is lost.
+#[cfg(feature = "longpoll_stream")]
+#[derive(Debug, Default)]
+pub(crate) struct ServerCache(std::sync::Mutex<std::collections::HashMap<String, ServerInfo>>);
+
+#[cfg(feature = "longpoll_stream")]
+impl ServerCache {
+    fn get(&self, key: &str) -> Option<ServerInfo> {
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(key)
+            .cloned()
+    }
+
+    fn set(&self, key: &str, server: ServerInfo) {
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .insert(key.to_owned(), server);
+    }
+
+    /// Updates the ts of the server, if its key isn't replaced by another stream.
+    fn set_ts(&self, key: &str, server_key: &str, ts: &str) {
+        if let Some(server) = self
+            .0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get_mut(key)
+            .filter(|server| server.key == server_key)
+        {
+            server.ts.clear();
+            server.ts.push_str(ts);
+        }
+    }
+
+    /// Removes the server, if its key isn't replaced by another stream.
+    fn invalidate(&self, key: &str, server_key: &str) {
+        let mut servers = self
+            .0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if servers
+            .get(key)
+            .is_some_and(|server| server.key == server_key)
+        {
+            servers.remove(key);
+        }
+    }
+}
+
+#[cfg(feature = "longpoll_stream")]
+#[derive(Clone, Serialize)]
+struct GroupServerRequest {
+    group_id: i64,
+}
+
+#[cfg(all(feature = "longpoll_stream", feature = "encode_json"))]
+#[derive(Clone, Serialize)]
+struct UserServerRequest {
+    lp_version: u32,
+    need_pts: u8,
+}
+
+#[cfg(all(feature = "longpoll_stream", feature = "encode_json"))]
+#[derive(Serialize)]
+struct HistoryRequest {
+    ts: String,
+    pts: u64,
+    lp_version: u32,
+}
+
+#[cfg(all(feature = "longpoll_stream", feature = "encode_json"))]
+#[derive(Clone, Serialize)]
+struct UserServerParams {
+    mode: LongPollMode,
+    version: u32,
+}
+
+/// Long poll server response.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
-enum LongPollResponse<R> {
+pub enum LongPollResponse<R> {
     Success(LongPollSuccess<R>),
     Error(LongPollError),
 }
 
+impl<R> LongPollResponse<R> {
+    fn into_result(self) -> VkApiResult<LongPollSuccess<R>> {
+        match self {
+            Self::Success(r) => Ok(r),
+            Self::Error(e) => Err(VkApiError::LongPoll(e)),
+        }
+    }
+}
+
 /// Long poll events chunk. You should to replace ts on next request with this value.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LongPollSuccess<R> {
@@ -190,6 +1395,34 @@ pub struct LongPollSuccess<R> {
     updates: Vec<R>,
 }
 
+impl<R> LongPollSuccess<R> {
+    /// Creates events chunk with given ts.
+    pub fn new<S: Into<String>>(ts: S, updates: Vec<R>) -> Self {
+        Self {
+            ts: ts.into(),
+            updates,
+        }
+    }
+
+    /// Returns the ts for the next request.
+    #[must_use]
+    pub fn ts(&self) -> &str {
+        &self.ts
+    }
+
+    /// Returns the events of the chunk.
+    #[must_use]
+    pub fn updates(&self) -> &[R] {
+        &self.updates
+    }
+
+    /// Returns the events of the chunk, dropping the ts.
+    #[must_use]
+    pub fn into_updates(self) -> Vec<R> {
+        self.updates
+    }
+}
+
 /// Long poll error.
 /// [Read more about possible errors](https://dev.vk.com/api/user-long-poll/getting-started#%D0%A4%D0%BE%D1%80%D0%BC%D0%B0%D1%82%20%D0%BE%D1%82%D0%B2%D0%B5%D1%82%D0%B0).
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -204,9 +1437,88 @@ pub struct LongPollError {
     max_version: Option<usize>,
 }
 
+impl LongPollError {
+    /// Creates error with given failure code.
+    #[must_use]
+    pub const fn new(failed: usize) -> Self {
+        Self {
+            failed,
+            ts: None,
+            min_version: None,
+            max_version: None,
+        }
+    }
+
+    /// Pass new ts, which server returns with the `failed: 1` code.
+    #[must_use]
+    pub fn with_ts<S: Into<String>>(mut self, ts: S) -> Self {
+        self.ts = Some(ts.into());
+        self
+    }
+
+    /// Pass supported versions range, which server returns with the `failed: 4` code.
+    #[must_use]
+    pub const fn with_versions(mut self, min_version: usize, max_version: usize) -> Self {
+        self.min_version = Some(min_version);
+        self.max_version = Some(max_version);
+        self
+    }
+
+    /// Returns the failure code.
+    #[must_use]
+    pub const fn failed(&self) -> usize {
+        self.failed
+    }
+
+    /// Returns the typed reason of the failure.
+    #[must_use]
+    pub const fn failure(&self) -> LongPollFailure {
+        match self.failed {
+            1 => LongPollFailure::Outdated,
+            2 => LongPollFailure::KeyExpired,
+            3 => LongPollFailure::InfoLost,
+            4 => LongPollFailure::InvalidVersion,
+            code => LongPollFailure::Unknown(code),
+        }
+    }
+
+    /// Returns the new ts of the `failed: 1` code.
+    #[must_use]
+    pub fn ts(&self) -> Option<&str> {
+        self.ts.as_deref()
+    }
+
+    /// Returns the minimum supported version of the `failed: 4` code.
+    #[must_use]
+    pub const fn min_version(&self) -> Option<usize> {
+        self.min_version
+    }
+
+    /// Returns the maximum supported version of the `failed: 4` code.
+    #[must_use]
+    pub const fn max_version(&self) -> Option<usize> {
+        self.max_version
+    }
+}
+
+/// Reason of the [`LongPollError`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum LongPollFailure {
+    /// Code 1, the events history is outdated or partially lost, continue with the new ts
+    Outdated,
+    /// Code 2, the key is expired, request the new one
+    KeyExpired,
+    /// Code 3, the user information is lost, request the new key and ts
+    InfoLost,
+    /// Code 4, the version isn't supported, see the supported range of the error
+    InvalidVersion,
+    /// Other codes
+    Unknown(usize),
+}
+
 impl Display for LongPollError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "long poll error occured, code: {}", self.failed,)
+        write!(f, "long poll error occured, code: {}", self.failed)
     }
 }
 
@@ -216,6 +1528,7 @@ impl Error for LongPollError {}
 /// * `server`, `key` and `ts` you should get from VK API.
 /// * `wait` is the timeout in seconds for this long poll request. Recommended value: 25.
 /// * `additional_params` is a custom struct, which will be inlined to request for passing external data, like a `mode`, `version`, etc. Put an empty tuple if you don't need it.
+///   Use [`LongPollMode`] for the `mode` of the User Long Poll API.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LongPollRequest<T> {
     pub server: String,
@@ -266,7 +1579,7 @@ impl<T> From<LongPollRequest<T>> for LongPollInnerRequest<T> {
 
 struct DeserializeUsizeOrString;
 
-impl<'de> serde::de::Visitor<'de> for DeserializeUsizeOrString {
+impl serde::de::Visitor<'_> for DeserializeUsizeOrString {
     type Value = String;
 
     fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
@@ -297,7 +1610,7 @@ impl<'de> serde::de::Visitor<'de> for DeserializeUsizeOrString {
 
 struct DeserializeUsizeOrStringOption;
 
-impl<'de> serde::de::Visitor<'de> for DeserializeUsizeOrStringOption {
+impl serde::de::Visitor<'_> for DeserializeUsizeOrStringOption {
     type Value = Option<String>;
 
     fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
@@ -351,7 +1664,7 @@ where
     deserializer.deserialize_any(DeserializeUsizeOrStringOption)
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "encode_json"))]
 mod tests {
     use crate::longpoll::{deserialize_usize_or_string, deserialize_usize_or_string_option};
     use serde::Deserialize;
@@ -369,6 +1682,153 @@ mod tests {
         ts: Option<String>,
     }
 
+    #[cfg(feature = "longpoll_stream")]
+    #[test]
+    fn heartbeat_on_empty_poll() {
+        use crate::longpoll::{LongPollSuccess, Polled};
+
+        let polled = LongPollSuccess::<i32>::new("2", vec![])
+            .into_results()
+            .into_polled();
+        assert!(matches!(polled.updates(), [Ok(Polled::Heartbeat { ts, .. })] if ts == "2"));
+
+        let polled = LongPollSuccess::new("3", vec![1, 2])
+            .into_results()
+            .into_polled();
+        let updates: Vec<_> = polled
+            .into_updates()
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(updates, [Polled::Update(1), Polled::Update(2)]);
+    }
+
+    #[cfg(all(feature = "longpoll_stream", feature = "encode_json"))]
+    #[tokio::test]
+    async fn filter_update_types() {
+        use crate::longpoll::{events_stream, LongPollRequest, LongPollSuccess};
+        use futures_util::StreamExt;
+        use serde_json::json;
+
+        #[derive(Debug, serde::Deserialize)]
+        struct MessageNew {
+            object: i32,
+        }
+
+        let mut chunks = vec![
+            LongPollSuccess::new(
+                "2",
+                vec![
+                    json!({"type": "message_new", "object": 1}),
+                    json!({"type": "wall_post_new", "object": {"id": 1}}),
+                    json!({"object": 2}),
+                    json!({"type": "message_new", "object": "exotic"}),
+                    json!({"type": "message_new", "object": 3}),
+                ],
+            ),
+            LongPollSuccess::new("3", vec![json!({"type": "message_new", "object": 4})]),
+        ]
+        .into_iter();
+        let request = LongPollRequest {
+            server: String::new(),
+            key: String::new(),
+            ts: "1".to_owned(),
+            wait: 0,
+            additional_params: (),
+        };
+
+        let stream = events_stream(request, None, move |_| {
+            let chunk = chunks
+                .next()
+                .map(|chunk| Ok(chunk.filter_types::<MessageNew>(&["message_new".to_owned()])));
+            async move { chunk }
+        });
+        let checkpoint = stream.checkpoint();
+        let updates: Vec<_> = stream.collect().await;
+
+        // the undecodable update fails only itself, the next updates and chunks are yielded
+        assert_eq!(updates.len(), 4);
+        assert!(updates[1].is_err());
+        let objects: Vec<_> = updates
+            .into_iter()
+            .filter_map(Result::ok)
+            .map(|update| update.object)
+            .collect();
+        assert_eq!(objects, [1, 3, 4]);
+        assert_eq!(checkpoint.ts(), "3");
+    }
+
+    #[cfg(feature = "longpoll_stream")]
+    #[tokio::test]
+    async fn merge_group_streams() {
+        use crate::longpoll::{
+            merge_streams, test_stream, LongPollError, LongPollResponse, LongPollSuccess,
+        };
+        use futures_util::StreamExt;
+
+        let busy = test_stream(vec![
+            LongPollResponse::Success(LongPollSuccess::new("1", (1..=50).collect())),
+            LongPollResponse::Success(LongPollSuccess::new("2", (51..=100).collect())),
+        ]);
+        let idle = test_stream(vec![
+            LongPollResponse::Success(LongPollSuccess::new("1", vec![0])),
+            LongPollResponse::Error(LongPollError::new(2)),
+        ]);
+
+        let updates: Vec<_> = merge_streams([(10, busy), (20, idle)])
+            .map(|(group_id, update)| (group_id, update.ok()))
+            .collect()
+            .await;
+
+        // the ready groups are polled in turns, so the idle group isn't starved behind the busy one
+        assert_eq!(
+            updates[..4],
+            [(10, Some(1)), (20, Some(0)), (10, Some(2)), (20, None)]
+        );
+        assert_eq!(
+            updates[4..],
+            (3..=100)
+                .map(|update| (10, Some(update)))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[cfg(feature = "longpoll_stream")]
+    #[tokio::test]
+    async fn stop_after_current_poll() {
+        use crate::longpoll::{test_stream, LongPollResponse, LongPollSuccess};
+        use futures_util::StreamExt;
+
+        let mut events = test_stream(vec![
+            LongPollResponse::Success(LongPollSuccess::new("1", vec![1, 2])),
+            LongPollResponse::Success(LongPollSuccess::new("2", vec![3])),
+        ]);
+        let stop = events.stop_handle();
+
+        assert_eq!(events.next().await.unwrap().unwrap(), 1);
+        stop.stop();
+        assert_eq!(events.next().await.unwrap().unwrap(), 2);
+        assert!(events.next().await.is_none());
+        assert_eq!(events.checkpoint().ts(), "1");
+    }
+
+    #[test]
+    fn long_poll_failures() {
+        use crate::longpoll::{LongPollError, LongPollFailure};
+
+        let error: LongPollError = serde_json::from_str(r#"{"failed":1,"ts":30}"#).unwrap();
+        assert_eq!(error.failure(), LongPollFailure::Outdated);
+        assert_eq!(error.ts(), Some("30"));
+
+        let error = LongPollError::new(4).with_versions(0, 3);
+        assert_eq!(error.failure(), LongPollFailure::InvalidVersion);
+        assert_eq!(
+            (error.min_version(), error.max_version()),
+            (Some(0), Some(3))
+        );
+        assert_eq!(LongPollError::new(7).failure(), LongPollFailure::Unknown(7));
+    }
+
     #[test]
     fn test_deserialize_ts_string() {
         let ts: Ts = serde_json::from_str(r#"{"ts": "123"}"#).unwrap();
@@ -398,4 +1858,145 @@ mod tests {
         let ts: TsOpt = serde_json::from_str("{}").unwrap();
         assert_eq!(ts.ts, None);
     }
+
+    #[cfg(feature = "longpoll_replay")]
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        use crate::longpoll::{test_stream, LongPollResponse, LongPollSuccess, VkLongPoll};
+        use futures_util::StreamExt;
+
+        let path =
+            std::env::temp_dir().join(format!("vkclient-replay-{}.ndjson", std::process::id()));
+
+        let recorded: Vec<_> = VkLongPoll::record(
+            test_stream(vec![LongPollResponse::Success(LongPollSuccess::new(
+                "1",
+                vec!["a".to_owned(), "b".to_owned()],
+            ))]),
+            &path,
+        )
+        .collect()
+        .await;
+        assert_eq!(recorded.len(), 2);
+
+        let replayed: Vec<String> = VkLongPoll::replay(&path, 0.0)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(replayed, vec!["a".to_owned(), "b".to_owned()]);
+    }
+
+    #[cfg(feature = "longpoll_stream")]
+    #[tokio::test]
+    async fn test_stream_recovers_ts_and_stops_on_failure() {
+        use crate::longpoll::{test_stream, LongPollError, LongPollResponse, LongPollSuccess};
+        use crate::VkApiError;
+        use futures_util::StreamExt;
+
+        let events: Vec<_> = test_stream(vec![
+            LongPollResponse::Success(LongPollSuccess::new("1", vec![1, 2])),
+            LongPollResponse::Error(LongPollError::new(1).with_ts("5")),
+            LongPollResponse::Success(LongPollSuccess::new("6", vec![3])),
+            LongPollResponse::Error(LongPollError::new(3)),
+            LongPollResponse::Success(LongPollSuccess::new("7", vec![4])),
+        ])
+        .collect()
+        .await;
+
+        assert_eq!(events.len(), 4);
+        assert_eq!(
+            events[..3]
+                .iter()
+                .map(|e| *e.as_ref().unwrap())
+                .collect::<Vec<i32>>(),
+            vec![1, 2, 3]
+        );
+        assert!(matches!(events[3], Err(VkApiError::LongPoll(_))));
+    }
+
+    #[cfg(feature = "longpoll_stream")]
+    #[tokio::test]
+    async fn subscription_retry_overrides_client() {
+        use crate::longpoll::{LongPollRequest, SubscribeOptions, VkLongPoll};
+        use crate::{Backoff, VkApiError};
+        use futures_util::StreamExt;
+        use std::time::Duration;
+
+        // the port is closed, so each poll fails with the transport error
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let server = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let hour = Duration::from_secs(3600);
+        let client = VkLongPoll::default().with_retry(Backoff::new(hour, hour));
+        let mut events = client.subscribe_with::<_, i32, _>(
+            LongPollRequest {
+                server,
+                key: String::new(),
+                ts: String::new(),
+                wait: 25,
+                additional_params: (),
+            },
+            SubscribeOptions::new().with_retry(
+                Backoff::new(Duration::from_millis(1), Duration::from_millis(1))
+                    .with_max_retries(1),
+            ),
+        );
+
+        let event = tokio::time::timeout(Duration::from_secs(10), events.next())
+            .await
+            .unwrap();
+        assert!(matches!(event, Some(Err(VkApiError::LongPollRequest(_)))));
+    }
+
+    #[cfg(feature = "longpoll_stream")]
+    #[tokio::test]
+    async fn checkpoint_follows_processed_chunks() {
+        use crate::longpoll::{test_stream, LongPollError, LongPollResponse, LongPollSuccess};
+        use futures_util::StreamExt;
+
+        let mut events = test_stream(vec![
+            LongPollResponse::Success(LongPollSuccess::new("1", vec![1, 2])),
+            LongPollResponse::Error(LongPollError::new(1).with_ts("5")),
+            LongPollResponse::Success(LongPollSuccess::new("6", vec![3])),
+        ]);
+        let checkpoint = events.checkpoint();
+
+        events.next().await;
+        events.next().await;
+        assert_eq!(checkpoint.ts(), "");
+
+        events.next().await;
+        assert_eq!(checkpoint.ts(), "5");
+
+        assert!(events.next().await.is_none());
+        assert_eq!(checkpoint.ts(), "6");
+    }
+
+    #[cfg(feature = "longpoll_stream")]
+    #[test]
+    fn cache_servers() {
+        use crate::longpoll::{ServerCache, ServerInfo};
+
+        let cache = ServerCache::default();
+        let server = |key: &str| ServerInfo {
+            key: key.to_owned(),
+            server: "lp.vk.com/wh1".to_owned(),
+            ts: "1".to_owned(),
+        };
+
+        cache.set("groups.getLongPollServer?group_id=1", server("a"));
+        cache.set_ts("groups.getLongPollServer?group_id=1", "a", "5");
+        assert_eq!(
+            cache.get("groups.getLongPollServer?group_id=1").unwrap().ts,
+            "5"
+        );
+
+        cache.invalidate("groups.getLongPollServer?group_id=1", "b");
+        assert!(cache.get("groups.getLongPollServer?group_id=1").is_some());
+        cache.invalidate("groups.getLongPollServer?group_id=1", "a");
+        assert!(cache.get("groups.getLongPollServer?group_id=1").is_none());
+    }
 }
@@ -0,0 +1,150 @@
+use crate::longpoll::{Checkpoint, LongPollStream, StopHandle};
+use crate::{VkApiError, VkApiResult};
+use futures_util::{Stream, StreamExt};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// Event of the [`LongPollBroadcast`], errors are shared by the receivers.
+pub type BroadcastEvent<I> = Result<I, Arc<VkApiError>>;
+
+/// # Broadcast of the long poll events
+/// Drives the stream in the background task and sends each event to every receiver,
+/// so the independent consumers, like the logger and the handler, get the same events.
+/// Receivers get only the events sent after they subscribed, and the ones, which fall behind
+/// by more than the capacity, get the [`broadcast::error::RecvError::Lagged`] error.
+/// The task ends with the stream or when the broadcast is dropped, then the receivers are closed.
+///
+/// ## Usage
+/// ```rust,no_run
+/// use vkclient::longpoll::{LongPollRequest, VkLongPoll};
+/// # let (key, server, ts) = (String::new(), String::new(), String::new());
+///
+/// async move {
+///     let broadcast = VkLongPoll::default()
+///         .subscribe::<_, serde_json::Value>(LongPollRequest {
+///             key,
+///             server,
+///             ts,
+///             wait: 25,
+///             additional_params: (),
+///         })
+///         .broadcast(64);
+///
+///     let mut logger = broadcast.subscribe();
+///     let mut handler = broadcast.subscribe();
+///     tokio::spawn(async move {
+///         while let Ok(event) = logger.recv().await {
+///             println!("{:?}", event);
+///         }
+///     });
+///     while let Ok(event) = handler.recv().await {
+///         // handle the event
+///     }
+/// };
+/// ```
+#[derive(Debug)]
+pub struct LongPollBroadcast<I> {
+    /// Weak, so the receivers are closed when the task ends
+    sender: broadcast::WeakSender<BroadcastEvent<I>>,
+    task: JoinHandle<()>,
+    checkpoint: Checkpoint,
+    stop: StopHandle,
+}
+
+impl<S> LongPollStream<S> {
+    /// Spawns the stream to the [`LongPollBroadcast`] with the `capacity` of the events, kept for the slow receivers.
+    /// It must be called inside the tokio runtime.
+    #[must_use]
+    pub fn broadcast<I>(self, capacity: usize) -> LongPollBroadcast<I>
+    where
+        S: Stream<Item = VkApiResult<I>> + Send + 'static,
+        I: Clone + Send + 'static,
+    {
+        let (events, _) = broadcast::channel(capacity.max(1));
+        let (checkpoint, stop) = (self.checkpoint(), self.stop_handle());
+
+        let sender = events.downgrade();
+        let task = tokio::spawn(async move {
+            let mut stream = self;
+            while let Some(event) = stream.next().await {
+                // events without the receivers are dropped
+                let _ = events.send(event.map_err(Arc::new));
+            }
+        });
+
+        LongPollBroadcast {
+            sender,
+            task,
+            checkpoint,
+            stop,
+        }
+    }
+}
+
+impl<I: Clone> LongPollBroadcast<I> {
+    /// Returns the receiver of the events sent after this call.
+    /// The receiver is closed, if the stream ended.
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<BroadcastEvent<I>> {
+        match self.sender.upgrade() {
+            Some(sender) => sender.subscribe(),
+            None => broadcast::channel(1).1,
+        }
+    }
+
+    /// Returns the count of the active receivers.
+    #[must_use]
+    pub fn receiver_count(&self) -> usize {
+        self.sender
+            .upgrade()
+            .map_or(0, |sender| sender.receiver_count())
+    }
+
+    /// Returns the ts of the events, which are sent to the receivers.
+    #[must_use]
+    pub fn checkpoint(&self) -> Checkpoint {
+        self.checkpoint.clone()
+    }
+
+    /// Returns the handle, which ends the stream after the current poll.
+    #[must_use]
+    pub fn stop_handle(&self) -> StopHandle {
+        self.stop.clone()
+    }
+
+    /// Returns true if the stream ended.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl<I> Drop for LongPollBroadcast<I> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::longpoll::{test_stream, LongPollError, LongPollResponse, LongPollSuccess};
+
+    #[tokio::test]
+    async fn send_events_to_every_receiver() {
+        let broadcast = test_stream(vec![
+            LongPollResponse::Success(LongPollSuccess::new("1", vec![1, 2])),
+            LongPollResponse::Error(LongPollError::new(2)),
+        ])
+        .broadcast(8);
+        let (mut first, mut second) = (broadcast.subscribe(), broadcast.subscribe());
+
+        for receiver in [&mut first, &mut second] {
+            assert_eq!(receiver.recv().await.unwrap().unwrap(), 1);
+            assert_eq!(receiver.recv().await.unwrap().unwrap(), 2);
+            assert!(receiver.recv().await.unwrap().is_err());
+            assert!(receiver.recv().await.is_err());
+        }
+        assert!(broadcast.subscribe().recv().await.is_err());
+    }
+}
@@ -0,0 +1,127 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+/// # Update, which may fail the typed decoding
+/// Subscribe with `Decoded<I>` instead of `I` to keep the stream, when a single update doesn't match
+/// its model: the update is yielded as [`Decoded::Failed`] with the raw value and the error,
+/// and the next updates are decoded as usual.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use futures_util::StreamExt;
+/// use vkclient::longpoll::{Decoded, LongPollRequest, VkLongPoll};
+/// # let (key, server, ts) = (String::new(), String::new(), String::new());
+///
+/// #[derive(Debug, serde::Deserialize)]
+/// struct Event {
+///     r#type: String,
+///     object: serde_json::Value,
+/// }
+///
+/// let longpoll_client = VkLongPoll::default();
+///
+/// longpoll_client
+///     .subscribe::<_, Decoded<Event>>(LongPollRequest {
+///         key,
+///         server,
+///         ts,
+///         wait: 25,
+///         additional_params: (),
+///     })
+///     .for_each(|event| async move {
+///         match event {
+///             Ok(Decoded::Ok(event)) => println!("{:?}", event),
+///             Ok(Decoded::Failed(failure)) => eprintln!("{}: {}", failure.error, failure.raw),
+///             Err(e) => eprintln!("{}", e),
+///         }
+///     });
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Decoded<I> {
+    Ok(I),
+    Failed(DecodeFailure),
+}
+
+/// Update, which doesn't match the model of the [`Decoded`]. It's serialized as the raw update.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeFailure {
+    /// Whole update as it's received
+    pub raw: Value,
+    /// Message of the decoding error
+    pub error: String,
+}
+
+impl<I> Decoded<I> {
+    /// Returns the decoded update, dropping the failure.
+    pub fn ok(self) -> Option<I> {
+        match self {
+            Self::Ok(update) => Some(update),
+            Self::Failed(_) => None,
+        }
+    }
+
+    /// Converts the failure into the `Err`.
+    pub fn into_result(self) -> Result<I, DecodeFailure> {
+        match self {
+            Self::Ok(update) => Ok(update),
+            Self::Failed(failure) => Err(failure),
+        }
+    }
+}
+
+impl<'de, I: serde::de::DeserializeOwned> Deserialize<'de> for Decoded<I> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = Value::deserialize(deserializer)?;
+        Ok(match I::deserialize(&raw) {
+            Ok(update) => Self::Ok(update),
+            Err(e) => Self::Failed(DecodeFailure {
+                raw,
+                error: e.to_string(),
+            }),
+        })
+    }
+}
+
+impl Serialize for DecodeFailure {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.raw.serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::longpoll::{Decoded, LongPollResponse};
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Like {
+        liker_id: i64,
+    }
+
+    #[test]
+    fn keep_undecodable_updates() {
+        let LongPollResponse::Success(success) =
+            serde_json::from_str::<LongPollResponse<Decoded<Like>>>(
+                r#"{"ts":"2","updates":[{"liker_id":1},{"liker_id":"exotic"},{"liker_id":3}]}"#,
+            )
+            .unwrap()
+        else {
+            panic!("failed response")
+        };
+
+        let updates = success.into_updates();
+        assert_eq!(updates[0], Decoded::Ok(Like { liker_id: 1 }));
+        assert!(matches!(
+            &updates[1],
+            Decoded::Failed(failure) if failure.raw["liker_id"] == "exotic" && !failure.error.is_empty()
+        ));
+        assert_eq!(
+            serde_json::to_string(&updates[1]).unwrap(),
+            r#"{"liker_id":"exotic"}"#
+        );
+        assert_eq!(
+            updates.into_iter().nth(2).and_then(Decoded::ok),
+            Some(Like { liker_id: 3 })
+        );
+    }
+}
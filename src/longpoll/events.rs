@@ -0,0 +1,179 @@
+use crate::messages::{Message, MessageRead, TypingState};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// # Event of the Bots Long Poll API
+/// Typed [community events](https://dev.vk.com/api/community-events/json-schema),
+/// which are decoded from the updates of the long poll stream or the Callback API requests.
+/// Events of the new types and the events, which don't match their models, are kept as [`GroupEvent::Unknown`].
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use futures_util::StreamExt;
+/// use vkclient::longpoll::{GroupEvent, LongPollRequest, VkLongPoll};
+/// # let (key, server, ts) = (String::new(), String::new(), String::new());
+///
+/// let longpoll_client = VkLongPoll::default();
+///
+/// longpoll_client
+///     .subscribe::<_, GroupEvent>(LongPollRequest {
+///         key,
+///         server,
+///         ts,
+///         wait: 25,
+///         additional_params: (),
+///     })
+///     .for_each(|event| async move {
+///         if let Ok(GroupEvent::MessageNew(event)) = event {
+///             println!("{}", event.message.text);
+///         }
+///     });
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "object", rename_all = "snake_case")]
+pub enum GroupEvent {
+    MessageNew(MessageNew),
+    MessageReply(Message),
+    MessageEdit(Message),
+    /// Click on the callback button
+    MessageEvent(MessageEvent),
+    MessageRead(MessageRead),
+    MessageTypingState(TypingState),
+    MessageAllow(MessageAllow),
+    MessageDeny(MessageDeny),
+    WallPostNew(WallPost),
+    WallRepost(WallPost),
+    GroupJoin(GroupJoin),
+    GroupLeave(GroupLeave),
+    /// Whole update of the other events
+    #[serde(untagged)]
+    Unknown(Value),
+}
+
+/// Object of the `message_new` event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageNew {
+    pub message: Message,
+    #[serde(default)]
+    pub client_info: Option<ClientInfo>,
+}
+
+/// Features of the user client, which sent the message.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ClientInfo {
+    #[serde(default)]
+    pub button_actions: Vec<String>,
+    #[serde(default)]
+    pub keyboard: bool,
+    #[serde(default)]
+    pub inline_keyboard: bool,
+    #[serde(default)]
+    pub carousel: bool,
+    #[serde(default)]
+    pub lang_id: i32,
+}
+
+/// Object of the `message_event` event, answer it with
+/// [messages.sendMessageEventAnswer](https://dev.vk.com/method/messages.sendMessageEventAnswer).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageEvent {
+    pub user_id: i64,
+    pub peer_id: i64,
+    pub event_id: String,
+    #[serde(default)]
+    pub payload: Option<Value>,
+    #[serde(default)]
+    pub conversation_message_id: Option<i64>,
+}
+
+/// Object of the `message_allow` event.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MessageAllow {
+    pub user_id: i64,
+    /// Param of the messages subscription widget
+    #[serde(default)]
+    pub key: Option<String>,
+}
+
+/// Object of the `message_deny` event.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MessageDeny {
+    pub user_id: i64,
+}
+
+/// Object of the `wall_post_new` and `wall_repost` events.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WallPost {
+    pub id: i64,
+    pub owner_id: i64,
+    #[serde(default)]
+    pub from_id: Option<i64>,
+    /// Admin, who published the post of the community
+    #[serde(default)]
+    pub created_by: Option<i64>,
+    pub date: i64,
+    #[serde(default)]
+    pub text: String,
+    /// `post`, `copy`, `reply`, `postpone` or `suggest`
+    #[serde(default)]
+    pub post_type: Option<String>,
+    #[serde(default)]
+    pub attachments: Vec<Value>,
+}
+
+/// Object of the `group_join` event.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GroupJoin {
+    pub user_id: i64,
+    /// `join`, `unsure`, `accepted`, `approved` or `request`
+    #[serde(default)]
+    pub join_type: Option<String>,
+}
+
+/// Object of the `group_leave` event.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GroupLeave {
+    pub user_id: i64,
+    /// 1 if the user left by themselves, 0 if the user was removed
+    #[serde(rename = "self", default)]
+    pub by_self: u8,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::longpoll::{GroupEvent, LongPollResponse};
+    use crate::messages::MessageRead;
+
+    #[test]
+    fn deserialize_group_events() {
+        let LongPollResponse::Success(success) =
+            serde_json::from_str::<LongPollResponse<GroupEvent>>(include_str!(
+                "../../fixtures/longpoll.bots.json"
+            ))
+            .unwrap()
+        else {
+            panic!("failed response")
+        };
+        assert!(matches!(
+            success.updates[0],
+            GroupEvent::MessageRead(MessageRead {
+                read_message_id: 52110,
+                ..
+            })
+        ));
+
+        let event: GroupEvent = serde_json::from_str(
+            r#"{"type":"message_new","object":{"message":{"id":1,"from_id":1,"peer_id":1,"date":0,"text":"hi"},"client_info":{"keyboard":true}}}"#,
+        )
+        .unwrap();
+        assert!(matches!(event, GroupEvent::MessageNew(event) if event.message.text == "hi"));
+
+        let event: GroupEvent =
+            serde_json::from_str(r#"{"type":"like_add","object":{"liker_id":1}}"#).unwrap();
+        assert!(matches!(&event, GroupEvent::Unknown(update) if update["type"] == "like_add"));
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"object":{"liker_id":1},"type":"like_add"}"#
+        );
+    }
+}
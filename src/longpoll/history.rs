@@ -0,0 +1,116 @@
+use crate::longpoll::{UserMessage, UserUpdate};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Flag of the outgoing message in the [`UserMessage::flags`].
+const OUTBOX: u32 = 2;
+
+/// # Missed updates of the User Long Poll API
+/// Response of [messages.getLongPollHistory](https://dev.vk.com/method/messages.getLongPollHistory)
+/// with the updates since the stored ts and pts. Request the next page with the `new_pts`, while `more` is 1.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LongPollHistory {
+    /// Updates in the short form, new messages are without their text
+    #[serde(default)]
+    pub history: Vec<UserUpdate>,
+    #[serde(default)]
+    pub messages: HistoryMessages,
+    #[serde(default)]
+    pub new_pts: Option<u64>,
+    #[serde(default)]
+    pub more: u8,
+}
+
+/// Messages of the [`LongPollHistory`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryMessages {
+    #[serde(default)]
+    pub count: u64,
+    #[serde(default)]
+    pub items: Vec<Value>,
+}
+
+impl LongPollHistory {
+    /// Returns the updates in order, new messages are replaced by the full ones from the `messages`.
+    pub fn into_updates(self) -> Vec<UserUpdate> {
+        let mut messages: HashMap<i64, UserMessage> = self
+            .messages
+            .items
+            .iter()
+            .filter_map(history_message)
+            .map(|message| (message.message_id, message))
+            .collect();
+
+        self.history
+            .into_iter()
+            .map(|update| {
+                let message_id = match &update {
+                    UserUpdate::MessageNew(message) => Some(message.message_id),
+                    UserUpdate::Unknown(values) if values.first() == Some(&4.into()) => {
+                        values.get(1).and_then(Value::as_i64)
+                    }
+                    _ => None,
+                };
+
+                match message_id.and_then(|id| messages.remove(&id)) {
+                    Some(message) => UserUpdate::MessageNew(message),
+                    None => update,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Converts the message object of the API to the message of the updates.
+fn history_message(message: &Value) -> Option<UserMessage> {
+    let int = |field: &str| message.get(field)?.as_i64();
+    let outbox = if int("out") == Some(1) { OUTBOX } else { 0 };
+
+    Some(UserMessage {
+        message_id: int("id")?,
+        flags: outbox,
+        peer_id: int("peer_id")?,
+        timestamp: int("date")?,
+        text: message.get("text")?.as_str()?.to_owned(),
+        extra: None,
+        attachments: message.get("attachments").cloned(),
+        random_id: int("random_id"),
+        conversation_message_id: int("conversation_message_id"),
+        edit_time: int("update_time"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::longpoll::{LongPollHistory, UserUpdate};
+
+    #[test]
+    fn replace_history_messages() {
+        let history: LongPollHistory = serde_json::from_str(
+            r#"{
+                "history": [[4, 1619, 51, 100001], [6, 100001, 1618]],
+                "messages": {"count": 1, "items": [
+                    {"id": 1619, "peer_id": 100001, "from_id": 1, "date": 1700000000, "out": 1, "text": "hi", "conversation_message_id": 12}
+                ]},
+                "new_pts": 10,
+                "more": 0
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(history.new_pts, Some(10));
+
+        let updates = history.into_updates();
+        assert!(matches!(
+            &updates[0],
+            UserUpdate::MessageNew(message) if message.text == "hi" && message.flags == 2
+        ));
+        assert_eq!(
+            updates[1],
+            UserUpdate::InboxRead {
+                peer_id: 100001,
+                message_id: 1618
+            }
+        );
+    }
+}
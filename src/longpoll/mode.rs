@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use std::ops::{BitOr, BitOrAssign};
+
+/// # Mode of the User Long Poll API
+/// Flags of the additional fields of the updates, which are passed as the numeric `mode` param
+/// of the [long poll requests](https://dev.vk.com/api/user-long-poll/getting-started).
+///
+/// ## Usage:
+/// ```rust
+/// use vkclient::longpoll::LongPollMode;
+///
+/// let mode = LongPollMode::ATTACHMENTS | LongPollMode::EXTRA | LongPollMode::RANDOM_ID;
+/// assert_eq!(mode.bits(), 194);
+/// assert!(mode.contains(LongPollMode::EXTRA));
+/// ```
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LongPollMode(u32);
+
+impl LongPollMode {
+    /// No additional fields
+    pub const NONE: Self = Self(0);
+    /// Attachments and the forwarded messages
+    pub const ATTACHMENTS: Self = Self(2);
+    /// Extended set of the events
+    pub const EXTENDED: Self = Self(8);
+    /// `pts` of the events
+    pub const PTS: Self = Self(32);
+    /// Platform of the friend in the online events
+    pub const EXTRA: Self = Self(64);
+    /// `random_id` of the messages
+    pub const RANDOM_ID: Self = Self(128);
+
+    /// Creates the mode from the raw flags, the unknown ones are kept.
+    #[must_use]
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the raw flags.
+    #[must_use]
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Returns true if all flags of the `other` are set.
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for LongPollMode {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for LongPollMode {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::longpoll::LongPollMode;
+
+    #[test]
+    fn serialize_mode() {
+        #[derive(serde::Serialize)]
+        struct Params {
+            mode: LongPollMode,
+        }
+
+        let mut mode = LongPollMode::ATTACHMENTS | LongPollMode::EXTENDED;
+        mode |= LongPollMode::PTS;
+
+        assert_eq!(
+            serde_urlencoded::to_string(Params { mode }).unwrap(),
+            "mode=42"
+        );
+        assert!(!mode.contains(LongPollMode::RANDOM_ID));
+    }
+}
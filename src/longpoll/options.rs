@@ -0,0 +1,314 @@
+use crate::longpoll::{LongPollRequest, LongPollSuccess, Polled};
+use crate::{Backoff, VkApiResult};
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Callback of the new long poll server.
+type Refresh<T> =
+    dyn Fn() -> Pin<Box<dyn Future<Output = VkApiResult<LongPollRequest<T>>> + Send>> + Send + Sync;
+
+/// # Options of the long poll subscription
+/// Options of the [`VkLongPoll::subscribe_with`](crate::longpoll::VkLongPoll::subscribe_with) stream,
+/// which can be combined. Default options subscribe like the [`VkLongPoll::subscribe`](crate::longpoll::VkLongPoll::subscribe).
+///
+/// ## Usage
+/// ```rust,no_run
+/// use futures_util::StreamExt;
+/// use vkclient::longpoll::{LongPollRequest, SubscribeOptions, VkLongPoll};
+/// use vkclient::VkApiResult;
+/// # let (key, server, ts) = (String::new(), String::new(), String::new());
+///
+/// async fn refresh() -> VkApiResult<LongPollRequest<()>> {
+///     // request the server with the groups.getLongPollServer
+///     # unimplemented!()
+/// }
+///
+/// let longpoll_client = VkLongPoll::default();
+///
+/// longpoll_client
+///     .subscribe_with::<_, serde_json::Value, _>(
+///         LongPollRequest {
+///             key,
+///             server,
+///             ts,
+///             wait: 25,
+///             additional_params: (),
+///         },
+///         SubscribeOptions::new()
+///             .with_chunks()
+///             .with_refresh(refresh)
+///             .with_types(&["message_new"]),
+///     )
+///     .for_each(|chunk| async move { println!("{:?}", chunk) });
+/// ```
+pub struct SubscribeOptions<T, M = Updates> {
+    pub(super) refresh: Option<Arc<Refresh<T>>>,
+    pub(super) retry: Option<Backoff>,
+    #[cfg(feature = "encode_json")]
+    pub(super) types: Option<Arc<[String]>>,
+    items: PhantomData<fn() -> M>,
+}
+
+impl<T> SubscribeOptions<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            refresh: None,
+            retry: None,
+            #[cfg(feature = "encode_json")]
+            types: None,
+            items: PhantomData,
+        }
+    }
+
+    /// Yield the [`Polled::Heartbeat`] after each poll without updates.
+    /// Supervise the stream by the time of the last item to tell the quiet stream from the stuck one.
+    ///
+    /// ## Usage
+    /// ```rust,no_run
+    /// use futures_util::StreamExt;
+    /// use vkclient::longpoll::{LongPollRequest, Polled, SubscribeOptions, VkLongPoll};
+    /// # let (key, server, ts) = (String::new(), String::new(), String::new());
+    ///
+    /// let longpoll_client = VkLongPoll::default();
+    ///
+    /// longpoll_client
+    ///     .subscribe_with::<_, serde_json::Value, _>(
+    ///         LongPollRequest {
+    ///             key,
+    ///             server,
+    ///             ts,
+    ///             wait: 25,
+    ///             additional_params: (),
+    ///         },
+    ///         SubscribeOptions::new().with_heartbeats(),
+    ///     )
+    ///     .for_each(|polled| async move {
+    ///         match polled {
+    ///             Ok(Polled::Update(update)) => println!("{:?}", update),
+    ///             Ok(Polled::Heartbeat { ts, .. }) => println!("no updates till {}", ts),
+    ///             Err(e) => eprintln!("{}", e),
+    ///         }
+    ///     });
+    /// ```
+    #[must_use]
+    pub fn with_heartbeats(self) -> SubscribeOptions<T, Heartbeats> {
+        self.with_items()
+    }
+
+    /// Yield the chunks of the updates instead of the updates one by one.
+    /// Each chunk has the ts of the next one, persist it after all events of the chunk are handled
+    /// and pass as the request ts after restart to continue from them.
+    /// Polls without updates yield the empty chunks, so they are the heartbeats as well.
+    ///
+    /// ## Usage
+    /// ```rust,no_run
+    /// use futures_util::StreamExt;
+    /// use vkclient::longpoll::{LongPollRequest, SubscribeOptions, VkLongPoll};
+    /// # let (key, server, ts) = (String::new(), String::new(), String::new());
+    ///
+    /// let longpoll_client = VkLongPoll::default();
+    ///
+    /// longpoll_client
+    ///     .subscribe_with::<_, serde_json::Value, _>(
+    ///         LongPollRequest {
+    ///             key,
+    ///             server,
+    ///             ts,
+    ///             wait: 25,
+    ///             additional_params: (),
+    ///         },
+    ///         SubscribeOptions::new().with_chunks(),
+    ///     )
+    ///     .for_each(|chunk| async move {
+    ///         if let Ok(chunk) = chunk {
+    ///             println!("{:?}", chunk.updates());
+    ///             println!("resume from {}", chunk.ts());
+    ///         }
+    ///     });
+    /// ```
+    #[must_use]
+    pub fn with_chunks(self) -> SubscribeOptions<T, Chunks> {
+        self.with_items()
+    }
+}
+
+impl<T, M: PollItems> SubscribeOptions<T, M> {
+    /// Pass the `refresh`, which is called for the new server instead of ending on the `failed: 2` and `failed: 3` errors.
+    /// The ts of the refreshed request is used only after the `failed: 3`, when the events history is lost.
+    /// The stream ends with the error, if `refresh` fails or the refreshed server fails again.
+    #[must_use]
+    pub fn with_refresh<F, Fut>(mut self, refresh: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = VkApiResult<LongPollRequest<T>>> + Send + 'static,
+    {
+        self.refresh = Some(Arc::new(move || Box::pin(refresh())));
+        self
+    }
+
+    /// Pass the backoff of the subscription, which overrides the backoff of the
+    /// [`VkLongPoll::with_retry`](crate::longpoll::VkLongPoll::with_retry) for this stream only.
+    ///
+    /// ## Usage
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use vkclient::longpoll::{LongPollRequest, SubscribeOptions, VkLongPoll};
+    /// use vkclient::Backoff;
+    /// # let (key, server, ts) = (String::new(), String::new(), String::new());
+    ///
+    /// let longpoll_client = VkLongPoll::default()
+    ///     .with_retry(Backoff::new(Duration::from_secs(1), Duration::from_secs(30)));
+    ///
+    /// let stream = longpoll_client.subscribe_with::<_, serde_json::Value, _>(
+    ///     LongPollRequest {
+    ///         key,
+    ///         server,
+    ///         ts,
+    ///         wait: 25,
+    ///         additional_params: (),
+    ///     },
+    ///     SubscribeOptions::new()
+    ///         .with_retry(Backoff::new(Duration::from_millis(100), Duration::from_secs(1)).with_max_retries(3)),
+    /// );
+    /// ```
+    #[must_use]
+    pub const fn with_retry(mut self, backoff: Backoff) -> Self {
+        self.retry = Some(backoff);
+        self
+    }
+
+    /// Yield only the updates with the `type` field in `types`.
+    /// Other updates are dropped before they are decoded to `I`, so `I` may describe only the wanted events.
+    /// Wanted updates are decoded one by one, the update, which doesn't match `I`, is yielded as the error
+    /// and the stream continues.
+    ///
+    /// ## Usage
+    /// ```rust,no_run
+    /// use futures_util::StreamExt;
+    /// use vkclient::longpoll::{LongPollRequest, SubscribeOptions, VkLongPoll};
+    /// # let (key, server, ts) = (String::new(), String::new(), String::new());
+    ///
+    /// let longpoll_client = VkLongPoll::default();
+    ///
+    /// longpoll_client
+    ///     .subscribe_with::<_, serde_json::Value, _>(
+    ///         LongPollRequest {
+    ///             key,
+    ///             server,
+    ///             ts,
+    ///             wait: 25,
+    ///             additional_params: (),
+    ///         },
+    ///         SubscribeOptions::new().with_types(&["message_new", "message_event"]),
+    ///     )
+    ///     .for_each(|r| async move { println!("{:?}", r) });
+    /// ```
+    #[cfg(feature = "encode_json")]
+    #[must_use]
+    pub fn with_types(mut self, types: &[&str]) -> Self {
+        self.types = Some(types.iter().map(|t| (*t).to_owned()).collect());
+        self
+    }
+
+    fn with_items<N>(self) -> SubscribeOptions<T, N> {
+        SubscribeOptions {
+            refresh: self.refresh,
+            retry: self.retry,
+            #[cfg(feature = "encode_json")]
+            types: self.types,
+            items: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for SubscribeOptions<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, M> Clone for SubscribeOptions<T, M> {
+    fn clone(&self) -> Self {
+        Self {
+            refresh: self.refresh.clone(),
+            retry: self.retry,
+            #[cfg(feature = "encode_json")]
+            types: self.types.clone(),
+            items: PhantomData,
+        }
+    }
+}
+
+impl<T, M> Debug for SubscribeOptions<T, M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("SubscribeOptions");
+        debug.field("refresh", &self.refresh.is_some());
+        debug.field("retry", &self.retry);
+        #[cfg(feature = "encode_json")]
+        debug.field("types", &self.types);
+        debug.field("items", &std::any::type_name::<M>()).finish()
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Items of the [`VkLongPoll::subscribe_with`](crate::longpoll::VkLongPoll::subscribe_with) stream,
+/// which are made of the polled chunks of the updates.
+pub trait PollItems: sealed::Sealed {
+    type Item<I>;
+
+    #[doc(hidden)]
+    fn items<I>(
+        chunk: LongPollSuccess<VkApiResult<I>>,
+    ) -> LongPollSuccess<VkApiResult<Self::Item<I>>>;
+}
+
+/// Updates of the chunks, the items of the default [`SubscribeOptions`].
+#[derive(Debug, Clone, Copy)]
+pub struct Updates;
+
+impl sealed::Sealed for Updates {}
+
+impl PollItems for Updates {
+    type Item<I> = I;
+
+    fn items<I>(chunk: LongPollSuccess<VkApiResult<I>>) -> LongPollSuccess<VkApiResult<I>> {
+        chunk
+    }
+}
+
+/// Updates and heartbeats, see [`SubscribeOptions::with_heartbeats`].
+#[derive(Debug, Clone, Copy)]
+pub struct Heartbeats;
+
+impl sealed::Sealed for Heartbeats {}
+
+impl PollItems for Heartbeats {
+    type Item<I> = Polled<I>;
+
+    fn items<I>(chunk: LongPollSuccess<VkApiResult<I>>) -> LongPollSuccess<VkApiResult<Polled<I>>> {
+        chunk.into_polled()
+    }
+}
+
+/// Chunks of the updates, see [`SubscribeOptions::with_chunks`].
+#[derive(Debug, Clone, Copy)]
+pub struct Chunks;
+
+impl sealed::Sealed for Chunks {}
+
+impl PollItems for Chunks {
+    type Item<I> = LongPollSuccess<I>;
+
+    fn items<I>(
+        chunk: LongPollSuccess<VkApiResult<I>>,
+    ) -> LongPollSuccess<VkApiResult<LongPollSuccess<I>>> {
+        chunk.into_chunk()
+    }
+}
@@ -0,0 +1,287 @@
+use crate::longpoll::{LongPollError, LongPollRequest, LongPollSuccess, VkLongPoll};
+use crate::{VkApiError, VkApiResult};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+#[cfg(feature = "longpoll_stream")]
+use std::future::Future;
+use std::marker::PhantomData;
+use std::ops::RangeInclusive;
+#[cfg(feature = "longpoll_stream")]
+use std::pin::Pin;
+#[cfg(feature = "longpoll_stream")]
+use std::task::{Context, Poll};
+
+/// Wait bounds in seconds, which are accepted by VK long poll servers.
+pub const WAIT_RANGE: RangeInclusive<usize> = 1..=90;
+
+/// # Manually driven long poll client
+/// Owns the request and keeps its ts actual between calls,
+/// so the manual polling loops don't copy the ts by hand.
+/// It's also a stream of the events, like the [`VkLongPoll::subscribe`] stream.
+///
+/// ## Usage
+/// ```rust,no_run
+/// use vkclient::longpoll::{VkLongPoll, LongPollRequest};
+/// # let (key, server, ts) = (String::new(), String::new(), String::new());
+///
+/// let longpoll_client = VkLongPoll::default();
+///
+/// let mut poller = longpoll_client
+///     .poller::<_, serde_json::Value>(LongPollRequest {
+///         key,
+///         server,
+///         ts,
+///         wait: 25,
+///         additional_params: (),
+///     })
+///     .unwrap();
+///
+/// async move {
+///     // catch up the missed events without waiting
+///     let missed = poller.poll_now().await.unwrap();
+///     let chunk = poller.next_chunk().await.unwrap();
+///     println!("{} events before ts {}", chunk.updates().len(), chunk.ts());
+/// };
+/// ```
+pub struct LongPoller<T, I> {
+    client: VkLongPoll,
+    request: LongPollRequest<T>,
+    #[cfg(feature = "longpoll_stream")]
+    stream: StreamState<I>,
+    events: PhantomData<fn() -> I>,
+}
+
+impl VkLongPoll {
+    /// Returns the manually driven long poll client for the request.
+    /// The request wait must be in the [`WAIT_RANGE`].
+    pub fn poller<T, I>(
+        &self,
+        request: LongPollRequest<T>,
+    ) -> Result<LongPoller<T, I>, InvalidWait> {
+        validate_wait(request.wait)?;
+
+        Ok(LongPoller {
+            client: self.clone(),
+            request,
+            #[cfg(feature = "longpoll_stream")]
+            stream: StreamState::default(),
+            events: PhantomData,
+        })
+    }
+}
+
+impl<T: Serialize + Clone + Send, I: DeserializeOwned> LongPoller<T, I> {
+    /// Waits for the events chunk up to the request wait and returns it with the new ts.
+    /// The ts of the next request is moved to the returned one.
+    pub async fn next_chunk(&mut self) -> VkApiResult<LongPollSuccess<I>> {
+        let request = self.request.clone();
+        self.fetch(request).await
+    }
+
+    /// Waits for the events up to the request wait and returns them.
+    pub async fn poll(&mut self) -> VkApiResult<Vec<I>> {
+        self.next_chunk().await.map(LongPollSuccess::into_updates)
+    }
+
+    /// Returns the already available events without waiting, with `wait=0`.
+    /// Use it to catch up the events after a pause, before the regular polling.
+    pub async fn poll_now(&mut self) -> VkApiResult<Vec<I>> {
+        let request = LongPollRequest {
+            wait: 0,
+            ..self.request.clone()
+        };
+        self.fetch(request).await.map(LongPollSuccess::into_updates)
+    }
+
+    async fn fetch(&mut self, request: LongPollRequest<T>) -> VkApiResult<LongPollSuccess<I>> {
+        let chunk = recover(self.client.subscribe_once(request).await)?;
+        self.request.ts.clone_from(&chunk.ts);
+        Ok(chunk)
+    }
+}
+
+/// Continues from the new ts of the outdated events history, like the stream does.
+fn recover<I>(response: VkApiResult<LongPollSuccess<I>>) -> VkApiResult<LongPollSuccess<I>> {
+    match response {
+        Err(VkApiError::LongPoll(LongPollError { ts: Some(ts), .. })) => {
+            Ok(LongPollSuccess::new(ts, Vec::new()))
+        }
+        response => response,
+    }
+}
+
+impl<T, I> LongPoller<T, I> {
+    /// Returns the ts of the next request.
+    pub fn ts(&self) -> &str {
+        &self.request.ts
+    }
+
+    /// Returns the wait of the regular requests.
+    pub const fn wait(&self) -> usize {
+        self.request.wait
+    }
+
+    /// Sets the wait of the regular requests, it must be in the [`WAIT_RANGE`].
+    pub fn set_wait(&mut self, wait: usize) -> Result<(), InvalidWait> {
+        validate_wait(wait)?;
+        self.request.wait = wait;
+        Ok(())
+    }
+
+    /// Returns the request with the actual ts, like for persisting it.
+    pub fn into_request(self) -> LongPollRequest<T> {
+        self.request
+    }
+}
+
+impl<T: Debug, I> Debug for LongPoller<T, I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LongPoller")
+            .field("client", &self.client)
+            .field("request", &self.request)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "longpoll_stream")]
+type PendingChunk<I> = Pin<Box<dyn Future<Output = VkApiResult<LongPollSuccess<I>>> + Send>>;
+
+/// Pending request and not yet taken events of the stream.
+#[cfg(feature = "longpoll_stream")]
+struct StreamState<I> {
+    pending: Option<PendingChunk<I>>,
+    buffered: std::collections::VecDeque<I>,
+    finished: bool,
+}
+
+#[cfg(feature = "longpoll_stream")]
+impl<I> Default for StreamState<I> {
+    fn default() -> Self {
+        Self {
+            pending: None,
+            buffered: std::collections::VecDeque::new(),
+            finished: false,
+        }
+    }
+}
+
+// fields are never pinned, the pending request is boxed
+#[cfg(feature = "longpoll_stream")]
+impl<T, I> Unpin for LongPoller<T, I> {}
+
+/// Yields the events one by one and ends on the first unrecoverable error,
+/// like the [`VkLongPoll::subscribe`] stream.
+#[cfg(feature = "longpoll_stream")]
+impl<T, I> futures_util::Stream for LongPoller<T, I>
+where
+    T: Serialize + Clone + Send + 'static,
+    I: DeserializeOwned + Send + 'static,
+{
+    type Item = VkApiResult<I>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(update) = this.stream.buffered.pop_front() {
+                return Poll::Ready(Some(Ok(update)));
+            }
+            if this.stream.finished {
+                return Poll::Ready(None);
+            }
+
+            let pending = this.stream.pending.get_or_insert_with(|| {
+                let client = this.client.clone();
+                let request = this.request.clone();
+                Box::pin(async move { recover(client.subscribe_once(request).await) })
+            });
+
+            let response = std::task::ready!(pending.as_mut().poll(cx));
+            this.stream.pending = None;
+
+            match response {
+                Ok(LongPollSuccess { ts, updates }) => {
+                    this.request.ts = ts;
+                    this.stream.buffered.extend(updates);
+                }
+                Err(e) => {
+                    this.stream.finished = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+        }
+    }
+}
+
+fn validate_wait(wait: usize) -> Result<(), InvalidWait> {
+    if WAIT_RANGE.contains(&wait) {
+        Ok(())
+    } else {
+        Err(InvalidWait(wait))
+    }
+}
+
+/// Long poll wait is out of the [`WAIT_RANGE`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct InvalidWait(pub usize);
+
+impl Display for InvalidWait {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "long poll wait must be in {}..={} seconds, got {}",
+            WAIT_RANGE.start(),
+            WAIT_RANGE.end(),
+            self.0
+        )
+    }
+}
+
+impl Error for InvalidWait {}
+
+#[cfg(test)]
+mod tests {
+    use crate::longpoll::poller::recover;
+    use crate::longpoll::{InvalidWait, LongPollError, LongPollRequest, VkLongPoll};
+    use crate::VkApiError;
+
+    #[test]
+    fn recover_outdated_history() {
+        let chunk = recover::<()>(Err(VkApiError::LongPoll(
+            LongPollError::new(1).with_ts("5"),
+        )))
+        .unwrap();
+        assert_eq!(chunk.ts(), "5");
+        assert!(chunk.updates().is_empty());
+
+        assert!(recover::<()>(Err(VkApiError::LongPoll(LongPollError::new(2)))).is_err());
+    }
+
+    #[test]
+    fn validate_wait() {
+        let request = |wait| LongPollRequest {
+            server: String::new(),
+            key: String::new(),
+            ts: "1".to_owned(),
+            wait,
+            additional_params: (),
+        };
+        let client = VkLongPoll::default();
+
+        assert_eq!(
+            client.poller::<_, ()>(request(0)).unwrap_err(),
+            InvalidWait(0)
+        );
+        assert_eq!(
+            client.poller::<_, ()>(request(91)).unwrap_err(),
+            InvalidWait(91)
+        );
+
+        let mut poller = client.poller::<_, ()>(request(25)).unwrap();
+        assert_eq!(poller.set_wait(100), Err(InvalidWait(100)));
+        assert_eq!(poller.wait(), 25);
+        assert_eq!(poller.ts(), "1");
+    }
+}
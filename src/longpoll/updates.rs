@@ -0,0 +1,324 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+/// # Update of the User Long Poll API
+/// Typed [updates](https://dev.vk.com/api/user-long-poll/getting-started), which VK sends as the positional arrays
+/// like `[4, message_id, flags, peer_id, ...]`.
+/// Updates of the other codes and the ones, which don't match their shapes, are kept as [`UserUpdate::Unknown`].
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use futures_util::StreamExt;
+/// use vkclient::longpoll::{LongPollRequest, UserUpdate, VkLongPoll};
+/// # let (key, server, ts) = (String::new(), String::new(), String::new());
+///
+/// let longpoll_client = VkLongPoll::default();
+///
+/// longpoll_client
+///     .subscribe::<_, UserUpdate>(LongPollRequest {
+///         key,
+///         server,
+///         ts,
+///         wait: 25,
+///         additional_params: (),
+///     })
+///     .for_each(|update| async move {
+///         if let Ok(UserUpdate::MessageNew(message)) = update {
+///             println!("{}", message.text);
+///         }
+///     });
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum UserUpdate {
+    /// Code 2, flags of the message are set
+    MessageFlagsSet {
+        message_id: i64,
+        flags: u32,
+        peer_id: i64,
+    },
+    /// Code 3, flags of the message are reset
+    MessageFlagsReset {
+        message_id: i64,
+        flags: u32,
+        peer_id: i64,
+    },
+    /// Code 4
+    MessageNew(UserMessage),
+    /// Code 5
+    MessageEdit(UserMessage),
+    /// Code 6, incoming messages up to `message_id` are read
+    InboxRead { peer_id: i64, message_id: i64 },
+    /// Code 7, outgoing messages up to `message_id` are read
+    OutboxRead { peer_id: i64, message_id: i64 },
+    /// Code 8, `user_id` is positive unlike the raw update
+    FriendOnline {
+        user_id: i64,
+        platform: u32,
+        timestamp: i64,
+    },
+    /// Code 9, `flags` is 0 when the friend left and 1 on the timeout, `user_id` is positive
+    FriendOffline {
+        user_id: i64,
+        flags: u32,
+        timestamp: i64,
+    },
+    /// Code 13, messages up to `message_id` are deleted
+    MessagesDeleted { peer_id: i64, message_id: i64 },
+    /// Code 63, users are typing in the conversation
+    Typing {
+        peer_id: i64,
+        user_ids: Vec<i64>,
+        total_count: u32,
+    },
+    /// Code 80, count of the unread conversations
+    UnreadCount { count: u32 },
+    /// Whole array of the other updates
+    Unknown(Vec<Value>),
+}
+
+/// Message of the [`UserUpdate::MessageNew`] and the [`UserUpdate::MessageEdit`] updates.
+/// Fields after `text` are sent depending on the `mode` and the `version` of the request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserMessage {
+    pub message_id: i64,
+    pub flags: u32,
+    pub peer_id: i64,
+    pub timestamp: i64,
+    pub text: String,
+    /// Title, sender and other additional fields
+    pub extra: Option<Value>,
+    pub attachments: Option<Value>,
+    pub random_id: Option<i64>,
+    pub conversation_message_id: Option<i64>,
+    pub edit_time: Option<i64>,
+}
+
+impl UserUpdate {
+    fn parse(values: &[Value]) -> Option<Self> {
+        let int = |i: usize| values.get(i)?.as_i64();
+        let uint = |i: usize| u32::try_from(values.get(i)?.as_u64()?).ok();
+
+        Some(match int(0)? {
+            2 => Self::MessageFlagsSet {
+                message_id: int(1)?,
+                flags: uint(2)?,
+                peer_id: int(3)?,
+            },
+            3 => Self::MessageFlagsReset {
+                message_id: int(1)?,
+                flags: uint(2)?,
+                peer_id: int(3)?,
+            },
+            4 => Self::MessageNew(UserMessage::parse(values)?),
+            5 => Self::MessageEdit(UserMessage::parse(values)?),
+            6 => Self::InboxRead {
+                peer_id: int(1)?,
+                message_id: int(2)?,
+            },
+            7 => Self::OutboxRead {
+                peer_id: int(1)?,
+                message_id: int(2)?,
+            },
+            8 => Self::FriendOnline {
+                user_id: -int(1)?,
+                platform: uint(2)?,
+                timestamp: int(3)?,
+            },
+            9 => Self::FriendOffline {
+                user_id: -int(1)?,
+                flags: uint(2)?,
+                timestamp: int(3)?,
+            },
+            13 => Self::MessagesDeleted {
+                peer_id: int(1)?,
+                message_id: int(2)?,
+            },
+            63 => Self::Typing {
+                peer_id: int(1)?,
+                user_ids: values
+                    .get(2)?
+                    .as_array()?
+                    .iter()
+                    .map(Value::as_i64)
+                    .collect::<Option<_>>()?,
+                total_count: uint(3)?,
+            },
+            80 => Self::UnreadCount { count: uint(1)? },
+            _ => return None,
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value> {
+        match self {
+            Self::MessageFlagsSet {
+                message_id,
+                flags,
+                peer_id,
+            } => vec![
+                2.into(),
+                (*message_id).into(),
+                (*flags).into(),
+                (*peer_id).into(),
+            ],
+            Self::MessageFlagsReset {
+                message_id,
+                flags,
+                peer_id,
+            } => vec![
+                3.into(),
+                (*message_id).into(),
+                (*flags).into(),
+                (*peer_id).into(),
+            ],
+            Self::MessageNew(message) => message.to_values(4),
+            Self::MessageEdit(message) => message.to_values(5),
+            Self::InboxRead {
+                peer_id,
+                message_id,
+            } => vec![6.into(), (*peer_id).into(), (*message_id).into()],
+            Self::OutboxRead {
+                peer_id,
+                message_id,
+            } => vec![7.into(), (*peer_id).into(), (*message_id).into()],
+            Self::FriendOnline {
+                user_id,
+                platform,
+                timestamp,
+            } => vec![
+                8.into(),
+                (-user_id).into(),
+                (*platform).into(),
+                (*timestamp).into(),
+            ],
+            Self::FriendOffline {
+                user_id,
+                flags,
+                timestamp,
+            } => vec![
+                9.into(),
+                (-user_id).into(),
+                (*flags).into(),
+                (*timestamp).into(),
+            ],
+            Self::MessagesDeleted {
+                peer_id,
+                message_id,
+            } => vec![13.into(), (*peer_id).into(), (*message_id).into()],
+            Self::Typing {
+                peer_id,
+                user_ids,
+                total_count,
+            } => vec![
+                63.into(),
+                (*peer_id).into(),
+                user_ids.clone().into(),
+                (*total_count).into(),
+            ],
+            Self::UnreadCount { count } => vec![80.into(), (*count).into()],
+            Self::Unknown(values) => values.clone(),
+        }
+    }
+}
+
+impl UserMessage {
+    fn parse(values: &[Value]) -> Option<Self> {
+        let int = |i: usize| values.get(i)?.as_i64();
+
+        Some(Self {
+            message_id: int(1)?,
+            flags: u32::try_from(values.get(2)?.as_u64()?).ok()?,
+            peer_id: int(3)?,
+            timestamp: int(4)?,
+            text: values.get(5)?.as_str()?.to_owned(),
+            extra: values.get(6).cloned(),
+            attachments: values.get(7).cloned(),
+            random_id: int(8),
+            conversation_message_id: int(9),
+            edit_time: int(10),
+        })
+    }
+
+    fn to_values(&self, code: u8) -> Vec<Value> {
+        let mut values = vec![
+            code.into(),
+            self.message_id.into(),
+            self.flags.into(),
+            self.peer_id.into(),
+            self.timestamp.into(),
+            self.text.clone().into(),
+            self.extra.clone().unwrap_or_default(),
+            self.attachments.clone().unwrap_or_default(),
+            self.random_id.into(),
+            self.conversation_message_id.into(),
+            self.edit_time.into(),
+        ];
+        while values.last().is_some_and(Value::is_null) {
+            values.pop();
+        }
+        values
+    }
+}
+
+impl<'de> Deserialize<'de> for UserUpdate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let values = Vec::<Value>::deserialize(deserializer)?;
+        Ok(Self::parse(&values).unwrap_or(Self::Unknown(values)))
+    }
+}
+
+impl Serialize for UserUpdate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_values().serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::longpoll::{UserMessage, UserUpdate};
+
+    #[test]
+    fn deserialize_user_updates() {
+        let updates: Vec<UserUpdate> = serde_json::from_str(
+            r#"[
+                [4, 1619, 49, 100001, 1700000000, "hi", {"title": ""}, {}, 0, 1205],
+                [8, -100001, 4, 1700000000],
+                [63, 2000000001, [100001], 1, 1700000000],
+                [114, {"peer_id": 1}]
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            updates[0],
+            UserUpdate::MessageNew(UserMessage {
+                message_id: 1619,
+                flags: 49,
+                peer_id: 100001,
+                timestamp: 1700000000,
+                text: "hi".to_owned(),
+                extra: Some(serde_json::json!({"title": ""})),
+                attachments: Some(serde_json::json!({})),
+                random_id: Some(0),
+                conversation_message_id: Some(1205),
+                edit_time: None,
+            })
+        );
+        assert_eq!(
+            updates[1],
+            UserUpdate::FriendOnline {
+                user_id: 100001,
+                platform: 4,
+                timestamp: 1700000000
+            }
+        );
+        assert!(
+            matches!(&updates[2], UserUpdate::Typing { user_ids, .. } if user_ids == &[100001])
+        );
+        assert!(matches!(&updates[3], UserUpdate::Unknown(values) if values.len() == 2));
+
+        assert_eq!(
+            serde_json::to_string(&updates[..2]).unwrap(),
+            r#"[[4,1619,49,100001,1700000000,"hi",{"title":""},{},0,1205],[8,-100001,4,1700000000]]"#
+        );
+    }
+}
@@ -0,0 +1,337 @@
+//! # Messages helpers
+//! Typed structures for the [messages methods](https://dev.vk.com/method/messages) params,
+//! which VK accepts as json strings, and the facade for sending messages.
+
+mod activity;
+mod call;
+mod delivery;
+mod folders;
+mod forward;
+mod ids;
+mod intent;
+mod keyboard;
+mod queue;
+mod template;
+mod text;
+
+use crate::bulk::or_cancel;
+use crate::{BulkResult, Json, List, VkApi, VkApiError, VkApiResult};
+pub use activity::*;
+pub use call::*;
+pub use delivery::{Delivery, DeliveryTracker};
+pub use folders::{Conversation, ConversationFolder, ConversationItem, ConversationPeer};
+pub use forward::*;
+use ids::MessageIdCache;
+pub use intent::{Intent, IntentError};
+pub use keyboard::*;
+pub use queue::MessageQueue;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::pin;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+pub use template::*;
+pub use text::*;
+
+/// # Messages facade
+/// Sends messages with the typed params.
+/// Keep the facade to reuse the cache of the message ids.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use vkclient::VkApi;
+/// # let access_token = String::new();
+/// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+///
+/// async {
+///     let ids = client.messages().send_long(1, "very long text").await.unwrap();
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct VkMessages {
+    client: VkApi,
+    ids: Arc<MessageIdCache>,
+}
+
+impl VkMessages {
+    /// Sends the message with [messages.send](https://dev.vk.com/method/messages.send) and returns its id.
+    /// The intent of the message is validated against the API version of the client before the request.
+    pub async fn send(&self, message: SendMessage) -> VkApiResult<i64> {
+        if let Some(intent) = message.intent {
+            intent
+                .validate(message.subscribe_id, self.client.version())
+                .map_err(VkApiError::InvalidIntent)?;
+        }
+        self.client.send_request("messages.send", message).await
+    }
+
+    /// Splits the text longer than 4096 chars to chunks and sends them in order.
+    /// Returns ids of the sent messages.
+    /// Use [`escape_mentions`] before sending the user text to chats.
+    pub async fn send_long<T: AsRef<str>>(&self, peer_id: i64, text: T) -> VkApiResult<Vec<i64>> {
+        let chunks = split_message(text.as_ref(), MAX_MESSAGE_LENGTH);
+        let mut ids = Vec::with_capacity(chunks.len());
+
+        for chunk in chunks {
+            ids.push(
+                self.send(SendMessage::new(peer_id).with_text(chunk))
+                    .await?,
+            );
+        }
+
+        Ok(ids)
+    }
+
+    /// Same as [`VkMessages::send_long`], but stops sending the chunks when the `cancel` future completes,
+    /// like `CancellationToken::cancelled()` or `tokio::time::sleep`.
+    /// Sending stops at the first failed chunk, the unsent chunks are returned in [`BulkResult::pending`].
+    pub async fn send_long_until<T: AsRef<str>, C: Future>(
+        &self,
+        peer_id: i64,
+        text: T,
+        cancel: C,
+    ) -> BulkResult<i64, String> {
+        let mut cancel = pin!(cancel);
+        let mut result = BulkResult::new();
+        let mut chunks = split_message(text.as_ref(), MAX_MESSAGE_LENGTH).into_iter();
+
+        for chunk in chunks.by_ref() {
+            let message = SendMessage::new(peer_id).with_text(chunk);
+            match or_cancel(cancel.as_mut(), self.send(message)).await {
+                None => {
+                    result.cancelled = true;
+                    result.pending.push(chunk.to_owned());
+                }
+                Some(Ok(id)) => {
+                    result.succeeded.push(id);
+                    continue;
+                }
+                Some(Err(e)) => result.failed.push((chunk.to_owned(), e)),
+            }
+            break;
+        }
+
+        result.pending.extend(chunks.map(str::to_owned));
+        result
+    }
+
+    /// Sends the message to every peer with the new random id, until the `cancel` future completes.
+    /// Sending continues after failures, failed and not sent peers can be retried with [`BulkResult::failed_inputs`].
+    /// Returns pairs of the peer id and the sent message id.
+    pub async fn broadcast<I: IntoIterator<Item = i64>, C: Future>(
+        &self,
+        peer_ids: I,
+        message: SendMessage,
+        cancel: C,
+    ) -> BulkResult<(i64, i64), i64> {
+        let mut cancel = pin!(cancel);
+        let mut result = BulkResult::new();
+        let mut peer_ids = peer_ids.into_iter();
+
+        for peer_id in peer_ids.by_ref() {
+            let message = SendMessage {
+                peer_id,
+                random_id: random_id(),
+                ..message.clone()
+            };
+            match or_cancel(cancel.as_mut(), self.send(message)).await {
+                None => {
+                    result.cancelled = true;
+                    result.pending.push(peer_id);
+                    break;
+                }
+                Some(Ok(id)) => result.succeeded.push((peer_id, id)),
+                Some(Err(e)) => result.failed.push((peer_id, e)),
+            }
+        }
+
+        result.pending.extend(peer_ids);
+        result
+    }
+
+    /// Marks the messages of the peer as read with [messages.markAsRead](https://dev.vk.com/method/messages.markAsRead).
+    /// Messages up to `start_message_id` are marked, or the whole conversation if it is `None`.
+    pub async fn mark_as_read(
+        &self,
+        peer_id: i64,
+        start_message_id: Option<i64>,
+    ) -> VkApiResult<()> {
+        self.client
+            .send_request::<u8, _, _>(
+                "messages.markAsRead",
+                MarkAsRead {
+                    peer_id,
+                    start_message_id,
+                    mark_conversation_as_read: start_message_id.is_none().then_some(1),
+                },
+            )
+            .await
+            .map(drop)
+    }
+
+    /// Shows the activity like typing in the conversation
+    /// with [messages.setActivity](https://dev.vk.com/method/messages.setActivity).
+    pub async fn set_activity(&self, peer_id: i64, activity: Activity) -> VkApiResult<()> {
+        self.client
+            .send_request::<u8, _, _>("messages.setActivity", SetActivity { peer_id, activity })
+            .await
+            .map(drop)
+    }
+
+    /// Sends the message like the [`VkMessages::send`] and returns the future,
+    /// which resolves when the `tracker` observes the message.
+    /// The conversation message id is requested with the [`VkMessages::conversation_message_ids`],
+    /// `None` is returned if the sent message isn't found.
+    pub async fn send_tracked(
+        &self,
+        message: SendMessage,
+        tracker: &DeliveryTracker,
+    ) -> VkApiResult<Option<Delivery>> {
+        let id = self.send(message).await?;
+        let ids = self.conversation_message_ids(&[id]).await?;
+
+        Ok(ids
+            .get(&id)
+            .map(|&(peer_id, cmid)| tracker.delivery(peer_id, cmid)))
+    }
+
+    /// Returns the message ids by the conversation message ids of the peer
+    /// with [messages.getByConversationMessageId](https://dev.vk.com/method/messages.getByConversationMessageId).
+    /// Lookups are batched by 100 ids and cached. Missing messages are absent in the result.
+    pub async fn message_ids(
+        &self,
+        peer_id: i64,
+        conversation_message_ids: &[i64],
+    ) -> VkApiResult<HashMap<i64, i64>> {
+        self.ids
+            .message_ids(&self.client, peer_id, conversation_message_ids)
+            .await
+    }
+
+    /// Returns the `(peer_id, conversation_message_id)` pairs by the message ids
+    /// with [messages.getById](https://dev.vk.com/method/messages.getById).
+    /// Lookups are batched by 100 ids and cached. Missing messages are absent in the result.
+    pub async fn conversation_message_ids(
+        &self,
+        message_ids: &[i64],
+    ) -> VkApiResult<HashMap<i64, (i64, i64)>> {
+        self.ids
+            .conversation_message_ids(&self.client, message_ids)
+            .await
+    }
+}
+
+impl From<VkApi> for VkMessages {
+    fn from(client: VkApi) -> Self {
+        Self {
+            client,
+            ids: Arc::new(MessageIdCache::new(10_000)),
+        }
+    }
+}
+
+/// Params of [messages.send](https://dev.vk.com/method/messages.send).
+#[derive(Debug, Clone, Serialize)]
+pub struct SendMessage {
+    pub peer_id: i64,
+    /// Unique id of the message, which protects from sending duplicates.
+    pub random_id: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attachment: Option<List<Vec<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<Json<Carousel>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forward: Option<Json<Forward>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keyboard: Option<Json<Keyboard>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub intent: Option<Intent>,
+    /// Id of the newsletter subscription of the [`Intent::PromoNewsletter`] and the [`Intent::NonPromoNewsletter`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscribe_id: Option<u8>,
+}
+
+impl SendMessage {
+    /// Creates the message to the peer with a new random id.
+    #[must_use]
+    pub fn new(peer_id: i64) -> Self {
+        Self {
+            peer_id,
+            random_id: random_id(),
+            message: None,
+            attachment: None,
+            template: None,
+            forward: None,
+            keyboard: None,
+            intent: None,
+            subscribe_id: None,
+        }
+    }
+
+    /// Pass the message text.
+    #[must_use]
+    pub fn with_text<T: Into<String>>(mut self, text: T) -> Self {
+        self.message = Some(text.into());
+        self
+    }
+
+    /// Pass the attachments in the `{type}{owner_id}_{media_id}` format.
+    #[must_use]
+    pub fn with_attachments<I: IntoIterator<Item = S>, S: Into<String>>(
+        mut self,
+        attachments: I,
+    ) -> Self {
+        self.attachment = Some(List(attachments.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Pass the forwarded messages or the reply built by [`Forward::build`].
+    #[must_use]
+    pub fn with_forward(mut self, forward: Json<Forward>) -> Self {
+        self.forward = Some(forward);
+        self
+    }
+
+    /// Pass the keyboard built by [`Keyboard::build`].
+    #[must_use]
+    pub fn with_keyboard(mut self, keyboard: Json<Keyboard>) -> Self {
+        self.keyboard = Some(keyboard);
+        self
+    }
+
+    /// Pass the carousel built by [`Carousel::build`].
+    #[must_use]
+    pub fn with_template(mut self, template: Json<Carousel>) -> Self {
+        self.template = Some(template);
+        self
+    }
+
+    /// Pass the intent and the `subscribe_id` of the newsletter.
+    #[must_use]
+    pub const fn with_intent(mut self, intent: Intent, subscribe_id: Option<u8>) -> Self {
+        self.intent = Some(intent);
+        self.subscribe_id = subscribe_id;
+        self
+    }
+}
+
+/// Returns the random id for [messages.send](https://dev.vk.com/method/messages.send).
+/// Ids are seeded by the process start time and unique within the process for the 2^31 sent messages.
+pub fn random_id() -> i32 {
+    static SEED: OnceLock<u32> = OnceLock::new();
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+    let seed = *SEED.get_or_init(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos() ^ d.as_secs() as u32)
+            .unwrap_or_default()
+    });
+    let counter = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    (seed.wrapping_add(counter.wrapping_mul(0x9E37_79B9)) & 0x7FFF_FFFF) as i32
+}
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+/// Activity of the user or the community in the conversation,
+/// set by [messages.setActivity](https://dev.vk.com/method/messages.setActivity).
+/// VK shows the activity for about 10 seconds or until the message is sent.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Activity {
+    #[serde(rename = "typing")]
+    Typing,
+    #[serde(rename = "audiomessage")]
+    AudioMessage,
+    #[serde(rename = "photo")]
+    Photo,
+    #[serde(rename = "video")]
+    Video,
+    #[serde(rename = "file")]
+    File,
+}
+
+/// Object of the `message_read` event of the Bots Long Poll API and the Callback API.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MessageRead {
+    pub from_id: i64,
+    pub peer_id: i64,
+    pub read_message_id: i64,
+    #[serde(default)]
+    pub conversation_message_id: Option<i64>,
+}
+
+/// Object of the `message_typing_state` event of the Bots Long Poll API and the Callback API.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TypingState {
+    pub state: Activity,
+    pub from_id: i64,
+    pub to_id: i64,
+}
+
+#[derive(Serialize)]
+pub struct MarkAsRead {
+    pub(crate) peer_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) start_message_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) mark_conversation_as_read: Option<u8>,
+}
+
+#[derive(Serialize)]
+pub struct SetActivity {
+    pub(crate) peer_id: i64,
+    #[serde(rename = "type")]
+    pub(crate) activity: Activity,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::messages::{Activity, MessageRead, TypingState};
+
+    #[test]
+    fn deserialize_events() {
+        assert_eq!(
+            serde_json::from_str::<TypingState>(r#"{"state":"typing","from_id":1,"to_id":-2}"#)
+                .unwrap(),
+            TypingState {
+                state: Activity::Typing,
+                from_id: 1,
+                to_id: -2
+            }
+        );
+        assert_eq!(
+            serde_json::from_str::<MessageRead>(
+                r#"{"from_id":1,"peer_id":1,"read_message_id":10}"#
+            )
+            .unwrap()
+            .read_message_id,
+            10
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use crate::messages::Message;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// `call` attachment of the message, which VK adds to the conversation after the call ends or changes its state.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Call {
+    pub initiator_id: i64,
+    pub receiver_id: i64,
+    pub state: CallState,
+    /// Unix time of the call start.
+    pub time: i64,
+    /// Duration in seconds, zero for the missed calls.
+    #[serde(default)]
+    pub duration: u32,
+    #[serde(default)]
+    pub video: bool,
+}
+
+/// State of the call.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallState {
+    /// Call took place
+    Reached,
+    CanceledByInitiator,
+    CanceledByReceiver,
+    /// State added in the newer API versions
+    #[serde(other)]
+    Unknown,
+}
+
+/// `audio_message` attachment of the message.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AudioMessage {
+    pub id: i64,
+    pub owner_id: i64,
+    /// Duration in seconds.
+    pub duration: u32,
+    #[serde(default)]
+    pub waveform: Vec<u8>,
+    #[serde(default)]
+    pub link_ogg: Option<String>,
+    #[serde(default)]
+    pub link_mp3: Option<String>,
+    #[serde(default)]
+    pub access_key: Option<String>,
+    /// Speech recognition result, available after the `transcript_state` becomes `done`.
+    #[serde(default)]
+    pub transcript: Option<String>,
+    #[serde(default)]
+    pub transcript_state: Option<String>,
+}
+
+impl Message {
+    /// Returns the `call` attachments of the message. Malformed attachments are skipped.
+    #[must_use]
+    pub fn calls(&self) -> Vec<Call> {
+        self.typed_attachments("call")
+    }
+
+    /// Returns the `audio_message` attachments of the message. Malformed attachments are skipped.
+    #[must_use]
+    pub fn audio_messages(&self) -> Vec<AudioMessage> {
+        self.typed_attachments("audio_message")
+    }
+
+    fn typed_attachments<T: DeserializeOwned>(&self, kind: &str) -> Vec<T> {
+        self.attachments
+            .iter()
+            .filter(|attachment| attachment["type"] == kind)
+            .filter_map(|attachment| T::deserialize(&attachment[kind]).ok())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::messages::{CallState, Message};
+
+    #[test]
+    fn typed_attachments() {
+        let message: Message = serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "from_id": 2,
+            "date": 1700000000,
+            "attachments": [
+                {"type": "call", "call": {"initiator_id": 2, "receiver_id": 3, "state": "canceled_by_receiver", "time": 1700000000, "duration": 0, "video": true}},
+                {"type": "audio_message", "audio_message": {"id": 4, "owner_id": 2, "duration": 5, "waveform": [0, 10], "link_ogg": "https://example.com/a.ogg"}},
+                {"type": "call", "call": {"initiator_id": 2, "receiver_id": 3, "state": "new_state", "time": 1700000001}}
+            ]
+        }))
+        .unwrap();
+
+        let calls = message.calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].state, CallState::CanceledByReceiver);
+        assert!(calls[0].video);
+        assert_eq!(calls[1].state, CallState::Unknown);
+        assert_eq!(message.audio_messages()[0].duration, 5);
+    }
+}
@@ -0,0 +1,157 @@
+use crate::messages::Message;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// Count of the confirmed messages, which are kept for the later [`DeliveryTracker::delivery`] calls.
+const CONFIRMED_CAPACITY: usize = 10_000;
+
+/// # Delivery tracker
+/// Correlates the sent messages with their long poll events, which confirm the delivery.
+/// Pass the events to [`DeliveryTracker::observe_event`] and await [`DeliveryTracker::delivery`]
+/// or [`VkMessages::send_tracked`](crate::messages::VkMessages::send_tracked) before proceeding.
+/// Messages confirmed before the `delivery` call are remembered, so the order doesn't matter.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use futures_util::StreamExt;
+/// use vkclient::longpoll::GroupEvent;
+/// use vkclient::messages::{DeliveryTracker, SendMessage};
+/// use vkclient::VkApi;
+/// # let access_token = String::new();
+/// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+/// let tracker = DeliveryTracker::default();
+///
+/// let events = tracker.clone();
+/// let longpoll = client
+///     .longpoll()
+///     .subscribe_group::<GroupEvent>(&client, 1)
+///     .for_each(move |event| {
+///         if let Ok(event) = &event {
+///             events.observe_event(event);
+///         }
+///         async {}
+///     });
+///
+/// async move {
+///     let delivery = client
+///         .messages()
+///         .send_tracked(SendMessage::new(1).with_text("hi"), &tracker)
+///         .await
+///         .unwrap();
+///     if let Some(delivery) = delivery {
+///         delivery.await;
+///     }
+/// };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DeliveryTracker(Arc<Mutex<Deliveries>>);
+
+#[derive(Debug, Default)]
+struct Deliveries {
+    confirmed: HashSet<(i64, i64)>,
+    waiting: HashMap<(i64, i64), Vec<Waker>>,
+}
+
+impl DeliveryTracker {
+    /// Marks the message as delivered and resolves its deliveries.
+    pub fn confirm(&self, peer_id: i64, conversation_message_id: i64) {
+        let mut deliveries = self
+            .0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        if deliveries.confirmed.len() >= CONFIRMED_CAPACITY {
+            deliveries.confirmed.clear();
+        }
+        deliveries
+            .confirmed
+            .insert((peer_id, conversation_message_id));
+
+        let wakers = deliveries
+            .waiting
+            .remove(&(peer_id, conversation_message_id));
+        drop(deliveries);
+        wakers.into_iter().flatten().for_each(Waker::wake);
+    }
+
+    /// Confirms the message, if it has the peer and the conversation message id.
+    pub fn observe(&self, message: &Message) {
+        if let (Some(peer_id), Some(cmid)) = (message.peer_id, message.conversation_message_id) {
+            self.confirm(peer_id, cmid);
+        }
+    }
+
+    /// Confirms the message of the `message_new` and the `message_reply` events.
+    /// The `message_reply` event is sent for the outgoing messages of the community.
+    #[cfg(feature = "longpoll")]
+    pub fn observe_event(&self, event: &crate::longpoll::GroupEvent) {
+        use crate::longpoll::GroupEvent;
+
+        match event {
+            GroupEvent::MessageNew(event) => self.observe(&event.message),
+            GroupEvent::MessageReply(message) => self.observe(message),
+            _ => {}
+        }
+    }
+
+    /// Returns the future, which resolves when the message is confirmed.
+    #[must_use]
+    pub fn delivery(&self, peer_id: i64, conversation_message_id: i64) -> Delivery {
+        Delivery {
+            tracker: self.clone(),
+            key: (peer_id, conversation_message_id),
+        }
+    }
+}
+
+/// Future of the [`DeliveryTracker::delivery`].
+#[derive(Debug)]
+pub struct Delivery {
+    tracker: DeliveryTracker,
+    key: (i64, i64),
+}
+
+impl Future for Delivery {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut deliveries = self
+            .tracker
+            .0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        if deliveries.confirmed.contains(&self.key) {
+            return Poll::Ready(());
+        }
+
+        let wakers = deliveries.waiting.entry(self.key).or_default();
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::messages::DeliveryTracker;
+
+    #[tokio::test]
+    async fn resolve_deliveries() {
+        let tracker = DeliveryTracker::default();
+
+        tracker.confirm(1, 10);
+        tracker.delivery(1, 10).await;
+
+        let delivery = tracker.delivery(2, 20);
+        let confirm = async {
+            tokio::task::yield_now().await;
+            tracker.confirm(2, 20);
+        };
+        tokio::join!(delivery, confirm);
+    }
+}
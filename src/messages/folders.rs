@@ -0,0 +1,196 @@
+use crate::messages::{Message, VkMessages};
+use crate::VkApiResult;
+use futures_util::Stream;
+use serde::{Deserialize, Serialize};
+
+/// Maximum count of the conversations per one request.
+const CONVERSATIONS_PAGE_SIZE: usize = 200;
+
+/// Folder of the conversations, passed as the `filter` of
+/// [messages.getConversations](https://dev.vk.com/method/messages.getConversations).
+/// Custom folders and tags of the community messages aren't available in the API,
+/// so the conversations are sorted with the important and the unanswered marks.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConversationFolder {
+    #[default]
+    All,
+    Unread,
+    /// Conversations marked by [`VkMessages::mark_important`]
+    Important,
+    /// Conversations of the community, which wait for the answer, see [`VkMessages::mark_answered`]
+    Unanswered,
+}
+
+/// Conversation with its last message, returned by [`VkMessages::conversations`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationItem {
+    pub conversation: Conversation,
+    #[serde(default)]
+    pub last_message: Option<Message>,
+}
+
+/// [Conversation object](https://dev.vk.com/reference/objects/conversation).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    pub peer: ConversationPeer,
+    #[serde(default)]
+    pub in_read: i64,
+    #[serde(default)]
+    pub out_read: i64,
+    #[serde(default)]
+    pub unread_count: Option<u32>,
+    #[serde(default)]
+    pub important: bool,
+    #[serde(default)]
+    pub unanswered: bool,
+}
+
+/// Peer of the [`Conversation`].
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ConversationPeer {
+    pub id: i64,
+    /// `user`, `chat`, `group` or `email`
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub local_id: Option<i64>,
+}
+
+impl VkMessages {
+    /// Returns the stream of the conversations of the folder, the recent first,
+    /// with [messages.getConversations](https://dev.vk.com/method/messages.getConversations).
+    /// Pages are requested lazily until the conversations run out or an error occurs.
+    pub fn conversations(
+        &self,
+        folder: ConversationFolder,
+    ) -> impl Stream<Item = VkApiResult<ConversationItem>> {
+        let client = self.client.clone();
+
+        async_stream::stream! {
+            let mut offset = 0;
+
+            loop {
+                let page: VkApiResult<ConversationsPage> = client
+                    .send_request(
+                        "messages.getConversations",
+                        GetConversations {
+                            filter: folder,
+                            offset,
+                            count: CONVERSATIONS_PAGE_SIZE,
+                        },
+                    )
+                    .await;
+
+                let ConversationsPage { count, items } = match page {
+                    Ok(page) => page,
+                    Err(e) => {
+                        yield Err(e);
+                        break;
+                    }
+                };
+
+                if items.is_empty() {
+                    break;
+                }
+                offset += items.len();
+
+                for item in items {
+                    yield Ok(item);
+                }
+
+                if offset >= count {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Marks the conversation as important or removes the mark
+    /// with [messages.markAsImportantConversation](https://dev.vk.com/method/messages.markAsImportantConversation).
+    pub async fn mark_important(&self, peer_id: i64, important: bool) -> VkApiResult<()> {
+        self.client
+            .send_request::<u8, _, _>(
+                "messages.markAsImportantConversation",
+                MarkConversation {
+                    peer_id,
+                    important: Some(u8::from(important)),
+                    answered: None,
+                },
+            )
+            .await
+            .map(drop)
+    }
+
+    /// Marks the conversation of the community as answered or unanswered
+    /// with [messages.markAsAnsweredConversation](https://dev.vk.com/method/messages.markAsAnsweredConversation).
+    pub async fn mark_answered(&self, peer_id: i64, answered: bool) -> VkApiResult<()> {
+        self.client
+            .send_request::<u8, _, _>(
+                "messages.markAsAnsweredConversation",
+                MarkConversation {
+                    peer_id,
+                    important: None,
+                    answered: Some(u8::from(answered)),
+                },
+            )
+            .await
+            .map(drop)
+    }
+}
+
+#[derive(Serialize)]
+struct GetConversations {
+    filter: ConversationFolder,
+    offset: usize,
+    count: usize,
+}
+
+#[derive(Deserialize)]
+struct ConversationsPage {
+    count: usize,
+    items: Vec<ConversationItem>,
+}
+
+#[derive(Serialize)]
+struct MarkConversation {
+    peer_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    important: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    answered: Option<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::messages::folders::{ConversationsPage, GetConversations};
+    use crate::messages::ConversationFolder;
+
+    #[test]
+    fn decode_conversations_page() {
+        let page: ConversationsPage = serde_json::from_str(
+            r#"{"count": 2, "items": [
+                {"conversation": {"peer": {"id": 1, "type": "user", "local_id": 1}, "in_read": 5, "out_read": 5, "important": true},
+                 "last_message": {"id": 5, "peer_id": 1, "from_id": 1, "date": 1700000000, "text": "hi"}},
+                {"conversation": {"peer": {"id": 2000000001, "type": "chat", "local_id": 1}, "unread_count": 3, "unanswered": true}}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(page.count, 2);
+        assert!(page.items[0].conversation.important);
+        assert_eq!(page.items[0].last_message.as_ref().unwrap().text, "hi");
+        assert_eq!(page.items[1].conversation.peer.kind, "chat");
+        assert!(page.items[1].conversation.unanswered);
+
+        let params = GetConversations {
+            filter: ConversationFolder::Unanswered,
+            offset: 200,
+            count: 200,
+        };
+        assert_eq!(
+            serde_urlencoded::to_string(params).unwrap(),
+            "filter=unanswered&offset=200&count=200"
+        );
+    }
+}
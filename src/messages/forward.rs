@@ -0,0 +1,274 @@
+use crate::Json;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Maximum nesting depth of the forwarded messages, which VK keeps.
+pub const MAX_FORWARD_DEPTH: usize = 45;
+/// Maximum count of the messages in one forward.
+pub const MAX_FORWARD_MESSAGES: usize = 100;
+
+/// Message with the nested forwarded messages and the reply,
+/// as returned by the messages methods and the long poll events.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    /// Message id. Forwarded messages and replies in chats have no id.
+    #[serde(default)]
+    pub id: i64,
+    #[serde(default)]
+    pub conversation_message_id: Option<i64>,
+    #[serde(default)]
+    pub peer_id: Option<i64>,
+    pub from_id: i64,
+    pub date: i64,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub attachments: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub fwd_messages: Vec<Self>,
+    #[serde(default)]
+    pub reply_message: Option<Box<Self>>,
+}
+
+impl Message {
+    /// Returns the forwarded messages and the replies of all levels up to `max_depth`, depth first.
+    /// Each message is returned with its depth, direct forwards have depth 1.
+    #[must_use]
+    pub fn forwarded(&self, max_depth: usize) -> Vec<(usize, &Self)> {
+        let mut result = Vec::new();
+        self.collect_forwarded(1, max_depth, &mut result);
+        result
+    }
+
+    fn collect_forwarded<'a>(
+        &'a self,
+        depth: usize,
+        max_depth: usize,
+        result: &mut Vec<(usize, &'a Self)>,
+    ) {
+        if depth > max_depth {
+            return;
+        }
+
+        for message in self
+            .reply_message
+            .as_deref()
+            .into_iter()
+            .chain(&self.fwd_messages)
+        {
+            result.push((depth, message));
+            message.collect_forwarded(depth + 1, max_depth, result);
+        }
+    }
+
+    /// Drops the forwarded messages and the replies nested deeper than `max_depth`.
+    pub fn truncate_forwarded(&mut self, max_depth: usize) {
+        if max_depth == 0 {
+            self.fwd_messages.clear();
+            self.reply_message = None;
+            return;
+        }
+
+        for message in self
+            .reply_message
+            .as_deref_mut()
+            .into_iter()
+            .chain(&mut self.fwd_messages)
+        {
+            message.truncate_forwarded(max_depth - 1);
+        }
+    }
+}
+
+/// The `forward` param of [messages.send](https://dev.vk.com/method/messages.send).
+/// Use the constructors, which keep the combination of fields accepted by VK.
+///
+/// ## Usage:
+/// ```rust
+/// use vkclient::messages::{Forward, SendMessage};
+///
+/// let message = SendMessage::new(2000000001)
+///     .with_text("answer")
+///     .with_forward(Forward::reply(2000000001, 15).build().unwrap());
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Forward {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner_id: Option<i64>,
+    pub peer_id: i64,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub conversation_message_ids: Vec<i64>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub message_ids: Vec<i64>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub is_reply: bool,
+}
+
+impl Forward {
+    /// Forwards messages of the conversation by their conversation message ids.
+    pub fn conversation_messages<I: IntoIterator<Item = i64>>(
+        peer_id: i64,
+        conversation_message_ids: I,
+    ) -> Self {
+        Self {
+            owner_id: None,
+            peer_id,
+            conversation_message_ids: conversation_message_ids.into_iter().collect(),
+            message_ids: Vec::new(),
+            is_reply: false,
+        }
+    }
+
+    /// Forwards messages by their global ids.
+    pub fn messages<I: IntoIterator<Item = i64>>(peer_id: i64, message_ids: I) -> Self {
+        Self {
+            owner_id: None,
+            peer_id,
+            conversation_message_ids: Vec::new(),
+            message_ids: message_ids.into_iter().collect(),
+            is_reply: false,
+        }
+    }
+
+    /// Replies to the message of the conversation.
+    #[must_use]
+    pub fn reply(peer_id: i64, conversation_message_id: i64) -> Self {
+        Self {
+            is_reply: true,
+            ..Self::conversation_messages(peer_id, [conversation_message_id])
+        }
+    }
+
+    /// Pass the owner of the messages. Required for the community tokens forwarding user messages.
+    #[must_use]
+    pub const fn with_owner_id(mut self, owner_id: i64) -> Self {
+        self.owner_id = Some(owner_id);
+        self
+    }
+
+    /// Checks the VK requirements to the forward.
+    pub const fn validate(&self) -> Result<(), ForwardError> {
+        let count = match (self.conversation_message_ids.len(), self.message_ids.len()) {
+            (0, 0) => return Err(ForwardError::Empty),
+            (0, count) | (count, 0) => count,
+            _ => return Err(ForwardError::MixedIds),
+        };
+
+        if count > MAX_FORWARD_MESSAGES {
+            return Err(ForwardError::TooManyMessages(count));
+        }
+        if self.is_reply && count != 1 {
+            return Err(ForwardError::ReplyToMany(count));
+        }
+
+        Ok(())
+    }
+
+    /// Validates the forward and wraps it for passing as the `forward` param.
+    pub fn build(self) -> Result<Json<Self>, ForwardError> {
+        self.validate()?;
+        Ok(Json(self))
+    }
+}
+
+/// Forward validation errors.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ForwardError {
+    /// No messages to forward
+    Empty,
+    /// Both conversation message ids and message ids are passed
+    MixedIds,
+    /// More than 100 messages
+    TooManyMessages(usize),
+    /// Reply to more than one message
+    ReplyToMany(usize),
+}
+
+impl Display for ForwardError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "forward has no messages"),
+            Self::MixedIds => write!(
+                f,
+                "forward can't contain both conversation message ids and message ids"
+            ),
+            Self::TooManyMessages(count) => write!(
+                f,
+                "forward has {count} messages, maximum is {MAX_FORWARD_MESSAGES}"
+            ),
+            Self::ReplyToMany(count) => {
+                write!(f, "reply must contain one message, but has {count}")
+            }
+        }
+    }
+}
+
+impl Error for ForwardError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::messages::{Forward, ForwardError, Message};
+
+    #[test]
+    fn serialize_forward() {
+        assert_eq!(
+            serde_json::to_string(&Forward::reply(2000000001, 15).build().unwrap().0).unwrap(),
+            r#"{"peer_id":2000000001,"conversation_message_ids":[15],"is_reply":true}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Forward::messages(1, [2, 3]).with_owner_id(-4)).unwrap(),
+            r#"{"owner_id":-4,"peer_id":1,"message_ids":[2,3]}"#
+        );
+    }
+
+    #[test]
+    fn validate_forward() {
+        assert_eq!(
+            Forward::messages(1, []).validate(),
+            Err(ForwardError::Empty)
+        );
+        assert_eq!(
+            Forward::messages(1, 0..101).validate(),
+            Err(ForwardError::TooManyMessages(101))
+        );
+    }
+
+    #[test]
+    fn walk_forwarded() {
+        let mut message: Message = serde_json::from_str(
+            r#"{
+                "id": 1, "from_id": 1, "date": 0, "text": "top",
+                "reply_message": {"from_id": 2, "date": 0, "text": "reply"},
+                "fwd_messages": [{
+                    "from_id": 3, "date": 0, "text": "level 1",
+                    "fwd_messages": [{"from_id": 4, "date": 0, "text": "level 2"}]
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        let texts = |m: &Message, depth| {
+            m.forwarded(depth)
+                .into_iter()
+                .map(|(d, m)| (d, m.text.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            texts(&message, 10),
+            vec![
+                (1, "reply".to_owned()),
+                (1, "level 1".to_owned()),
+                (2, "level 2".to_owned())
+            ]
+        );
+
+        message.truncate_forwarded(1);
+        assert_eq!(texts(&message, 10).len(), 2);
+    }
+}
@@ -0,0 +1,234 @@
+use crate::messages::Message;
+use crate::{List, VkApi, VkApiResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Maximum count of ids per one lookup request.
+const LOOKUP_BATCH: usize = 100;
+
+/// Cache of the `message_id` to `(peer_id, conversation_message_id)` pairs.
+/// Cache is cleared when it is full, the ids never change so it can't be stale.
+#[derive(Debug)]
+pub struct MessageIdCache {
+    capacity: usize,
+    ids: Mutex<MessageIds>,
+}
+
+#[derive(Debug, Default)]
+struct MessageIds {
+    by_conversation: HashMap<(i64, i64), i64>,
+    by_id: HashMap<i64, (i64, i64)>,
+}
+
+impl MessageIdCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ids: Mutex::default(),
+        }
+    }
+
+    fn insert(&self, id: i64, peer_id: i64, conversation_message_id: i64) {
+        let mut ids = self
+            .ids
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        if ids.by_id.len() >= self.capacity {
+            ids.by_id.clear();
+            ids.by_conversation.clear();
+        }
+
+        ids.by_id.insert(id, (peer_id, conversation_message_id));
+        ids.by_conversation
+            .insert((peer_id, conversation_message_id), id);
+    }
+
+    /// Returns the message ids by the conversation message ids of the peer.
+    /// Missing messages are absent in the result.
+    pub(crate) async fn message_ids(
+        &self,
+        client: &VkApi,
+        peer_id: i64,
+        conversation_message_ids: &[i64],
+    ) -> VkApiResult<HashMap<i64, i64>> {
+        let mut result = HashMap::with_capacity(conversation_message_ids.len());
+        let mut missing = Vec::new();
+
+        {
+            let ids = self
+                .ids
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            for &cmid in conversation_message_ids {
+                match ids.by_conversation.get(&(peer_id, cmid)) {
+                    Some(&id) => {
+                        result.insert(cmid, id);
+                    }
+                    None => missing.push(cmid),
+                }
+            }
+        }
+
+        for batch in missing.chunks(LOOKUP_BATCH) {
+            let Items { items } = client
+                .send_request::<Items<Message>, _, _>(
+                    "messages.getByConversationMessageId",
+                    ByConversationMessageIdRequest {
+                        peer_id,
+                        conversation_message_ids: List(batch.to_vec()),
+                    },
+                )
+                .await?;
+
+            for message in items {
+                if let Some(cmid) = message.conversation_message_id {
+                    self.insert(message.id, peer_id, cmid);
+                    result.insert(cmid, message.id);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Returns the `(peer_id, conversation_message_id)` pairs by the message ids.
+    /// Missing messages are absent in the result.
+    pub(crate) async fn conversation_message_ids(
+        &self,
+        client: &VkApi,
+        message_ids: &[i64],
+    ) -> VkApiResult<HashMap<i64, (i64, i64)>> {
+        let mut result = HashMap::with_capacity(message_ids.len());
+        let mut missing = Vec::new();
+
+        {
+            let ids = self
+                .ids
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            for &id in message_ids {
+                match ids.by_id.get(&id) {
+                    Some(&pair) => {
+                        result.insert(id, pair);
+                    }
+                    None => missing.push(id),
+                }
+            }
+        }
+
+        for batch in missing.chunks(LOOKUP_BATCH) {
+            let Items { items } = client
+                .send_request::<Items<Message>, _, _>(
+                    "messages.getById",
+                    ByIdRequest {
+                        message_ids: List(batch.to_vec()),
+                    },
+                )
+                .await?;
+
+            for message in items {
+                if let (Some(peer_id), Some(cmid)) =
+                    (message.peer_id, message.conversation_message_id)
+                {
+                    self.insert(message.id, peer_id, cmid);
+                    result.insert(message.id, (peer_id, cmid));
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[derive(Deserialize)]
+struct Items<T> {
+    items: Vec<T>,
+}
+
+#[derive(Serialize)]
+struct ByConversationMessageIdRequest {
+    peer_id: i64,
+    conversation_message_ids: List<Vec<i64>>,
+}
+
+#[derive(Serialize)]
+struct ByIdRequest {
+    message_ids: List<Vec<i64>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::messages::ids::MessageIdCache;
+    use crate::vkapi::local_api;
+    use crate::VkApiBuilder;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    /// Returns the messages with the `id` of `conversation_message_id + 1000` and records the ids of the requests.
+    async fn messages_api(requests: Arc<Mutex<Vec<usize>>>) -> crate::VkApi {
+        local_api(VkApiBuilder::new("token".into()), move |form| {
+            let form: HashMap<String, String> = serde_urlencoded::from_str(form).unwrap();
+            let cmids: Vec<i64> = form["conversation_message_ids"]
+                .split(',')
+                .map(|cmid| cmid.parse().unwrap())
+                .collect();
+            requests.lock().unwrap().push(cmids.len());
+
+            let items: Vec<_> = cmids
+                .iter()
+                .map(|cmid| {
+                    serde_json::json!({
+                        "id": cmid + 1000,
+                        "conversation_message_id": cmid,
+                        "peer_id": 1,
+                        "from_id": 1,
+                        "date": 0,
+                    })
+                })
+                .collect();
+            serde_json::json!({"response": {"items": items}}).to_string()
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn lookup_missing_ids_in_batches() {
+        let requests = Arc::default();
+        let client = messages_api(Arc::clone(&requests)).await;
+        let cache = MessageIdCache::new(1000);
+
+        let cmids: Vec<i64> = (1..=250).collect();
+        let ids = cache.message_ids(&client, 1, &cmids).await.unwrap();
+        assert_eq!(ids.len(), 250);
+        assert_eq!(ids[&250], 1250);
+        assert_eq!(*requests.lock().unwrap(), [100, 100, 50]);
+
+        // the cached ids are returned without the requests in both directions
+        cache.message_ids(&client, 1, &cmids).await.unwrap();
+        let pairs = cache
+            .conversation_message_ids(&client, &[1001, 1250])
+            .await
+            .unwrap();
+        assert_eq!(pairs[&1250], (1, 250));
+        assert_eq!(requests.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn clear_full_cache() {
+        let requests = Arc::default();
+        let client = messages_api(Arc::clone(&requests)).await;
+        let cache = MessageIdCache::new(2);
+
+        cache.message_ids(&client, 1, &[1, 2]).await.unwrap();
+        cache.message_ids(&client, 1, &[1, 2]).await.unwrap();
+        assert_eq!(*requests.lock().unwrap(), [2]);
+
+        // the full cache is cleared before the new id, so the old ids are requested again
+        cache.message_ids(&client, 1, &[3]).await.unwrap();
+        cache.message_ids(&client, 1, &[3]).await.unwrap();
+        cache.message_ids(&client, 1, &[1, 3]).await.unwrap();
+        assert_eq!(*requests.lock().unwrap(), [2, 1, 1]);
+    }
+}
@@ -0,0 +1,242 @@
+use crate::messages::VkMessages;
+use crate::{Version, VkApiError, VkApiResult};
+use futures_util::Stream;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Maximum count of the users per one request.
+const INTENT_USERS_PAGE_SIZE: usize = 200;
+/// Maximum id of the newsletter subscription.
+const MAX_SUBSCRIBE_ID: u8 = 100;
+
+/// [Intent](https://dev.vk.com/api/community-messages/intents) of the community message,
+/// which tells VK why the community writes to the user.
+/// Messages with the intent, which doesn't match their content, may lead to the messages block of the community.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Intent {
+    /// Reply to the user, who wrote to the community in the last 24 hours
+    #[default]
+    Default,
+    /// Advertising newsletter, requires the subscription of the user
+    PromoNewsletter,
+    /// Non advertising newsletter, requires the subscription of the user
+    NonPromoNewsletter,
+    /// Notification confirmed by the user, like the order status
+    ConfirmedNotification,
+    /// Invitation of the user to the bot from the advertising
+    BotAdInvite,
+    /// Advertising message of the bot to the user from the advertising
+    BotAdPromo,
+    PurchaseUpdate,
+    AccountUpdate,
+    GameNotification,
+    CustomerSupport,
+    /// Newsletter of the community, which passed the moderation
+    ModeratedNewsletter,
+}
+
+impl Intent {
+    /// Returns the minimal API version, which accepts the intent.
+    #[must_use]
+    pub const fn min_version(self) -> Version {
+        match self {
+            Self::Default => Version::MIN_SUPPORTED,
+            _ => Version(5, 103),
+        }
+    }
+
+    /// Returns true if the intent is sent with the `subscribe_id` of the newsletter.
+    #[must_use]
+    pub const fn has_subscription(self) -> bool {
+        matches!(self, Self::PromoNewsletter | Self::NonPromoNewsletter)
+    }
+
+    /// Returns true if the users of the intent can be listed with [`VkMessages::intent_users`].
+    #[must_use]
+    pub const fn has_users(self) -> bool {
+        matches!(
+            self,
+            Self::PromoNewsletter | Self::NonPromoNewsletter | Self::ConfirmedNotification
+        )
+    }
+
+    /// Checks, that the intent with the `subscribe_id` is accepted by the API version.
+    pub fn validate(self, subscribe_id: Option<u8>, version: Version) -> Result<(), IntentError> {
+        if version < self.min_version() {
+            return Err(IntentError::UnsupportedVersion(self, version));
+        }
+
+        match subscribe_id {
+            Some(_) if !self.has_subscription() => Err(IntentError::UnexpectedSubscription(self)),
+            Some(id) if id == 0 || id > MAX_SUBSCRIBE_ID => {
+                Err(IntentError::InvalidSubscription(id))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Display for Intent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Default => "default",
+            Self::PromoNewsletter => "promo_newsletter",
+            Self::NonPromoNewsletter => "non_promo_newsletter",
+            Self::ConfirmedNotification => "confirmed_notification",
+            Self::BotAdInvite => "bot_ad_invite",
+            Self::BotAdPromo => "bot_ad_promo",
+            Self::PurchaseUpdate => "purchase_update",
+            Self::AccountUpdate => "account_update",
+            Self::GameNotification => "game_notification",
+            Self::CustomerSupport => "customer_support",
+            Self::ModeratedNewsletter => "moderated_newsletter",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Invalid intent of the message, which is rejected before the request.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum IntentError {
+    /// Intent isn't accepted by the API version of the client
+    UnsupportedVersion(Intent, Version),
+    /// `subscribe_id` is passed with the intent without the subscription
+    UnexpectedSubscription(Intent),
+    /// `subscribe_id` is out of the 1..=100 range
+    InvalidSubscription(u8),
+    /// Users of the intent can't be listed
+    NoUsers(Intent),
+}
+
+impl Display for IntentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnsupportedVersion(intent, version) => write!(
+                f,
+                "intent {intent} requires api version {}, but {version} is used",
+                intent.min_version()
+            ),
+            Self::UnexpectedSubscription(intent) => {
+                write!(f, "intent {intent} doesn't accept subscribe_id")
+            }
+            Self::InvalidSubscription(id) => {
+                write!(f, "subscribe_id {id} is out of 1..={MAX_SUBSCRIBE_ID}")
+            }
+            Self::NoUsers(intent) => write!(f, "users of the intent {intent} can't be listed"),
+        }
+    }
+}
+
+impl Error for IntentError {}
+
+impl VkMessages {
+    /// Returns the stream of the ids of the users, who allowed the messages with the intent,
+    /// with [messages.getIntentUsers](https://dev.vk.com/method/messages.getIntentUsers).
+    /// Only the newsletters and the confirmed notifications have the users,
+    /// pass the `subscribe_id` of the newsletter.
+    pub fn intent_users(
+        &self,
+        intent: Intent,
+        subscribe_id: Option<u8>,
+    ) -> impl Stream<Item = VkApiResult<i64>> {
+        let client = self.client.clone();
+
+        async_stream::stream! {
+            let checked = if intent.has_users() {
+                intent.validate(subscribe_id, client.version())
+            } else {
+                Err(IntentError::NoUsers(intent))
+            };
+            if let Err(e) = checked {
+                yield Err(VkApiError::InvalidIntent(e));
+                return;
+            }
+
+            let mut offset = 0;
+            loop {
+                let page: VkApiResult<IntentUsersPage> = client
+                    .send_request(
+                        "messages.getIntentUsers",
+                        GetIntentUsers {
+                            intent,
+                            subscribe_id,
+                            offset,
+                            count: INTENT_USERS_PAGE_SIZE,
+                        },
+                    )
+                    .await;
+
+                let IntentUsersPage { count, items } = match page {
+                    Ok(page) => page,
+                    Err(e) => {
+                        yield Err(e);
+                        break;
+                    }
+                };
+
+                if items.is_empty() {
+                    break;
+                }
+                offset += items.len();
+
+                for item in items {
+                    yield Ok(item);
+                }
+
+                if offset >= count {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct GetIntentUsers {
+    intent: Intent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    subscribe_id: Option<u8>,
+    offset: usize,
+    count: usize,
+}
+
+#[derive(Deserialize)]
+struct IntentUsersPage {
+    count: usize,
+    items: Vec<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::messages::{Intent, IntentError, SendMessage};
+    use crate::Version;
+
+    #[test]
+    fn validate_intents() {
+        let version = Version(5, 199);
+
+        assert_eq!(Intent::Default.validate(None, Version(5, 90)), Ok(()));
+        assert_eq!(
+            Intent::PromoNewsletter.validate(Some(1), Version(5, 90)),
+            Err(IntentError::UnsupportedVersion(
+                Intent::PromoNewsletter,
+                Version(5, 90)
+            ))
+        );
+        assert_eq!(Intent::PromoNewsletter.validate(Some(1), version), Ok(()));
+        assert_eq!(
+            Intent::CustomerSupport.validate(Some(1), version),
+            Err(IntentError::UnexpectedSubscription(Intent::CustomerSupport))
+        );
+        assert_eq!(
+            Intent::NonPromoNewsletter.validate(Some(101), version),
+            Err(IntentError::InvalidSubscription(101))
+        );
+
+        let message = SendMessage::new(1).with_intent(Intent::NonPromoNewsletter, Some(3));
+        let params = serde_urlencoded::to_string(message).unwrap();
+        assert!(params.contains("intent=non_promo_newsletter&subscribe_id=3"));
+    }
+}
@@ -0,0 +1,358 @@
+use crate::Json;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Maximum length of the button label.
+pub const MAX_LABEL_LENGTH: usize = 40;
+/// Maximum length of the button payload.
+pub const MAX_PAYLOAD_LENGTH: usize = 255;
+/// Maximum count of the buttons in the keyboard row.
+pub const MAX_ROW_BUTTONS: usize = 5;
+/// Maximum count of the rows of the regular keyboard.
+pub const MAX_ROWS: usize = 10;
+/// Maximum count of the buttons of the regular keyboard.
+pub const MAX_BUTTONS: usize = 40;
+/// Maximum count of the rows of the inline keyboard.
+pub const MAX_INLINE_ROWS: usize = 6;
+/// Maximum count of the buttons of the inline keyboard.
+pub const MAX_INLINE_BUTTONS: usize = 10;
+
+/// # Bot keyboard
+/// Pass it as the `keyboard` param of [messages.send](https://dev.vk.com/method/messages.send).
+/// The inline keyboard is attached to the message, so it's shown in the group chats too.
+/// [`Keyboard::build`] checks the VK limits and reports every violating button,
+/// instead of the opaque errors of VK.
+///
+/// ## Usage:
+/// ```rust
+/// use vkclient::messages::{Button, Keyboard};
+///
+/// let keyboard = Keyboard::inline()
+///     .with_row(vec![Button::text("Yes"), Button::text("No")])
+///     .with_row(vec![Button::callback("Later").with_payload(r#"{"later":1}"#)])
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Keyboard {
+    #[serde(default)]
+    pub one_time: bool,
+    #[serde(default)]
+    pub inline: bool,
+    #[serde(default)]
+    pub buttons: Vec<Vec<Button>>,
+}
+
+impl Keyboard {
+    /// Creates an empty regular keyboard.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty inline keyboard.
+    #[must_use]
+    pub fn inline() -> Self {
+        Self {
+            inline: true,
+            ..Self::default()
+        }
+    }
+
+    /// Hide the regular keyboard after the button click.
+    #[must_use]
+    pub const fn with_one_time(mut self, one_time: bool) -> Self {
+        self.one_time = one_time;
+        self
+    }
+
+    /// Pass new row of the buttons.
+    #[must_use]
+    pub fn with_row(mut self, row: Vec<Button>) -> Self {
+        self.buttons.push(row);
+        self
+    }
+
+    /// Checks the VK limits of the keyboard.
+    pub fn validate(&self) -> Result<(), KeyboardError> {
+        let (max_rows, max_buttons) = if self.inline {
+            (MAX_INLINE_ROWS, MAX_INLINE_BUTTONS)
+        } else {
+            (MAX_ROWS, MAX_BUTTONS)
+        };
+
+        let mut violations = Vec::new();
+        if self.inline && self.one_time {
+            violations.push(KeyboardViolation::OneTimeInline);
+        }
+        if self.buttons.len() > max_rows {
+            violations.push(KeyboardViolation::TooManyRows(self.buttons.len()));
+        }
+
+        let total: usize = self.buttons.iter().map(Vec::len).sum();
+        if total > max_buttons {
+            violations.push(KeyboardViolation::TooManyButtons(total));
+        }
+
+        for (row, buttons) in self.buttons.iter().enumerate() {
+            if buttons.len() > MAX_ROW_BUTTONS {
+                violations.push(KeyboardViolation::TooManyRowButtons {
+                    row,
+                    count: buttons.len(),
+                });
+            }
+
+            for (column, button) in buttons.iter().enumerate() {
+                if button.label().chars().count() > MAX_LABEL_LENGTH {
+                    violations.push(KeyboardViolation::LabelTooLong { row, column });
+                }
+                if let Some(length) = button.payload().map(str::len) {
+                    if length > MAX_PAYLOAD_LENGTH {
+                        violations.push(KeyboardViolation::PayloadTooLong {
+                            row,
+                            column,
+                            length,
+                        });
+                    }
+                }
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(KeyboardError(violations))
+        }
+    }
+
+    /// Validates the keyboard and wraps it for passing as the `keyboard` param.
+    pub fn build(self) -> Result<Json<Self>, KeyboardError> {
+        self.validate()?;
+        Ok(Json(self))
+    }
+}
+
+/// Violated limit of the keyboard. Buttons are addressed by the row and the column from zero.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum KeyboardViolation {
+    /// Inline keyboard can't be one time
+    OneTimeInline,
+    /// Keyboard has more rows than allowed
+    TooManyRows(usize),
+    /// Keyboard has more buttons than allowed
+    TooManyButtons(usize),
+    /// Row has more than 5 buttons
+    TooManyRowButtons { row: usize, count: usize },
+    /// Button label is longer than 40 chars
+    LabelTooLong { row: usize, column: usize },
+    /// Button payload is longer than 255 bytes
+    PayloadTooLong {
+        row: usize,
+        column: usize,
+        length: usize,
+    },
+}
+
+impl Display for KeyboardViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OneTimeInline => write!(f, "inline keyboard can't be one time"),
+            Self::TooManyRows(count) => write!(f, "keyboard has too many rows: {count}"),
+            Self::TooManyButtons(count) => write!(f, "keyboard has too many buttons: {count}"),
+            Self::TooManyRowButtons { row, count } => write!(
+                f,
+                "row {row} has {count} buttons, maximum is {MAX_ROW_BUTTONS}"
+            ),
+            Self::LabelTooLong { row, column } => write!(
+                f,
+                "button {row}:{column} label is longer than {MAX_LABEL_LENGTH} chars"
+            ),
+            Self::PayloadTooLong {
+                row,
+                column,
+                length,
+            } => write!(
+                f,
+                "button {row}:{column} payload is {length} bytes, maximum is {MAX_PAYLOAD_LENGTH}"
+            ),
+        }
+    }
+}
+
+/// Keyboard validation error with all violated limits.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct KeyboardError(pub Vec<KeyboardViolation>);
+
+impl Display for KeyboardError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("invalid keyboard: ")?;
+        for (i, violation) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            Display::fmt(violation, f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for KeyboardError {}
+
+/// Keyboard or template button.
+/// [Read more about buttons](https://dev.vk.com/api/bots/development/keyboard).
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Button {
+    pub action: ButtonAction,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<ButtonColor>,
+}
+
+impl Button {
+    /// Creates the button, which sends its label as a message.
+    pub fn text<L: Into<String>>(label: L) -> Self {
+        Self {
+            action: ButtonAction::Text {
+                label: label.into(),
+                payload: None,
+            },
+            color: None,
+        }
+    }
+
+    /// Creates the button, which opens the link.
+    pub fn open_link<L: Into<String>, U: Into<String>>(label: L, link: U) -> Self {
+        Self {
+            action: ButtonAction::OpenLink {
+                label: label.into(),
+                link: link.into(),
+                payload: None,
+            },
+            color: None,
+        }
+    }
+
+    /// Creates the button, which sends the `message_event` to the bot without a message.
+    pub fn callback<L: Into<String>>(label: L) -> Self {
+        Self {
+            action: ButtonAction::Callback {
+                label: label.into(),
+                payload: None,
+            },
+            color: None,
+        }
+    }
+
+    /// Pass the button color. Links can't be colored.
+    #[must_use]
+    pub const fn with_color(mut self, color: ButtonColor) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Pass the json payload, which will be returned with the button event.
+    #[must_use]
+    pub fn with_payload<P: Into<String>>(mut self, payload: P) -> Self {
+        match &mut self.action {
+            ButtonAction::Text { payload: p, .. }
+            | ButtonAction::OpenLink { payload: p, .. }
+            | ButtonAction::Callback { payload: p, .. } => *p = Some(payload.into()),
+        }
+        self
+    }
+
+    /// Returns the button label.
+    #[must_use]
+    pub fn label(&self) -> &str {
+        match &self.action {
+            ButtonAction::Text { label, .. }
+            | ButtonAction::OpenLink { label, .. }
+            | ButtonAction::Callback { label, .. } => label,
+        }
+    }
+
+    /// Returns the button payload.
+    #[must_use]
+    pub fn payload(&self) -> Option<&str> {
+        match &self.action {
+            ButtonAction::Text { payload, .. }
+            | ButtonAction::OpenLink { payload, .. }
+            | ButtonAction::Callback { payload, .. } => payload.as_deref(),
+        }
+    }
+}
+
+/// Button action.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ButtonAction {
+    Text {
+        label: String,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        payload: Option<String>,
+    },
+    OpenLink {
+        label: String,
+        link: String,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        payload: Option<String>,
+    },
+    Callback {
+        label: String,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        payload: Option<String>,
+    },
+}
+
+/// Button color.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ButtonColor {
+    Primary,
+    Secondary,
+    Negative,
+    Positive,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::messages::{Button, Keyboard, KeyboardError, KeyboardViolation};
+
+    #[test]
+    fn serialize_keyboard() {
+        let keyboard = Keyboard::inline()
+            .with_row(vec![Button::text("Yes")])
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&keyboard.0).unwrap(),
+            r#"{"one_time":false,"inline":true,"buttons":[[{"action":{"type":"text","label":"Yes"}}]]}"#
+        );
+    }
+
+    #[test]
+    fn validate_keyboard() {
+        let keyboard = Keyboard::inline()
+            .with_one_time(true)
+            .with_row(vec![Button::text("Yes"); 6])
+            .with_row(vec![Button::text("No").with_payload("x".repeat(256))]);
+
+        assert_eq!(
+            keyboard.validate(),
+            Err(KeyboardError(vec![
+                KeyboardViolation::OneTimeInline,
+                KeyboardViolation::TooManyRowButtons { row: 0, count: 6 },
+                KeyboardViolation::PayloadTooLong {
+                    row: 1,
+                    column: 0,
+                    length: 256
+                },
+            ]))
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use crate::messages::{SendMessage, VkMessages};
+use crate::VkApiResult;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// # Queued messages sender
+/// Sends the messages in turns, taking the peers round-robin,
+/// so one chatty conversation can't starve the other peers under the rate limit of the client.
+/// Messages of the same peer are sent one at a time in the order of the calls.
+/// Clones share the queue.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use vkclient::messages::SendMessage;
+/// use vkclient::VkApi;
+/// # let access_token = String::new();
+/// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+/// let queue = client.messages().queue(4);
+///
+/// async move {
+///     let id = queue.send(SendMessage::new(1).with_text("hi")).await.unwrap();
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct MessageQueue {
+    messages: VkMessages,
+    schedule: Arc<Mutex<Schedule>>,
+}
+
+impl MessageQueue {
+    /// Creates the queue, which sends up to `concurrency` messages at once. Zero is treated as one.
+    #[must_use]
+    pub fn new(messages: VkMessages, concurrency: usize) -> Self {
+        Self {
+            messages,
+            schedule: Arc::new(Mutex::new(Schedule::new(concurrency))),
+        }
+    }
+
+    /// Waits for the turn of the peer and sends the message like the [`VkMessages::send`].
+    pub async fn send(&self, message: SendMessage) -> VkApiResult<i64> {
+        let _permit = self.turn(message.peer_id).await;
+        self.messages.send(message).await
+    }
+
+    /// Returns the count of the messages waiting for their turn.
+    pub fn waiting(&self) -> usize {
+        let schedule = self
+            .schedule
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        schedule.queues.values().map(VecDeque::len).sum()
+    }
+
+    fn turn(&self, peer_id: i64) -> Turn {
+        let mut schedule = self
+            .schedule
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let ticket = schedule.push(peer_id);
+        let granted = schedule.dispatch();
+        drop(schedule);
+        wake(granted);
+
+        Turn {
+            schedule: self.schedule.clone(),
+            peer_id,
+            ticket,
+            done: false,
+        }
+    }
+}
+
+impl VkMessages {
+    /// Returns the [`MessageQueue`], which sends up to `concurrency` messages at once with the fair turns of the peers.
+    #[must_use]
+    pub fn queue(&self, concurrency: usize) -> MessageQueue {
+        MessageQueue::new(self.clone(), concurrency)
+    }
+}
+
+/// Round-robin turns of the peers.
+#[derive(Debug)]
+struct Schedule {
+    /// Count of the messages, which can be sent now
+    free: usize,
+    next_ticket: u64,
+    /// Tickets of the waiting messages by the peer
+    queues: HashMap<i64, VecDeque<u64>>,
+    /// Peers with the waiting messages and without the message being sent, the next one first
+    order: VecDeque<i64>,
+    /// Peers with the message being sent
+    busy: HashSet<i64>,
+    /// Tickets, which got the turn and aren't polled yet
+    granted: HashSet<u64>,
+    wakers: HashMap<u64, Waker>,
+}
+
+impl Schedule {
+    fn new(concurrency: usize) -> Self {
+        Self {
+            free: concurrency.max(1),
+            next_ticket: 0,
+            queues: HashMap::new(),
+            order: VecDeque::new(),
+            busy: HashSet::new(),
+            granted: HashSet::new(),
+            wakers: HashMap::new(),
+        }
+    }
+
+    /// Queues the message of the peer and returns its ticket.
+    fn push(&mut self, peer_id: i64) -> u64 {
+        let ticket = self.next_ticket;
+        self.next_ticket += 1;
+
+        let queue = self.queues.entry(peer_id).or_default();
+        queue.push_back(ticket);
+        if queue.len() == 1 && !self.busy.contains(&peer_id) {
+            self.order.push_back(peer_id);
+        }
+        ticket
+    }
+
+    /// Gives the free turns to the next peers and returns the wakers of their messages.
+    fn dispatch(&mut self) -> Vec<Waker> {
+        let mut granted = Vec::new();
+
+        while self.free > 0 {
+            let Some(peer_id) = self.order.pop_front() else {
+                break;
+            };
+            let Some(queue) = self.queues.get_mut(&peer_id) else {
+                continue;
+            };
+            let Some(ticket) = queue.pop_front() else {
+                continue;
+            };
+            if queue.is_empty() {
+                self.queues.remove(&peer_id);
+            }
+
+            self.free -= 1;
+            self.busy.insert(peer_id);
+            self.granted.insert(ticket);
+            granted.extend(self.wakers.remove(&ticket));
+        }
+
+        granted
+    }
+
+    /// Ends the turn of the peer, its next message waits for the turn after the other peers.
+    fn release(&mut self, peer_id: i64) -> Vec<Waker> {
+        self.free += 1;
+        self.busy.remove(&peer_id);
+        if self.queues.contains_key(&peer_id) {
+            self.order.push_back(peer_id);
+        }
+        self.dispatch()
+    }
+
+    /// Removes the message, which stopped waiting for the turn.
+    fn cancel(&mut self, peer_id: i64, ticket: u64) {
+        self.wakers.remove(&ticket);
+        if let Some(queue) = self.queues.get_mut(&peer_id) {
+            queue.retain(|t| *t != ticket);
+            if queue.is_empty() {
+                self.queues.remove(&peer_id);
+                self.order.retain(|p| *p != peer_id);
+            }
+        }
+    }
+}
+
+fn wake(wakers: Vec<Waker>) {
+    wakers.into_iter().for_each(Waker::wake);
+}
+
+/// Future of the turn, which resolves to the permit of sending the message.
+struct Turn {
+    schedule: Arc<Mutex<Schedule>>,
+    peer_id: i64,
+    ticket: u64,
+    done: bool,
+}
+
+impl Future for Turn {
+    type Output = Permit;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut schedule = self
+            .schedule
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        if schedule.granted.remove(&self.ticket) {
+            drop(schedule);
+            self.done = true;
+            return Poll::Ready(Permit {
+                schedule: self.schedule.clone(),
+                peer_id: self.peer_id,
+            });
+        }
+
+        schedule.wakers.insert(self.ticket, cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for Turn {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+
+        let mut schedule = self
+            .schedule
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let granted = if schedule.granted.remove(&self.ticket) {
+            schedule.release(self.peer_id)
+        } else {
+            schedule.cancel(self.peer_id, self.ticket);
+            Vec::new()
+        };
+        drop(schedule);
+        wake(granted);
+    }
+}
+
+/// Turn of the peer, which ends on drop.
+struct Permit {
+    schedule: Arc<Mutex<Schedule>>,
+    peer_id: i64,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut schedule = self
+            .schedule
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let granted = schedule.release(self.peer_id);
+        drop(schedule);
+        wake(granted);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::messages::queue::Schedule;
+
+    #[test]
+    fn take_peers_round_robin() {
+        let mut schedule = Schedule::new(1);
+        let mut sent = Vec::new();
+
+        for peer_id in [1, 1, 1, 2, 3] {
+            let ticket = schedule.push(peer_id);
+            sent.push((peer_id, ticket));
+        }
+        schedule.dispatch();
+
+        let mut turns = Vec::new();
+        while let Some(&ticket) = schedule.granted.iter().next() {
+            schedule.granted.remove(&ticket);
+            let (peer_id, _) = sent.iter().find(|(_, t)| *t == ticket).unwrap();
+            turns.push(*peer_id);
+            schedule.release(*peer_id);
+        }
+
+        assert_eq!(turns, [1, 2, 3, 1, 1]);
+    }
+
+    #[test]
+    fn skip_cancelled_messages() {
+        let mut schedule = Schedule::new(2);
+
+        schedule.push(1);
+        let cancelled = schedule.push(2);
+        schedule.cancel(2, cancelled);
+        schedule.push(3);
+        schedule.dispatch();
+
+        assert_eq!(schedule.granted.len(), 2);
+        assert!(!schedule.granted.contains(&cancelled));
+        assert!(schedule.queues.is_empty() && schedule.order.is_empty());
+    }
+}
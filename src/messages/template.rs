@@ -0,0 +1,298 @@
+use crate::messages::keyboard::{Button, MAX_LABEL_LENGTH, MAX_PAYLOAD_LENGTH};
+use crate::Json;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Maximum count of the carousel elements.
+pub const MAX_ELEMENTS: usize = 10;
+/// Maximum count of the buttons in the carousel element.
+pub const MAX_ELEMENT_BUTTONS: usize = 3;
+/// Maximum length of the element title and description.
+pub const MAX_TEXT_LENGTH: usize = 80;
+
+/// # Carousel message template
+/// Pass it as the `template` param of [messages.send](https://dev.vk.com/method/messages.send).
+/// VK requires all elements of the carousel to have the same set of fields and the same buttons count,
+/// so [`Carousel::build`] checks it before sending.
+///
+/// ## Usage:
+/// ```rust
+/// use vkclient::messages::{Button, Carousel, CarouselElement};
+///
+/// let template = Carousel::new()
+///     .with_element(
+///         CarouselElement::new()
+///             .with_title("First")
+///             .with_description("Description")
+///             .with_button(Button::text("Buy")),
+///     )
+///     .with_element(
+///         CarouselElement::new()
+///             .with_title("Second")
+///             .with_description("Description")
+///             .with_button(Button::text("Buy")),
+///     )
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename = "carousel")]
+pub struct Carousel {
+    pub elements: Vec<CarouselElement>,
+}
+
+impl Carousel {
+    /// Creates an empty carousel.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pass new element to the carousel.
+    #[must_use]
+    pub fn with_element(mut self, element: CarouselElement) -> Self {
+        self.elements.push(element);
+        self
+    }
+
+    /// Checks the VK limits of the carousel.
+    pub fn validate(&self) -> Result<(), TemplateError> {
+        let first = self.elements.first().ok_or(TemplateError::Empty)?;
+        if self.elements.len() > MAX_ELEMENTS {
+            return Err(TemplateError::TooManyElements(self.elements.len()));
+        }
+
+        for (i, element) in self.elements.iter().enumerate() {
+            element.validate(i)?;
+            if !element.same_shape(first) {
+                return Err(TemplateError::InconsistentElement(i));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validates the carousel and wraps it for passing as the `template` param.
+    pub fn build(self) -> Result<Json<Self>, TemplateError> {
+        self.validate()?;
+        Ok(Json(self))
+    }
+}
+
+/// Element of the carousel.
+/// Element must contain a photo or a title with a description.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CarouselElement {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Photo id in the `{owner_id}_{photo_id}` format.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub photo_id: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<ElementAction>,
+    #[serde(default)]
+    pub buttons: Vec<Button>,
+}
+
+impl CarouselElement {
+    /// Creates an empty element.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pass the element title.
+    #[must_use]
+    pub fn with_title<T: Into<String>>(mut self, title: T) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Pass the element description.
+    #[must_use]
+    pub fn with_description<T: Into<String>>(mut self, description: T) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Pass the element photo.
+    #[must_use]
+    pub fn with_photo(mut self, owner_id: i64, photo_id: i64) -> Self {
+        self.photo_id = Some(format!("{owner_id}_{photo_id}"));
+        self
+    }
+
+    /// Pass the action on the element click.
+    #[must_use]
+    pub fn with_action(mut self, action: ElementAction) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    /// Pass new button to the element.
+    #[must_use]
+    pub fn with_button(mut self, button: Button) -> Self {
+        self.buttons.push(button);
+        self
+    }
+
+    fn validate(&self, i: usize) -> Result<(), TemplateError> {
+        let has_text = self.title.is_some() && self.description.is_some();
+        if !has_text && self.photo_id.is_none() {
+            return Err(TemplateError::EmptyElement(i));
+        }
+
+        let too_long = |text: &Option<String>| {
+            text.as_ref()
+                .is_some_and(|t| t.chars().count() > MAX_TEXT_LENGTH)
+        };
+        if too_long(&self.title) || too_long(&self.description) {
+            return Err(TemplateError::TextTooLong(i));
+        }
+
+        if self.buttons.is_empty() || self.buttons.len() > MAX_ELEMENT_BUTTONS {
+            return Err(TemplateError::ButtonsCount(i, self.buttons.len()));
+        }
+
+        for button in &self.buttons {
+            if button.label().chars().count() > MAX_LABEL_LENGTH {
+                return Err(TemplateError::LabelTooLong(i));
+            }
+            if button
+                .payload()
+                .is_some_and(|p| p.len() > MAX_PAYLOAD_LENGTH)
+            {
+                return Err(TemplateError::PayloadTooLong(i));
+            }
+        }
+
+        Ok(())
+    }
+
+    const fn same_shape(&self, other: &Self) -> bool {
+        self.title.is_some() == other.title.is_some()
+            && self.description.is_some() == other.description.is_some()
+            && self.photo_id.is_some() == other.photo_id.is_some()
+            && self.buttons.len() == other.buttons.len()
+    }
+}
+
+/// Action on the carousel element click.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ElementAction {
+    OpenLink { link: String },
+    OpenPhoto,
+}
+
+/// Template validation errors. Each variant holds the index of the invalid element.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TemplateError {
+    /// Carousel has no elements
+    Empty,
+    /// Carousel has more than 10 elements
+    TooManyElements(usize),
+    /// Element has neither a photo, nor a title with a description
+    EmptyElement(usize),
+    /// Element has other set of fields or buttons count than the first one
+    InconsistentElement(usize),
+    /// Element title or description is longer than 80 chars
+    TextTooLong(usize),
+    /// Element has no buttons or more than 3 buttons
+    ButtonsCount(usize, usize),
+    /// Button label is longer than 40 chars
+    LabelTooLong(usize),
+    /// Button payload is longer than 255 bytes
+    PayloadTooLong(usize),
+}
+
+impl Display for TemplateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "carousel has no elements"),
+            Self::TooManyElements(count) => write!(
+                f,
+                "carousel has {count} elements, maximum is {MAX_ELEMENTS}"
+            ),
+            Self::EmptyElement(i) => write!(
+                f,
+                "carousel element {i} has neither a photo, nor a title with a description"
+            ),
+            Self::InconsistentElement(i) => write!(
+                f,
+                "carousel element {i} has other fields or buttons count than the first element"
+            ),
+            Self::TextTooLong(i) => write!(
+                f,
+                "carousel element {i} title or description is longer than {MAX_TEXT_LENGTH} chars"
+            ),
+            Self::ButtonsCount(i, count) => write!(
+                f,
+                "carousel element {i} has {count} buttons, expected 1..={MAX_ELEMENT_BUTTONS}"
+            ),
+            Self::LabelTooLong(i) => write!(
+                f,
+                "carousel element {i} button label is longer than {MAX_LABEL_LENGTH} chars"
+            ),
+            Self::PayloadTooLong(i) => write!(
+                f,
+                "carousel element {i} button payload is longer than {MAX_PAYLOAD_LENGTH} bytes"
+            ),
+        }
+    }
+}
+
+impl Error for TemplateError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::messages::{Button, ButtonColor, Carousel, CarouselElement, TemplateError};
+
+    fn element() -> CarouselElement {
+        CarouselElement::new()
+            .with_title("Title")
+            .with_description("Description")
+            .with_button(Button::text("Buy").with_color(ButtonColor::Positive))
+    }
+
+    #[test]
+    fn serialize_carousel() {
+        let template = Carousel::new().with_element(element()).build().unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&template.0).unwrap(),
+            r#"{"type":"carousel","elements":[{"title":"Title","description":"Description","buttons":[{"action":{"type":"text","label":"Buy"},"color":"positive"}]}]}"#
+        );
+    }
+
+    #[test]
+    fn validate_carousel() {
+        assert_eq!(Carousel::new().validate(), Err(TemplateError::Empty));
+        assert_eq!(
+            Carousel::new()
+                .with_element(element())
+                .with_element(element().with_photo(-1, 2))
+                .validate(),
+            Err(TemplateError::InconsistentElement(1))
+        );
+        assert_eq!(
+            Carousel::new()
+                .with_element(CarouselElement::new().with_photo(-1, 2))
+                .validate(),
+            Err(TemplateError::ButtonsCount(0, 0))
+        );
+
+        let mut carousel = Carousel::new();
+        for _ in 0..11 {
+            carousel = carousel.with_element(element());
+        }
+        assert_eq!(carousel.validate(), Err(TemplateError::TooManyElements(11)));
+    }
+}
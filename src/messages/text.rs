@@ -0,0 +1,143 @@
+/// Maximum length of the message text in chars.
+pub const MAX_MESSAGE_LENGTH: usize = 4096;
+
+/// Mentions, which notify all members of the chat.
+const MASS_MENTIONS: [&str; 4] = ["all", "online", "everyone", "here"];
+
+const ZERO_WIDTH_SPACE: char = '\u{200B}';
+
+/// Chunk boundaries by priority.
+const BOUNDARIES: [&str; 6] = ["\n\n", "\n", ". ", "! ", "? ", " "];
+
+/// Splits the text to chunks not longer than `limit` chars.
+///
+/// Chunks are cut at paragraph breaks, then at line breaks, then at sentence ends, then at spaces,
+/// and never inside of the `[id1|Name]` mentions if possible.
+/// Text without suitable boundaries is cut by chars.
+///
+/// ```rust
+/// use vkclient::messages::split_message;
+///
+/// assert_eq!(split_message("first line\nsecond line", 15), vec!["first line\n", "second line"]);
+/// ```
+#[must_use]
+pub fn split_message(text: &str, limit: usize) -> Vec<&str> {
+    let limit = limit.max(1);
+    let mut chunks = Vec::new();
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let end = match rest.char_indices().nth(limit) {
+            None => rest.len(),
+            Some((end, _)) => find_boundary(&rest[..end]).unwrap_or(end),
+        };
+
+        chunks.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+
+    chunks
+}
+
+/// Returns the end of the best boundary in the text.
+/// Boundaries in the second half of the text are preferred by priority, others are used as fallback.
+fn find_boundary(text: &str) -> Option<usize> {
+    let boundaries = BOUNDARIES.iter().filter_map(|separator| {
+        text.match_indices(separator)
+            .map(|(i, s)| i + s.len())
+            .filter(|&end| !inside_mention(&text[..end]))
+            .last()
+    });
+
+    boundaries
+        .clone()
+        .find(|&end| end > text.len() / 2)
+        .or_else(|| boundaries.max())
+}
+
+fn inside_mention(prefix: &str) -> bool {
+    match (prefix.rfind('['), prefix.rfind(']')) {
+        (Some(open), Some(close)) => open > close,
+        (Some(_), None) => true,
+        _ => false,
+    }
+}
+
+/// Breaks the mass mentions like `@all` or `*online` and the `[id1|Name]` mention links,
+/// so the text is shown as is without notifying anyone.
+///
+/// ```rust
+/// use vkclient::messages::escape_mentions;
+///
+/// assert_eq!(escape_mentions("hi @all"), "hi @\u{200B}all");
+/// assert_eq!(escape_mentions("[id1|Pavel]"), "[\u{200B}id1|Pavel]");
+/// ```
+#[must_use]
+pub fn escape_mentions(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (i, c) in text.char_indices() {
+        escaped.push(c);
+
+        let rest = &text[i + c.len_utf8()..];
+        let is_mention = match c {
+            '@' | '*' => MASS_MENTIONS.iter().any(|m| starts_with_word(rest, m)),
+            '[' => rest.starts_with("id") || rest.starts_with("club") || rest.starts_with("public"),
+            _ => false,
+        };
+
+        if is_mention {
+            escaped.push(ZERO_WIDTH_SPACE);
+        }
+    }
+
+    escaped
+}
+
+fn starts_with_word(text: &str, word: &str) -> bool {
+    text.get(..word.len())
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(word))
+        && !text[word.len()..]
+            .chars()
+            .next()
+            .is_some_and(char::is_alphanumeric)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::messages::{escape_mentions, split_message};
+
+    #[test]
+    fn split_by_boundaries() {
+        assert_eq!(
+            split_message("one.\n\ntwo. three four", 12),
+            vec!["one.\n\ntwo. ", "three four"]
+        );
+        assert_eq!(split_message("abcdef", 4), vec!["abcd", "ef"]);
+        assert_eq!(split_message("", 4), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn split_by_chars() {
+        assert_eq!(split_message("абв где", 4), vec!["абв ", "где"]);
+    }
+
+    #[test]
+    fn split_outside_mentions() {
+        assert_eq!(
+            split_message("hi [id1|Pavel Durov]", 17),
+            vec!["hi ", "[id1|Pavel Durov]"]
+        );
+    }
+
+    #[test]
+    fn escape_mass_mentions() {
+        assert_eq!(
+            escape_mentions("@All *online"),
+            "@\u{200B}All *\u{200B}online"
+        );
+        assert_eq!(
+            escape_mentions("@allies mail@domain"),
+            "@allies mail@domain"
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use crate::{Compression, Encoding, MetricsRecorder, RequestMetrics};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Upper bounds of the response size buckets in bytes, the last bucket is unbounded.
+const SIZE_BOUNDS: [u64; 6] = [1 << 10, 4 << 10, 16 << 10, 64 << 10, 256 << 10, 1 << 20];
+/// Maximum count of the tracked methods, protects from unbounded growth with generated method names.
+const METHODS_LIMIT: usize = 1024;
+
+/// Log of the requests, which are slower than the threshold.
+pub type SlowRequestLog = Arc<dyn Fn(&SlowRequest<'_>) + Send + Sync>;
+
+/// Request, which is slower than the threshold of [`VkApiBuilder::with_slow_request_log`](crate::VkApiBuilder::with_slow_request_log).
+#[derive(Debug, Clone, Copy)]
+pub struct SlowRequest<'a> {
+    pub method: &'a str,
+    /// Time from sending the request to decoding the response
+    pub duration: Duration,
+    /// Size of the response body before the decompression
+    pub bytes: usize,
+    pub encoding: Encoding,
+    pub compression: Compression,
+}
+
+/// Counts of the responses by their size.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct SizeHistogram {
+    counts: [u64; SIZE_BOUNDS.len() + 1],
+}
+
+impl SizeHistogram {
+    /// Returns the upper bounds of the buckets in bytes, the last bucket counts the larger responses.
+    #[must_use]
+    pub const fn bounds() -> &'static [u64] {
+        &SIZE_BOUNDS
+    }
+
+    /// Returns the counts of the buckets, the smallest responses first.
+    #[must_use]
+    pub const fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    fn record(&mut self, bytes: u64) {
+        let bucket = SIZE_BOUNDS.partition_point(|bound| *bound < bytes);
+        if let Some(count) = self.counts.get_mut(bucket) {
+            *count += 1;
+        }
+    }
+}
+
+/// Response sizes and durations of the method, see [`VkApi::method_stats`](crate::VkApi::method_stats).
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct MethodSnapshot {
+    pub requests: u64,
+    /// Slower requests than the threshold of the log
+    pub slow_requests: u64,
+    /// Total size of the responses before the decompression
+    pub bytes: u64,
+    pub duration: Duration,
+    pub max_duration: Duration,
+    pub sizes: SizeHistogram,
+}
+
+impl MethodSnapshot {
+    /// Returns the average size of the responses.
+    #[must_use]
+    pub fn mean_bytes(&self) -> u64 {
+        self.bytes.checked_div(self.requests).unwrap_or_default()
+    }
+
+    /// Returns the average duration of the requests.
+    #[must_use]
+    pub fn mean_duration(&self) -> Duration {
+        u32::try_from(self.requests)
+            .ok()
+            .and_then(|requests| self.duration.checked_div(requests))
+            .unwrap_or_default()
+    }
+
+    fn record(&mut self, request: &SlowRequest<'_>, slow: bool) {
+        let bytes = request.bytes as u64;
+        self.requests += 1;
+        self.slow_requests += u64::from(slow);
+        self.bytes += bytes;
+        self.duration += request.duration;
+        self.max_duration = self.max_duration.max(request.duration);
+        self.sizes.record(bytes);
+    }
+}
+
+/// Aggregator of the responses of the client by their methods.
+pub struct MethodStats {
+    slow_log: Option<(Duration, SlowRequestLog)>,
+    methods: Mutex<HashMap<String, MethodSnapshot>>,
+}
+
+impl MethodStats {
+    pub(crate) fn new() -> Self {
+        Self {
+            slow_log: None,
+            methods: Mutex::default(),
+        }
+    }
+
+    /// Pass the log of the requests, which are slower than the threshold.
+    pub(crate) fn with_slow_log(mut self, threshold: Duration, log: SlowRequestLog) -> Self {
+        self.slow_log = Some((threshold, log));
+        self
+    }
+
+    fn record_request(&self, request: &SlowRequest<'_>) {
+        let slow = match &self.slow_log {
+            Some((threshold, log)) if request.duration >= *threshold => {
+                log(request);
+                true
+            }
+            _ => false,
+        };
+
+        let mut methods = self
+            .methods
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if let Some(stats) = methods.get_mut(request.method) {
+            stats.record(request, slow);
+        } else if methods.len() < METHODS_LIMIT {
+            let mut stats = MethodSnapshot::default();
+            stats.record(request, slow);
+            methods.insert(request.method.to_owned(), stats);
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> HashMap<String, MethodSnapshot> {
+        self.methods
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+    }
+}
+
+impl MetricsRecorder for MethodStats {
+    /// Records the requests of VK API methods, the requests to the long poll and upload servers are skipped.
+    fn record(&self, metrics: &RequestMetrics<'_>) {
+        let (Some(method), Some(encoding)) = (metrics.method, metrics.encoding) else {
+            return;
+        };
+
+        self.record_request(&SlowRequest {
+            method,
+            duration: metrics.elapsed,
+            bytes: metrics.bytes,
+            encoding,
+            compression: metrics.compression,
+        });
+    }
+}
+
+impl Clone for MethodStats {
+    /// Clones the settings with the empty state, stats of the built clients are shared instead.
+    fn clone(&self) -> Self {
+        Self {
+            slow_log: self.slow_log.clone(),
+            methods: Mutex::default(),
+        }
+    }
+}
+
+impl Debug for MethodStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MethodStats")
+            .field(
+                "slow_threshold",
+                &self.slow_log.as_ref().map(|(threshold, _)| threshold),
+            )
+            .field("methods", &self.methods)
+            .finish()
+    }
+}
+
+#[cfg(all(test, feature = "encode_json"))]
+mod tests {
+    use crate::method_stats::{MethodStats, SlowRequest};
+    use crate::{Compression, Encoding, MetricsRecorder, RequestMetrics, SizeHistogram};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn aggregate_methods() {
+        let logged = Arc::new(AtomicUsize::new(0));
+        let counter = logged.clone();
+        let stats = MethodStats::new().with_slow_log(
+            Duration::from_secs(1),
+            Arc::new(move |request: &SlowRequest<'_>| {
+                assert_eq!(request.method, "wall.get");
+                counter.fetch_add(request.bytes, Ordering::Relaxed);
+            }),
+        );
+
+        for (duration, bytes) in [(100, 512), (1500, 300_000), (200, 2000)] {
+            stats.record(&RequestMetrics {
+                host: "api.vk.com",
+                method: Some("wall.get"),
+                elapsed: Duration::from_millis(duration),
+                bytes,
+                encoding: Some(Encoding::Json),
+                compression: Compression::None,
+                error: None,
+            });
+        }
+        // requests to the long poll and upload servers aren't methods
+        stats.record(&RequestMetrics {
+            host: "lp.vk.com",
+            method: None,
+            elapsed: Duration::from_secs(25),
+            bytes: 10,
+            encoding: None,
+            compression: Compression::None,
+            error: None,
+        });
+        assert_eq!(stats.snapshot().len(), 1);
+
+        let snapshot = stats.snapshot().remove("wall.get").unwrap();
+        assert_eq!((snapshot.requests, snapshot.slow_requests), (3, 1));
+        assert_eq!(snapshot.mean_bytes(), 100_837);
+        assert_eq!(snapshot.mean_duration(), Duration::from_millis(600));
+        assert_eq!(snapshot.max_duration, Duration::from_millis(1500));
+        assert_eq!(snapshot.sizes.counts(), [1, 1, 0, 0, 0, 1, 0]);
+        assert_eq!(
+            SizeHistogram::bounds().len() + 1,
+            snapshot.sizes.counts().len()
+        );
+        assert_eq!(logged.load(Ordering::Relaxed), 300_000);
+    }
+}
@@ -0,0 +1,52 @@
+use crate::{Compression, Encoding};
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Measurements of the request to VK API, the long poll or the upload server.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestMetrics<'a> {
+    /// Host of the server
+    pub host: &'a str,
+    /// Method of VK API, `None` for the long poll and upload servers
+    pub method: Option<&'a str>,
+    /// Time from sending the request to receiving the whole body, with decoding the response of VK API
+    pub elapsed: Duration,
+    /// Size of the received body before the decompression
+    pub bytes: usize,
+    /// Encoding of the response of VK API, `None` for the long poll and upload servers
+    pub encoding: Option<Encoding>,
+    pub compression: Compression,
+    /// [`VkApiError::label`](crate::VkApiError::label) of the failed request
+    pub error: Option<&'static str>,
+}
+
+/// Recorder of the [`RequestMetrics`], like the counters and histograms of your metrics system.
+pub trait MetricsRecorder: Send + Sync {
+    fn record(&self, metrics: &RequestMetrics<'_>);
+}
+
+impl<F> MetricsRecorder for F
+where
+    F: Fn(&RequestMetrics<'_>) + Send + Sync,
+{
+    fn record(&self, metrics: &RequestMetrics<'_>) {
+        self(metrics);
+    }
+}
+
+/// Registered recorder, shared by the clients.
+#[derive(Clone)]
+pub struct RecorderHandle(pub(crate) Arc<dyn MetricsRecorder>);
+
+impl MetricsRecorder for RecorderHandle {
+    fn record(&self, metrics: &RequestMetrics<'_>) {
+        self.0.record(metrics);
+    }
+}
+
+impl Debug for RecorderHandle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MetricsRecorder")
+    }
+}
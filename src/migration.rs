@@ -0,0 +1,317 @@
+use crate::{TokenScope, Version, VkApiWrapper};
+use std::fmt::{Display, Formatter};
+
+/// Breaking changes of the API methods, which are known to the client.
+/// The list isn't exhaustive, see the [versions changelog](https://dev.vk.com/reference/versions)
+/// and add the changes important for you with [`WrapperRegistry::with_change`].
+const BREAKING_CHANGES: &[BreakingChange] = &[
+    BreakingChange {
+        version: Version(5, 90),
+        method: "messages.send",
+        field: Some("random_id"),
+        description: "`random_id` is required to deduplicate the messages",
+    },
+    BreakingChange {
+        version: Version(5, 103),
+        method: "groups.setLongPollSettings",
+        field: Some("api_version"),
+        description: "`message_new` event object contains the `message` and `client_info` fields instead of the message",
+    },
+    BreakingChange {
+        version: Version(5, 103),
+        method: "groups.setCallbackSettings",
+        field: Some("api_version"),
+        description: "`message_new` event object contains the `message` and `client_info` fields instead of the message",
+    },
+];
+
+/// Change of the method behavior since the version.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BreakingChange {
+    pub version: Version,
+    /// Method name, or the prefix ending with `*` like `messages.*`
+    pub method: &'static str,
+    /// Changed param or response field
+    pub field: Option<&'static str>,
+    pub description: &'static str,
+}
+
+impl BreakingChange {
+    fn matches(&self, method: &str) -> bool {
+        match self.method.strip_suffix('*') {
+            Some(prefix) => method.starts_with(prefix),
+            None => self.method == method,
+        }
+    }
+}
+
+/// # Registry of the wrappers
+/// Register the wrappers of your application to check them with
+/// [`VkApi::check_compatibility`](crate::VkApi::check_compatibility) before the version upgrade,
+/// and to look up their methods, versions and token kinds at runtime, like for the permission manifests.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use serde::Serialize;
+/// use vkclient::{Version, VkApi, VkApiWrapper, WrapperRegistry};
+///
+/// #[derive(Serialize)]
+/// struct MessagesSend {
+///     peer_id: i64,
+///     message: String,
+/// }
+///
+/// impl VkApiWrapper for MessagesSend {
+///     type Response = i64;
+///
+///     fn get_method_name() -> &'static str {
+///         "messages.send"
+///     }
+///
+///     fn get_version() -> Version {
+///         Version(5, 81)
+///     }
+/// }
+///
+/// # let access_token = String::new();
+/// let client: VkApi = vkclient::VkApiBuilder::new(access_token)
+///     .with_wrapper_registry(WrapperRegistry::new().with_wrapper::<MessagesSend>())
+///     .into();
+///
+/// for issue in client.check_compatibility(Version(5, 199)).issues {
+///     println!("{issue}");
+/// }
+///
+/// let registry = client.wrapper_registry().unwrap();
+/// assert_eq!(registry.get("messages.send").unwrap().version, Version(5, 81));
+/// ```
+#[derive(Clone, Debug)]
+pub struct WrapperRegistry {
+    wrappers: Vec<WrapperInfo>,
+    changes: Vec<BreakingChange>,
+}
+
+/// Metadata of the registered wrapper.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WrapperInfo {
+    pub method: &'static str,
+    pub version: Version,
+    pub requires_auth: bool,
+    pub token_scopes: &'static [TokenScope],
+}
+
+impl WrapperInfo {
+    /// Returns the metadata of the wrapper.
+    #[must_use]
+    pub fn of<W: VkApiWrapper>() -> Self {
+        Self {
+            method: W::get_method_name(),
+            version: W::get_version(),
+            requires_auth: W::requires_auth(),
+            token_scopes: W::token_scopes(),
+        }
+    }
+
+    /// Returns true if the method accepts the token of the kind.
+    #[must_use]
+    pub fn accepts(&self, scope: TokenScope) -> bool {
+        self.token_scopes.contains(&scope)
+    }
+}
+
+impl WrapperRegistry {
+    /// Creates the registry with the built-in breaking changes.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            wrappers: Vec::new(),
+            changes: BREAKING_CHANGES.to_vec(),
+        }
+    }
+
+    /// Registers the wrapper with its metadata. Wrapper of the registered method replaces the previous one.
+    #[must_use]
+    pub fn with_wrapper<W: VkApiWrapper>(mut self) -> Self {
+        let info = WrapperInfo::of::<W>();
+        match self.wrappers.iter_mut().find(|w| w.method == info.method) {
+            Some(registered) => *registered = info,
+            None => self.wrappers.push(info),
+        }
+        self
+    }
+
+    /// Returns the registered wrappers in the registration order.
+    #[must_use]
+    pub fn wrappers(&self) -> &[WrapperInfo] {
+        &self.wrappers
+    }
+
+    /// Returns the wrapper of the method.
+    #[must_use]
+    pub fn get(&self, method: &str) -> Option<&WrapperInfo> {
+        self.wrappers.iter().find(|w| w.method == method)
+    }
+
+    /// Returns the registered methods, which accept the token of the kind.
+    pub fn methods_for(&self, scope: TokenScope) -> impl Iterator<Item = &'static str> + '_ {
+        self.wrappers
+            .iter()
+            .filter(move |w| w.accepts(scope))
+            .map(|w| w.method)
+    }
+
+    /// Adds the breaking change, which is missing in the built-in list.
+    #[must_use]
+    pub fn with_change(mut self, change: BreakingChange) -> Self {
+        self.changes.push(change);
+        self
+    }
+
+    /// Returns the changes of the registered wrappers between their versions and the target one.
+    #[must_use]
+    pub fn check(&self, target: Version) -> CompatibilityReport {
+        let issues = self
+            .wrappers
+            .iter()
+            .flat_map(|wrapper| {
+                let (method, version) = (wrapper.method, wrapper.version);
+                self.changes
+                    .iter()
+                    .filter(move |change| {
+                        change.matches(method)
+                            && version < change.version
+                            && change.version <= target
+                    })
+                    .map(move |&change| CompatibilityIssue {
+                        method,
+                        version,
+                        change,
+                    })
+            })
+            .collect();
+
+        CompatibilityReport { target, issues }
+    }
+}
+
+impl Default for WrapperRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Result of the compatibility check.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompatibilityReport {
+    pub target: Version,
+    pub issues: Vec<CompatibilityIssue>,
+}
+
+impl CompatibilityReport {
+    /// Returns true if no registered wrapper is affected by the known changes.
+    #[must_use]
+    pub const fn is_compatible(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Breaking change, which affects the registered wrapper.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompatibilityIssue {
+    pub method: &'static str,
+    /// Version of the wrapper
+    pub version: Version,
+    pub change: BreakingChange,
+}
+
+impl Display for CompatibilityIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} -> {}",
+            self.method, self.version, self.change.version
+        )?;
+        if let Some(field) = self.change.field {
+            write!(f, " `{field}`")?;
+        }
+        write!(f, ": {}", self.change.description)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BreakingChange, TokenScope, Version, VkApiWrapper, WrapperRegistry};
+
+    struct MessagesSend;
+
+    impl VkApiWrapper for MessagesSend {
+        type Response = i64;
+
+        fn get_method_name() -> &'static str {
+            "messages.send"
+        }
+
+        fn get_version() -> Version {
+            Version(5, 81)
+        }
+
+        fn token_scopes() -> &'static [TokenScope] {
+            &[TokenScope::User, TokenScope::Group]
+        }
+    }
+
+    struct UsersGet;
+
+    impl VkApiWrapper for UsersGet {
+        type Response = ();
+
+        fn get_method_name() -> &'static str {
+            "users.get"
+        }
+    }
+
+    #[test]
+    fn report_changes() {
+        let registry = WrapperRegistry::new()
+            .with_wrapper::<MessagesSend>()
+            .with_wrapper::<UsersGet>()
+            .with_change(BreakingChange {
+                version: Version(5, 150),
+                method: "users.*",
+                field: None,
+                description: "test change",
+            });
+
+        let report = registry.check(Version(5, 89));
+        assert!(report.is_compatible());
+
+        let report = registry.check(Version(5, 199));
+        assert_eq!(report.issues.len(), 2);
+        assert_eq!(
+            report.issues[0].to_string(),
+            "messages.send 5.81 -> 5.90 `random_id`: `random_id` is required to deduplicate the messages"
+        );
+        assert_eq!(report.issues[1].method, "users.get");
+    }
+
+    #[test]
+    fn introspect_wrappers() {
+        let registry = WrapperRegistry::new()
+            .with_wrapper::<MessagesSend>()
+            .with_wrapper::<UsersGet>()
+            .with_wrapper::<MessagesSend>();
+
+        assert_eq!(registry.wrappers().len(), 2);
+        assert_eq!(
+            registry.get("users.get").unwrap().version,
+            Version::default()
+        );
+        assert!(registry.get("users.search").is_none());
+        assert_eq!(
+            registry
+                .methods_for(TokenScope::Service)
+                .collect::<Vec<_>>(),
+            ["users.get"]
+        );
+    }
+}
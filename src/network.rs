@@ -0,0 +1,68 @@
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::net::SocketAddr;
+
+/// Preferred address family for the connections to VK hosts.
+///
+/// With both families available, connection to the preferred family is tried first
+/// and the other family is tried after the 300ms fallback delay of hyper.
+/// Some ISPs serve broken IPv6 routes to VK hosts, use `Ipv4Only` to avoid them at all.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum AddressFamily {
+    /// Addresses are used in the order returned by the system resolver.
+    #[default]
+    Any,
+    PreferIpv4,
+    PreferIpv6,
+    Ipv4Only,
+    Ipv6Only,
+}
+
+/// Resolver, which orders or filters the system resolver addresses by the family.
+#[derive(Debug)]
+pub struct FamilyResolver(pub(crate) AddressFamily);
+
+impl Resolve for FamilyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let family = self.0;
+        let host = name.as_str().to_owned();
+
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((host, 0)).await?;
+            let addrs: Addrs = Box::new(family.apply(addrs).into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+impl AddressFamily {
+    fn apply<I: IntoIterator<Item = SocketAddr>>(self, addrs: I) -> Vec<SocketAddr> {
+        let mut addrs: Vec<_> = addrs.into_iter().collect();
+
+        match self {
+            Self::Any => {}
+            Self::PreferIpv4 => addrs.sort_by_key(SocketAddr::is_ipv6),
+            Self::PreferIpv6 => addrs.sort_by_key(SocketAddr::is_ipv4),
+            Self::Ipv4Only => addrs.retain(SocketAddr::is_ipv4),
+            Self::Ipv6Only => addrs.retain(SocketAddr::is_ipv6),
+        }
+
+        addrs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::network::AddressFamily;
+    use std::net::SocketAddr;
+
+    #[test]
+    fn order_addresses() {
+        let v4: SocketAddr = "87.240.132.78:0".parse().unwrap();
+        let v6: SocketAddr = "[2a00:bdc0:3:103:1:1:1:1]:0".parse().unwrap();
+
+        assert_eq!(AddressFamily::PreferIpv4.apply([v6, v4]), vec![v4, v6]);
+        assert_eq!(AddressFamily::PreferIpv6.apply([v4, v6]), vec![v6, v4]);
+        assert_eq!(AddressFamily::Ipv4Only.apply([v6, v4]), vec![v4]);
+        assert_eq!(AddressFamily::Any.apply([v6, v4]), vec![v6, v4]);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::structs::Version;
+use crate::{VkApi, VkApiResult, VkApiWrapper};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// # Client for the open VK API methods
+/// Sends requests without an access token, like to the open utils and apps methods.
+/// Built by [`VkApiBuilder::unauthenticated`](crate::VkApiBuilder::unauthenticated).
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use vkclient::VkOpenApi;
+///
+/// let client: VkOpenApi = vkclient::VkApiBuilder::unauthenticated().into();
+///
+/// async {
+///     let time: u64 = client.send_request("utils.getServerTime", ()).await.unwrap();
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct VkOpenApi {
+    client: VkApi,
+}
+
+impl VkOpenApi {
+    /// Send request to the open VK API method.
+    pub async fn send_request<T, B, M>(&self, method: M, body: B) -> VkApiResult<T>
+    where
+        T: DeserializeOwned,
+        B: Serialize + Send,
+        M: AsRef<str> + Send,
+    {
+        self.client.send_request(method, body).await
+    }
+
+    /// Send request to the open VK API method with wrapper.
+    pub async fn send_request_with_wrapper<W>(&self, wrapper: W) -> VkApiResult<W::Response>
+    where
+        W: VkApiWrapper + Serialize + Send,
+    {
+        self.client.send_request_with_wrapper(wrapper).await
+    }
+
+    /// Send request to the open VK API method with specific version.
+    pub async fn send_request_with_version<T, B, M>(
+        &self,
+        method: M,
+        body: B,
+        version: Version,
+    ) -> VkApiResult<T>
+    where
+        T: DeserializeOwned,
+        B: Serialize + Send,
+        M: AsRef<str> + Send,
+    {
+        self.client
+            .send_request_with_version(method, body, version)
+            .await
+    }
+}
+
+impl From<VkApi> for VkOpenApi {
+    fn from(client: VkApi) -> Self {
+        Self { client }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{VkApiBuilder, VkApiError, VkApiWrapper};
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct AccountGetInfo;
+
+    impl VkApiWrapper for AccountGetInfo {
+        type Response = ();
+
+        fn get_method_name() -> &'static str {
+            "account.getInfo"
+        }
+    }
+
+    #[tokio::test]
+    async fn require_auth_for_wrappers() {
+        let client = VkApiBuilder::unauthenticated().build().unwrap();
+
+        assert!(matches!(
+            client.send_request_with_wrapper(AccountGetInfo).await,
+            Err(VkApiError::AuthRequired("account.getInfo"))
+        ));
+    }
+}
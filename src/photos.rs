@@ -0,0 +1,161 @@
+//! # Photo uploads
+//! Helpers, which chain the upload server request, the upload and the save of the photos.
+
+use crate::upload::{
+    FileKind, FormBuilder, PhotoUploadResponse, UploadError, UploadServer, UploadServerError,
+    UploadSource, UploadStep,
+};
+use crate::{VkApi, VkApiError};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// Value of the `photo` field of the upload response, when the server rejected the file.
+const EMPTY_PHOTO: &str = "[]";
+
+/// Photo saved with [photos.saveMessagesPhoto](https://dev.vk.com/method/photos.saveMessagesPhoto).
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SavedPhoto {
+    pub id: i64,
+    pub owner_id: i64,
+    #[serde(default)]
+    pub access_key: Option<String>,
+}
+
+impl SavedPhoto {
+    /// Returns the attachment of the messages in the `photo{owner_id}_{id}_{access_key}` format.
+    #[must_use]
+    pub fn attachment(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Display for SavedPhoto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "photo{}_{}", self.owner_id, self.id)?;
+        match &self.access_key {
+            Some(access_key) => write!(f, "_{access_key}"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Uploads the photo for the messages of the peer and returns its attachment for
+/// [`SendMessage::with_attachments`](crate::messages::SendMessage::with_attachments).
+/// Gets the upload server with [photos.getMessagesUploadServer](https://dev.vk.com/method/photos.getMessagesUploadServer),
+/// uploads the photo and saves it with [photos.saveMessagesPhoto](https://dev.vk.com/method/photos.saveMessagesPhoto).
+/// The photo is checked against the size limit and the media types of the photos before the upload server is requested.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use vkclient::upload::UploadSource;
+/// use vkclient::VkApi;
+/// # let access_token = String::new();
+/// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+///
+/// async move {
+///     let photo = UploadSource::bytes("cat.jpg", std::fs::read("cat.jpg").unwrap());
+///     let attachment = vkclient::photos::upload_message_photo(&client, 2000000001, photo)
+///         .await
+///         .unwrap();
+/// };
+/// ```
+pub async fn upload_message_photo(
+    api: &VkApi,
+    peer_id: i64,
+    source: UploadSource,
+) -> Result<String, UploadError> {
+    upload_message_photo_saved(api, peer_id, source)
+        .await
+        .map(|photo| photo.attachment())
+}
+
+/// Uploads the photo for the messages of the peer like [`upload_message_photo`] and returns the saved photo.
+pub async fn upload_message_photo_saved(
+    api: &VkApi,
+    peer_id: i64,
+    source: UploadSource,
+) -> Result<SavedPhoto, UploadError> {
+    let upload_error = |error| UploadError {
+        step: UploadStep::Upload,
+        error,
+    };
+
+    source.check(FileKind::Photo).await.map_err(upload_error)?;
+
+    let server: UploadServer = api
+        .send_request("photos.getMessagesUploadServer", PeerParams { peer_id })
+        .await
+        .map_err(|error| UploadError {
+            step: UploadStep::Server,
+            error,
+        })?;
+
+    let form = FormBuilder::new()
+        .with_source("photo", source)
+        .build()
+        .await
+        .map_err(upload_error)?;
+    let uploaded: PhotoUploadResponse = api
+        .uploader()
+        .upload_json(&server.upload_url, form)
+        .await
+        .and_then(check_uploaded)
+        .map_err(upload_error)?;
+
+    let photos: Vec<SavedPhoto> = api
+        .send_request("photos.saveMessagesPhoto", uploaded)
+        .await
+        .map_err(|error| UploadError {
+            step: UploadStep::Save,
+            error,
+        })?;
+
+    photos.into_iter().next().ok_or(UploadError {
+        step: UploadStep::Save,
+        error: VkApiError::ResponseDeserialize(crate::ResponseDeserialize::BadEncoding),
+    })
+}
+
+/// Returns the error, if the server accepted the request, but rejected the photo.
+fn check_uploaded(uploaded: PhotoUploadResponse) -> crate::VkApiResult<PhotoUploadResponse> {
+    if uploaded.photo.is_empty() || uploaded.photo == EMPTY_PHOTO {
+        return Err(VkApiError::Upload(UploadServerError {
+            status: StatusCode::OK,
+            message: "no photo in response".to_owned(),
+        }));
+    }
+    Ok(uploaded)
+}
+
+#[derive(Serialize)]
+struct PeerParams {
+    peer_id: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::photos::{check_uploaded, SavedPhoto};
+    use crate::upload::{FileKind, PhotoUploadResponse, UploadSource};
+
+    #[tokio::test]
+    async fn prepare_message_photos() {
+        let photos: Vec<SavedPhoto> = serde_json::from_str(
+            r#"[{"id":457239017,"album_id":-64,"owner_id":1,"access_key":"f1e2","sizes":[],"text":"","date":1700000000}]"#,
+        )
+        .unwrap();
+        assert_eq!(photos[0].attachment(), "photo1_457239017_f1e2");
+
+        let uploaded = PhotoUploadResponse {
+            server: 1,
+            photo: "[]".to_owned(),
+            hash: "abc".to_owned(),
+        };
+        assert!(check_uploaded(uploaded).is_err());
+
+        let png = UploadSource::bytes("cat.png", b"\x89PNG\r\n\x1a\n".to_vec());
+        assert!(png.check(FileKind::Photo).await.is_ok());
+        let pdf = UploadSource::bytes("cat.pdf", b"%PDF-1.7".to_vec());
+        assert!(pdf.check(FileKind::Photo).await.is_err());
+    }
+}
@@ -0,0 +1,343 @@
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{CertificateError, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use std::sync::Arc;
+
+/// Prefix of the pins in the [HPKP](https://www.rfc-editor.org/rfc/rfc7469#section-2.4) format.
+const PIN_PREFIX: &str = "sha256/";
+const SEQUENCE_TAG: u8 = 0x30;
+/// Tag of the explicit version of the certificate.
+const VERSION_TAG: u8 = 0xA0;
+
+/// SHA-256 hash of the `SubjectPublicKeyInfo` of the certificate.
+/// The pin outlives the certificate renewals with the same key.
+///
+/// The pin of the certificate can be calculated with openssl:
+/// ```sh
+/// openssl x509 -in cert.pem -pubkey -noout | openssl pkey -pubin -outform der | openssl dgst -sha256 -binary | base64
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct TlsPin([u8; 32]);
+
+impl TlsPin {
+    /// Creates the pin from the raw hash.
+    #[must_use]
+    pub const fn new(hash: [u8; 32]) -> Self {
+        Self(hash)
+    }
+
+    /// Parses the base64 hash, optionally prefixed with `sha256/`.
+    pub fn from_base64(pin: &str) -> Option<Self> {
+        let pin = pin.strip_prefix(PIN_PREFIX).unwrap_or(pin);
+        decode_base64(pin)?.try_into().ok().map(Self)
+    }
+
+    /// Hashes the DER encoded `SubjectPublicKeyInfo`.
+    #[must_use]
+    pub fn from_public_key_der(spki: &[u8]) -> Self {
+        let digest = ring::digest::digest(&ring::digest::SHA256, spki);
+        Self(
+            digest
+                .as_ref()
+                .try_into()
+                .expect("sha256 digest has 32 bytes"),
+        )
+    }
+
+    /// Returns the pin of the public key of the DER encoded certificate.
+    pub fn from_certificate_der(certificate: &[u8]) -> Option<Self> {
+        subject_public_key_info(certificate).map(Self::from_public_key_der)
+    }
+}
+
+/// # Pinned public keys of the hosts
+/// Connections to the pinned hosts and their subdomains are accepted only when some certificate of the chain
+/// matches one of the pins of the host, in addition to the usual validation with the built-in Mozilla roots.
+/// Pass the pins of the current and the next keys to rotate them without the downtime.
+/// The most specific host is used, so `api.vk.com` pins override `vk.com` ones.
+///
+/// Pinned clients validate the chains with the built-in roots only,
+/// the root certificates passed to the builders aren't used.
+///
+/// ## Usage:
+/// ```rust
+/// use vkclient::{TlsPin, TlsPins, VkApi};
+/// # let access_token = String::new();
+/// let current = TlsPin::from_base64("sha256/2qdWFAtMRPNLJiyqLYmb9akV6G73qCtomTjVrJjGJM4=").unwrap();
+/// let next = TlsPin::from_base64("rG4cXx2GjIP8ZfFySvHd7oxHK8xYXxVy6wf2xd0HpF0=").unwrap();
+///
+/// let client: VkApi = vkclient::VkApiBuilder::new(access_token)
+///     .with_tls_pins(TlsPins::new().with_host("vk.com", [current, next]))
+///     .into();
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TlsPins {
+    hosts: Vec<(String, Vec<TlsPin>)>,
+}
+
+impl TlsPins {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { hosts: Vec::new() }
+    }
+
+    /// Adds the pins of the host and its subdomains.
+    #[must_use]
+    pub fn with_host<H: Into<String>, P: IntoIterator<Item = TlsPin>>(
+        mut self,
+        host: H,
+        pins: P,
+    ) -> Self {
+        let host = host.into();
+        match self.hosts.iter_mut().find(|(h, _)| *h == host) {
+            Some((_, existing)) => existing.extend(pins),
+            None => self.hosts.push((host, pins.into_iter().collect())),
+        }
+        self
+    }
+
+    /// Returns the pinned hosts.
+    pub fn hosts(&self) -> impl Iterator<Item = &str> {
+        self.hosts.iter().map(|(host, _)| host.as_str())
+    }
+
+    /// Returns the pins of the most specific pinned host, which matches the host.
+    #[must_use]
+    pub fn for_host(&self, host: &str) -> Option<&[TlsPin]> {
+        self.hosts
+            .iter()
+            .filter(|(pinned, _)| {
+                host == pinned
+                    || host
+                        .strip_suffix(pinned.as_str())
+                        .is_some_and(|subdomain| subdomain.ends_with('.'))
+            })
+            .max_by_key(|(pinned, _)| pinned.len())
+            .map(|(_, pins)| pins.as_slice())
+    }
+
+    /// Returns the tls settings, which check the pins, with the protocols of the client.
+    pub(crate) fn tls_config(&self, alpn: &[&[u8]]) -> rustls::ClientConfig {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        let verifier = PinningVerifier {
+            inner: WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+                .build()
+                .expect("built-in roots aren't empty"),
+            pins: self.clone(),
+        };
+
+        let mut config = rustls::ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .expect("ring provider supports the default protocols")
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth();
+        config.alpn_protocols = alpn.iter().map(|protocol| protocol.to_vec()).collect();
+        config
+    }
+
+    fn verify_chain(
+        &self,
+        host: &str,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+    ) -> bool {
+        let Some(pins) = self.for_host(host) else {
+            return true;
+        };
+
+        std::iter::once(end_entity)
+            .chain(intermediates)
+            .filter_map(|certificate| TlsPin::from_certificate_der(certificate))
+            .any(|pin| pins.contains(&pin))
+    }
+}
+
+/// Verifier of the certificates, which checks the pins after the validation of the chain.
+#[derive(Debug)]
+struct PinningVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    pins: TlsPins,
+}
+
+impl ServerCertVerifier for PinningVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+
+        if self
+            .pins
+            .verify_chain(&server_name.to_str(), end_entity, intermediates)
+        {
+            Ok(verified)
+        } else {
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::ApplicationVerificationFailure,
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+/// Returns the DER encoded `SubjectPublicKeyInfo` of the X.509 certificate.
+fn subject_public_key_info(certificate: &[u8]) -> Option<&[u8]> {
+    let certificate = DerValue::parse(certificate)?.content;
+    let mut tbs = DerValue::parse(certificate)?.content;
+    if tbs.first() == Some(&VERSION_TAG) {
+        tbs = DerValue::parse(tbs)?.rest;
+    }
+    // serial number, signature algorithm, issuer, validity and subject
+    for _ in 0..5 {
+        tbs = DerValue::parse(tbs)?.rest;
+    }
+
+    let spki = DerValue::parse(tbs)?;
+    (spki.tag == SEQUENCE_TAG).then_some(spki.encoded)
+}
+
+/// First DER value of the input.
+struct DerValue<'a> {
+    tag: u8,
+    /// Whole encoding of the value with the tag and the length
+    encoded: &'a [u8],
+    content: &'a [u8],
+    /// Rest of the input after the value
+    rest: &'a [u8],
+}
+
+impl<'a> DerValue<'a> {
+    fn parse(input: &'a [u8]) -> Option<Self> {
+        let (&tag, rest) = input.split_first()?;
+        let (&length, rest) = rest.split_first()?;
+
+        let (length, rest) = if length < 0x80 {
+            (usize::from(length), rest)
+        } else {
+            let octets = usize::from(length & 0x7F);
+            if octets == 0 || octets > 4 || rest.len() < octets {
+                return None;
+            }
+            let (length, rest) = rest.split_at(octets);
+            let length = length
+                .iter()
+                .fold(0, |length, octet| length << 8 | usize::from(*octet));
+            (length, rest)
+        };
+
+        if rest.len() < length {
+            return None;
+        }
+        let header = input.len() - rest.len();
+        let (content, rest) = rest.split_at(length);
+        Some(Self {
+            tag,
+            encoded: input.get(..header + length)?,
+            content,
+            rest,
+        })
+    }
+}
+
+/// Decodes the standard base64 with the optional padding.
+fn decode_base64(input: &str) -> Option<Vec<u8>> {
+    let input = input.trim_end_matches('=');
+    let mut output = Vec::with_capacity(input.len() * 3 / 4);
+    let (mut buffer, mut bits) = (0_u32, 0);
+
+    for byte in input.bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = buffer << 6 | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pinning::{decode_base64, TlsPin, TlsPins};
+
+    /// Self signed certificate of `api.vk.com` with the P-256 key.
+    const CERTIFICATE: &str = "MIIBfzCCASWgAwIBAgIUL0E/sMvI0I6f7GLey6RI9KVms3QwCgYIKoZIzj0EAwIw\
+        FTETMBEGA1UEAwwKYXBpLnZrLmNvbTAeFw0yNjEwMTgwMzA1MTVaFw0zNjEwMTUw\
+        MzA1MTVaMBUxEzARBgNVBAMMCmFwaS52ay5jb20wWTATBgcqhkjOPQIBBggqhkjO\
+        PQMBBwNCAATF9z6TIfU8aZ3Xw2pnm7CSXiSyBt8QkjNFpf7ko/uI8gISp1f55vIH\
+        +jF348M8fNAmNLlF1lZ2x5TZKdrR9NMGo1MwUTAdBgNVHQ4EFgQU7vmcfPmimxrg\
+        r84k4yB1l2RbrJcwHwYDVR0jBBgwFoAU7vmcfPmimxrgr84k4yB1l2RbrJcwDwYD\
+        VR0TAQH/BAUwAwEB/zAKBggqhkjOPQQDAgNIADBFAiAtyQ1/2cBghgbeTyegqb7F\
+        AdqnNx2u94/zADVfS5rSxwIhAKqXnLu3l2Hfqf98KB2X/wpgDTyh6x8qOA4E35I3\
+        U405";
+
+    #[test]
+    fn match_certificate_pins() {
+        let certificate = decode_base64(CERTIFICATE).unwrap();
+        let pin =
+            TlsPin::from_base64("sha256/2qdWFAtMRPNLJiyqLYmb9akV6G73qCtomTjVrJjGJM4=").unwrap();
+        let other = TlsPin::new([7; 32]);
+        assert_eq!(TlsPin::from_certificate_der(&certificate), Some(pin));
+        assert_eq!(TlsPin::from_certificate_der(&certificate[..100]), None);
+        assert_eq!(TlsPin::from_base64("2qdWFAtMRPNLJiyq"), None);
+
+        let pins = TlsPins::new()
+            .with_host("vk.com", [other])
+            .with_host("api.vk.com", [other])
+            .with_host("api.vk.com", [pin]);
+        assert_eq!(pins.for_host("lp.vk.com"), Some([other].as_slice()));
+        assert_eq!(pins.for_host("api.vk.com"), Some([other, pin].as_slice()));
+        assert_eq!(pins.for_host("evilvk.com"), None);
+
+        let certificate = certificate.into();
+        assert!(pins.verify_chain("api.vk.com", &certificate, &[]));
+        assert!(!pins.verify_chain("lp.vk.com", &certificate, &[]));
+        assert!(pins.verify_chain("example.com", &certificate, &[]));
+    }
+}
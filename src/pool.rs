@@ -0,0 +1,156 @@
+use bytes::BytesMut;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Reusable buffers for response bodies and decompression output of one client.
+#[derive(Debug)]
+pub struct BufferPool {
+    buffers: Mutex<Vec<BytesMut>>,
+    max_buffers: usize,
+    max_capacity: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    discarded: AtomicU64,
+}
+
+impl BufferPool {
+    pub fn new(max_buffers: usize, max_capacity: usize) -> Self {
+        Self {
+            buffers: Mutex::new(Vec::with_capacity(max_buffers)),
+            max_buffers,
+            max_capacity,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            discarded: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns an empty buffer with at least `capacity` bytes reserved.
+    /// The buffer is returned to the pool on drop, so it isn't lost on the early returns.
+    pub fn get(&self, capacity: usize) -> PooledBuffer<'_> {
+        let buffer = self
+            .buffers
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .pop();
+
+        let buffer = buffer.map_or_else(
+            || {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                BytesMut::with_capacity(capacity)
+            },
+            |mut buffer| {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                buffer.reserve(capacity);
+                buffer
+            },
+        );
+        PooledBuffer { pool: self, buffer }
+    }
+
+    /// Returns the buffer to the pool.
+    /// Buffers larger than the capacity limit or above the pool size are dropped.
+    fn put(&self, mut buffer: BytesMut) {
+        if buffer.capacity() > self.max_capacity {
+            self.discarded.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        let mut buffers = self
+            .buffers
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if buffers.len() >= self.max_buffers {
+            self.discarded.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        buffer.clear();
+        buffers.push(buffer);
+    }
+
+    pub fn stats(&self) -> BufferPoolStats {
+        BufferPoolStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            discarded: self.discarded.load(Ordering::Relaxed),
+            pooled: self
+                .buffers
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .len(),
+        }
+    }
+}
+
+/// Buffer of the pool, which is put back on drop.
+#[derive(Debug)]
+pub struct PooledBuffer<'a> {
+    pool: &'a BufferPool,
+    buffer: BytesMut,
+}
+
+impl Deref for PooledBuffer<'_> {
+    type Target = BytesMut;
+
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+
+impl DerefMut for PooledBuffer<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.buffer
+    }
+}
+
+impl Drop for PooledBuffer<'_> {
+    fn drop(&mut self) {
+        self.pool.put(std::mem::take(&mut self.buffer));
+    }
+}
+
+/// Buffer pool counters.
+/// Many misses mean the pool is too small, many discards mean the capacity limit is too low.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct BufferPoolStats {
+    /// Buffers taken from the pool
+    pub hits: u64,
+    /// Buffers allocated because the pool was empty
+    pub misses: u64,
+    /// Buffers dropped instead of returning to the pool
+    pub discarded: u64,
+    /// Buffers in the pool now
+    pub pooled: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pool::{BufferPool, BufferPoolStats};
+    use bytes::BufMut;
+
+    #[test]
+    fn reuse_buffers() {
+        let pool = BufferPool::new(1, 1024);
+
+        let mut buffer = pool.get(16);
+        buffer.put_slice(b"response");
+        drop(buffer);
+
+        let buffer = pool.get(16);
+        assert!(buffer.is_empty());
+        drop(buffer);
+        drop(pool.get(2048));
+
+        assert_eq!(
+            pool.stats(),
+            BufferPoolStats {
+                hits: 2,
+                misses: 1,
+                discarded: 1,
+                pooled: 0,
+            }
+        );
+    }
+}
@@ -0,0 +1,38 @@
+//! # Prelude
+//! Commonly needed items of the client, import them at once:
+//! ```rust,no_run
+//! use vkclient::prelude::*;
+//! # let access_token = String::new();
+//! let client: VkApi = VkApiBuilder::new(access_token).into();
+//!
+//! async {
+//!     let users: Vec<serde_json::Value> = client
+//!         .send_request("users.get", [("user_ids", List(vec![1, 2]))])
+//!         .await
+//!         .unwrap();
+//! };
+//! ```
+//! Stream extensions of `futures-util` are imported anonymously to call `next` and `try_next`
+//! on the long poll and search streams without naming the traits.
+
+pub use crate::{
+    Auth, List, Version, VkApi, VkApiBuilder, VkApiError, VkApiResult, VkApiWrapper, VkError,
+};
+
+#[cfg(feature = "encode_json")]
+pub use crate::VkApiClient;
+
+#[cfg(feature = "longpoll")]
+pub use crate::longpoll::{LongPollRequest, LongPollResponse, VkLongPoll};
+
+#[cfg(all(feature = "longpoll", feature = "longpoll_stream"))]
+pub use crate::longpoll::LongPollStream;
+
+#[cfg(all(feature = "longpoll", feature = "messages"))]
+pub use crate::longpoll::GroupEvent;
+
+#[cfg(feature = "uploader")]
+pub use crate::upload::{UploadError, VkUploader};
+
+#[cfg(feature = "futures-util")]
+pub use futures_util::{StreamExt as _, TryStreamExt as _};
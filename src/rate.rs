@@ -0,0 +1,210 @@
+use crate::TokenScope;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+impl TokenScope {
+    /// Returns the documented rate limit of the token kind.
+    #[must_use]
+    pub const fn rate_limit(self) -> RateLimit {
+        match self {
+            Self::User | Self::Service => RateLimit::USER,
+            Self::Group => RateLimit::GROUP,
+        }
+    }
+}
+
+/// Requests rate limit of the token.
+/// VK allows 3 requests per second for the user tokens and 20 requests per second for the community tokens,
+/// exceeding requests fail with the error 6.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use vkclient::{RateLimit, VkApi};
+/// # let access_token = String::new();
+/// let client: VkApi = vkclient::VkApiBuilder::new(access_token)
+///     .with_rate_limit(RateLimit::USER)
+///     .into();
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RateLimit {
+    interval: Duration,
+    burst: u32,
+}
+
+impl RateLimit {
+    /// Limit of the user tokens.
+    pub const USER: Self = Self::per_second(3);
+    /// Limit of the community tokens.
+    pub const GROUP: Self = Self::per_second(20);
+
+    /// Creates the limit, which spreads the requests evenly over the second.
+    /// Zero is treated as one request per second.
+    #[must_use]
+    pub const fn per_second(requests: u32) -> Self {
+        let requests = if requests == 0 { 1 } else { requests };
+        Self {
+            interval: Duration::from_nanos(1_000_000_000 / requests as u64),
+            burst: 1,
+        }
+    }
+
+    /// Returns the minimal interval between the requests.
+    #[must_use]
+    pub const fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns the count of the requests, which can be sent at once.
+    #[must_use]
+    pub const fn burst(&self) -> u32 {
+        self.burst
+    }
+
+    /// Pass the count of the requests, which can be sent at once after the idle time. Default is 1.
+    /// Bursts are spread less evenly, so VK may reject them near the second bounds.
+    #[must_use]
+    pub const fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = if burst == 0 { 1 } else { burst };
+        self
+    }
+}
+
+/// Token bucket, which queues the requests in the order of arrival.
+/// It's implemented as the generic cell rate algorithm, so the state is a single instant.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    /// Theoretical arrival time of the next request with the empty bucket
+    next: Mutex<Option<Instant>>,
+    requests: AtomicU64,
+    delayed: AtomicU64,
+    waiting: AtomicU32,
+}
+
+impl RateLimiter {
+    pub(crate) const fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            next: Mutex::new(None),
+            requests: AtomicU64::new(0),
+            delayed: AtomicU64::new(0),
+            waiting: AtomicU32::new(0),
+        }
+    }
+
+    /// Waits until the request can be sent.
+    pub(crate) async fn acquire(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+
+        let delay = self.reserve(Instant::now());
+        if !delay.is_zero() {
+            self.delayed.fetch_add(1, Ordering::Relaxed);
+            let _waiting = Waiting::new(&self.waiting);
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    pub(crate) fn quota(&self) -> Quota {
+        Quota {
+            limit: self.limit,
+            requests: self.requests.load(Ordering::Relaxed),
+            delayed: self.delayed.load(Ordering::Relaxed),
+            waiting: self.waiting.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Reserves the slot of the request and returns the delay before it.
+    fn reserve(&self, now: Instant) -> Duration {
+        let mut next = self
+            .next
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        let tat = next.map_or(now, |next| next.max(now));
+        let tolerance = self.limit.interval * (self.limit.burst - 1);
+        let start = tat
+            .checked_sub(tolerance)
+            .map_or(now, |start| start.max(now));
+
+        *next = Some(tat + self.limit.interval);
+        start - now
+    }
+}
+
+/// Counts the waiting request until it's sent or cancelled.
+struct Waiting<'a>(&'a AtomicU32);
+
+impl<'a> Waiting<'a> {
+    fn new(counter: &'a AtomicU32) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self(counter)
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Rate limit of the client and its consumption.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Quota {
+    pub limit: RateLimit,
+    /// Requests passed through the limiter
+    pub requests: u64,
+    /// Requests, which waited for their turn
+    pub delayed: u64,
+    /// Requests waiting for their turn now
+    pub waiting: u32,
+}
+
+impl Clone for RateLimiter {
+    /// Clones the limit with the empty state, limiters of the built clients are shared instead.
+    fn clone(&self) -> Self {
+        Self::new(self.limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rate::{RateLimit, RateLimiter, TokenScope};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn spread_requests() {
+        let limiter = RateLimiter::new(RateLimit::per_second(4));
+        let now = Instant::now();
+
+        assert_eq!(limiter.reserve(now), Duration::ZERO);
+        assert_eq!(limiter.reserve(now), Duration::from_millis(250));
+        assert_eq!(limiter.reserve(now), Duration::from_millis(500));
+        assert_eq!(
+            limiter.reserve(now + Duration::from_secs(2)),
+            Duration::ZERO
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn count_consumption() {
+        let limiter = RateLimiter::new(TokenScope::Group.rate_limit());
+
+        limiter.acquire().await;
+        limiter.acquire().await;
+
+        let quota = limiter.quota();
+        assert_eq!(quota.limit, RateLimit::GROUP);
+        assert_eq!((quota.requests, quota.delayed, quota.waiting), (2, 1, 0));
+    }
+
+    #[test]
+    fn allow_bursts() {
+        let limiter = RateLimiter::new(RateLimit::per_second(4).with_burst(2));
+        let now = Instant::now();
+
+        assert_eq!(limiter.reserve(now), Duration::ZERO);
+        assert_eq!(limiter.reserve(now), Duration::ZERO);
+        assert_eq!(limiter.reserve(now), Duration::from_millis(250));
+    }
+}
@@ -0,0 +1,288 @@
+use crate::Backoff;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Kind of the request, which selects its [`ResiliencePolicy`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum RequestKind {
+    /// API methods without side effects, like `users.get` or `groups.search`
+    Read,
+    /// Other API methods, which may change the state, like `messages.send`
+    Write,
+    /// Long poll server requests
+    LongPoll,
+    /// Upload server requests
+    Upload,
+}
+
+impl RequestKind {
+    /// Classifies the API method by its name: `get*`, `search*`, `is*` and `check*` methods are reads.
+    #[must_use]
+    pub fn of_method(method: &str) -> Self {
+        let action = method.rsplit('.').next().unwrap_or(method);
+
+        if ["get", "search", "is", "check"]
+            .iter()
+            .any(|prefix| action.starts_with(prefix))
+        {
+            Self::Read
+        } else {
+            Self::Write
+        }
+    }
+
+    /// Returns the item of the kind from the items of all kinds in the declaration order.
+    const fn of<T>(self, items: &[T; 4]) -> &T {
+        let [read, write, longpoll, upload] = items;
+        match self {
+            Self::Read => read,
+            Self::Write => write,
+            Self::LongPoll => longpoll,
+            Self::Upload => upload,
+        }
+    }
+
+    const fn of_mut<T>(self, items: &mut [T; 4]) -> &mut T {
+        let [read, write, longpoll, upload] = items;
+        match self {
+            Self::Read => read,
+            Self::Write => write,
+            Self::LongPoll => longpoll,
+            Self::Upload => upload,
+        }
+    }
+}
+
+impl Display for RequestKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Read => write!(f, "read"),
+            Self::Write => write!(f, "write"),
+            Self::LongPoll => write!(f, "longpoll"),
+            Self::Upload => write!(f, "upload"),
+        }
+    }
+}
+
+/// # Resilience policy
+/// Timeout, retries and circuit breaker of the requests of one [`RequestKind`].
+/// Default policy has no timeout, retries and breaker.
+///
+/// API requests apply the whole policy. Retries and breakers count the errors of
+/// [`VkApiError::is_retryable`](crate::VkApiError::is_retryable) only.
+/// Long poll and upload clients of the [`VkApi`](crate::VkApi) apply the timeout,
+/// long poll retries are set with [`VkLongPoll::with_retry`](crate::longpoll::VkLongPoll::with_retry).
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use vkclient::{Backoff, Breaker, RequestKind, ResiliencePolicy, VkApi};
+/// # let access_token = String::new();
+/// let client: VkApi = vkclient::VkApiBuilder::new(access_token)
+///     .with_resilience_policy(
+///         RequestKind::Read,
+///         ResiliencePolicy::new()
+///             .with_timeout(Duration::from_secs(10))
+///             .with_retry(Backoff::new(Duration::from_millis(200), Duration::from_secs(5)).with_max_retries(3))
+///             .with_breaker(Breaker::new(5, Duration::from_secs(30))),
+///     )
+///     .into();
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ResiliencePolicy {
+    timeout: Option<Duration>,
+    retry: Option<Backoff>,
+    breaker: Option<Breaker>,
+}
+
+impl ResiliencePolicy {
+    /// Creates the policy without timeout, retries and breaker.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            timeout: None,
+            retry: None,
+            breaker: None,
+        }
+    }
+
+    /// Pass the timeout of the whole request. For the long poll requests it's added to the `wait` time.
+    #[must_use]
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Pass the backoff of the retries. Retry the write requests with care, because
+    /// the timed out request may be already executed by VK.
+    #[must_use]
+    pub const fn with_retry(mut self, backoff: Backoff) -> Self {
+        self.retry = Some(backoff);
+        self
+    }
+
+    /// Pass the circuit breaker, which is shared by the requests of the kind.
+    #[must_use]
+    pub const fn with_breaker(mut self, breaker: Breaker) -> Self {
+        self.breaker = Some(breaker);
+        self
+    }
+
+    /// Returns the timeout of the requests.
+    #[must_use]
+    pub const fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Returns the backoff of the retries.
+    #[must_use]
+    pub const fn retry(&self) -> Option<Backoff> {
+        self.retry
+    }
+
+    /// Returns the circuit breaker settings.
+    #[must_use]
+    pub const fn breaker(&self) -> Option<Breaker> {
+        self.breaker
+    }
+}
+
+/// Circuit breaker settings. After the `failures` in a row the requests fail fast with
+/// [`VkApiError::CircuitOpen`](crate::VkApiError::CircuitOpen) during the `cooldown`.
+/// Then the requests are sent again, and the next failure opens the breaker immediately.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Breaker {
+    failures: u32,
+    cooldown: Duration,
+}
+
+impl Breaker {
+    /// Creates the breaker. Zero failures are treated as one.
+    #[must_use]
+    pub const fn new(failures: u32, cooldown: Duration) -> Self {
+        Self {
+            failures: if failures == 0 { 1 } else { failures },
+            cooldown,
+        }
+    }
+
+    /// Returns the count of the failures in a row, which opens the breaker.
+    #[must_use]
+    pub const fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Returns the duration of the open breaker.
+    #[must_use]
+    pub const fn cooldown(&self) -> Duration {
+        self.cooldown
+    }
+}
+
+/// Policies of the client and the states of their breakers.
+#[derive(Debug, Default)]
+pub struct Policies {
+    policies: [ResiliencePolicy; 4],
+    breakers: [BreakerState; 4],
+}
+
+impl Policies {
+    pub(crate) fn set(&mut self, kind: RequestKind, policy: ResiliencePolicy) {
+        *kind.of_mut(&mut self.policies) = policy;
+    }
+
+    pub(crate) const fn get(&self, kind: RequestKind) -> &ResiliencePolicy {
+        kind.of(&self.policies)
+    }
+
+    /// Returns false if the breaker of the kind is open.
+    pub(crate) fn allow(&self, kind: RequestKind, policy: &ResiliencePolicy) -> bool {
+        policy
+            .breaker
+            .is_none_or(|_| kind.of(&self.breakers).allow(Instant::now()))
+    }
+
+    /// Records the result of the request for the breaker of the kind.
+    pub(crate) fn record(&self, kind: RequestKind, policy: &ResiliencePolicy, failed: bool) {
+        if let Some(breaker) = policy.breaker {
+            kind.of(&self.breakers)
+                .record(breaker, failed, Instant::now());
+        }
+    }
+}
+
+impl Clone for Policies {
+    /// Clones the policies with the closed breakers, breakers of the built clients are shared instead.
+    fn clone(&self) -> Self {
+        Self {
+            policies: self.policies,
+            breakers: Default::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    failures_in_row: AtomicU32,
+    open_until: Mutex<Option<Instant>>,
+}
+
+impl BreakerState {
+    fn allow(&self, now: Instant) -> bool {
+        self.open_until
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .is_none_or(|until| now >= until)
+    }
+
+    fn record(&self, breaker: Breaker, failed: bool, now: Instant) {
+        if !failed {
+            self.failures_in_row.store(0, Ordering::Relaxed);
+            return;
+        }
+
+        if self.failures_in_row.fetch_add(1, Ordering::Relaxed) + 1 >= breaker.failures {
+            *self
+                .open_until
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(now + breaker.cooldown);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::resilience::{BreakerState, RequestKind};
+    use crate::Breaker;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn classify_methods() {
+        assert_eq!(RequestKind::of_method("users.get"), RequestKind::Read);
+        assert_eq!(RequestKind::of_method("groups.isMember"), RequestKind::Read);
+        assert_eq!(RequestKind::of_method("messages.send"), RequestKind::Write);
+        assert_eq!(RequestKind::of_method("execute"), RequestKind::Write);
+    }
+
+    #[test]
+    fn open_breaker() {
+        let breaker = Breaker::new(2, Duration::from_secs(10));
+        let state = BreakerState::default();
+        let now = Instant::now();
+
+        state.record(breaker, true, now);
+        assert!(state.allow(now));
+        state.record(breaker, true, now);
+        assert!(!state.allow(now + Duration::from_secs(5)));
+        assert!(state.allow(now + Duration::from_secs(10)));
+
+        state.record(breaker, true, now + Duration::from_secs(10));
+        assert!(!state.allow(now + Duration::from_secs(11)));
+
+        state.record(breaker, false, now + Duration::from_secs(20));
+        state.record(breaker, true, now + Duration::from_secs(20));
+        assert!(state.allow(now + Duration::from_secs(20)));
+    }
+}
@@ -0,0 +1,301 @@
+use crate::{VkApi, VkApiError, VkApiResult};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::ops::{BitOr, BitOrAssign};
+
+/// Error code of the user methods called with the community token.
+const GROUP_AUTH_FAILED: i16 = 27;
+
+/// Names of the user [access rights](https://dev.vk.com/reference/access-rights#Права%20доступа%20для%20токена%20пользователя).
+const USER_SCOPES: &[(Scope, &str)] = &[
+    (Scope::NOTIFY, "notify"),
+    (Scope::FRIENDS, "friends"),
+    (Scope::PHOTOS, "photos"),
+    (Scope::AUDIO, "audio"),
+    (Scope::VIDEO, "video"),
+    (Scope::STORIES, "stories"),
+    (Scope::PAGES, "pages"),
+    (Scope::MENU, "menu"),
+    (Scope::STATUS, "status"),
+    (Scope::NOTES, "notes"),
+    (Scope::MESSAGES, "messages"),
+    (Scope::WALL, "wall"),
+    (Scope::ADS, "ads"),
+    (Scope::OFFLINE, "offline"),
+    (Scope::DOCS, "docs"),
+    (Scope::GROUPS, "groups"),
+    (Scope::NOTIFICATIONS, "notifications"),
+    (Scope::STATS, "stats"),
+    (Scope::EMAIL, "email"),
+    (Scope::MARKET, "market"),
+    (Scope::PHONE_NUMBER, "phone_number"),
+];
+
+/// Names of the community [access rights](https://dev.vk.com/reference/access-rights#Права%20доступа%20для%20токена%20сообщества).
+const COMMUNITY_SCOPES: &[(Scope, &str)] = &[
+    (Scope::COMMUNITY_STORIES, "stories"),
+    (Scope::PHOTOS, "photos"),
+    (Scope::APP_WIDGET, "app_widget"),
+    (Scope::MESSAGES, "messages"),
+    (Scope::DOCS, "docs"),
+    (Scope::MANAGE, "manage"),
+];
+
+/// # Access rights of the token
+/// Bit mask of the [access rights](https://dev.vk.com/reference/access-rights).
+/// User and community tokens share some bits with the different meanings,
+/// like [`Scope::STORIES`] and [`Scope::APP_WIDGET`].
+///
+/// ## Usage:
+/// ```rust
+/// use vkclient::Scope;
+///
+/// let scope = Scope::MESSAGES | Scope::DOCS | Scope::from_name("offline").unwrap();
+/// assert_eq!(scope.bits(), 200704);
+/// assert!(scope.contains(Scope::DOCS));
+/// ```
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Scope(u32);
+
+impl Scope {
+    pub const NONE: Self = Self(0);
+    pub const NOTIFY: Self = Self(1);
+    pub const FRIENDS: Self = Self(2);
+    pub const PHOTOS: Self = Self(4);
+    pub const AUDIO: Self = Self(8);
+    pub const VIDEO: Self = Self(16);
+    pub const STORIES: Self = Self(64);
+    pub const PAGES: Self = Self(128);
+    /// Link to the application in the left menu
+    pub const MENU: Self = Self(256);
+    pub const STATUS: Self = Self(1024);
+    pub const NOTES: Self = Self(2048);
+    pub const MESSAGES: Self = Self(4096);
+    pub const WALL: Self = Self(8192);
+    pub const ADS: Self = Self(32768);
+    /// Token without the expiration
+    pub const OFFLINE: Self = Self(65536);
+    pub const DOCS: Self = Self(131_072);
+    pub const GROUPS: Self = Self(262_144);
+    pub const NOTIFICATIONS: Self = Self(524_288);
+    pub const STATS: Self = Self(1_048_576);
+    pub const EMAIL: Self = Self(4_194_304);
+    pub const MARKET: Self = Self(134_217_728);
+    pub const PHONE_NUMBER: Self = Self(268_435_456);
+    /// Stories of the community token
+    pub const COMMUNITY_STORIES: Self = Self(1);
+    /// Widgets of the community applications, community token only
+    pub const APP_WIDGET: Self = Self(64);
+    /// Management of the community, community token only
+    pub const MANAGE: Self = Self(262_144);
+
+    /// Creates the scope from the raw bit mask, the unknown bits are kept.
+    #[must_use]
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the raw bit mask.
+    #[must_use]
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Returns true if all rights of the `other` are set.
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns the rights of the `self`, which are absent in the `other`.
+    #[must_use]
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    /// Returns true if no rights are set.
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the user right by its name, like `messages`.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        USER_SCOPES
+            .iter()
+            .find(|(_, n)| *n == name)
+            .map(|(scope, _)| *scope)
+    }
+
+    /// Returns the names of the set rights, the unknown bits are skipped.
+    pub fn names(self, community: bool) -> impl Iterator<Item = &'static str> {
+        rights(community)
+            .iter()
+            .filter(move |(scope, _)| self.contains(*scope))
+            .map(|(_, name)| *name)
+    }
+}
+
+impl BitOr for Scope {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Scope {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// Result of the [`VkApi::verify_scopes`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ScopeReport {
+    pub required: Scope,
+    pub granted: Scope,
+    /// The token belongs to the community
+    pub community: bool,
+}
+
+impl ScopeReport {
+    /// Returns the required rights, which aren't granted.
+    #[must_use]
+    pub const fn missing(&self) -> Scope {
+        self.required.difference(self.granted)
+    }
+
+    /// Returns true if all required rights are granted.
+    #[must_use]
+    pub const fn is_satisfied(&self) -> bool {
+        self.missing().is_empty()
+    }
+
+    /// Returns the report as the error, if some rights are missing.
+    pub const fn ensure(self) -> Result<Self, Self> {
+        if self.is_satisfied() {
+            Ok(self)
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl Display for ScopeReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let token = if self.community { "community" } else { "user" };
+        if self.is_satisfied() {
+            return write!(f, "{token} token has all required scopes");
+        }
+
+        let missing = self.missing();
+        let names = missing.names(self.community).collect::<Vec<_>>();
+        write!(f, "{token} token lacks scopes: {}", names.join(", "))?;
+
+        let known = rights(self.community)
+            .iter()
+            .fold(Scope::NONE, |known, (scope, _)| known | *scope);
+        let unknown = missing.difference(known);
+        if !unknown.is_empty() {
+            write!(f, " (unknown bits {})", unknown.bits())?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ScopeReport {}
+
+/// Returns the names of the user or the community rights.
+const fn rights(community: bool) -> &'static [(Scope, &'static str)] {
+    if community {
+        COMMUNITY_SCOPES
+    } else {
+        USER_SCOPES
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenPermissions {
+    mask: u32,
+}
+
+impl VkApi {
+    /// Checks, that the token has the `required` rights, so the deployment fails fast
+    /// instead of the access errors of the later requests.
+    /// The rights of the user token are requested with
+    /// [account.getAppPermissions](https://dev.vk.com/method/account.getAppPermissions),
+    /// the ones of the community token with
+    /// [groups.getTokenPermissions](https://dev.vk.com/method/groups.getTokenPermissions).
+    ///
+    /// ## Usage:
+    /// ```rust,no_run
+    /// use vkclient::{Scope, VkApi};
+    /// # let access_token = String::new();
+    /// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+    ///
+    /// async {
+    ///     let report = client.verify_scopes(Scope::MESSAGES | Scope::DOCS).await.unwrap();
+    ///     if let Err(report) = report.ensure() {
+    ///         panic!("{report}");
+    ///     }
+    /// };
+    /// ```
+    pub async fn verify_scopes(&self, required: Scope) -> VkApiResult<ScopeReport> {
+        let user: VkApiResult<u32> = self.send_request("account.getAppPermissions", ()).await;
+
+        let (granted, community) = match user {
+            Ok(mask) => (mask, false),
+            Err(VkApiError::Vk(e)) if e.code() == GROUP_AUTH_FAILED => {
+                let permissions: TokenPermissions =
+                    self.send_request("groups.getTokenPermissions", ()).await?;
+                (permissions.mask, true)
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok(ScopeReport {
+            required,
+            granted: Scope::from_bits(granted),
+            community,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Scope, ScopeReport};
+
+    #[test]
+    fn report_missing_scopes() {
+        let report = ScopeReport {
+            required: Scope::MESSAGES | Scope::DOCS | Scope::OFFLINE,
+            granted: Scope::from_bits(4096 + 2),
+            community: false,
+        };
+        assert_eq!(report.missing(), Scope::DOCS | Scope::OFFLINE);
+        assert_eq!(
+            report.ensure().unwrap_err().to_string(),
+            "user token lacks scopes: offline, docs"
+        );
+
+        let report = ScopeReport {
+            required: Scope::APP_WIDGET | Scope::from_bits(1 << 30),
+            granted: Scope::MESSAGES,
+            community: true,
+        };
+        assert_eq!(
+            report.to_string(),
+            "community token lacks scopes: app_widget (unknown bits 1073741824)"
+        );
+        assert!(ScopeReport {
+            required: Scope::MESSAGES,
+            granted: Scope::MESSAGES | Scope::MANAGE,
+            community: true,
+        }
+        .is_satisfied());
+    }
+}
@@ -0,0 +1,575 @@
+use crate::bulk::or_cancel;
+use crate::{BulkResult, List, VkApi, VkApiResult};
+use futures_util::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::future::Future;
+use std::hash::Hash;
+use std::pin::pin;
+use std::time::Duration;
+
+/// # Search facade
+/// Typed builders for the VK search methods, which share pagination, pacing between pages and
+/// deduplication of repeated items.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use futures_util::StreamExt;
+/// use vkclient::VkApi;
+/// # let access_token = String::new();
+/// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+///
+/// let users = client
+///     .search()
+///     .users("Pavel")
+///     .with_age(18, 30)
+///     .with_limit(100)
+///     .stream()
+///     .for_each(|user| async move { println!("{:?}", user) });
+/// ```
+#[derive(Debug, Clone)]
+pub struct VkSearch {
+    client: VkApi,
+    interval: Duration,
+}
+
+impl VkSearch {
+    /// Pass the pause between page requests. Default is zero.
+    /// Use it to stay under the requests per second limit of your token.
+    #[must_use]
+    pub const fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Search users with [users.search](https://dev.vk.com/method/users.search).
+    pub fn users<Q: Into<String>>(&self, q: Q) -> SearchRequest<UsersSearch> {
+        self.request(UsersSearch {
+            q: q.into(),
+            ..UsersSearch::default()
+        })
+    }
+
+    /// Search communities with [groups.search](https://dev.vk.com/method/groups.search).
+    pub fn groups<Q: Into<String>>(&self, q: Q) -> SearchRequest<GroupsSearch> {
+        self.request(GroupsSearch {
+            q: q.into(),
+            ..GroupsSearch::default()
+        })
+    }
+
+    /// Search audio records with [audio.search](https://dev.vk.com/method/audio.search).
+    /// The method is available only for the tokens with the audio access.
+    pub fn audio<Q: Into<String>>(&self, q: Q) -> SearchRequest<AudioSearch> {
+        self.request(AudioSearch {
+            q: q.into(),
+            ..AudioSearch::default()
+        })
+    }
+
+    /// Creates the search request for any custom domain.
+    pub fn request<D: SearchDomain>(&self, params: D) -> SearchRequest<D> {
+        SearchRequest {
+            client: self.client.clone(),
+            params,
+            offset: 0,
+            page_size: D::get_max_count(),
+            limit: None,
+            interval: self.interval,
+        }
+    }
+
+    /// Returns the unified stream of users, groups and audio records found by query.
+    /// Domains are searched one by one, an error stops only the failed domain.
+    pub fn all<Q: Into<String>>(&self, q: Q) -> impl Stream<Item = VkApiResult<SearchResult>> {
+        let q = q.into();
+
+        self.users(q.clone())
+            .stream()
+            .map(|r| r.map(SearchResult::User))
+            .chain(
+                self.groups(q.clone())
+                    .stream()
+                    .map(|r| r.map(SearchResult::Group)),
+            )
+            .chain(self.audio(q).stream().map(|r| r.map(SearchResult::Audio)))
+    }
+}
+
+impl From<VkApi> for VkSearch {
+    fn from(client: VkApi) -> Self {
+        Self {
+            client,
+            interval: Duration::ZERO,
+        }
+    }
+}
+
+/// # Search method description
+/// Describes params of the search method and the type of found items.
+pub trait SearchDomain: Serialize + Clone + Send + Sync {
+    type Item: DeserializeOwned + SearchItem;
+
+    /// Method name
+    fn get_method_name() -> &'static str;
+
+    /// Maximum items count, which method returns per one request
+    fn get_max_count() -> usize;
+}
+
+/// Found item, which can be deduplicated between the pages.
+pub trait SearchItem {
+    type Key: Eq + Hash;
+
+    /// Unique key of the item
+    fn key(&self) -> Self::Key;
+}
+
+/// Search request builder.
+#[derive(Debug, Clone)]
+pub struct SearchRequest<D> {
+    client: VkApi,
+    params: D,
+    offset: usize,
+    page_size: usize,
+    limit: Option<usize>,
+    interval: Duration,
+}
+
+impl<D: SearchDomain> SearchRequest<D> {
+    /// Pass the offset of the first found item. Default is 0.
+    #[must_use]
+    pub const fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Pass the items count per one request. Default is the maximum count of the method.
+    #[must_use]
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.clamp(1, D::get_max_count());
+        self
+    }
+
+    /// Pass the maximum count of the items returned by stream. Default is unlimited.
+    #[must_use]
+    pub const fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Pass the pause between page requests.
+    #[must_use]
+    pub const fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Returns one page of found items from given offset.
+    pub async fn fetch_page(
+        &self,
+        offset: usize,
+        count: usize,
+    ) -> VkApiResult<SearchPage<D::Item>> {
+        self.client
+            .send_request(
+                D::get_method_name(),
+                SearchPageBody {
+                    params: &self.params,
+                    offset,
+                    count,
+                },
+            )
+            .await
+    }
+
+    /// Returns the stream of found items.
+    /// Pages are requested lazily until the stream is dropped, the results run out or the limit is reached.
+    /// Items repeated between the pages are skipped.
+    pub fn stream(self) -> impl Stream<Item = VkApiResult<D::Item>> {
+        async_stream::stream! {
+            let mut seen = HashSet::new();
+            let mut offset = self.offset;
+            let mut found = 0;
+
+            loop {
+                let count = match self.limit {
+                    Some(limit) if limit <= found => break,
+                    Some(limit) => self.page_size.min(limit - found),
+                    None => self.page_size,
+                };
+
+                if offset != self.offset && !self.interval.is_zero() {
+                    tokio::time::sleep(self.interval).await;
+                }
+
+                let SearchPage { count: total, items } = match self.fetch_page(offset, count).await {
+                    Ok(page) => page,
+                    Err(e) => {
+                        yield Err(e);
+                        break;
+                    }
+                };
+
+                if items.is_empty() {
+                    break;
+                }
+                offset += items.len();
+
+                for item in items {
+                    if seen.insert(item.key()) {
+                        found += 1;
+                        yield Ok(item);
+                    }
+                }
+
+                if offset >= total {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Collects found items until the `cancel` future completes,
+    /// like `CancellationToken::cancelled()` or `tokio::time::sleep`.
+    /// Items found before the cancellation or the error are returned.
+    pub async fn collect_until<C: Future>(self, cancel: C) -> BulkResult<D::Item> {
+        let mut cancel = pin!(cancel);
+        let mut stream = pin!(self.stream());
+        let mut result = BulkResult::new();
+
+        loop {
+            match or_cancel(cancel.as_mut(), stream.next()).await {
+                None => result.cancelled = true,
+                Some(Some(Ok(item))) => {
+                    result.succeeded.push(item);
+                    continue;
+                }
+                Some(Some(Err(e))) => result.failed.push(((), e)),
+                Some(None) => {}
+            }
+
+            return result;
+        }
+    }
+}
+
+impl SearchRequest<UsersSearch> {
+    /// Pass additional profile fields to return.
+    #[must_use]
+    pub fn with_fields<I: IntoIterator<Item = S>, S: Into<String>>(mut self, fields: I) -> Self {
+        self.params.fields = Some(List(fields.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Pass the city id.
+    #[must_use]
+    pub const fn with_city(mut self, city: u32) -> Self {
+        self.params.city = Some(city);
+        self
+    }
+
+    /// Pass the country id.
+    #[must_use]
+    pub const fn with_country(mut self, country: u32) -> Self {
+        self.params.country = Some(country);
+        self
+    }
+
+    /// Pass the sex: 1 is female, 2 is male.
+    #[must_use]
+    pub const fn with_sex(mut self, sex: u8) -> Self {
+        self.params.sex = Some(sex);
+        self
+    }
+
+    /// Pass the age bounds.
+    #[must_use]
+    pub const fn with_age(mut self, from: u8, to: u8) -> Self {
+        self.params.age_from = Some(from);
+        self.params.age_to = Some(to);
+        self
+    }
+
+    /// Search only online users.
+    #[must_use]
+    pub const fn with_online(mut self) -> Self {
+        self.params.online = Some(1);
+        self
+    }
+}
+
+impl SearchRequest<GroupsSearch> {
+    /// Pass the community type.
+    #[must_use]
+    pub const fn with_type(mut self, group_type: GroupType) -> Self {
+        self.params.group_type = Some(group_type);
+        self
+    }
+
+    /// Pass the city id.
+    #[must_use]
+    pub const fn with_city(mut self, city_id: u32) -> Self {
+        self.params.city_id = Some(city_id);
+        self
+    }
+
+    /// Pass the country id.
+    #[must_use]
+    pub const fn with_country(mut self, country_id: u32) -> Self {
+        self.params.country_id = Some(country_id);
+        self
+    }
+
+    /// Pass the sort order. See [groups.search](https://dev.vk.com/method/groups.search) for possible values.
+    #[must_use]
+    pub const fn with_sort(mut self, sort: u8) -> Self {
+        self.params.sort = Some(sort);
+        self
+    }
+}
+
+impl SearchRequest<AudioSearch> {
+    /// Enables correction of typos in the query.
+    #[must_use]
+    pub const fn with_auto_complete(mut self) -> Self {
+        self.params.auto_complete = Some(1);
+        self
+    }
+
+    /// Search only by the artist name.
+    #[must_use]
+    pub const fn with_performer_only(mut self) -> Self {
+        self.params.performer_only = Some(1);
+        self
+    }
+
+    /// Pass the sort order: 0 is by popularity, 1 is by duration, 2 is by date.
+    #[must_use]
+    pub const fn with_sort(mut self, sort: u8) -> Self {
+        self.params.sort = Some(sort);
+        self
+    }
+}
+
+/// One page of found items.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchPage<T> {
+    pub count: usize,
+    pub items: Vec<T>,
+}
+
+#[derive(Debug, Serialize)]
+struct SearchPageBody<'a, D> {
+    #[serde(flatten)]
+    params: &'a D,
+    offset: usize,
+    count: usize,
+}
+
+/// Params of [users.search](https://dev.vk.com/method/users.search).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsersSearch {
+    pub q: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<List<Vec<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sex: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age_from: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age_to: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub online: Option<u8>,
+}
+
+impl SearchDomain for UsersSearch {
+    type Item = SearchUser;
+
+    fn get_method_name() -> &'static str {
+        "users.search"
+    }
+
+    fn get_max_count() -> usize {
+        1000
+    }
+}
+
+/// Params of [groups.search](https://dev.vk.com/method/groups.search).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GroupsSearch {
+    pub q: String,
+    #[serde(rename = "type")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_type: Option<GroupType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city_id: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country_id: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<u8>,
+}
+
+impl SearchDomain for GroupsSearch {
+    type Item = SearchGroup;
+
+    fn get_method_name() -> &'static str {
+        "groups.search"
+    }
+
+    fn get_max_count() -> usize {
+        1000
+    }
+}
+
+/// Community type
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupType {
+    Group,
+    Page,
+    Event,
+}
+
+/// Params of [audio.search](https://dev.vk.com/method/audio.search).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AudioSearch {
+    pub q: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_complete: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub performer_only: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<u8>,
+}
+
+impl SearchDomain for AudioSearch {
+    type Item = SearchAudio;
+
+    fn get_method_name() -> &'static str {
+        "audio.search"
+    }
+
+    fn get_max_count() -> usize {
+        300
+    }
+}
+
+/// User found by [users.search](https://dev.vk.com/method/users.search).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchUser {
+    pub id: i64,
+    pub first_name: String,
+    pub last_name: String,
+    #[serde(default)]
+    pub deactivated: Option<String>,
+    #[serde(default)]
+    pub is_closed: Option<bool>,
+}
+
+impl SearchItem for SearchUser {
+    type Key = i64;
+
+    fn key(&self) -> Self::Key {
+        self.id
+    }
+}
+
+/// Community found by [groups.search](https://dev.vk.com/method/groups.search).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchGroup {
+    pub id: i64,
+    pub name: String,
+    pub screen_name: String,
+    #[serde(default)]
+    pub is_closed: Option<u8>,
+    #[serde(rename = "type")]
+    #[serde(default)]
+    pub group_type: Option<GroupType>,
+}
+
+impl SearchItem for SearchGroup {
+    type Key = i64;
+
+    fn key(&self) -> Self::Key {
+        self.id
+    }
+}
+
+/// Audio record found by [audio.search](https://dev.vk.com/method/audio.search).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchAudio {
+    pub id: i64,
+    pub owner_id: i64,
+    pub artist: String,
+    pub title: String,
+    #[serde(default)]
+    pub duration: Option<u32>,
+}
+
+impl SearchItem for SearchAudio {
+    type Key = (i64, i64);
+
+    fn key(&self) -> Self::Key {
+        (self.owner_id, self.id)
+    }
+}
+
+/// Item of the unified search stream.
+#[derive(Debug, Clone)]
+pub enum SearchResult {
+    User(SearchUser),
+    Group(SearchGroup),
+    Audio(SearchAudio),
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search::{GroupType, GroupsSearch, SearchPageBody, UsersSearch};
+    use crate::List;
+
+    #[test]
+    fn serialize_users_page() {
+        let params = UsersSearch {
+            q: "Pavel".to_owned(),
+            fields: Some(List(vec!["sex".to_owned(), "city".to_owned()])),
+            age_from: Some(18),
+            ..UsersSearch::default()
+        };
+
+        let body = serde_urlencoded::to_string(SearchPageBody {
+            params: &params,
+            offset: 10,
+            count: 20,
+        })
+        .unwrap();
+
+        assert_eq!(
+            body,
+            "q=Pavel&fields=sex%2Ccity&age_from=18&offset=10&count=20"
+        );
+    }
+
+    #[test]
+    fn serialize_groups_page() {
+        let params = GroupsSearch {
+            q: "rust".to_owned(),
+            group_type: Some(GroupType::Page),
+            ..GroupsSearch::default()
+        };
+
+        let body = serde_urlencoded::to_string(SearchPageBody {
+            params: &params,
+            offset: 0,
+            count: 1000,
+        })
+        .unwrap();
+
+        assert_eq!(body, "q=rust&type=page&offset=0&count=1000");
+    }
+}
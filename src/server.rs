@@ -0,0 +1,131 @@
+use crate::{Compression, MetricsRecorder, RequestMetrics, VkApiError, VkApiResult};
+use reqwest::Url;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Settings of the requests to the long poll and upload servers.
+#[derive(Clone)]
+pub struct ServerConfig {
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) compression: Compression,
+    pub(crate) allowed_hosts: Option<Arc<[String]>>,
+    pub(crate) metrics: Option<Arc<dyn MetricsRecorder>>,
+    /// Faults of the long poll requests
+    #[cfg(all(feature = "chaos", feature = "longpoll"))]
+    pub(crate) chaos: Option<crate::chaos::Chaos>,
+}
+
+impl ServerConfig {
+    /// Returns the error if the allowlist is set and the host of the url isn't in it.
+    /// Subdomains of the allowed hosts are allowed too.
+    pub(crate) fn check_host(&self, url: &str) -> VkApiResult<()> {
+        let Some(allowed_hosts) = &self.allowed_hosts else {
+            return Ok(());
+        };
+
+        let url = Url::parse(url).map_err(|_| VkApiError::HostNotAllowed(url.to_owned()))?;
+        let host = url.host_str().unwrap_or_default();
+
+        if allowed_hosts.iter().any(|allowed| {
+            host == allowed
+                || host
+                    .strip_suffix(allowed.as_str())
+                    .is_some_and(|subdomain| subdomain.ends_with('.'))
+        }) {
+            Ok(())
+        } else {
+            Err(VkApiError::HostNotAllowed(host.to_owned()))
+        }
+    }
+
+    pub(crate) const fn accept_encoding(&self) -> &'static str {
+        match self.compression {
+            #[cfg(feature = "compression_zstd")]
+            Compression::Zstd { .. } => "zstd",
+            #[cfg(feature = "compression_gzip")]
+            Compression::Gzip => "gzip",
+            Compression::None => "identity",
+        }
+    }
+
+    /// Records the result of the request, which is started at `started`.
+    pub(crate) fn record<T>(
+        &self,
+        url: &str,
+        started: Instant,
+        bytes: usize,
+        result: &VkApiResult<T>,
+    ) {
+        let Some(metrics) = &self.metrics else {
+            return;
+        };
+
+        let url = Url::parse(url).ok();
+        metrics.record(&RequestMetrics {
+            host: url.as_ref().and_then(Url::host_str).unwrap_or_default(),
+            method: None,
+            elapsed: started.elapsed(),
+            bytes,
+            encoding: None,
+            compression: self.compression,
+            error: result.as_ref().err().map(VkApiError::label),
+        });
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "compression_gzip")] {
+                let compression = Compression::Gzip;
+            } else {
+                let compression = Compression::None;
+            }
+        }
+
+        Self {
+            timeout: None,
+            compression,
+            allowed_hosts: None,
+            metrics: None,
+            #[cfg(all(feature = "chaos", feature = "longpoll"))]
+            chaos: None,
+        }
+    }
+}
+
+impl Debug for ServerConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServerConfig")
+            .field("timeout", &self.timeout)
+            .field("compression", &self.compression)
+            .field("allowed_hosts", &self.allowed_hosts)
+            .field("metrics", &self.metrics.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::ServerConfig;
+    use crate::VkApiError;
+
+    #[test]
+    fn check_allowed_hosts() {
+        let config = ServerConfig {
+            allowed_hosts: Some(["vk.com".to_owned()].into()),
+            ..ServerConfig::default()
+        };
+
+        assert!(config.check_host("https://vk.com/upload").is_ok());
+        assert!(config
+            .check_host("https://pu.vk.com/c123/upload.php")
+            .is_ok());
+        assert!(matches!(
+            config.check_host("https://evilvk.com/upload"),
+            Err(VkApiError::HostNotAllowed(host)) if host == "evilvk.com"
+        ));
+        assert!(ServerConfig::default().check_host("not a url").is_ok());
+    }
+}
@@ -0,0 +1,217 @@
+use crate::VkError;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Count of the buckets of the sliding window, the window moves by one bucket.
+const BUCKETS: u128 = 60;
+
+/// Log of the sampled VK errors with the count of their code in the window.
+pub type ErrorLog = Arc<dyn Fn(&VkError, u64) + Send + Sync>;
+
+/// Counts of the VK error codes over the sliding window, see [`VkApi::error_stats`](crate::VkApi::error_stats).
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ErrorSnapshot {
+    window: Duration,
+    counts: HashMap<i16, u64>,
+}
+
+impl ErrorSnapshot {
+    /// Returns the duration of the window.
+    #[must_use]
+    pub const fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Returns the count of the errors with the code in the window.
+    #[must_use]
+    pub fn count(&self, code: i16) -> u64 {
+        self.counts.get(&code).copied().unwrap_or_default()
+    }
+
+    /// Returns the count of all errors in the window.
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    /// Returns the counts of the errors by their codes.
+    #[must_use]
+    pub const fn counts(&self) -> &HashMap<i16, u64> {
+        &self.counts
+    }
+}
+
+/// Aggregator of the VK errors of the client.
+pub struct ErrorStats {
+    window: Duration,
+    log: Option<(u64, ErrorLog)>,
+    state: Mutex<Buckets>,
+}
+
+#[derive(Debug)]
+struct Buckets {
+    started: Instant,
+    /// Counts of the codes by the bucket number since the start, the oldest first
+    buckets: VecDeque<(u128, HashMap<i16, u64>)>,
+    /// Count of the errors of each code since the start for the sampling
+    seen: HashMap<i16, u64>,
+}
+
+impl ErrorStats {
+    pub(crate) fn new(window: Duration) -> Self {
+        Self {
+            window,
+            log: None,
+            state: Mutex::new(Buckets {
+                started: Instant::now(),
+                buckets: VecDeque::new(),
+                seen: HashMap::new(),
+            }),
+        }
+    }
+
+    pub(crate) const fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Pass the log of each `every` error of the code, it's called with the count of the code in the window.
+    pub(crate) fn with_log(mut self, every: u64, log: ErrorLog) -> Self {
+        self.log = Some((every.max(1), log));
+        self
+    }
+
+    pub(crate) fn record(&self, error: &VkError) {
+        self.record_at(error, Instant::now());
+    }
+
+    fn record_at(&self, error: &VkError, now: Instant) {
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let bucket = self.bucket(&state, now);
+        state.expire(bucket);
+
+        match state.buckets.back_mut() {
+            Some((number, counts)) if *number == bucket => {
+                *counts.entry(error.code()).or_default() += 1;
+            }
+            _ => state
+                .buckets
+                .push_back((bucket, HashMap::from([(error.code(), 1)]))),
+        }
+
+        let Some((every, log)) = &self.log else {
+            return;
+        };
+        let seen = state.seen.entry(error.code()).or_default();
+        *seen += 1;
+        if (*seen - 1).is_multiple_of(*every) {
+            let count = state.counts().remove(&error.code()).unwrap_or_default();
+            drop(state);
+            log(error, count);
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> ErrorSnapshot {
+        self.snapshot_at(Instant::now())
+    }
+
+    fn snapshot_at(&self, now: Instant) -> ErrorSnapshot {
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let bucket = self.bucket(&state, now);
+        state.expire(bucket);
+
+        ErrorSnapshot {
+            window: self.window,
+            counts: state.counts(),
+        }
+    }
+
+    fn bucket(&self, state: &Buckets, now: Instant) -> u128 {
+        let width = (self.window.as_nanos() / BUCKETS).max(1);
+        now.saturating_duration_since(state.started).as_nanos() / width
+    }
+}
+
+impl Buckets {
+    /// Removes the buckets, which are out of the window ending with the bucket.
+    fn expire(&mut self, bucket: u128) {
+        while self
+            .buckets
+            .front()
+            .is_some_and(|(number, _)| number + BUCKETS <= bucket)
+        {
+            self.buckets.pop_front();
+        }
+    }
+
+    fn counts(&self) -> HashMap<i16, u64> {
+        let mut result = HashMap::new();
+        for (code, count) in self.buckets.iter().flat_map(|(_, counts)| counts) {
+            *result.entry(*code).or_default() += count;
+        }
+        result
+    }
+}
+
+impl Clone for ErrorStats {
+    /// Clones the settings with the empty state, stats of the built clients are shared instead.
+    fn clone(&self) -> Self {
+        Self {
+            log: self.log.clone(),
+            ..Self::new(self.window)
+        }
+    }
+}
+
+impl Debug for ErrorStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ErrorStats")
+            .field("window", &self.window)
+            .field("log_every", &self.log.as_ref().map(|(every, _)| every))
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stats::ErrorStats;
+    use crate::VkError;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn count_errors_in_window() {
+        let logged = Arc::new(AtomicU64::new(0));
+        let counter = logged.clone();
+        let stats = ErrorStats::new(Duration::from_secs(60)).with_log(
+            2,
+            Arc::new(move |_: &VkError, _: u64| {
+                counter.fetch_add(1, Ordering::Relaxed);
+            }),
+        );
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            stats.record_at(&VkError::new(5, "auth"), now);
+        }
+        stats.record_at(&VkError::new(6, "too many"), now + Duration::from_secs(30));
+
+        let snapshot = stats.snapshot_at(now + Duration::from_secs(31));
+        assert_eq!((snapshot.count(5), snapshot.count(6)), (3, 1));
+        assert_eq!(snapshot.total(), 4);
+        assert_eq!(logged.load(Ordering::Relaxed), 3);
+
+        let snapshot = stats.snapshot_at(now + Duration::from_secs(61));
+        assert_eq!((snapshot.count(5), snapshot.count(6)), (0, 1));
+    }
+}
@@ -0,0 +1,364 @@
+//! # Streaming API
+//! Client of the [Streaming API](https://dev.vk.com/api/streaming/getting-started),
+//! which delivers the public posts, comments and shares matched by the keyword rules over WebSocket.
+//! The API requires the service token of the application.
+
+mod websocket;
+
+use crate::inner::{decode, http1_client_builder, ClientConfig};
+use crate::{BuildError, DecodeStrictness, VkApi, VkApiError, VkApiResult};
+use futures_util::Stream;
+use reqwest::header::{HeaderValue, CONTENT_TYPE};
+use reqwest::{Client, Method};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// # Streaming API client
+/// Manages the rules and returns the stream of the matched events.
+/// Rules are shared by all connections of the application.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use futures_util::StreamExt;
+/// use vkclient::streaming::{StreamingMessage, StreamingRule};
+/// use vkclient::VkApi;
+/// # let service_token = String::new();
+/// let client: VkApi = vkclient::VkApiBuilder::new(service_token).into();
+///
+/// async move {
+///     let streaming = client.streaming().await.unwrap();
+///     streaming.add_rule(StreamingRule::new("cats", "кот -собака")).await.unwrap();
+///
+///     let mut events = std::pin::pin!(streaming.stream().await.unwrap());
+///     while let Some(message) = events.next().await {
+///         if let Ok(StreamingMessage::Event(event)) = message {
+///             println!("{} matched {:?}", event.event_url, event.tags);
+///         }
+///     }
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct VkStreaming {
+    client: Client,
+    endpoint: String,
+    key: String,
+}
+
+impl VkStreaming {
+    /// Creates the client of the server returned by [streaming.getServerUrl](https://dev.vk.com/method/streaming.getServerUrl).
+    pub fn new<E: Into<String>, K: Into<String>>(endpoint: E, key: K) -> Result<Self, BuildError> {
+        Self::with_config(&ClientConfig::default(), endpoint.into(), key.into())
+            .map_err(BuildError::Client)
+    }
+
+    /// Requests the server of the application with the `api` client.
+    pub(crate) async fn request_server(api: &VkApi, config: &ClientConfig) -> VkApiResult<Self> {
+        let server: StreamingServer = api.send_request("streaming.getServerUrl", ()).await?;
+        Self::with_config(config, server.endpoint, server.key).map_err(VkApiError::Request)
+    }
+
+    fn with_config(config: &ClientConfig, endpoint: String, key: String) -> reqwest::Result<Self> {
+        // WebSocket connections can be upgraded from the http/1 only
+        let client = http1_client_builder(config).build()?;
+
+        Ok(Self {
+            client,
+            endpoint,
+            key,
+        })
+    }
+
+    /// Returns the host of the server.
+    #[must_use]
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Returns the rules of the application.
+    pub async fn rules(&self) -> VkApiResult<Vec<StreamingRule>> {
+        self.request(Method::GET, None::<()>)
+            .await
+            .map(Option::unwrap_or_default)
+    }
+
+    /// Adds the rule. The tag must be unique among the rules of the application.
+    pub async fn add_rule(&self, rule: StreamingRule) -> VkApiResult<()> {
+        self.request(Method::POST, Some(AddRule { rule }))
+            .await
+            .map(drop)
+    }
+
+    /// Deletes the rule by its tag.
+    pub async fn delete_rule<T: Into<String>>(&self, tag: T) -> VkApiResult<()> {
+        self.request(Method::DELETE, Some(DeleteRule { tag: tag.into() }))
+            .await
+            .map(drop)
+    }
+
+    /// Connects to the server and returns the stream of the events matched by the rules.
+    /// The stream ends when the server closes the connection, or with the first error.
+    /// Reconnect to continue, events sent while disconnected are lost.
+    pub async fn stream(&self) -> VkApiResult<impl Stream<Item = VkApiResult<StreamingMessage>>> {
+        let url = format!("https://{}/stream?key={}", self.endpoint, self.key);
+        let mut socket = websocket::connect(&self.client, &url).await?;
+
+        Ok(async_stream::stream! {
+            loop {
+                match socket.next_message().await {
+                    Ok(Some(message)) => {
+                        let message = decode_json::<StreamMessage>(&message)
+                            .and_then(StreamMessage::into_result);
+                        let failed = message.is_err();
+                        yield message;
+                        if failed {
+                            break;
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        yield Err(VkApiError::IO(e));
+                        break;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Sends the request to the rules of the application and returns them, if the response has the rules.
+    async fn request<B: Serialize>(
+        &self,
+        method: Method,
+        body: Option<B>,
+    ) -> VkApiResult<Option<Vec<StreamingRule>>> {
+        let url = format!("https://{}/rules?key={}", self.endpoint, self.key);
+        let mut request = self.client.request(method, url);
+        if let Some(body) = body {
+            request = request
+                .header(CONTENT_TYPE, "application/json")
+                .body(serde_json::to_vec(&body).expect("rule is serializable"));
+        }
+
+        let body = request
+            .send()
+            .await
+            .map_err(VkApiError::Request)?
+            .bytes()
+            .await
+            .map_err(VkApiError::Request)?;
+
+        match decode_json::<RulesResponse>(&body)? {
+            RulesResponse {
+                error: Some(error), ..
+            } => Err(VkApiError::Streaming(error)),
+            RulesResponse { rules, .. } => Ok(rules),
+        }
+    }
+}
+
+/// Decodes the json body, which the Streaming API sends regardless of the accepted encodings.
+fn decode_json<T: DeserializeOwned>(body: &[u8]) -> VkApiResult<T> {
+    decode(
+        Some(&HeaderValue::from_static("application/json")),
+        body,
+        DecodeStrictness::Lenient,
+    )
+}
+
+/// Keyword rule of the events, see the [rules syntax](https://dev.vk.com/api/streaming/getting-started#Правила).
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct StreamingRule {
+    /// Keywords, like `кот -собака` for the posts with the cat and without the dog
+    pub value: String,
+    /// Unique tag, which is returned with the matched events
+    pub tag: String,
+}
+
+impl StreamingRule {
+    /// Creates the rule with the tag and the keywords.
+    pub fn new<T: Into<String>, V: Into<String>>(tag: T, value: V) -> Self {
+        Self {
+            value: value.into(),
+            tag: tag.into(),
+        }
+    }
+}
+
+/// Message of the [`VkStreaming::stream`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum StreamingMessage {
+    Event(Box<StreamingEvent>),
+    /// Notice of the server, like the upcoming restart
+    Service(ServiceMessage),
+}
+
+/// [Event](https://dev.vk.com/api/streaming/getting-started#Чтение%20потока) matched by the rules.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamingEvent {
+    /// `post`, `comment` or `share`
+    pub event_type: String,
+    pub event_id: StreamingEventId,
+    pub event_url: String,
+    #[serde(default)]
+    pub text: String,
+    /// `new`, `update`, `delete` or `restore`
+    pub action: String,
+    pub action_time: i64,
+    #[serde(default)]
+    pub creation_time: Option<i64>,
+    #[serde(default)]
+    pub attachments: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub geo: Option<serde_json::Value>,
+    #[serde(default)]
+    pub shared_post_text: Option<String>,
+    #[serde(default)]
+    pub shared_post_creation_time: Option<i64>,
+    #[serde(default)]
+    pub signer_id: Option<i64>,
+    /// Tags of the matched rules
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub author: StreamingAuthor,
+}
+
+/// Id of the [`StreamingEvent`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct StreamingEventId {
+    pub post_owner_id: i64,
+    pub post_id: i64,
+    #[serde(default)]
+    pub comment_id: Option<i64>,
+    #[serde(default)]
+    pub shared_post_id: Option<i64>,
+}
+
+/// Author of the [`StreamingEvent`].
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StreamingAuthor {
+    pub id: i64,
+    #[serde(default)]
+    pub author_url: String,
+    #[serde(default)]
+    pub shared_post_author_id: Option<i64>,
+    #[serde(default)]
+    pub shared_post_author_url: Option<String>,
+    #[serde(default)]
+    pub platform: Option<u8>,
+}
+
+/// Service message of the [`VkStreaming::stream`].
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ServiceMessage {
+    pub message: String,
+    pub service_code: u32,
+}
+
+/// Error of the Streaming API, see the [error codes](https://dev.vk.com/api/streaming/getting-started#Коды%20ошибок).
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StreamingError {
+    pub message: String,
+    pub error_code: u32,
+}
+
+impl Display for StreamingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "streaming api error occured, code: {}, message: {}",
+            self.error_code, self.message
+        )
+    }
+}
+
+impl Error for StreamingError {}
+
+#[derive(Deserialize)]
+struct StreamingServer {
+    endpoint: String,
+    key: String,
+}
+
+#[derive(Serialize)]
+struct AddRule {
+    rule: StreamingRule,
+}
+
+#[derive(Serialize)]
+struct DeleteRule {
+    tag: String,
+}
+
+#[derive(Deserialize)]
+struct RulesResponse {
+    #[serde(default)]
+    rules: Option<Vec<StreamingRule>>,
+    #[serde(default)]
+    error: Option<StreamingError>,
+}
+
+#[derive(Deserialize)]
+struct StreamMessage {
+    #[serde(default)]
+    event: Option<StreamingEvent>,
+    #[serde(default)]
+    service_message: Option<ServiceMessage>,
+    #[serde(default)]
+    error: Option<StreamingError>,
+}
+
+impl StreamMessage {
+    fn into_result(self) -> VkApiResult<StreamingMessage> {
+        match self {
+            Self {
+                error: Some(error), ..
+            } => Err(VkApiError::Streaming(error)),
+            Self {
+                event: Some(event), ..
+            } => Ok(StreamingMessage::Event(Box::new(event))),
+            Self {
+                service_message: Some(message),
+                ..
+            } => Ok(StreamingMessage::Service(message)),
+            _ => Err(VkApiError::ResponseDeserialize(
+                crate::ResponseDeserialize::BadEncoding,
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::streaming::{decode_json, StreamMessage, StreamingMessage};
+    use crate::VkApiError;
+
+    #[test]
+    fn decode_stream_messages() {
+        let event = decode_json::<StreamMessage>(
+            r#"{"code":100,"event":{"event_type":"post","event_id":{"post_owner_id":-1,"post_id":10},
+            "event_url":"https://vk.com/wall-1_10","text":"кот","action":"new","action_time":1700000000,
+            "creation_time":1700000000,"attachments":[],"tags":["cats"],"author":{"id":-1,"author_url":"https://vk.com/club1"}}}"#
+                .as_bytes(),
+        )
+        .and_then(StreamMessage::into_result)
+        .unwrap();
+        assert!(matches!(
+            event,
+            StreamingMessage::Event(event) if event.tags == ["cats"] && event.event_id.post_id == 10
+        ));
+
+        let service = decode_json::<StreamMessage>(
+            br#"{"code":300,"service_message":{"message":"restart","service_code":3000}}"#,
+        )
+        .and_then(StreamMessage::into_result)
+        .unwrap();
+        assert!(matches!(service, StreamingMessage::Service(m) if m.service_code == 3000));
+
+        let error = decode_json::<StreamMessage>(
+            br#"{"code":400,"error":{"message":"key is invalid","error_code":1000}}"#,
+        )
+        .and_then(StreamMessage::into_result);
+        assert!(matches!(error, Err(VkApiError::Streaming(e)) if e.error_code == 1000));
+    }
+}
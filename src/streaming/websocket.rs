@@ -0,0 +1,119 @@
+//! WebSocket connection of the Streaming API.
+//! The handshake is sent by the http client, so the connection shares its tls settings, like the pinned keys,
+//! and the frames of the upgraded connection are handled by the [`tokio_tungstenite`].
+
+use crate::{VkApiError, VkApiResult};
+use futures_util::StreamExt;
+use reqwest::header::{CONNECTION, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY, UPGRADE};
+use reqwest::{Client, StatusCode};
+use std::io::{Error, ErrorKind};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_tungstenite::tungstenite::handshake::client::generate_key;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::{Role, WebSocketConfig};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+/// Maximum size of the message, protects the memory from the broken frames.
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Connection after the handshake.
+pub struct WebSocket<S> {
+    stream: WebSocketStream<S>,
+}
+
+/// Opens the connection to the `https` url, which is upgraded to the WebSocket.
+/// The client must be http/1 only, http/2 connections can't be upgraded.
+pub async fn connect(client: &Client, url: &str) -> VkApiResult<WebSocket<reqwest::Upgraded>> {
+    let key = generate_key();
+
+    let response = client
+        .get(url)
+        .header(CONNECTION, "Upgrade")
+        .header(UPGRADE, "websocket")
+        .header("Sec-WebSocket-Version", "13")
+        .header(SEC_WEBSOCKET_KEY, &key)
+        .send()
+        .await
+        .map_err(VkApiError::Request)?;
+
+    let response = response.error_for_status().map_err(VkApiError::Request)?;
+    if response.status() != StatusCode::SWITCHING_PROTOCOLS {
+        return Err(VkApiError::IO(invalid_data(
+            "websocket handshake isn't accepted",
+        )));
+    }
+
+    let accepted = response
+        .headers()
+        .get(SEC_WEBSOCKET_ACCEPT)
+        .is_some_and(|accept| accept.as_bytes() == derive_accept_key(key.as_bytes()).as_bytes());
+    if !accepted {
+        return Err(VkApiError::IO(invalid_data(
+            "websocket handshake has invalid accept key",
+        )));
+    }
+
+    let stream = response.upgrade().await.map_err(VkApiError::Request)?;
+    Ok(WebSocket::client(stream).await)
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> WebSocket<S> {
+    /// Wraps the upgraded stream of the client.
+    async fn client(stream: S) -> Self {
+        let config = WebSocketConfig::default()
+            .max_message_size(Some(MAX_MESSAGE_SIZE))
+            .max_frame_size(Some(MAX_MESSAGE_SIZE));
+
+        Self {
+            stream: WebSocketStream::from_raw_socket(stream, Role::Client, Some(config)).await,
+        }
+    }
+
+    /// Returns the payload of the next text or binary message, or `None` after the close frame.
+    /// Pings are answered while waiting.
+    pub(crate) async fn next_message(&mut self) -> std::io::Result<Option<Vec<u8>>> {
+        while let Some(message) = self.stream.next().await {
+            match message.map_err(Error::other)? {
+                Message::Text(text) => return Ok(Some(text.as_bytes().to_vec())),
+                Message::Binary(payload) => return Ok(Some(payload.into())),
+                Message::Close(_) => return Ok(None),
+                Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => {}
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn invalid_data(message: &'static str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::streaming::websocket::WebSocket;
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::protocol::Role;
+    use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::WebSocketStream;
+
+    #[tokio::test]
+    async fn read_messages() {
+        let (client, server) = tokio::io::duplex(1024);
+        let mut socket = WebSocket::client(client).await;
+        let mut server = WebSocketStream::from_raw_socket(server, Role::Server, None).await;
+
+        server.send(Message::text(r#"{"a":1}"#)).await.unwrap();
+        server.send(Message::Ping("p".into())).await.unwrap();
+        server.send(Message::binary(&b"{}"[..])).await.unwrap();
+        server.send(Message::Close(None)).await.unwrap();
+
+        assert_eq!(socket.next_message().await.unwrap().unwrap(), br#"{"a":1}"#);
+        assert_eq!(socket.next_message().await.unwrap().unwrap(), b"{}");
+        assert!(socket.next_message().await.unwrap().is_none());
+
+        // the ping is answered with the pong of its payload
+        let pong = server.next().await.unwrap().unwrap();
+        assert_eq!(pong, Message::Pong("p".into()));
+    }
+}
@@ -1,4 +1,8 @@
-use serde::{Serialize, Serializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 /// Vk API List helper
 /// Serialize any iterable struct with `ToString` items to string separated by comma.
@@ -23,24 +27,24 @@ where
     }
 }
 
-impl<Item, Iter> ToString for List<Iter>
+impl<Item, Iter> Display for List<Iter>
 where
     Item: ToString,
     for<'a> &'a Iter: IntoIterator<Item = &'a Item>,
 {
-    fn to_string(&self) -> String {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let mut iter = self.0.into_iter();
 
-        let mut result = match iter.next() {
-            None => return String::new(),
-            Some(i) => i.to_string(),
-        };
+        match iter.next() {
+            None => return Ok(()),
+            Some(i) => f.write_str(&i.to_string())?,
+        }
 
         for i in iter {
-            result.push(',');
-            result.push_str(&i.to_string());
+            f.write_str(",")?;
+            f.write_str(&i.to_string())?;
         }
-        result
+        Ok(())
     }
 }
 
@@ -54,10 +58,222 @@ where
     }
 }
 
+/// Value of the dynamic request param for [`VkApi::send_request_params`](crate::VkApi::send_request_params).
+///
+/// Booleans are sent as `1` or `0`, lists are joined by comma and json values are sent as json strings.
+/// Example:
+/// ```rust
+/// use vkclient::ParamValue;
+/// assert_eq!(ParamValue::from(vec![1, 2]).to_string(), "1,2");
+/// assert_eq!(ParamValue::from(true).to_string(), "1");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamValue {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+    List(Vec<Self>),
+    #[cfg(feature = "encode_json")]
+    Json(serde_json::Value),
+}
+
+impl Display for ParamValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Str(s) => f.write_str(s),
+            Self::Int(i) => Display::fmt(i, f),
+            Self::Bool(b) => f.write_str(if *b { "1" } else { "0" }),
+            Self::List(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    Display::fmt(item, f)?;
+                }
+                Ok(())
+            }
+            #[cfg(feature = "encode_json")]
+            Self::Json(value) => Display::fmt(value, f),
+        }
+    }
+}
+
+impl Serialize for ParamValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Int(i) => serializer.serialize_i64(*i),
+            value => serializer.collect_str(value),
+        }
+    }
+}
+
+impl From<&str> for ParamValue {
+    fn from(s: &str) -> Self {
+        Self::Str(s.to_owned())
+    }
+}
+
+impl From<String> for ParamValue {
+    fn from(s: String) -> Self {
+        Self::Str(s)
+    }
+}
+
+impl From<i64> for ParamValue {
+    fn from(i: i64) -> Self {
+        Self::Int(i)
+    }
+}
+
+impl From<i32> for ParamValue {
+    fn from(i: i32) -> Self {
+        Self::Int(i.into())
+    }
+}
+
+impl From<u32> for ParamValue {
+    fn from(i: u32) -> Self {
+        Self::Int(i.into())
+    }
+}
+
+impl From<bool> for ParamValue {
+    fn from(b: bool) -> Self {
+        Self::Bool(b)
+    }
+}
+
+impl<T: Into<Self>> From<Vec<T>> for ParamValue {
+    fn from(items: Vec<T>) -> Self {
+        Self::List(items.into_iter().map(Into::into).collect())
+    }
+}
+
+#[cfg(feature = "encode_json")]
+impl From<serde_json::Value> for ParamValue {
+    fn from(value: serde_json::Value) -> Self {
+        Self::Json(value)
+    }
+}
+
+/// Flattens the json object to the form fields.
+/// Scalars and arrays of scalars are allowed, `null` fields and bodies are skipped,
+/// nested objects and arrays are rejected, because they can't be sent as form fields.
+#[cfg(feature = "encode_json")]
+pub fn flatten_json(
+    body: serde_json::Value,
+) -> Result<Vec<(String, ParamValue)>, serde_urlencoded::ser::Error> {
+    use serde_json::Value;
+
+    fn scalar(field: &str, value: Value) -> Result<Option<ParamValue>, String> {
+        Ok(match value {
+            Value::Null => None,
+            Value::Bool(b) => Some(ParamValue::Bool(b)),
+            Value::Number(n) => Some(n.as_i64().map_or_else(
+                || ParamValue::Str(n.to_string()),
+                ParamValue::Int,
+            )),
+            Value::String(s) => Some(ParamValue::Str(s)),
+            Value::Array(_) | Value::Object(_) => {
+                return Err(format!(
+                    "field `{field}` is nested, only scalars and arrays of scalars can be sent, wrap it with `Json` to send as json string"
+                ))
+            }
+        })
+    }
+
+    let fields = match body {
+        Value::Object(fields) => fields,
+        Value::Null => return Ok(Vec::new()),
+        _ => {
+            return Err(serde_urlencoded::ser::Error::Custom(
+                "body must be a json object".into(),
+            ))
+        }
+    };
+
+    let mut params = Vec::with_capacity(fields.len());
+    for (field, value) in fields {
+        let value = match value {
+            Value::Array(items) => Some(ParamValue::List(
+                items
+                    .into_iter()
+                    .filter_map(|item| scalar(&field, item).transpose())
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| serde_urlencoded::ser::Error::Custom(e.into()))?,
+            )),
+            value => {
+                scalar(&field, value).map_err(|e| serde_urlencoded::ser::Error::Custom(e.into()))?
+            }
+        };
+
+        if let Some(value) = value {
+            params.push((field, value));
+        }
+    }
+
+    Ok(params)
+}
+
+/// Vk API Json helper
+/// Serialize any `Serialize` struct to json string, for params like a `keyboard`, `template` or `forward`.
+/// Example:
+/// ```rust
+/// use vkclient::Json;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Forward {
+///     peer_id: i64,
+///     is_reply: bool,
+/// }
+///
+/// assert_eq!(
+///     serde_urlencoded::to_string([("forward", Json(Forward { peer_id: 1, is_reply: true }))]).unwrap(),
+///     "forward=%7B%22peer_id%22%3A1%2C%22is_reply%22%3Atrue%7D"
+/// );
+/// ```
+#[cfg(feature = "encode_json")]
+#[derive(Copy, Clone, Debug, Default, Ord, PartialOrd, Eq, PartialEq)]
+pub struct Json<T>(pub T);
+
+#[cfg(feature = "encode_json")]
+impl<T: Serialize> Serialize for Json<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let json = serde_json::to_string(&self.0).map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&json)
+    }
+}
+
+#[cfg(feature = "encode_json")]
+impl<T> From<T> for Json<T> {
+    fn from(t: T) -> Self {
+        Self(t)
+    }
+}
+
 /// Major and minor versions of VK API
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+/// Use `TryFrom` to check the version is supported, like for versions from configs.
+/// Example:
+/// ```rust
+/// use vkclient::Version;
+/// assert_eq!(Version::try_from("5.199"), Ok(Version(5, 199)));
+/// assert!(Version::try_from("4.100").is_err());
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Ord, PartialOrd)]
 pub struct Version(pub u8, pub u16);
 
+impl Version {
+    /// Minimal version, which VK API still supports.
+    pub const MIN_SUPPORTED: Self = Self(5, 81);
+}
+
 impl Default for Version {
     fn default() -> Self {
         Self(5, 131)
@@ -73,12 +289,85 @@ impl Serialize for Version {
     }
 }
 
-impl ToString for Version {
-    fn to_string(&self) -> String {
-        format!("{}.{}", self.0, self.1)
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.0, self.1)
+    }
+}
+
+impl TryFrom<(u8, u16)> for Version {
+    type Error = VersionError;
+
+    fn try_from((major, minor): (u8, u16)) -> Result<Self, Self::Error> {
+        let version = Self(major, minor);
+        if major == Self::MIN_SUPPORTED.0 && version >= Self::MIN_SUPPORTED {
+            Ok(version)
+        } else {
+            Err(VersionError::Unsupported(version))
+        }
+    }
+}
+
+impl TryFrom<&str> for Version {
+    type Error = VersionError;
+
+    fn try_from(version: &str) -> Result<Self, Self::Error> {
+        let (major, minor) = version
+            .split_once('.')
+            .and_then(|(major, minor)| Some((major.parse().ok()?, minor.parse().ok()?)))
+            .ok_or_else(|| VersionError::Format(version.to_owned()))?;
+
+        Self::try_from((major, minor))
+    }
+}
+
+impl FromStr for Version {
+    type Err = VersionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let version = Cow::<str>::deserialize(deserializer)?;
+        Self::try_from(version.as_ref()).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Version parsing errors.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VersionError {
+    /// Version is not in the `major.minor` format
+    Format(String),
+    /// Version is older than [`Version::MIN_SUPPORTED`] or has other major version
+    Unsupported(Version),
+}
+
+impl Display for VersionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Format(version) => {
+                write!(
+                    f,
+                    "vk api version {version:?} is not in the major.minor format"
+                )
+            }
+            Self::Unsupported(version) => write!(
+                f,
+                "vk api version {version} is not supported, minimal is {}",
+                Version::MIN_SUPPORTED
+            ),
+        }
     }
 }
 
+impl Error for VersionError {}
+
 #[cfg(test)]
 mod tests {
     use crate::structs::{List, Version};
@@ -96,8 +385,83 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg(feature = "encode_json")]
+    fn serialize_json() {
+        use crate::structs::Json;
+
+        assert_eq!(
+            serde_urlencoded::to_string([("ids", Json(vec![1, 2]))]).unwrap(),
+            "ids=%5B1%2C2%5D".to_owned()
+        );
+    }
+
+    #[test]
+    fn serialize_params() {
+        use crate::structs::ParamValue;
+
+        assert_eq!(
+            serde_urlencoded::to_string([
+                ("ids", ParamValue::from(vec![1, 2])),
+                ("count", ParamValue::from(10)),
+                ("extended", ParamValue::from(false)),
+                ("q", ParamValue::from("vk api")),
+            ])
+            .unwrap(),
+            "ids=1%2C2&count=10&extended=0&q=vk+api".to_owned()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "encode_json")]
+    fn flatten_json_body() {
+        use crate::structs::flatten_json;
+
+        let params = flatten_json(serde_json::json!({
+            "user_ids": [1, 2],
+            "fields": "sex",
+            "offset": null,
+        }))
+        .unwrap();
+        assert_eq!(
+            serde_urlencoded::to_string(params).unwrap(),
+            "fields=sex&user_ids=1%2C2".to_owned()
+        );
+
+        let error = flatten_json(serde_json::json!({"keyboard": {"buttons": []}})).unwrap_err();
+        assert!(error.to_string().contains("`keyboard` is nested"));
+        assert!(flatten_json(serde_json::json!({"ids": [[1]]})).is_err());
+        assert!(flatten_json(serde_json::json!([1])).is_err());
+    }
+
     #[test]
     fn serialize_version() {
         assert_eq!(Version(5, 131).to_string(), "5.131".to_owned());
     }
+
+    #[test]
+    fn parse_version() {
+        use crate::structs::VersionError;
+
+        assert_eq!("5.199".parse(), Ok(Version(5, 199)));
+        assert_eq!(
+            Version::try_from("5"),
+            Err(VersionError::Format("5".to_owned()))
+        );
+        assert_eq!(
+            Version::try_from((5, 80)),
+            Err(VersionError::Unsupported(Version(5, 80)))
+        );
+        assert!(Version::try_from((6, 0)).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "encode_json")]
+    fn deserialize_version() {
+        assert_eq!(
+            serde_json::from_str::<Version>(r#""5.131""#).unwrap(),
+            Version(5, 131)
+        );
+        assert!(serde_json::from_str::<Version>(r#""5.x""#).is_err());
+    }
 }
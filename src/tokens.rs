@@ -0,0 +1,196 @@
+use crate::VkError;
+use std::fmt::{Debug, Formatter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// # Pool of the access tokens
+/// Rotates the tokens round-robin and sidelines the ones, which VK rejected with
+/// the too many requests error 6 or the method quota error 29, until their cooldown passes.
+/// Requests are repeated with the next available token.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use vkclient::{TokenPool, VkApi};
+/// # let tokens = vec![String::new(), String::new()];
+/// let pool = TokenPool::new(tokens).expect("no tokens");
+/// let client: VkApi = vkclient::VkApiBuilder::with_token_pool(pool).into();
+/// ```
+pub struct TokenPool {
+    tokens: Vec<PooledToken>,
+    next: AtomicUsize,
+    cooldown: Duration,
+    quota_cooldown: Duration,
+}
+
+struct PooledToken {
+    token: String,
+    sidelined_until: Mutex<Option<Instant>>,
+}
+
+impl TokenPool {
+    /// Creates the pool. Returns `None` for the empty tokens.
+    pub fn new<I, T>(tokens: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let tokens: Vec<_> = tokens
+            .into_iter()
+            .map(|token| PooledToken {
+                token: token.into(),
+                sidelined_until: Mutex::new(None),
+            })
+            .collect();
+
+        if tokens.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            tokens,
+            next: AtomicUsize::new(0),
+            cooldown: Duration::from_secs(1),
+            quota_cooldown: Duration::from_hours(1),
+        })
+    }
+
+    /// Pass the time, while the token is sidelined after the error 6. Default is 1 second.
+    #[must_use]
+    pub const fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Pass the time, while the token is sidelined after the method quota error 29. Default is 1 hour.
+    #[must_use]
+    pub const fn with_quota_cooldown(mut self, cooldown: Duration) -> Self {
+        self.quota_cooldown = cooldown;
+        self
+    }
+
+    /// Returns the count of the tokens.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Returns false, because the pool can't be empty.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Returns the count of the tokens, which aren't sidelined now.
+    #[must_use]
+    pub fn available(&self) -> usize {
+        let now = Instant::now();
+        self.tokens
+            .iter()
+            .filter(|token| token.available(now))
+            .count()
+    }
+
+    pub(crate) fn first(&self) -> &str {
+        self.tokens.first().map_or("", |token| &token.token)
+    }
+
+    /// Returns the token for the next request, skipping the sidelined ones.
+    /// If all tokens are sidelined, the one released first is returned.
+    pub(crate) fn acquire(&self) -> (usize, &str) {
+        self.acquire_at(Instant::now())
+    }
+
+    /// Sidelines the token, if it's rejected with the rate limit error.
+    /// Returns true if the request can be repeated with another token.
+    pub(crate) fn reject(&self, index: usize, error: &VkError) -> bool {
+        let cooldown = match error.code() {
+            VkError::TOO_MANY_REQUESTS => self.cooldown,
+            VkError::RATE_LIMIT_REACHED => self.quota_cooldown,
+            _ => return false,
+        };
+
+        let now = Instant::now();
+        if let Some(token) = self.tokens.get(index) {
+            *token
+                .sidelined_until
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(now + cooldown);
+        }
+
+        self.tokens.iter().any(|token| token.available(now))
+    }
+
+    fn acquire_at(&self, now: Instant) -> (usize, &str) {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let len = self.tokens.len();
+        let tokens = self.tokens.iter().enumerate();
+
+        tokens
+            .clone()
+            .cycle()
+            .skip(start.checked_rem(len).unwrap_or_default())
+            .take(len)
+            .find(|(_, token)| token.available(now))
+            .or_else(|| tokens.min_by_key(|(_, token)| token.sidelined_until()))
+            .map_or((0, ""), |(index, token)| (index, &token.token))
+    }
+}
+
+impl PooledToken {
+    fn sidelined_until(&self) -> Option<Instant> {
+        *self
+            .sidelined_until
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn available(&self, now: Instant) -> bool {
+        self.sidelined_until().is_none_or(|until| now >= until)
+    }
+}
+
+impl Debug for TokenPool {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenPool")
+            .field("tokens", &self.tokens.len())
+            .field("available", &self.available())
+            .field("cooldown", &self.cooldown)
+            .field("quota_cooldown", &self.quota_cooldown)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{TokenPool, VkError};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn rotate_tokens() {
+        let pool = TokenPool::new(["a", "b", "c"]).unwrap();
+        let now = Instant::now();
+
+        assert_eq!(pool.acquire_at(now).1, "a");
+        assert_eq!(pool.acquire_at(now).1, "b");
+        assert_eq!(pool.acquire_at(now).1, "c");
+        assert_eq!(pool.acquire_at(now).1, "a");
+        assert!(TokenPool::new(Vec::<String>::new()).is_none());
+    }
+
+    #[test]
+    fn sideline_rate_limited() {
+        let pool = TokenPool::new(["a", "b"])
+            .unwrap()
+            .with_cooldown(Duration::from_secs(60));
+
+        assert!(!pool.reject(0, &VkError::new(VkError::ACCESS_DENIED, "")));
+        assert!(pool.reject(0, &VkError::new(VkError::TOO_MANY_REQUESTS, "")));
+        assert_eq!(pool.available(), 1);
+        assert_eq!(pool.acquire().1, "b");
+        assert_eq!(pool.acquire().1, "b");
+
+        assert!(!pool.reject(1, &VkError::new(VkError::RATE_LIMIT_REACHED, "")));
+        assert_eq!(pool.acquire().1, "a");
+    }
+}
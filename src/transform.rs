@@ -0,0 +1,146 @@
+use crate::vkapi::DecodeStrictness;
+use crate::VkApiResult;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+/// # Response transformer
+/// Rewrites the decoded responses of the matched methods before they're deserialized into the models.
+/// Register it with [`VkApiBuilder::with_response_transformer`](crate::VkApiBuilder::with_response_transformer)
+/// to keep the shims of the different API versions in one place.
+/// Closures `Fn(&str, &mut Value)` implement it.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use vkclient::VkApi;
+/// # let access_token = String::new();
+/// let client: VkApi = vkclient::VkApiBuilder::new(access_token)
+///     .with_response_transformer("users.*", |_method: &str, response: &mut serde_json::Value| {
+///         for user in response.as_array_mut().into_iter().flatten() {
+///             if let Some(user) = user.as_object_mut() {
+///                 if let Some(id) = user.remove("uid") {
+///                     user.insert("id".to_owned(), id);
+///                 }
+///             }
+///         }
+///     })
+///     .into();
+/// ```
+pub trait ResponseTransformer: Send + Sync {
+    /// Rewrites the response of the method in place.
+    fn transform(&self, method: &str, response: &mut Value);
+}
+
+impl<F> ResponseTransformer for F
+where
+    F: Fn(&str, &mut Value) + Send + Sync,
+{
+    fn transform(&self, method: &str, response: &mut Value) {
+        self(method, response);
+    }
+}
+
+/// Pattern of the method names: the exact name, the prefix ending with `*` like `messages.*`, or `*` for all methods.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum MethodPattern {
+    Exact(String),
+    Prefix(String),
+}
+
+impl MethodPattern {
+    fn new(pattern: String) -> Self {
+        match pattern.strip_suffix('*') {
+            Some(prefix) => Self::Prefix(prefix.to_owned()),
+            None => Self::Exact(pattern),
+        }
+    }
+
+    fn matches(&self, method: &str) -> bool {
+        match self {
+            Self::Exact(name) => name == method,
+            Self::Prefix(prefix) => method.starts_with(prefix.as_str()),
+        }
+    }
+}
+
+/// Registered transformers in the order of registration.
+#[derive(Clone, Default)]
+pub struct Transformers(Vec<(MethodPattern, Arc<dyn ResponseTransformer>)>);
+
+impl Transformers {
+    pub(crate) fn push(&mut self, pattern: String, transformer: Arc<dyn ResponseTransformer>) {
+        self.0.push((MethodPattern::new(pattern), transformer));
+    }
+
+    /// Returns true if any transformer matches the method.
+    pub(crate) fn matches(&self, method: &str) -> bool {
+        self.0.iter().any(|(pattern, _)| pattern.matches(method))
+    }
+
+    /// Applies all matched transformers in order and deserializes the result.
+    pub(crate) fn apply<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        mut response: Value,
+        strictness: DecodeStrictness,
+    ) -> VkApiResult<T> {
+        for (pattern, transformer) in &self.0 {
+            if pattern.matches(method) {
+                transformer.transform(method, &mut response);
+            }
+        }
+
+        crate::inner::from_value(response, strictness)
+    }
+}
+
+impl Debug for Transformers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.0.iter().map(|(pattern, _)| pattern))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transform::{MethodPattern, Transformers};
+    use crate::DecodeStrictness;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+
+    #[test]
+    fn match_patterns() {
+        assert!(MethodPattern::new("users.get".to_owned()).matches("users.get"));
+        assert!(!MethodPattern::new("users.get".to_owned()).matches("users.getFollowers"));
+        assert!(MethodPattern::new("users.*".to_owned()).matches("users.getFollowers"));
+        assert!(!MethodPattern::new("users.*".to_owned()).matches("groups.get"));
+        assert!(MethodPattern::new("*".to_owned()).matches("groups.get"));
+    }
+
+    #[test]
+    fn apply_in_order() {
+        let mut transformers = Transformers::default();
+        transformers.push(
+            "users.*".to_owned(),
+            Arc::new(|_: &str, response: &mut Value| response["id"] = response["uid"].take()),
+        );
+        transformers.push(
+            "*".to_owned(),
+            Arc::new(|method: &str, response: &mut Value| {
+                response["method"] = Value::from(method);
+            }),
+        );
+
+        let response: Value = transformers
+            .apply("users.get", json!({"uid": 1}), DecodeStrictness::Lenient)
+            .unwrap();
+        assert_eq!(
+            response,
+            json!({"uid": null, "id": 1, "method": "users.get"})
+        );
+        assert!(transformers.matches("groups.get"));
+        assert!(!Transformers::default().matches("groups.get"));
+    }
+}
@@ -1,18 +1,27 @@
-use crate::inner::{create_client, uncompress};
-use crate::{VkApiError, VkApiResult};
-use bytes::{Buf, BufMut, BytesMut};
-use cfg_if::cfg_if;
-use reqwest::header::{ACCEPT, ACCEPT_ENCODING, CONTENT_ENCODING};
+use crate::bulk::or_cancel;
+use crate::inner::{client_builder, create_client, uncompress, ClientConfig};
+use crate::server::ServerConfig;
+use crate::{BuildError, Certificate, Compression, VkApi, VkApiError, VkApiResult};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use reqwest::header::{ACCEPT, ACCEPT_ENCODING, CONTENT_ENCODING, LOCATION};
 pub use reqwest::multipart::Form;
-use reqwest::Client;
+use reqwest::multipart::Part;
+use reqwest::{redirect, Client, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
 use std::io::Read;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /// # Upload files to VK Uploader Servers
 /// Firstly you need to get any uploader server from VK API.
 /// As example [photos.getUploadServer](https://dev.vk.com/method/photos.getUploadServer).
 /// Then you can use this struct to upload files.
 /// Example:
-/// ```rust
+/// ```rust,no_run
 /// use vkclient::upload::{Form, VkUploader};
 /// let uploader = VkUploader::default();
 ///
@@ -21,74 +30,1169 @@ use std::io::Read;
 ///
 /// async {
 ///     let response: String = uploader.upload(url, form).await.expect("uploading error");
-/// }
+/// };
 /// ```
 ///
-/// ```rust
+/// ```rust,no_run
 /// use vkclient::VkApi;
+/// # let access_token = String::new();
 /// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
 ///
 /// let uploader = client.uploader();
 /// ```
 ///
+/// Use [`VkUploaderBuilder`] to configure the standalone client.
+///
 /// [Read more about uploads](https://dev.vk.com/api/upload).
 #[derive(Clone, Debug)]
 pub struct VkUploader {
     client: Client,
+    config: ServerConfig,
+    /// Redirects are followed by the http client, not by the uploader
+    client_redirects: bool,
 }
 
+/// Count of the redirects, after which the last response is returned.
+const MAX_REDIRECTS: usize = 10;
+/// Length of the file beginning, which is enough to detect its media type.
+#[cfg(feature = "upload_file")]
+const MEDIA_TYPE_HEAD_LEN: usize = 16;
+/// Length of the response, which is kept as the error message of the failed status.
+const MAX_ERROR_MESSAGE_CHARS: usize = 512;
+
 impl VkUploader {
+    /// Creates the builder of the standalone client.
+    #[must_use]
+    pub fn builder() -> VkUploaderBuilder {
+        VkUploaderBuilder::default()
+    }
+
+    /// Pass the timeout of the whole upload request. Default is unlimited.
+    #[must_use]
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = Some(timeout);
+        self
+    }
+
+    /// Pass the recorder of the requests metrics.
+    #[must_use]
+    pub fn with_metrics<M: crate::MetricsRecorder + 'static>(mut self, metrics: M) -> Self {
+        self.config.metrics = Some(Arc::new(metrics));
+        self
+    }
+
     /// Upload any form to given url.
-    /// Supports gzip encoding for responses.
+    /// Supports compression of the responses.
     /// Returns String, which must be passed to VK save file API.
+    /// Error responses of the server are returned as [`VkApiError::Upload`].
     pub async fn upload<U: AsRef<str> + Send>(&self, url: U, form: Form) -> VkApiResult<String> {
-        cfg_if! {
-            if #[cfg(feature = "compression_gzip")] {
-                let encoding ="gzip";
-            } else {
-                let encoding ="identity";
+        self.upload_with_redirects(url, form, true).await
+    }
+
+    /// Upload the file from disk to given url like [`VkUploader::upload`] in the `field_name` of the form.
+    /// The file is streamed without loading it into memory, its name is taken from the path
+    /// and its media type is detected by the content or guessed by the extension.
+    ///
+    /// ## Usage:
+    /// ```rust,no_run
+    /// use vkclient::upload::VkUploader;
+    /// let uploader = VkUploader::default();
+    ///
+    /// async {
+    ///     let response = uploader
+    ///         .upload_file("https://...", "video_file", "/tmp/video.mp4")
+    ///         .await
+    ///         .expect("uploading error");
+    /// };
+    /// ```
+    #[cfg(feature = "upload_file")]
+    pub async fn upload_file<U, F, P>(&self, url: U, field_name: F, path: P) -> VkApiResult<String>
+    where
+        U: AsRef<str> + Send,
+        F: Into<Cow<'static, str>>,
+        P: AsRef<std::path::Path>,
+    {
+        let part = file_part(path.as_ref()).await?;
+        self.upload(url, Form::new().part(field_name, part)).await
+    }
+
+    /// Upload the form to given url like [`VkUploader::upload`] and repeat it with the backoff delays,
+    /// while the upload fails with the retryable errors, like timeouts or server errors.
+    /// The form is rebuilt for each attempt, because the sent one can't be reused.
+    ///
+    /// ## Usage:
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use vkclient::upload::{FormBuilder, VkUploader};
+    /// use vkclient::Backoff;
+    /// let uploader = VkUploader::default();
+    ///
+    /// let form = FormBuilder::new().with_bytes("photo", "cat.png", b"\x89PNG\r\n\x1a\n".to_vec());
+    /// let backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(10)).with_max_retries(3);
+    ///
+    /// async move {
+    ///     let response = uploader
+    ///         .upload_with_retry("https://...", &form, backoff)
+    ///         .await
+    ///         .expect("uploading error");
+    /// };
+    /// ```
+    pub async fn upload_with_retry<U: AsRef<str> + Send>(
+        &self,
+        url: U,
+        form: &FormBuilder,
+        backoff: crate::Backoff,
+    ) -> VkApiResult<String> {
+        match self
+            .upload_with_retry_until(url, form, backoff, std::future::pending::<()>())
+            .await
+        {
+            Some(result) => result,
+            None => unreachable!("pending future never completes"),
+        }
+    }
+
+    /// Same as [`VkUploader::upload_with_retry`], but stops the upload and the retries
+    /// when the `cancel` future completes, like `CancellationToken::cancelled()` or `tokio::time::sleep`.
+    /// Returns `None` if the upload is cancelled.
+    pub async fn upload_with_retry_until<U: AsRef<str> + Send, C: std::future::Future>(
+        &self,
+        url: U,
+        form: &FormBuilder,
+        backoff: crate::Backoff,
+        cancel: C,
+    ) -> Option<VkApiResult<String>> {
+        let url = url.as_ref();
+        let mut cancel = std::pin::pin!(cancel);
+        let mut failures = 0;
+
+        loop {
+            let upload = async { self.upload(url, form.build().await?).await };
+            match or_cancel(cancel.as_mut(), upload).await? {
+                Err(e) if e.is_retryable() => {
+                    failures += 1;
+                    match backoff.delay(failures) {
+                        Some(delay) => {
+                            or_cancel(cancel.as_mut(), tokio::time::sleep(delay)).await?;
+                        }
+                        None => return Some(Err(e)),
+                    }
+                }
+                result => return Some(result),
             }
         }
+    }
+
+    /// Upload any form to given url like [`VkUploader::upload`] and decode the json response.
+    /// Upload servers don't always send the json content type, so the body is decoded as json regardless of it.
+    ///
+    /// ## Usage:
+    /// ```rust,no_run
+    /// use vkclient::upload::{Form, PhotoUploadResponse, VkUploader};
+    /// let uploader = VkUploader::default();
+    ///
+    /// async {
+    ///     let response: PhotoUploadResponse = uploader
+    ///         .upload_json("https://...", Form::new())
+    ///         .await
+    ///         .expect("uploading error");
+    /// };
+    /// ```
+    #[cfg(feature = "encode_json")]
+    pub async fn upload_json<T: DeserializeOwned, U: AsRef<str> + Send>(
+        &self,
+        url: U,
+        form: Form,
+    ) -> VkApiResult<T> {
+        decode_json(&self.upload(url, form).await?)
+    }
+
+    /// Upload any form to given url like [`VkUploader::upload`] and choose whether to follow
+    /// the `301`, `302` and `303` redirects of the upload server with the `GET` requests.
+    /// The body of the redirect is returned as is when they aren't followed.
+    ///
+    /// Redirects are followed per call by the clients of [`VkUploaderBuilder`] only,
+    /// the targets are checked with the allowlist and get the cookies of the cookie store.
+    /// Other clients follow the redirects by the http client policy.
+    pub async fn upload_with_redirects<U: AsRef<str> + Send>(
+        &self,
+        url: U,
+        form: Form,
+        follow_redirects: bool,
+    ) -> VkApiResult<String> {
+        let url = url.as_ref();
+        self.config.check_host(url)?;
 
         let req = self
             .client
-            .post(url.as_ref())
-            .header(ACCEPT_ENCODING, encoding)
+            .post(url)
+            .header(ACCEPT_ENCODING, self.config.accept_encoding())
             .header(ACCEPT, "application/json")
             .multipart(form);
+        let req = match self.config.timeout {
+            Some(timeout) => req.timeout(timeout),
+            None => req,
+        };
 
-        let mut response = req.send().await.map_err(VkApiError::Request)?;
-        let conent_length = response.content_length();
+        let started = Instant::now();
+        let mut bytes = 0;
+        let result = async {
+            let mut response = req.send().await.map_err(VkApiError::Request)?;
+            if follow_redirects && !self.client_redirects {
+                response = self.follow_redirects(response).await?;
+            }
+            let status = response.status();
+            let conent_length = response.content_length();
 
-        let mut body = BytesMut::with_capacity(conent_length.unwrap_or_default() as usize);
-        while let Some(buf) = response.chunk().await.map_err(VkApiError::Request)? {
-            body.put(buf)
-        }
+            let mut body = BytesMut::with_capacity(conent_length.unwrap_or_default() as usize);
+            while let Some(buf) = response.chunk().await.map_err(VkApiError::Request)? {
+                body.put(buf);
+            }
+            bytes = body.len();
+
+            let headers = response.headers();
+
+            let content_encoding = headers.get(CONTENT_ENCODING);
+
+            let mut body = uncompress(
+                content_encoding,
+                body.reader(),
+                self.config.compression.zstd_window_log_max(),
+            )?;
+
+            let mut response = String::new();
 
-        let headers = response.headers();
+            body.read_to_string(&mut response).map_err(VkApiError::IO)?;
 
-        let content_encoding = headers.get(CONTENT_ENCODING);
+            match UploadServerError::detect(status, &response) {
+                Some(error) => Err(VkApiError::Upload(error)),
+                None => Ok(response),
+            }
+        }
+        .await;
+
+        self.config.record(url, started, bytes, &result);
+        result
+    }
 
-        let mut body = uncompress(content_encoding, body.reader())?;
+    async fn follow_redirects(
+        &self,
+        mut response: reqwest::Response,
+    ) -> VkApiResult<reqwest::Response> {
+        for _ in 0..MAX_REDIRECTS {
+            if !matches!(
+                response.status(),
+                StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND | StatusCode::SEE_OTHER
+            ) {
+                break;
+            }
 
-        let mut response = String::new();
+            let Some(location) = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .and_then(|location| response.url().join(location).ok())
+            else {
+                break;
+            };
+            self.config.check_host(location.as_str())?;
 
-        body.read_to_string(&mut response).map_err(VkApiError::IO)?;
+            let req = self
+                .client
+                .get(location)
+                .header(ACCEPT_ENCODING, self.config.accept_encoding())
+                .header(ACCEPT, "application/json");
+            let req = match self.config.timeout {
+                Some(timeout) => req.timeout(timeout),
+                None => req,
+            };
+            response = req.send().await.map_err(VkApiError::Request)?;
+        }
 
         Ok(response)
     }
+
+    /// Uploads the form and saves the file with the VK API method.
+    /// `save_params` builds the params of the save method from the upload response.
+    /// Errors contain the failed step, use [`UploadError::is_retryable`] to decide on repeating the upload.
+    ///
+    /// ## Usage:
+    /// ```rust,no_run
+    /// use vkclient::upload::{Form, PhotoUploadResponse};
+    /// use vkclient::VkApi;
+    /// # let access_token = String::new();
+    /// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+    ///
+    /// async {
+    ///     let photos: serde_json::Value = client
+    ///         .uploader()
+    ///         .upload_and_save(&client, "https://...", Form::new(), "photos.saveMessagesPhoto", |response| {
+    ///             serde_json::from_str::<PhotoUploadResponse>(&response).unwrap()
+    ///         })
+    ///         .await
+    ///         .unwrap();
+    /// };
+    /// ```
+    pub async fn upload_and_save<T, P, U, F>(
+        &self,
+        client: &VkApi,
+        url: U,
+        form: Form,
+        save_method: &str,
+        save_params: F,
+    ) -> Result<T, UploadError>
+    where
+        T: DeserializeOwned,
+        P: Serialize + Send,
+        U: AsRef<str> + Send,
+        F: FnOnce(String) -> P,
+    {
+        let response = self.upload(url, form).await.map_err(|error| UploadError {
+            step: UploadStep::Upload,
+            error,
+        })?;
+
+        client
+            .send_request(save_method, save_params(response))
+            .await
+            .map_err(|error| UploadError {
+                step: UploadStep::Save,
+                error,
+            })
+    }
+}
+
+/// Response of the photo upload servers, which is passed to the photos save methods as is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhotoUploadResponse {
+    pub server: i64,
+    pub photo: String,
+    pub hash: String,
+}
+
+/// # Rebuildable multipart form
+/// Keeps the sources of the form parts, so the form can be built again for the retries of the upload.
+/// Contents are shared by the clones and the built forms without copying.
+///
+/// ## Usage:
+/// ```rust
+/// use vkclient::upload::FormBuilder;
+///
+/// let form = FormBuilder::new()
+///     .with_text("caption", "cat")
+///     .with_bytes("photo", "cat.png", b"\x89PNG\r\n\x1a\n".to_vec());
+///
+/// async move {
+///     let first = form.build().await.unwrap();
+///     let retry = form.build().await.unwrap();
+/// };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FormBuilder {
+    parts: Vec<(Cow<'static, str>, PartSource)>,
+}
+
+#[derive(Debug, Clone)]
+enum PartSource {
+    Text(String),
+    File(UploadSource),
+}
+
+impl FormBuilder {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { parts: Vec::new() }
+    }
+
+    /// Adds the text field.
+    #[must_use]
+    pub fn with_text<N: Into<Cow<'static, str>>, V: Into<String>>(
+        mut self,
+        name: N,
+        value: V,
+    ) -> Self {
+        self.parts
+            .push((name.into(), PartSource::Text(value.into())));
+        self
+    }
+
+    /// Adds the file from memory, its media type is detected by the content.
+    #[must_use]
+    pub fn with_bytes<N, F, C>(self, name: N, file_name: F, content: C) -> Self
+    where
+        N: Into<Cow<'static, str>>,
+        F: Into<String>,
+        C: Into<Bytes>,
+    {
+        self.with_source(name, UploadSource::bytes(file_name, content))
+    }
+
+    /// Adds the file from disk, which is streamed like in [`VkUploader::upload_file`] on each build.
+    #[cfg(feature = "upload_file")]
+    #[must_use]
+    pub fn with_file<N, P>(self, name: N, path: P) -> Self
+    where
+        N: Into<Cow<'static, str>>,
+        P: Into<std::path::PathBuf>,
+    {
+        self.with_source(name, UploadSource::file(path))
+    }
+
+    /// Adds the file from the source.
+    #[must_use]
+    pub fn with_source<N: Into<Cow<'static, str>>>(
+        mut self,
+        name: N,
+        source: UploadSource,
+    ) -> Self {
+        self.parts.push((name.into(), PartSource::File(source)));
+        self
+    }
+
+    /// Builds the form, files from disk are opened again.
+    pub async fn build(&self) -> VkApiResult<Form> {
+        let mut form = Form::new();
+
+        for (name, source) in &self.parts {
+            form = match source {
+                PartSource::Text(value) => form.text(name.clone(), value.clone()),
+                PartSource::File(source) => form.part(name.clone(), source.part().await?),
+            };
+        }
+
+        Ok(form)
+    }
+}
+
+/// File of the upload from memory or from disk.
+#[derive(Debug, Clone)]
+pub enum UploadSource {
+    Bytes {
+        file_name: String,
+        content: Bytes,
+    },
+    /// File from disk, which is streamed without loading it into memory
+    #[cfg(feature = "upload_file")]
+    File(std::path::PathBuf),
+}
+
+impl UploadSource {
+    /// Creates the file from memory.
+    pub fn bytes<F: Into<String>, C: Into<Bytes>>(file_name: F, content: C) -> Self {
+        Self::Bytes {
+            file_name: file_name.into(),
+            content: content.into(),
+        }
+    }
+
+    /// Creates the file from disk, its name is taken from the path.
+    #[cfg(feature = "upload_file")]
+    pub fn file<P: Into<std::path::PathBuf>>(path: P) -> Self {
+        Self::File(path.into())
+    }
+
+    /// Checks the size and the media type of the file in memory or the size of the file on disk
+    /// against the limits of the kind.
+    pub async fn check(&self, kind: FileKind) -> VkApiResult<()> {
+        match self {
+            Self::Bytes { content, .. } => {
+                kind.check_size(content.len() as u64)?;
+                kind.check_media_type(content).map(drop)
+            }
+            #[cfg(feature = "upload_file")]
+            Self::File(path) => {
+                let metadata = tokio::fs::metadata(path).await.map_err(VkApiError::IO)?;
+                kind.check_size(metadata.len())
+            }
+        }
+    }
+
+    /// Returns the form part of the file with the media type detected by the content.
+    async fn part(&self) -> VkApiResult<Part> {
+        match self {
+            Self::Bytes { file_name, content } => {
+                let part = Part::stream_with_length(content.clone(), content.len() as u64)
+                    .file_name(file_name.clone());
+                match media_type(content) {
+                    Some(media_type) => part.mime_str(media_type).map_err(VkApiError::Request),
+                    None => Ok(part),
+                }
+            }
+            #[cfg(feature = "upload_file")]
+            Self::File(path) => file_part(path).await,
+        }
+    }
+}
+
+/// Returns the streamed part of the file from disk with the name taken from the path
+/// and the media type detected by the content or guessed by the extension.
+#[cfg(feature = "upload_file")]
+async fn file_part(path: &std::path::Path) -> VkApiResult<Part> {
+    use tokio::io::AsyncReadExt;
+
+    let mut head = Vec::with_capacity(MEDIA_TYPE_HEAD_LEN);
+    tokio::fs::File::open(path)
+        .await
+        .map_err(VkApiError::IO)?
+        .take(MEDIA_TYPE_HEAD_LEN as u64)
+        .read_to_end(&mut head)
+        .await
+        .map_err(VkApiError::IO)?;
+
+    let part = Part::file(path).await.map_err(VkApiError::IO)?;
+    match media_type(&head) {
+        Some(media_type) => part.mime_str(media_type).map_err(VkApiError::Request),
+        None => Ok(part),
+    }
+}
+
+/// Kind of the uploaded file with its [size limit](https://dev.vk.com/api/upload).
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum FileKind {
+    /// Photo up to 50 MB
+    Photo,
+    /// Document up to 200 MB
+    Doc,
+    /// Audio up to 200 MB
+    Audio,
+    /// Video up to 256 GB
+    Video,
+}
+
+impl FileKind {
+    /// Returns the maximum size of the files in bytes.
+    #[must_use]
+    pub const fn max_size(self) -> u64 {
+        const MB: u64 = 1024 * 1024;
+
+        match self {
+            Self::Photo => 50 * MB,
+            Self::Doc | Self::Audio => 200 * MB,
+            Self::Video => 256 * 1024 * MB,
+        }
+    }
+
+    /// Returns the media types of the files, which VK accepts, or `None` if any type is accepted.
+    #[must_use]
+    pub const fn media_types(self) -> Option<&'static [&'static str]> {
+        match self {
+            Self::Photo => Some(&["image/jpeg", "image/png", "image/gif"]),
+            Self::Audio => Some(&["audio/mpeg"]),
+            Self::Video => Some(&[
+                "video/mp4",
+                "video/quicktime",
+                "video/webm",
+                "video/x-msvideo",
+            ]),
+            Self::Doc => None,
+        }
+    }
+
+    /// Returns the [`VkApiError::UnsupportedMediaType`] if the type of the content isn't accepted.
+    /// Content of the unknown type is rejected, when the kind accepts only the listed types.
+    pub fn check_media_type(self, content: &[u8]) -> VkApiResult<Option<&'static str>> {
+        let media_type = media_type(content);
+
+        match self.media_types() {
+            Some(accepted)
+                if !media_type.is_some_and(|media_type| accepted.contains(&media_type)) =>
+            {
+                Err(VkApiError::UnsupportedMediaType(UnsupportedMediaType {
+                    kind: self,
+                    media_type,
+                }))
+            }
+            _ => Ok(media_type),
+        }
+    }
+
+    /// Returns the [`VkApiError::FileTooLarge`] if the size exceeds the limit.
+    pub const fn check_size(self, size: u64) -> VkApiResult<()> {
+        if size > self.max_size() {
+            return Err(VkApiError::FileTooLarge(FileTooLarge {
+                kind: self,
+                size,
+                limit: self.max_size(),
+            }));
+        }
+        Ok(())
+    }
+
+    /// Builds the multipart form with the file in the `field`, checking its size and type before the upload.
+    /// The media type of the part is detected by the content.
+    ///
+    /// ## Usage:
+    /// ```rust
+    /// use vkclient::upload::FileKind;
+    ///
+    /// let png = b"\x89PNG\r\n\x1a\n".to_vec();
+    /// let form = FileKind::Photo.form("photo", "cat.png", png).unwrap();
+    /// assert!(FileKind::Photo.form("photo", "cat.pdf", b"%PDF-1.7".to_vec()).is_err());
+    /// ```
+    pub fn form<N, C>(self, field: &'static str, file_name: N, content: C) -> VkApiResult<Form>
+    where
+        N: Into<String>,
+        C: Into<Vec<u8>>,
+    {
+        let content = content.into();
+        self.check_size(content.len() as u64)?;
+        let media_type = self.check_media_type(&content)?;
+
+        let part = reqwest::multipart::Part::bytes(content).file_name(file_name.into());
+        let part = match media_type {
+            Some(media_type) => part.mime_str(media_type).map_err(VkApiError::Request)?,
+            None => part,
+        };
+        Ok(Form::new().part(field, part))
+    }
+}
+
+impl Display for FileKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Photo => write!(f, "photo"),
+            Self::Doc => write!(f, "document"),
+            Self::Audio => write!(f, "audio"),
+            Self::Video => write!(f, "video"),
+        }
+    }
+}
+
+/// Returns the media type of the content by its magic bytes, or `None` if the type is unknown.
+#[must_use]
+pub fn media_type(content: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: [(&[u8], &str); 9] = [
+        (b"\xFF\xD8\xFF", "image/jpeg"),
+        (b"\x89PNG\r\n\x1A\n", "image/png"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"ID3", "audio/mpeg"),
+        (b"\xFF\xFB", "audio/mpeg"),
+        (b"\x1A\x45\xDF\xA3", "video/webm"),
+    ];
+
+    if let Some((_, media_type)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| content.starts_with(signature))
+    {
+        return Some(media_type);
+    }
+
+    match (content.get(..4), content.get(4..8), content.get(8..12)) {
+        (Some(b"RIFF"), _, Some(b"WEBP")) => Some("image/webp"),
+        (Some(b"RIFF"), _, Some(b"AVI ")) => Some("video/x-msvideo"),
+        (_, Some(b"ftyp"), Some(b"qt  ")) => Some("video/quicktime"),
+        (_, Some(b"ftyp"), _) => Some("video/mp4"),
+        _ => None,
+    }
+}
+
+/// File, which is rejected before the upload, because its type isn't accepted by its kind.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct UnsupportedMediaType {
+    pub kind: FileKind,
+    /// Detected type of the file, `None` if it's unknown
+    pub media_type: Option<&'static str>,
+}
+
+impl Display for UnsupportedMediaType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of the {} type isn't supported",
+            self.kind,
+            self.media_type.unwrap_or("unknown")
+        )
+    }
+}
+
+/// File, which is rejected before the upload, because it exceeds the size limit of its kind.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct FileTooLarge {
+    pub kind: FileKind,
+    /// Size of the file in bytes
+    pub size: u64,
+    /// Maximum size of the kind in bytes
+    pub limit: u64,
+}
+
+impl Display for FileTooLarge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of {} bytes exceeds the limit of {} bytes",
+            self.kind, self.size, self.limit
+        )
+    }
+}
+
+/// Error response of the upload server, like `{"error": "..."}`, the html page or the failed status.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UploadServerError {
+    pub status: StatusCode,
+    /// Error text of the server, or the beginning of the response if there is no text
+    pub message: String,
+}
+
+impl UploadServerError {
+    /// Returns the error of the response, or `None` if it isn't the failure.
+    fn detect(status: StatusCode, response: &str) -> Option<Self> {
+        let message =
+            if let Some(error) = crate::error_payload::parse_upload_error(response.as_bytes()) {
+                error
+            } else if response.trim_start().starts_with('<') {
+                html_title(response)
+                    .unwrap_or("html page returned")
+                    .to_owned()
+            } else if status.is_client_error() || status.is_server_error() {
+                response.chars().take(MAX_ERROR_MESSAGE_CHARS).collect()
+            } else {
+                return None;
+            };
+
+        Some(Self { status, message })
+    }
+}
+
+impl Display for UploadServerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "upload server rejected the file with status {}: {}",
+            self.status, self.message
+        )
+    }
+}
+
+impl Error for UploadServerError {}
+
+/// Returns the title of the html page, like the one of the nginx error pages.
+fn html_title(page: &str) -> Option<&str> {
+    let start = page.find("<title>")? + "<title>".len();
+    let end = page[start..].find("</title>")? + start;
+    Some(page[start..end].trim()).filter(|title| !title.is_empty())
+}
+
+/// Document saved with [docs.save](https://dev.vk.com/method/docs.save).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Doc {
+    pub id: i64,
+    pub owner_id: i64,
+    pub title: String,
+    /// Size in bytes.
+    pub size: u64,
+    pub ext: String,
+    pub url: String,
+    pub date: i64,
+    /// 1 is text, 2 is archive, 3 is gif, 4 is image, 5 is audio, 6 is video, 7 is e-book, 8 is unknown.
+    #[serde(rename = "type")]
+    pub doc_type: u8,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub access_key: Option<String>,
+}
+
+impl Doc {
+    /// Returns the attachment of the messages and the posts in the `doc{owner_id}_{id}_{access_key}` format.
+    #[must_use]
+    pub fn attachment(&self) -> String {
+        match &self.access_key {
+            Some(access_key) => format!("doc{}_{}_{access_key}", self.owner_id, self.id),
+            None => format!("doc{}_{}", self.owner_id, self.id),
+        }
+    }
+}
+
+/// Response of [docs.save](https://dev.vk.com/method/docs.save).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedDoc {
+    pub doc: Doc,
+}
+
+/// Upload server of the files, which is returned by the `*.get*UploadServer` methods.
+#[cfg(feature = "encode_json")]
+#[derive(Deserialize)]
+pub(crate) struct UploadServer {
+    pub(crate) upload_url: String,
+}
+
+/// Params of [docs.save](https://dev.vk.com/method/docs.save).
+#[cfg(feature = "encode_json")]
+#[derive(Serialize)]
+pub(crate) struct SaveDoc<'a> {
+    pub(crate) file: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub(crate) title: String,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    pub(crate) tags: &'a str,
+}
+
+/// Decodes the json response of the upload server.
+#[cfg(feature = "encode_json")]
+fn decode_json<T: DeserializeOwned>(response: &str) -> VkApiResult<T> {
+    crate::inner::decode(
+        Some(&reqwest::header::HeaderValue::from_static(
+            "application/json",
+        )),
+        response.as_bytes(),
+        crate::DecodeStrictness::Lenient,
+    )
+}
+
+/// Extracts the `file` field from the response of the document upload servers.
+/// Upload servers return `{"error": "..."}` instead of it for the rejected files.
+#[cfg(feature = "encode_json")]
+pub(crate) fn uploaded_file(response: &str) -> VkApiResult<String> {
+    #[derive(Deserialize)]
+    struct Uploaded {
+        file: Option<String>,
+        error: Option<String>,
+    }
+
+    let uploaded: Uploaded = serde_json::from_str(response)
+        .map_err(|e| VkApiError::ResponseDeserialize(crate::ResponseDeserialize::Json(e)))?;
+
+    match uploaded {
+        Uploaded {
+            file: Some(file), ..
+        } => Ok(file),
+        Uploaded { error, .. } => Err(VkApiError::Upload(UploadServerError {
+            status: StatusCode::OK,
+            message: error.unwrap_or_else(|| "no file in response".to_owned()),
+        })),
+    }
+}
+
+/// Step of the upload flow.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum UploadStep {
+    /// Getting the upload server with the VK API method
+    Server,
+    /// Uploading the file to the upload server
+    Upload,
+    /// Saving the uploaded file with the VK API method
+    Save,
+}
+
+impl Display for UploadStep {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Server => write!(f, "server"),
+            Self::Upload => write!(f, "upload"),
+            Self::Save => write!(f, "save"),
+        }
+    }
+}
+
+/// Error of the upload flow with the failed step.
+#[derive(Debug)]
+pub struct UploadError {
+    pub step: UploadStep,
+    pub error: VkApiError,
+}
+
+impl UploadError {
+    /// Returns true if the upload may succeed when it's repeated from the start,
+    /// like after timeouts, server errors or flood control.
+    /// Definitive failures, like an invalid hash or a bad file, return false.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        self.error.is_retryable()
+    }
+}
+
+impl Display for UploadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} step failed: {}", self.step, self.error)
+    }
+}
+
+impl Error for UploadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
 }
 
 impl From<Client> for VkUploader {
     fn from(client: Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            config: ServerConfig::default(),
+            client_redirects: true,
+        }
     }
 }
 
 impl Default for VkUploader {
+    /// # Panics
+    /// Panics if the http client can't be built, use the client of [`VkApi`](crate::VkApi) to avoid it.
     fn default() -> Self {
-        Self {
-            client: create_client(),
+        Self::from(create_client(&ClientConfig::default()).expect("http client can't be built"))
+    }
+}
+
+/// # Builder of the uploader client
+/// Configures the standalone [`VkUploader`], the one of [`VkApi::uploader`]
+/// shares the connection pool and the timeout of the API client.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use vkclient::upload::VkUploader;
+///
+/// let uploader = VkUploader::builder()
+///     .with_timeout(Duration::from_secs(60))
+///     .with_allowed_hosts(["vk.com", "userapi.com"])
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct VkUploaderBuilder {
+    client: Option<Client>,
+    client_config: ClientConfig,
+    config: ServerConfig,
+    #[cfg(feature = "upload_cookies")]
+    cookie_store: bool,
+}
+
+impl VkUploaderBuilder {
+    /// Pass the http client, which replaces the one built from the connection settings.
+    /// Redirects and cookies are handled by its own policy then.
+    #[must_use]
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Pass root certificates, which replace the built-in root set of the client.
+    #[must_use]
+    pub fn with_root_certificates(mut self, root_certificates: Vec<Certificate>) -> Self {
+        self.client_config.root_certificates = root_certificates;
+        self
+    }
+
+    /// Pass the timeout of establishing the connection.
+    #[must_use]
+    pub const fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.client_config.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Enables the cookie store, which is shared by the uploads of the client.
+    /// Keeps the session affinity of the upload servers, which set cookies before the redirects.
+    #[cfg(feature = "upload_cookies")]
+    #[must_use]
+    pub const fn with_cookie_store(mut self) -> Self {
+        self.cookie_store = true;
+        self
+    }
+
+    /// Pass the timeout of the whole upload request. Default is unlimited.
+    #[must_use]
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = Some(timeout);
+        self
+    }
+
+    /// Pass the compression of the responses. Default is `Compression::Gzip` if it's enabled.
+    #[must_use]
+    pub const fn with_compression(mut self, compression: Compression) -> Self {
+        self.config.compression = compression;
+        self
+    }
+
+    /// Pass the hosts of the upload servers, which the files are allowed to be sent to.
+    /// Subdomains of the hosts are allowed too. Default is any host.
+    #[must_use]
+    pub fn with_allowed_hosts<I, S>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.allowed_hosts = Some(hosts.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Pass the recorder of the requests metrics.
+    #[must_use]
+    pub fn with_metrics<M: crate::MetricsRecorder + 'static>(mut self, metrics: M) -> Self {
+        self.config.metrics = Some(Arc::new(metrics));
+        self
+    }
+
+    /// Builds the client.
+    pub fn build(self) -> Result<VkUploader, BuildError> {
+        if let Some(client) = self.client {
+            return Ok(VkUploader {
+                client,
+                config: self.config,
+                client_redirects: true,
+            });
         }
+
+        let builder = client_builder(&self.client_config).redirect(redirect::Policy::none());
+        #[cfg(feature = "upload_cookies")]
+        let builder = builder.cookie_store(self.cookie_store);
+
+        Ok(VkUploader {
+            client: builder.build().map_err(BuildError::Client)?,
+            config: self.config,
+            client_redirects: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::upload::{UploadError, UploadStep};
+    use crate::{VkApiError, VkError};
+
+    fn save_error(code: i16) -> UploadError {
+        UploadError {
+            step: UploadStep::Save,
+            error: VkApiError::Vk(VkError::new(code, "error")),
+        }
+    }
+
+    #[test]
+    fn retryable_save_errors() {
+        assert!(save_error(10).is_retryable());
+        assert!(save_error(9).is_retryable());
+        assert!(!save_error(121).is_retryable());
+        assert_eq!(
+            save_error(121).to_string(),
+            "save step failed: vk api error occurred. Code: 121, message: error"
+        );
+    }
+
+    #[test]
+    fn detect_media_types() {
+        use crate::upload::{media_type, FileKind};
+
+        assert_eq!(media_type(b"\xFF\xD8\xFF\xE0"), Some("image/jpeg"));
+        assert_eq!(media_type(b"\0\0\0\x18ftypmp42"), Some("video/mp4"));
+        assert_eq!(media_type(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(media_type(b"plain text"), None);
+
+        assert!(FileKind::Doc.check_media_type(b"plain text").is_ok());
+        assert!(matches!(
+            FileKind::Photo.check_media_type(b"RIFF\0\0\0\0WEBPVP8 "),
+            Err(VkApiError::UnsupportedMediaType(e)) if e.media_type == Some("image/webp")
+        ));
+    }
+
+    #[tokio::test]
+    async fn rebuild_forms() {
+        use crate::upload::{FormBuilder, VkUploader};
+        use crate::{Backoff, VkApiError};
+
+        let form = FormBuilder::new().with_text("caption", "cat").with_bytes(
+            "photo",
+            "cat.png",
+            b"\x89PNG\r\n\x1a\n".to_vec(),
+        );
+        assert!(form.build().await.is_ok());
+        assert!(form.clone().build().await.is_ok());
+
+        #[cfg(feature = "upload_file")]
+        assert!(matches!(
+            form.with_file("video", "/nonexistent/video.mp4")
+                .build()
+                .await,
+            Err(VkApiError::IO(_))
+        ));
+
+        let uploader = VkUploader::builder()
+            .with_allowed_hosts(["vk.com"])
+            .build()
+            .unwrap();
+        let error = uploader
+            .upload_with_retry(
+                "https://example.com",
+                &FormBuilder::new(),
+                Backoff::default(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, VkApiError::HostNotAllowed(_)));
+
+        let cancelled = uploader
+            .upload_with_retry_until(
+                "https://vk.com",
+                &FormBuilder::new(),
+                Backoff::default(),
+                std::future::ready(()),
+            )
+            .await;
+        assert!(cancelled.is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "encode_json")]
+    fn parse_uploaded_file() {
+        use crate::upload::{uploaded_file, SavedDoc};
+
+        assert_eq!(uploaded_file(r#"{"file":"abc"}"#).unwrap(), "abc");
+        assert!(uploaded_file(r#"{"error":"no file"}"#)
+            .unwrap_err()
+            .to_string()
+            .contains("no file"));
+
+        let saved: SavedDoc = serde_json::from_str(
+            r#"{"type":"doc","doc":{"id":1,"owner_id":-2,"title":"Report","size":10,"ext":"pdf","url":"https://vk.com/doc-2_1","date":1700000000,"type":1,"tags":["news"]}}"#,
+        )
+        .unwrap();
+        assert_eq!(saved.doc.owner_id, -2);
+        assert_eq!(saved.doc.tags, ["news"]);
+        assert_eq!(saved.doc.attachment(), "doc-2_1");
+    }
+
+    #[test]
+    fn detect_upload_server_errors() {
+        use crate::upload::UploadServerError;
+        use reqwest::StatusCode;
+
+        let error = UploadServerError::detect(
+            StatusCode::OK,
+            r#"{"error":"ERR_UPLOAD_BAD_IMAGE_SIZE: photo size is invalid","bwact":"do_add"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            error.message,
+            "ERR_UPLOAD_BAD_IMAGE_SIZE: photo size is invalid"
+        );
+
+        let error = UploadServerError::detect(
+            StatusCode::BAD_GATEWAY,
+            "<html><head><title>502 Bad Gateway</title></head></html>",
+        )
+        .unwrap();
+        assert_eq!(error.message, "502 Bad Gateway");
+        assert!(crate::VkApiError::Upload(error).is_retryable());
+
+        assert_eq!(
+            UploadServerError::detect(StatusCode::PAYLOAD_TOO_LARGE, "too large")
+                .unwrap()
+                .message,
+            "too large"
+        );
+        assert!(
+            UploadServerError::detect(StatusCode::OK, r#"{"server":1,"photo":"[]"}"#).is_none()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "encode_json")]
+    fn decode_upload_response() {
+        use crate::upload::{decode_json, PhotoUploadResponse};
+
+        let response: PhotoUploadResponse =
+            decode_json(r#"{"server":1,"photo":"[]","hash":"abc"}"#).unwrap();
+        assert_eq!(response.server, 1);
+        assert_eq!(response.hash, "abc");
+        assert!(decode_json::<PhotoUploadResponse>("<html></html>").is_err());
     }
 }
@@ -1,38 +1,67 @@
-use crate::inner::{create_client, decode, uncompress, VkApiInner};
-use crate::structs::Version;
+use crate::builder::BuildError;
+use crate::captcha::{Captcha, MAX_CAPTCHA_ATTEMPTS};
+use crate::connection::{ConnectionPool, ConnectionStats};
+use crate::inner::{decode, uncompress, CompressReader, UrlCache, VkApiInner};
+use crate::pool::{BufferPool, BufferPoolStats};
+use crate::structs::{ParamValue, Version};
 use crate::wrapper::VkApiWrapper;
-use bytes::{Buf, BufMut, BytesMut};
+use bytes::BufMut;
 use cfg_if::cfg_if;
 use reqwest::header::{ACCEPT, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE};
-use reqwest::Client;
 use serde::de::DeserializeOwned;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /// # Base VK API client realisation.
 /// This client supports zstd compression and msgpack format of VK API. It's works with http2 only connections.
 ///
 /// ## Usage
-/// ```rust
+/// ```rust,no_run
 /// use vkclient::VkApi;
+/// # let access_token = String::new();
 /// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
 /// ```
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), vkclient::BuildError> {
+/// # let access_token = String::new();
+/// let client = vkclient::VkApiBuilder::new(access_token).build()?;
+/// # Ok(())
+/// # }
+/// ```
 #[derive(Debug, Clone)]
 pub struct VkApi {
     inner: Arc<VkApiInner>,
-    client: Client,
+    connection: Arc<ConnectionPool>,
+    #[cfg(all(feature = "encode_json", feature = "encode_msgpack"))]
+    auto: Arc<crate::auto::AutoEncoding>,
+    pool: Arc<BufferPool>,
+    urls: Arc<UrlCache>,
+    #[cfg(all(feature = "longpoll", feature = "longpoll_stream"))]
+    long_poll_servers: Arc<crate::longpoll::ServerCache>,
 }
 
 impl VkApi {
-    pub(crate) fn from_inner(inner: VkApiInner) -> Self {
-        let client = create_client();
+    pub(crate) fn from_inner(inner: VkApiInner) -> Result<Self, BuildError> {
+        let connection =
+            ConnectionPool::new(inner.client_config.clone()).map_err(BuildError::Client)?;
+        let pool = BufferPool::new(inner.buffer_pool_size, inner.buffer_max_capacity);
 
-        Self {
+        Ok(Self {
             inner: Arc::new(inner),
-            client,
-        }
+            connection: Arc::new(connection),
+            #[cfg(all(feature = "encode_json", feature = "encode_msgpack"))]
+            auto: Arc::default(),
+            pool: Arc::new(pool),
+            urls: Arc::default(),
+            #[cfg(all(feature = "longpoll", feature = "longpoll_stream"))]
+            long_poll_servers: Arc::default(),
+        })
     }
 
     /// Send request to VK API. See list of [VK API methods](https://dev.vk.com/method).
@@ -78,10 +107,58 @@ impl VkApi {
     where
         W: VkApiWrapper + Serialize + Send,
     {
+        if W::requires_auth() && matches!(self.inner.auth, Auth::None) {
+            return Err(VkApiError::AuthRequired(W::get_method_name()));
+        }
+
         self.send_request_with_version(W::get_method_name(), wrapper, W::get_version())
             .await
     }
 
+    /// Send request to VK API with the dynamic params, like from a `HashMap` or a `BTreeMap`,
+    /// when defining the serde struct per call is impractical.
+    /// ```rust,no_run
+    /// use std::collections::HashMap;
+    /// use vkclient::{ParamValue, VkApi};
+    /// # let access_token = String::new();
+    /// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+    ///
+    /// let params = HashMap::from([
+    ///     ("user_ids".into(), ParamValue::from(vec![1, 2])),
+    ///     ("fields".into(), ParamValue::from("sex")),
+    /// ]);
+    ///
+    /// async {
+    ///     let users: serde_json::Value = client.send_request_params("users.get", params).await.unwrap();
+    /// };
+    /// ```
+    pub async fn send_request_params<'a, T, M, I>(&self, method: M, params: I) -> VkApiResult<T>
+    where
+        T: DeserializeOwned,
+        M: AsRef<str> + Send,
+        I: IntoIterator<Item = (Cow<'a, str>, ParamValue)>,
+    {
+        let params: BTreeMap<_, _> = params.into_iter().collect();
+        self.send_request(method, params).await
+    }
+
+    /// Send request to VK API with the json object body, like a generated one from the script.
+    /// Fields must be scalars or arrays of scalars, nested values are rejected with
+    /// [`VkApiError::RequestSerialize`] before the request is sent.
+    #[cfg(feature = "encode_json")]
+    pub async fn send_request_json<T, M>(
+        &self,
+        method: M,
+        body: serde_json::Value,
+    ) -> VkApiResult<T>
+    where
+        T: DeserializeOwned,
+        M: AsRef<str> + Send,
+    {
+        let params = crate::structs::flatten_json(body).map_err(VkApiError::RequestSerialize)?;
+        self.send_request(method, params).await
+    }
+
     /// Send request to VK API with specific version.
     pub async fn send_request_with_version<T, B, M>(
         &self,
@@ -94,30 +171,522 @@ impl VkApi {
         B: Serialize + Send,
         M: AsRef<str> + Send,
     {
+        let method = method.as_ref();
+        let form = self.encode(body, &version)?;
+
         cfg_if! {
-            if #[cfg(feature = "encode_msgpack")] {
-                let url = if matches!(self.inner.format, Encoding::Msgpack) {
-                    format!(
-                        "https://{}/method/{}.msgpack",
-                        self.inner.domain,
-                        method.as_ref()
-                    )
-                } else {
-                    format!("https://{}/method/{}", self.inner.domain, method.as_ref())
+            if #[cfg(feature = "resilience")] {
+                let policy = *self.inner.policies.get(crate::RequestKind::of_method(method));
+                self.send_with_policy(method, &form, &policy, false).await
+            } else {
+                self.send_attempt(method, &form, None, false).await
+            }
+        }
+    }
+
+    /// Send request to VK API with the policy, which overrides the one of the method kind.
+    /// The breaker state of the method kind is shared with the other requests.
+    #[cfg(feature = "resilience")]
+    pub async fn send_request_with_policy<T, B, M>(
+        &self,
+        method: M,
+        body: B,
+        policy: &crate::ResiliencePolicy,
+    ) -> VkApiResult<T>
+    where
+        T: DeserializeOwned,
+        B: Serialize + Send,
+        M: AsRef<str> + Send,
+    {
+        let method = method.as_ref();
+        let form = self.encode(body, &self.inner.version)?;
+        self.send_with_policy(method, &form, policy, false).await
+    }
+
+    /// Send request to VK API with the access token instead of the client ones,
+    /// like on behalf of the users of your service. The token pool and the VK ID session of the client aren't used,
+    /// the rate limit, the captcha solver and the resilience policies are shared with the other requests.
+    /// ```rust,no_run
+    /// use vkclient::VkApi;
+    /// # let (access_token, user_token) = (String::new(), String::new());
+    /// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+    ///
+    /// async {
+    ///     let users: serde_json::Value = client.send_request_as(&user_token, "users.get", ()).await.unwrap();
+    /// };
+    /// ```
+    pub async fn send_request_as<T, B, M>(
+        &self,
+        access_token: &str,
+        method: M,
+        body: B,
+    ) -> VkApiResult<T>
+    where
+        T: DeserializeOwned,
+        B: Serialize + Send,
+        M: AsRef<str> + Send,
+    {
+        let method = method.as_ref();
+        let form = VkApiBody {
+            v: &self.inner.version,
+            access_token: Some(access_token),
+            body,
+        }
+        .encode()
+        .map_err(VkApiError::RequestSerialize)?;
+
+        cfg_if! {
+            if #[cfg(feature = "resilience")] {
+                let policy = *self.inner.policies.get(crate::RequestKind::of_method(method));
+                self.send_with_policy(method, &form, &policy, true).await
+            } else {
+                self.send_attempt(method, &form, None, true).await
+            }
+        }
+    }
+
+    /// Send request to VK API and return the uncompressed body without decoding.
+    /// The response is requested in the format of the client, json for the `Encoding::Auto`.
+    /// VK errors aren't parsed and captchas aren't solved, the error is returned in the body.
+    /// ```rust,no_run
+    /// use vkclient::VkApi;
+    /// # let access_token = String::new();
+    /// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+    ///
+    /// async {
+    ///     let response = client.send_request_raw("users.get", ()).await.unwrap();
+    ///     println!("{:?}: {}", response.content_type(), response.bytes().len());
+    /// };
+    /// ```
+    pub async fn send_request_raw<B, M>(&self, method: M, body: B) -> VkApiResult<RawResponse>
+    where
+        B: Serialize + Send,
+        M: AsRef<str> + Send,
+    {
+        let method = method.as_ref();
+        let mut form = self.encode(body, &self.inner.version)?;
+
+        if let Some(pool) = &self.inner.token_pool {
+            form = with_token(&form, pool.acquire().1)?;
+        }
+        #[cfg(feature = "vk_id")]
+        if let Some(refresher) = &self.inner.refresher {
+            form = with_token(&form, &refresher.access_token().await)?;
+        }
+
+        #[cfg(feature = "rate_limit")]
+        if let Some(limiter) = &self.inner.rate_limiter {
+            limiter.acquire().await;
+        }
+
+        #[cfg(feature = "chaos")]
+        if let Some(chaos) = &self.inner.chaos {
+            if let Some(fault) = chaos.before_request().await {
+                return Err(fault.into_error(false));
+            }
+        }
+
+        cfg_if! {
+            if #[cfg(all(feature = "encode_json", feature = "encode_msgpack"))] {
+                let format = match self.inner.format {
+                    Encoding::Auto => Encoding::Json,
+                    format => format,
                 };
             } else {
-                let url = format!("https://{}/method/{}", self.inner.domain, method.as_ref());
+                let format = self.inner.format;
+            }
+        }
+
+        cfg_if! {
+            if #[cfg(feature = "resilience")] {
+                let timeout = self.inner.policies.get(crate::RequestKind::of_method(method)).timeout();
+            } else {
+                let timeout = None;
+            }
+        }
+
+        let response = self
+            .post(method, form, timeout, format)
+            .send()
+            .await
+            .map_err(|e| self.connection.failed(e))?;
+        let headers = response.headers();
+        let content_type = headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(ToOwned::to_owned);
+        let content_encoding = headers.get(CONTENT_ENCODING).cloned();
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| self.connection.failed(e))?;
+        self.connection.succeeded();
+
+        let body = match uncompress(
+            content_encoding.as_ref(),
+            &body[..],
+            self.inner.encoding.zstd_window_log_max(),
+        )? {
+            CompressReader::Skip(_) => body,
+            #[cfg_attr(
+                not(any(feature = "compression_zstd", feature = "compression_gzip")),
+                allow(unreachable_patterns)
+            )]
+            mut reader => {
+                let mut uncompressed = Vec::with_capacity(body.len());
+                std::io::Read::read_to_end(&mut reader, &mut uncompressed)
+                    .map_err(VkApiError::IO)?;
+                uncompressed.into()
             }
+        };
+
+        Ok(RawResponse { content_type, body })
+    }
+
+    /// Encodes the form body with the version and the static access token.
+    /// Tokens of the pool and the VK ID session are added by the attempts, because they change.
+    fn encode<B: Serialize>(&self, body: B, version: &Version) -> VkApiResult<String> {
+        let access_token = match &self.inner.auth {
+            Auth::Token(_) if self.inner.token_pool.is_some() => None,
+            #[cfg(feature = "vk_id")]
+            Auth::Token(_) if self.inner.refresher.is_some() => None,
+            Auth::Token(token) => Some(token.as_str()),
+            Auth::None => None,
+        };
+
+        VkApiBody {
+            v: version,
+            access_token,
+            body,
         }
+        .encode()
+        .map_err(VkApiError::RequestSerialize)
+    }
+
+    /// Repeats the retryable failures with the backoff of the policy and counts them by the breaker.
+    #[cfg(feature = "resilience")]
+    async fn send_with_policy<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        form: &str,
+        policy: &crate::ResiliencePolicy,
+        pinned_token: bool,
+    ) -> VkApiResult<T> {
+        let kind = crate::RequestKind::of_method(method);
+        let policies = &self.inner.policies;
+        let mut failures = 0;
+
+        loop {
+            if !policies.allow(kind, policy) {
+                return Err(VkApiError::CircuitOpen(kind));
+            }
+
+            let result = self
+                .send_attempt(method, form, policy.timeout(), pinned_token)
+                .await;
+            let failed = matches!(&result, Err(e) if e.is_retryable());
+            policies.record(kind, policy, failed);
+
+            if failed {
+                failures += 1;
+                if let Some(delay) = policy.retry().and_then(|backoff| backoff.delay(failures)) {
+                    tokio::time::sleep(delay).await;
+                    continue;
+                }
+            }
 
-        let request = self
-            .client
-            .post(url)
+            return result;
+        }
+    }
+
+    /// Sends the encoded form body once, with the token of the pool or the current VK ID token if it's set.
+    /// Form with the `pinned_token` is sent as is.
+    async fn send_attempt<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        form: &str,
+        timeout: Option<Duration>,
+        pinned_token: bool,
+    ) -> VkApiResult<T> {
+        if pinned_token {
+            return self.send_decoded(method, form.to_owned(), timeout).await;
+        }
+
+        if let Some(pool) = &self.inner.token_pool {
+            return self.send_pooled(method, form, pool, timeout).await;
+        }
+
+        #[cfg(feature = "vk_id")]
+        if let Some(refresher) = &self.inner.refresher {
+            return self.send_refreshing(method, form, refresher, timeout).await;
+        }
+
+        self.send_decoded(method, form.to_owned(), timeout).await
+    }
+
+    /// Sends the encoded form body with the tokens of the pool
+    /// and repeats it with the next token, while VK rejects them with the rate limit errors.
+    async fn send_pooled<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        form: &str,
+        pool: &crate::TokenPool,
+        timeout: Option<Duration>,
+    ) -> VkApiResult<T> {
+        for _ in 1..pool.len() {
+            let (index, token) = pool.acquire();
+            match self
+                .send_decoded(method, with_token(form, token)?, timeout)
+                .await
+            {
+                Err(VkApiError::Vk(error)) if pool.reject(index, &error) => {}
+                result => return result,
+            }
+        }
+
+        let (_, token) = pool.acquire();
+        self.send_decoded(method, with_token(form, token)?, timeout)
+            .await
+    }
+
+    /// Sends the encoded form body without the access token with the current VK ID token
+    /// and repeats it once with the refreshed token after the error 5.
+    #[cfg(feature = "vk_id")]
+    async fn send_refreshing<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        form: &str,
+        refresher: &crate::vkid::TokenRefresher,
+        timeout: Option<Duration>,
+    ) -> VkApiResult<T> {
+        let token = refresher.access_token().await;
+        let error = match self
+            .send_decoded(method, with_token(form, &token)?, timeout)
+            .await
+        {
+            Err(VkApiError::Vk(error)) if error.is_auth_failed() => error,
+            result => return result,
+        };
+
+        match refresher.refresh(&token).await {
+            Ok(token) => {
+                self.send_decoded(method, with_token(form, &token)?, timeout)
+                    .await
+            }
+            Err(_) => Err(VkApiError::Vk(error)),
+        }
+    }
+
+    /// Sends the encoded form body and applies the response transformers.
+    async fn send_decoded<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        form: String,
+        timeout: Option<Duration>,
+    ) -> VkApiResult<T> {
+        #[cfg(feature = "encode_json")]
+        if self.inner.transformers.matches(method) {
+            let response = self.send_solving(method, form, timeout).await?;
+            return self
+                .inner
+                .transformers
+                .apply(method, response, self.inner.strictness);
+        }
+
+        self.send_solving(method, form, timeout).await
+    }
+
+    /// Sends the encoded form body and repeats it with the solved captchas.
+    async fn send_solving<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        form: String,
+        timeout: Option<Duration>,
+    ) -> VkApiResult<T> {
+        let Some(solver) = &self.inner.captcha_solver else {
+            return self.send_form(method, form, timeout).await;
+        };
+
+        let mut attempt = form.clone();
+        for _ in 0..MAX_CAPTCHA_ATTEMPTS {
+            let (error, captcha) = match self.send_form(method, attempt, timeout).await {
+                Err(VkApiError::Vk(error)) => match error.captcha() {
+                    Some(captcha) => (error, captcha),
+                    None => return Err(VkApiError::Vk(error)),
+                },
+                result => return result,
+            };
+
+            let Some(key) = solver.0.solve(&captcha).await else {
+                return Err(VkApiError::Vk(error));
+            };
+
+            let solved = serde_urlencoded::to_string([
+                ("captcha_sid", captcha.sid.as_str()),
+                ("captcha_key", key.as_str()),
+            ])
+            .map_err(VkApiError::RequestSerialize)?;
+            attempt = format!("{form}&{solved}");
+        }
+
+        self.send_form(method, attempt, timeout).await
+    }
+
+    /// Records the metrics of the request to the method stats and the recorder of the client.
+    fn record_metrics(&self, metrics: &crate::RequestMetrics<'_>) {
+        use crate::MetricsRecorder;
+
+        if let Some(stats) = &self.inner.method_stats {
+            stats.record(metrics);
+        }
+        if let Some(recorder) = &self.inner.metrics {
+            recorder.record(metrics);
+        }
+    }
+
+    /// Sends the encoded form body and decodes the response.
+    async fn send_form<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        form: String,
+        timeout: Option<Duration>,
+    ) -> VkApiResult<T> {
+        #[cfg(feature = "rate_limit")]
+        if let Some(limiter) = &self.inner.rate_limiter {
+            limiter.acquire().await;
+        }
+
+        #[cfg(feature = "chaos")]
+        if let Some(chaos) = &self.inner.chaos {
+            if let Some(fault) = chaos.before_request().await {
+                return Err(fault.into_error(false));
+            }
+        }
+
+        cfg_if! {
+            if #[cfg(all(feature = "encode_json", feature = "encode_msgpack"))] {
+                let format = match self.inner.format {
+                    Encoding::Auto => self.auto.next(),
+                    format => format,
+                };
+            } else {
+                let format = self.inner.format;
+            }
+        }
+
+        let started = Instant::now();
+        let mut response = self
+            .post(method, form, timeout, format)
+            .send()
+            .await
+            .map_err(|e| self.connection.failed(e))?;
+        let conent_length = response.content_length();
+
+        let mut body = self.pool.get(conent_length.unwrap_or_default() as usize);
+        while let Some(buf) = response
+            .chunk()
+            .await
+            .map_err(|e| self.connection.failed(e))?
+        {
+            body.put(buf);
+        }
+        self.connection.succeeded();
+
+        let headers = response.headers();
+
+        let content_type = headers.get(CONTENT_TYPE);
+        let content_encoding = headers.get(CONTENT_ENCODING);
+
+        let bytes = body.len();
+        #[cfg(all(feature = "encode_json", feature = "encode_msgpack"))]
+        let decode_started = Instant::now();
+
+        let resp = match uncompress(
+            content_encoding,
+            &body[..],
+            self.inner.encoding.zstd_window_log_max(),
+        )? {
+            CompressReader::Skip(_) => {
+                decode_response(content_type, &body[..], self.inner.strictness)
+            }
+            #[cfg_attr(
+                not(any(feature = "compression_zstd", feature = "compression_gzip")),
+                allow(unreachable_patterns)
+            )]
+            mut reader => {
+                let mut scratch = self.pool.get(body.len());
+                std::io::copy(&mut reader, &mut (&mut *scratch).writer())
+                    .map_err(VkApiError::IO)
+                    .and_then(|_| {
+                        decode_response(content_type, &scratch[..], self.inner.strictness)
+                    })
+            }
+        };
+        drop(body);
+        self.record_metrics(&crate::RequestMetrics {
+            host: &self.inner.domain,
+            method: Some(method),
+            elapsed: started.elapsed(),
+            bytes,
+            encoding: Some(format),
+            compression: self.inner.encoding,
+            error: resp.as_ref().err().map(VkApiError::label),
+        });
+        let resp = resp?;
+
+        #[cfg(all(feature = "encode_json", feature = "encode_msgpack"))]
+        if matches!(self.inner.format, Encoding::Auto) {
+            self.auto.record(format, bytes, decode_started.elapsed());
+        }
+
+        match resp {
+            Response {
+                error: Some(error), ..
+            } => {
+                if let Some(stats) = &self.inner.error_stats {
+                    stats.record(&error);
+                }
+                Err(VkApiError::Vk(error))
+            }
+            Response {
+                response: Some(response),
+                ..
+            } => Ok(response),
+            _ => Err(VkApiError::ResponseDeserialize(
+                ResponseDeserialize::BadEncoding,
+            )),
+        }
+    }
+
+    /// Builds the request of the method with the encoded form body.
+    fn post(
+        &self,
+        method: &str,
+        form: String,
+        timeout: Option<Duration>,
+        format: Encoding,
+    ) -> reqwest::RequestBuilder {
+        cfg_if! {
+            if #[cfg(feature = "encode_msgpack")] {
+                let msgpack = matches!(format, Encoding::Msgpack);
+            } else {
+                let msgpack = false;
+            }
+        }
+
+        let client = self.connection.client();
+        let request = match self.urls.get(&self.inner.base_url, method, msgpack) {
+            Ok(url) => client.post(url),
+            Err(url) => client.post(url),
+        };
+
+        let request = request
             .header(
                 ACCEPT_ENCODING,
                 match self.inner.encoding {
                     #[cfg(feature = "compression_zstd")]
-                    Compression::Zstd => "zstd",
+                    Compression::Zstd { .. } => "zstd",
                     #[cfg(feature = "compression_gzip")]
                     Compression::Gzip => "gzip",
                     Compression::None => "identity",
@@ -125,57 +694,218 @@ impl VkApi {
             )
             .header(
                 ACCEPT,
-                match self.inner.format {
+                match format {
                     #[cfg(feature = "encode_msgpack")]
                     Encoding::Msgpack => "application/x-msgpack",
                     #[cfg(feature = "encode_json")]
                     Encoding::Json => "application/json",
+                    #[cfg(all(feature = "encode_json", feature = "encode_msgpack"))]
+                    Encoding::Auto => "application/json",
+                    #[allow(deprecated)]
                     Encoding::None => "text/*",
                 },
             )
             .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
-            .form(&VkApiBody {
-                v: &version,
-                access_token: self.inner.access_token.as_str(),
-                body,
-            });
+            .body(form);
+        match timeout {
+            Some(timeout) => request.timeout(timeout),
+            None => request,
+        }
+    }
 
-        let mut response = request.send().await.map_err(VkApiError::Request)?;
-        let conent_length = response.content_length();
+    /// Returns measurements of the `Encoding::Auto` probes and the selected encoding.
+    #[cfg(all(feature = "encode_json", feature = "encode_msgpack"))]
+    #[must_use]
+    pub fn encoding_stats(&self) -> crate::auto::EncodingStats {
+        self.auto.stats()
+    }
 
-        let mut body = BytesMut::with_capacity(conent_length.unwrap_or_default() as usize);
-        while let Some(buf) = response.chunk().await.map_err(VkApiError::Request)? {
-            body.put(buf)
-        }
+    /// Returns the rate limit of the client and its consumption, if the limit is set.
+    #[cfg(feature = "rate_limit")]
+    pub fn quota(&self) -> Option<crate::Quota> {
+        self.inner
+            .rate_limiter
+            .as_ref()
+            .map(crate::rate::RateLimiter::quota)
+    }
 
-        let headers = response.headers();
+    /// Returns the counts of the VK errors over the window, if the stats are enabled with
+    /// [`VkApiBuilder::with_error_stats`](crate::VkApiBuilder::with_error_stats).
+    pub fn error_stats(&self) -> Option<crate::ErrorSnapshot> {
+        self.inner
+            .error_stats
+            .as_ref()
+            .map(crate::stats::ErrorStats::snapshot)
+    }
 
-        let content_type = headers.get(CONTENT_TYPE);
-        let content_encoding = headers.get(CONTENT_ENCODING);
+    /// Returns the response sizes and durations by the methods, if the stats are enabled with
+    /// [`VkApiBuilder::with_method_stats`](crate::VkApiBuilder::with_method_stats).
+    pub fn method_stats(&self) -> Option<std::collections::HashMap<String, crate::MethodSnapshot>> {
+        self.inner
+            .method_stats
+            .as_ref()
+            .map(crate::method_stats::MethodStats::snapshot)
+    }
 
-        let resp =
-            decode::<Response<T>, _>(content_type, uncompress(content_encoding, body.reader())?)?;
+    /// Returns the effective configuration of the client without the access tokens,
+    /// which can be attached to the bug reports or served by the health endpoints.
+    ///
+    /// ## Usage:
+    /// ```rust
+    /// use vkclient::VkApi;
+    /// # let access_token = String::new();
+    /// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
+    ///
+    /// let description = client.describe();
+    /// assert_eq!(description.domain, "api.vk.com");
+    /// ```
+    #[must_use]
+    pub fn describe(&self) -> crate::ClientDescription {
+        crate::ClientDescription::of(&self.inner)
+    }
 
-        match resp {
-            Response::Success { response } => Ok(response),
-            Response::Error { error } => Err(VkApiError::Vk(error)),
-        }
+    /// Returns counters of the stale connections, after which the http client is rebuilt.
+    #[must_use]
+    pub fn connection_stats(&self) -> ConnectionStats {
+        self.connection.stats()
+    }
+
+    /// Returns counters of the response buffers pool.
+    #[must_use]
+    pub fn buffer_pool_stats(&self) -> BufferPoolStats {
+        self.pool.stats()
+    }
+
+    /// Returns the API version of the requests.
+    #[must_use]
+    pub fn version(&self) -> Version {
+        self.inner.version
     }
 
     /// Returns `VkLongPoll` client with the same connection pool as the vk api client.
     #[cfg(feature = "longpoll")]
+    #[must_use]
     pub fn longpoll(&self) -> crate::longpoll::VkLongPoll {
-        crate::longpoll::VkLongPoll::from(self.client.clone())
+        let longpoll = crate::longpoll::VkLongPoll::from(self.connection.client());
+        let longpoll = match &self.inner.metrics {
+            Some(recorder) => longpoll.with_metrics(recorder.clone()),
+            None => longpoll,
+        };
+        #[cfg(feature = "chaos")]
+        let longpoll = match &self.inner.chaos {
+            Some(chaos) => longpoll.with_chaos(chaos.clone()),
+            None => longpoll,
+        };
+        #[cfg(feature = "resilience")]
+        if let Some(timeout) = self
+            .inner
+            .policies
+            .get(crate::RequestKind::LongPoll)
+            .timeout()
+        {
+            return longpoll.with_timeout(timeout);
+        }
+        longpoll
+    }
+
+    /// Returns the long poll servers, which are shared by the managed streams of the client.
+    #[cfg(all(feature = "longpoll", feature = "longpoll_stream"))]
+    pub(crate) fn long_poll_servers(&self) -> &crate::longpoll::ServerCache {
+        &self.long_poll_servers
     }
 
     /// Returns `VkUploader` client with the same connection pool as the vk api client.
     #[cfg(feature = "uploader")]
+    #[must_use]
     pub fn uploader(&self) -> crate::upload::VkUploader {
-        crate::upload::VkUploader::from(self.client.clone())
+        let uploader = crate::upload::VkUploader::from(self.connection.client());
+        let uploader = match &self.inner.metrics {
+            Some(recorder) => uploader.with_metrics(recorder.clone()),
+            None => uploader,
+        };
+        #[cfg(feature = "resilience")]
+        if let Some(timeout) = self
+            .inner
+            .policies
+            .get(crate::RequestKind::Upload)
+            .timeout()
+        {
+            return uploader.with_timeout(timeout);
+        }
+        uploader
+    }
+
+    /// Returns `VkDownloader` client with the same connection pool as the vk api client.
+    #[cfg(feature = "download")]
+    #[must_use]
+    pub fn downloader(&self) -> crate::download::VkDownloader {
+        crate::download::VkDownloader::from(self.connection.client())
+    }
+
+    /// Returns `VkMessages` facade, which uses this client for requests.
+    #[cfg(feature = "messages")]
+    #[must_use]
+    pub fn messages(&self) -> crate::messages::VkMessages {
+        crate::messages::VkMessages::from(self.clone())
+    }
+
+    /// Reports the known breaking changes of the wrappers registered with
+    /// [`VkApiBuilder::with_wrapper_registry`](crate::VkApiBuilder::with_wrapper_registry)
+    /// between their versions and the target one. The report is empty without the registry.
+    #[must_use]
+    pub fn check_compatibility(&self, target: Version) -> crate::CompatibilityReport {
+        match &self.inner.wrapper_registry {
+            Some(registry) => registry.check(target),
+            None => crate::CompatibilityReport {
+                target,
+                issues: Vec::new(),
+            },
+        }
+    }
+
+    /// Returns the registry of the wrappers passed to
+    /// [`VkApiBuilder::with_wrapper_registry`](crate::VkApiBuilder::with_wrapper_registry).
+    #[must_use]
+    pub fn wrapper_registry(&self) -> Option<&crate::WrapperRegistry> {
+        self.inner.wrapper_registry.as_ref()
+    }
+
+    /// Returns the current VK ID tokens of the client built with
+    /// [`VkApiBuilder::with_token_refresh`](crate::VkApiBuilder::with_token_refresh).
+    /// Store them after the requests, because VK ID invalidates the refreshed tokens.
+    #[cfg(feature = "vk_id")]
+    pub async fn vk_id_tokens(&self) -> Option<crate::vkid::Tokens> {
+        match &self.inner.refresher {
+            Some(refresher) => Some(refresher.tokens().await),
+            None => None,
+        }
+    }
+
+    /// Returns `VkGroups` facade of the community, which uses this client for requests.
+    #[cfg(feature = "groups")]
+    #[must_use]
+    pub fn groups(&self, group_id: u64) -> crate::groups::VkGroups {
+        crate::groups::VkGroups::new(self.clone(), group_id)
+    }
+
+    /// Returns `VkSearch` facade, which uses this client for requests.
+    #[cfg(feature = "search")]
+    #[must_use]
+    pub fn search(&self) -> crate::search::VkSearch {
+        crate::search::VkSearch::from(self.clone())
+    }
+
+    /// Returns `VkStreaming` client of the server requested with
+    /// [streaming.getServerUrl](https://dev.vk.com/method/streaming.getServerUrl).
+    /// The client must be created with the service token of the application.
+    #[cfg(feature = "streaming")]
+    pub async fn streaming(&self) -> VkApiResult<crate::streaming::VkStreaming> {
+        crate::streaming::VkStreaming::request_server(self, &self.inner.client_config).await
     }
 }
 
 /// Vk Api errors.
+///
 /// `VkApiError::Vk` - is an error of buisness logic, like expired token or incorrect request params
 /// Other errors is about things around your request, like a serialization/deserialization or network errors.
 #[derive(Debug)]
@@ -187,8 +917,96 @@ pub enum VkApiError {
     IO(std::io::Error),
     #[cfg(feature = "longpoll")]
     LongPoll(crate::longpoll::LongPollError),
+    /// Request to the long poll server failed, unlike `Request` errors of the API host
+    #[cfg(feature = "longpoll")]
+    LongPollRequest(reqwest::Error),
+    /// Method requires an access token, but the client has none
+    AuthRequired(&'static str),
+    /// Circuit breaker of the request kind is open after the failures in a row,
+    /// the request isn't sent until the cooldown passes
+    #[cfg(feature = "resilience")]
+    CircuitOpen(crate::RequestKind),
+    /// Host of the long poll or upload server isn't in the allowlist of the client
+    #[cfg(any(feature = "longpoll", feature = "uploader"))]
+    HostNotAllowed(String),
+    /// File exceeds the size limit of its kind and isn't uploaded
+    #[cfg(feature = "uploader")]
+    FileTooLarge(crate::upload::FileTooLarge),
+    /// Type of the file isn't accepted by its kind and the file isn't uploaded
+    #[cfg(feature = "uploader")]
+    UnsupportedMediaType(crate::upload::UnsupportedMediaType),
+    /// Upload server rejected the file with the error response or the html page
+    #[cfg(feature = "uploader")]
+    Upload(crate::upload::UploadServerError),
+    /// Intent of the message isn't valid
+    #[cfg(feature = "messages")]
+    InvalidIntent(crate::messages::IntentError),
+    /// Error of the Streaming API server
+    #[cfg(feature = "streaming")]
+    Streaming(crate::streaming::StreamingError),
+}
+
+impl VkApiError {
+    /// Returns the error kind name, which is stable and can be used as a metrics label.
+    /// Network failures of the API host are labeled `request`
+    /// and the ones of the long poll server are labeled `longpoll_request`.
+    #[must_use]
+    pub const fn label(&self) -> &'static str {
+        match self {
+            Self::Request(_) => "request",
+            Self::RequestSerialize(_) => "request_serialize",
+            Self::ResponseDeserialize(_) => "response_deserialize",
+            Self::Vk(_) => "vk",
+            Self::IO(_) => "io",
+            #[cfg(feature = "longpoll")]
+            Self::LongPoll(_) => "longpoll",
+            #[cfg(feature = "longpoll")]
+            Self::LongPollRequest(_) => "longpoll_request",
+            Self::AuthRequired(_) => "auth_required",
+            #[cfg(feature = "resilience")]
+            Self::CircuitOpen(_) => "circuit_open",
+            #[cfg(any(feature = "longpoll", feature = "uploader"))]
+            Self::HostNotAllowed(_) => "host_not_allowed",
+            #[cfg(feature = "uploader")]
+            Self::FileTooLarge(_) => "file_too_large",
+            #[cfg(feature = "uploader")]
+            Self::UnsupportedMediaType(_) => "unsupported_media_type",
+            #[cfg(feature = "uploader")]
+            Self::Upload(_) => "upload",
+            #[cfg(feature = "messages")]
+            Self::InvalidIntent(_) => "invalid_intent",
+            #[cfg(feature = "streaming")]
+            Self::Streaming(_) => "streaming",
+        }
+    }
+
+    /// Returns true if the request may succeed when it's repeated later,
+    /// like after timeouts, server errors, flood control or rate limit errors.
+    /// Definitive failures, like invalid params or access errors, return false.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Request(e) => {
+                e.is_timeout() || e.is_connect() || e.status().is_some_and(|s| s.is_server_error())
+            }
+            Self::IO(_) => true,
+            Self::Vk(e) => RETRYABLE_CODES.contains(&e.code()),
+            #[cfg(feature = "uploader")]
+            Self::Upload(e) => e.status.is_server_error(),
+            _ => false,
+        }
+    }
 }
 
+/// Codes of the VK errors, after which the request may succeed later:
+/// unknown error, too many requests, flood control and internal server error.
+const RETRYABLE_CODES: [i16; 4] = [
+    VkError::UNKNOWN,
+    VkError::TOO_MANY_REQUESTS,
+    VkError::FLOOD_CONTROL,
+    VkError::INTERNAL_SERVER_ERROR,
+];
+
 impl Display for VkApiError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -199,6 +1017,27 @@ impl Display for VkApiError {
             Self::RequestSerialize(e) => Display::fmt(e, f),
             #[cfg(feature = "longpoll")]
             Self::LongPoll(e) => Display::fmt(e, f),
+            #[cfg(feature = "longpoll")]
+            Self::LongPollRequest(e) => write!(f, "long poll server request failed: {e}"),
+            Self::AuthRequired(method) => {
+                write!(f, "method {method} requires an access token")
+            }
+            #[cfg(feature = "resilience")]
+            Self::CircuitOpen(kind) => {
+                write!(f, "circuit breaker of the {kind} requests is open")
+            }
+            #[cfg(any(feature = "longpoll", feature = "uploader"))]
+            Self::HostNotAllowed(host) => write!(f, "host {host} isn't allowed"),
+            #[cfg(feature = "uploader")]
+            Self::FileTooLarge(e) => Display::fmt(e, f),
+            #[cfg(feature = "uploader")]
+            Self::UnsupportedMediaType(e) => Display::fmt(e, f),
+            #[cfg(feature = "uploader")]
+            Self::Upload(e) => Display::fmt(e, f),
+            #[cfg(feature = "messages")]
+            Self::InvalidIntent(e) => Display::fmt(e, f),
+            #[cfg(feature = "streaming")]
+            Self::Streaming(e) => Display::fmt(e, f),
         }
     }
 }
@@ -215,6 +1054,9 @@ pub enum ResponseDeserialize {
     #[cfg(feature = "encode_msgpack")]
     MsgPack(rmp_serde::decode::Error),
     BadEncoding,
+    /// Response contains fields, which are missing in the model. Returned in the strict mode only.
+    #[cfg(feature = "strict_decode")]
+    UnknownFields(Vec<String>),
 }
 
 impl Display for ResponseDeserialize {
@@ -227,23 +1069,248 @@ impl Display for ResponseDeserialize {
             Self::BadEncoding => {
                 write!(f, "vk api bad encoding or compression returned")
             }
+            #[cfg(feature = "strict_decode")]
+            Self::UnknownFields(fields) => {
+                write!(f, "vk api returned unknown fields: {}", fields.join(", "))
+            }
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(untagged)]
-enum Response<T> {
-    Success { response: T },
-    Error { error: VkError },
+/// Not untagged enum, so the strict mode can track unknown fields of the response.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de>"))]
+struct Response<T> {
+    #[serde(default, deserialize_with = "present")]
+    response: Option<T>,
+    #[serde(default)]
+    error: Option<VkError>,
+}
+
+/// Keeps `null` response as `Some`, so `Option` responses are decoded.
+fn present<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+    deserializer: D,
+) -> Result<Option<T>, D::Error> {
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Decodes the response, falling back to the json decoding of the VK errors,
+/// so errors are returned even if the client can't decode the body, like the json error for the msgpack client with `encode_json`.
+fn decode_response<T: DeserializeOwned>(
+    content_type: Option<&reqwest::header::HeaderValue>,
+    body: &[u8],
+    strictness: DecodeStrictness,
+) -> VkApiResult<Response<T>> {
+    decode(content_type, body, strictness).or_else(|e| match e {
+        VkApiError::ResponseDeserialize(_) => match crate::error_payload::parse_error(body) {
+            Some(error) => Ok(Response {
+                response: None,
+                error: Some(error),
+            }),
+            None => Err(e),
+        },
+        e => Err(e),
+    })
+}
+
+/// Undecoded response of [`VkApi::send_request_raw`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RawResponse {
+    content_type: Option<String>,
+    body: bytes::Bytes,
+}
+
+impl RawResponse {
+    /// Returns the `Content-Type` header of the response.
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// Returns the uncompressed body.
+    pub fn bytes(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Returns the uncompressed body without copying.
+    pub fn into_bytes(self) -> bytes::Bytes {
+        self.body
+    }
+
+    /// Returns the body as text, or `BadEncoding` if it isn't valid UTF-8.
+    pub fn text(&self) -> VkApiResult<&str> {
+        std::str::from_utf8(&self.body)
+            .map_err(|_| VkApiError::ResponseDeserialize(ResponseDeserialize::BadEncoding))
+    }
 }
 
 /// VK Backend business logic errors.
 /// [More info about codes](https://dev.vk.com/reference/errors).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Errors are serialized in the VK format, so they can be passed through to your own API responses.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct VkError {
-    error_code: i16,
-    error_msg: String,
+    pub(crate) error_code: i16,
+    pub(crate) error_msg: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) request_params: Vec<RequestParam>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) captcha_sid: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) captcha_img: Option<String>,
+}
+
+impl VkError {
+    pub const UNKNOWN: i16 = 1;
+    pub const APP_DISABLED: i16 = 2;
+    pub const UNKNOWN_METHOD: i16 = 3;
+    pub const AUTHORIZATION_FAILED: i16 = 5;
+    pub const TOO_MANY_REQUESTS: i16 = 6;
+    pub const PERMISSION_DENIED: i16 = 7;
+    pub const INVALID_REQUEST: i16 = 8;
+    pub const FLOOD_CONTROL: i16 = 9;
+    pub const INTERNAL_SERVER_ERROR: i16 = 10;
+    pub const CAPTCHA_NEEDED: i16 = 14;
+    pub const ACCESS_DENIED: i16 = 15;
+    pub const RATE_LIMIT_REACHED: i16 = 29;
+    pub const INVALID_PARAM: i16 = 100;
+
+    /// Creates the error, like for testing the error handling.
+    pub fn new<M: Into<String>>(code: i16, message: M) -> Self {
+        Self {
+            error_code: code,
+            error_msg: message.into(),
+            request_params: Vec::new(),
+            captcha_sid: None,
+            captcha_img: None,
+        }
+    }
+
+    /// Returns the [error code](https://dev.vk.com/reference/errors).
+    #[must_use]
+    pub const fn code(&self) -> i16 {
+        self.error_code
+    }
+
+    /// Returns the error description.
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.error_msg
+    }
+
+    /// Returns the params of the failed request, as VK received them. The access token is not included.
+    #[must_use]
+    pub fn request_params(&self) -> &[RequestParam] {
+        &self.request_params
+    }
+
+    /// Returns the captcha of the error 14, which must be solved to repeat the request.
+    #[must_use]
+    pub fn captcha(&self) -> Option<Captcha> {
+        Some(Captcha {
+            sid: self.captcha_sid.clone()?,
+            img: self.captcha_img.clone().unwrap_or_default(),
+        })
+    }
+
+    /// Returns the typed error code.
+    #[must_use]
+    pub const fn kind(&self) -> ErrorCode {
+        ErrorCode::from_code(self.error_code)
+    }
+
+    /// Returns true for the requests per second, the flood control and the method quota limits.
+    #[must_use]
+    pub const fn is_rate_limited(&self) -> bool {
+        matches!(
+            self.kind(),
+            ErrorCode::TooManyRequests | ErrorCode::FloodControl | ErrorCode::RateLimitReached
+        )
+    }
+
+    /// Returns true if the token is invalid or expired.
+    #[must_use]
+    pub const fn is_auth_failed(&self) -> bool {
+        matches!(self.kind(), ErrorCode::AuthFailed)
+    }
+}
+
+/// Typed [error code](https://dev.vk.com/reference/errors) of the [`VkError`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ErrorCode {
+    Unknown,
+    AppDisabled,
+    UnknownMethod,
+    AuthFailed,
+    TooManyRequests,
+    PermissionDenied,
+    InvalidRequest,
+    FloodControl,
+    InternalServerError,
+    CaptchaNeeded,
+    AccessDenied,
+    RateLimitReached,
+    InvalidParam,
+    /// Code without the variant
+    Other(i16),
+}
+
+impl ErrorCode {
+    /// Returns the typed error code by the number.
+    #[must_use]
+    pub const fn from_code(code: i16) -> Self {
+        match code {
+            VkError::UNKNOWN => Self::Unknown,
+            VkError::APP_DISABLED => Self::AppDisabled,
+            VkError::UNKNOWN_METHOD => Self::UnknownMethod,
+            VkError::AUTHORIZATION_FAILED => Self::AuthFailed,
+            VkError::TOO_MANY_REQUESTS => Self::TooManyRequests,
+            VkError::PERMISSION_DENIED => Self::PermissionDenied,
+            VkError::INVALID_REQUEST => Self::InvalidRequest,
+            VkError::FLOOD_CONTROL => Self::FloodControl,
+            VkError::INTERNAL_SERVER_ERROR => Self::InternalServerError,
+            VkError::CAPTCHA_NEEDED => Self::CaptchaNeeded,
+            VkError::ACCESS_DENIED => Self::AccessDenied,
+            VkError::RATE_LIMIT_REACHED => Self::RateLimitReached,
+            VkError::INVALID_PARAM => Self::InvalidParam,
+            code => Self::Other(code),
+        }
+    }
+
+    /// Returns the number of the error code.
+    #[must_use]
+    pub const fn code(self) -> i16 {
+        match self {
+            Self::Unknown => VkError::UNKNOWN,
+            Self::AppDisabled => VkError::APP_DISABLED,
+            Self::UnknownMethod => VkError::UNKNOWN_METHOD,
+            Self::AuthFailed => VkError::AUTHORIZATION_FAILED,
+            Self::TooManyRequests => VkError::TOO_MANY_REQUESTS,
+            Self::PermissionDenied => VkError::PERMISSION_DENIED,
+            Self::InvalidRequest => VkError::INVALID_REQUEST,
+            Self::FloodControl => VkError::FLOOD_CONTROL,
+            Self::InternalServerError => VkError::INTERNAL_SERVER_ERROR,
+            Self::CaptchaNeeded => VkError::CAPTCHA_NEEDED,
+            Self::AccessDenied => VkError::ACCESS_DENIED,
+            Self::RateLimitReached => VkError::RATE_LIMIT_REACHED,
+            Self::InvalidParam => VkError::INVALID_PARAM,
+            Self::Other(code) => code,
+        }
+    }
+}
+
+impl From<i16> for ErrorCode {
+    fn from(code: i16) -> Self {
+        Self::from_code(code)
+    }
+}
+
+/// Param of the failed request, returned with the [`VkError`].
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RequestParam {
+    pub key: String,
+    pub value: String,
 }
 
 impl Display for VkError {
@@ -256,30 +1323,434 @@ impl Display for VkError {
     }
 }
 
+/// Appends the access token to the encoded form body.
+fn with_token(form: &str, token: &str) -> VkApiResult<String> {
+    serde_urlencoded::to_string([("access_token", token)])
+        .map(|token| format!("{form}&{token}"))
+        .map_err(VkApiError::RequestSerialize)
+}
+
 #[derive(Debug, Clone, Serialize)]
 struct VkApiBody<'a, T> {
     v: &'a Version,
-    access_token: &'a str,
-    #[serde(flatten)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    access_token: Option<&'a str>,
+    #[serde(skip)]
     body: T,
 }
 
+/// Params, which are set by the client and can't be passed in the body.
+const RESERVED_PARAMS: [&str; 2] = ["v", "access_token"];
+
+impl<T: Serialize> VkApiBody<'_, T> {
+    /// Encodes the form body. Body fields, which collide with the reserved params, are rejected,
+    /// because the duplicated form fields are resolved by VK in undefined order.
+    fn encode(&self) -> Result<String, serde_urlencoded::ser::Error> {
+        let body = serde_urlencoded::to_string(&self.body)?;
+
+        // reserved names have no escaped chars, so the raw keys are compared
+        if let Some(field) = body
+            .split('&')
+            .filter_map(|pair| pair.split('=').next())
+            .find(|key| RESERVED_PARAMS.contains(key))
+        {
+            return Err(serde_urlencoded::ser::Error::Custom(
+                format!("body field `{field}` collides with the param set by the client").into(),
+            ));
+        }
+
+        let mut form = serde_urlencoded::to_string(self)?;
+        if !body.is_empty() {
+            form.push('&');
+            form.push_str(&body);
+        }
+        Ok(form)
+    }
+}
+
 impl Error for VkError {}
 
 #[derive(Clone, Copy, Debug)]
 pub enum Compression {
+    /// Zstd compression.
+    /// * `level` is the compression level for request bodies, must be in the range of the zstd levels.
+    /// * `window_log_max` is the maximum decoder window size as a power of two, must be in the range 10..=31.
+    ///
+    /// Use [`Compression::zstd`] to validate params.
     #[cfg(feature = "compression_zstd")]
-    Zstd,
+    Zstd {
+        level: i32,
+        window_log_max: u32,
+    },
     #[cfg(feature = "compression_gzip")]
     Gzip,
     None,
 }
 
-#[derive(Clone, Copy, Debug)]
+impl Compression {
+    /// Zstd compression with default level and window.
+    #[cfg(feature = "compression_zstd")]
+    pub const ZSTD: Self = Self::Zstd {
+        level: zstd::DEFAULT_COMPRESSION_LEVEL,
+        window_log_max: 27,
+    };
+
+    /// Creates zstd compression with validation of the params.
+    #[cfg(feature = "compression_zstd")]
+    pub fn zstd(level: i32, window_log_max: u32) -> Result<Self, CompressionError> {
+        if !zstd::compression_level_range().contains(&level) {
+            return Err(CompressionError::ZstdLevel(level));
+        }
+        if !(10..=31).contains(&window_log_max) {
+            return Err(CompressionError::ZstdWindowLogMax(window_log_max));
+        }
+
+        Ok(Self::Zstd {
+            level,
+            window_log_max,
+        })
+    }
+
+    pub(crate) const fn zstd_window_log_max(&self) -> Option<u32> {
+        match self {
+            #[cfg(feature = "compression_zstd")]
+            Self::Zstd { window_log_max, .. } => Some(*window_log_max),
+            _ => None,
+        }
+    }
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for Compression {
+    fn default() -> Self {
+        cfg_if! {
+            if #[cfg(feature = "compression_zstd")] {
+                Self::ZSTD
+            } else if #[cfg(feature = "compression_gzip")] {
+                Self::Gzip
+            } else {
+                Self::None
+            }
+        }
+    }
+}
+
+/// Invalid compression params.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CompressionError {
+    ZstdLevel(i32),
+    ZstdWindowLogMax(u32),
+}
+
+impl Display for CompressionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ZstdLevel(level) => write!(f, "zstd compression level {level} is out of range"),
+            Self::ZstdWindowLogMax(window_log_max) => {
+                write!(
+                    f,
+                    "zstd window log {window_log_max} is out of range 10..=31"
+                )
+            }
+        }
+    }
+}
+
+impl Error for CompressionError {}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Encoding {
     #[cfg(feature = "encode_msgpack")]
     Msgpack,
     #[cfg(feature = "encode_json")]
     Json,
+    /// Probes json and msgpack on the first responses and locks in the faster one for the client.
+    /// See [`VkApi::encoding_stats`](crate::VkApi::encoding_stats).
+    #[cfg(all(feature = "encode_json", feature = "encode_msgpack"))]
+    Auto,
+    /// Requests the plain text responses, which can't be decoded into the models.
+    #[deprecated(
+        note = "responses fail with `BadEncoding`, use `VkApi::send_request_raw` to get the undecoded body"
+    )]
+    None,
+}
+
+/// Authorization of the client requests.
+#[derive(Clone, Eq, PartialEq)]
+pub enum Auth {
+    /// Access token of the user, community or service
+    Token(String),
+    /// Requests are sent without the `access_token` field, for the open methods only
     None,
 }
+
+impl Debug for Auth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Token(_) => write!(f, "Token(***)"),
+            Self::None => write!(f, "None"),
+        }
+    }
+}
+
+/// Handling of the response fields, which are missing in the models.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DecodeStrictness {
+    /// Unknown fields are ignored.
+    #[default]
+    Lenient,
+    /// Unknown fields fail the request with [`ResponseDeserialize::UnknownFields`],
+    /// so you are alerted the moment VK adds fields your models don't cover.
+    #[cfg(feature = "strict_decode")]
+    Strict,
+}
+
+/// Builds the client, which sends the requests to the local http server.
+/// The handler gets the form of the request and returns the json body of the response.
+#[cfg(all(
+    test,
+    feature = "encode_json",
+    any(feature = "compression_zstd", feature = "messages")
+))]
+pub(crate) async fn local_api<F>(builder: crate::VkApiBuilder, handler: F) -> VkApi
+where
+    F: Fn(&str) -> String + Send + 'static,
+{
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Returns the form of the request, when the head and the body are read.
+    fn form(request: &str) -> Option<&str> {
+        let (head, body) = request.split_once("\r\n\r\n")?;
+        let length: usize = head.lines().find_map(|line| {
+            line.to_lowercase()
+                .strip_prefix("content-length: ")?
+                .parse()
+                .ok()
+        })?;
+        (body.len() >= length).then_some(body)
+    }
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let mut request = String::new();
+            let mut buf = [0; 4096];
+            let body = loop {
+                if let Some(form) = form(&request) {
+                    break handler(form);
+                }
+                match socket.read(&mut buf).await {
+                    Ok(0) | Err(_) => break String::new(),
+                    Ok(read) => request.push_str(&String::from_utf8_lossy(&buf[..read])),
+                }
+            };
+
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        }
+    });
+
+    let mut client = builder
+        .with_compression(Compression::None)
+        .with_encoding(Encoding::Json)
+        .build()
+        .unwrap();
+    Arc::get_mut(&mut client.inner).unwrap().base_url = format!("http://{addr}/method/");
+    client.connection.set_client(reqwest::Client::new());
+    client
+}
+
+#[cfg(all(test, feature = "compression_zstd"))]
+mod tests {
+    use crate::vkapi::{Compression, CompressionError, VkApiBody, VkApiError};
+    use crate::Version;
+
+    #[test]
+    #[cfg(feature = "encode_json")]
+    fn serialize_vk_error() {
+        use crate::vkapi::VkError;
+
+        let json = r#"{"error_code":6,"error_msg":"Too many requests per second","request_params":[{"key":"method","value":"users.get"}]}"#;
+        let error: VkError = serde_json::from_str(json).unwrap();
+
+        assert_eq!(error.code(), VkError::TOO_MANY_REQUESTS);
+        assert_eq!(error.request_params()[0].value, "users.get");
+        assert_eq!(serde_json::to_string(&error).unwrap(), json);
+        assert_eq!(
+            serde_json::to_string(&VkError::new(VkError::UNKNOWN, "Unknown error")).unwrap(),
+            r#"{"error_code":1,"error_msg":"Unknown error"}"#
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "encode_json")]
+    fn captcha_error() {
+        use crate::vkapi::VkError;
+        use crate::Captcha;
+
+        let error: VkError = serde_json::from_str(
+            r#"{"error_code":14,"error_msg":"Captcha needed","captcha_sid":"123","captcha_img":"https://api.vk.com/captcha.php?sid=123"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            error.captcha(),
+            Some(Captcha {
+                sid: "123".to_owned(),
+                img: "https://api.vk.com/captcha.php?sid=123".to_owned()
+            })
+        );
+        assert_eq!(VkError::new(6, "").captcha(), None);
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "encode_json")]
+    async fn repeat_with_solved_captcha() {
+        use crate::captcha::{Captcha, CaptchaSolver, MAX_CAPTCHA_ATTEMPTS};
+        use crate::vkapi::{local_api, VkApi};
+        use crate::VkApiBuilder;
+        use std::future::Future;
+        use std::pin::Pin;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::{Arc, Mutex};
+
+        struct CountingSolver(AtomicUsize);
+
+        impl CaptchaSolver for CountingSolver {
+            fn solve<'a>(
+                &'a self,
+                captcha: &'a Captcha,
+            ) -> Pin<Box<dyn Future<Output = Option<String>> + Send + 'a>> {
+                let attempt = self.0.fetch_add(1, Ordering::Relaxed);
+                Box::pin(async move { Some(format!("{}-{attempt}", captcha.sid)) })
+            }
+        }
+
+        /// Returns the captcha error, until the captcha key of the `solved_attempt` is sent.
+        async fn captcha_api(solved_attempt: &str, forms: Arc<Mutex<Vec<String>>>) -> VkApi {
+            let solved = format!("captcha_key=123-{solved_attempt}");
+            let builder = VkApiBuilder::new("token".into())
+                .with_captcha_solver(CountingSolver(AtomicUsize::new(0)));
+
+            local_api(builder, move |form| {
+                forms.lock().unwrap().push(form.to_owned());
+                if form.contains(&solved) {
+                    r#"{"response":1}"#.to_owned()
+                } else {
+                    r#"{"error":{"error_code":14,"error_msg":"Captcha needed","captcha_sid":"123","captcha_img":"img"}}"#.to_owned()
+                }
+            })
+            .await
+        }
+
+        // the form is repeated with the key of the last captcha
+        let forms = Arc::default();
+        let client = captcha_api("1", Arc::clone(&forms)).await;
+        let response: u8 = client
+            .send_request("users.get", [("user_ids", "1")])
+            .await
+            .unwrap();
+        assert_eq!(response, 1);
+        let sent = std::mem::take(&mut *forms.lock().unwrap());
+        assert_eq!(sent.len(), 3);
+        assert_eq!(
+            sent[2],
+            "v=5.131&access_token=token&user_ids=1&captcha_sid=123&captcha_key=123-1"
+        );
+
+        // the captcha error is returned after the max attempts
+        let forms = Arc::default();
+        let client = captcha_api("never", Arc::clone(&forms)).await;
+        let error = client
+            .send_request::<u8, _, _>("users.get", [("user_ids", "1")])
+            .await
+            .unwrap_err();
+        assert!(matches!(error, VkApiError::Vk(error) if error.captcha().is_some()));
+        assert_eq!(forms.lock().unwrap().len(), MAX_CAPTCHA_ATTEMPTS + 1);
+    }
+
+    #[test]
+    fn typed_error_codes() {
+        use crate::vkapi::{ErrorCode, VkError};
+
+        assert_eq!(VkError::new(6, "").kind(), ErrorCode::TooManyRequests);
+        assert!(VkError::new(9, "").is_rate_limited());
+        assert!(VkError::new(5, "").is_auth_failed());
+        assert_eq!(ErrorCode::from(917), ErrorCode::Other(917));
+        assert_eq!(ErrorCode::CaptchaNeeded.code(), 14);
+    }
+
+    #[test]
+    fn error_labels() {
+        assert_eq!(
+            VkApiError::AuthRequired("users.get").label(),
+            "auth_required"
+        );
+        #[cfg(feature = "longpoll")]
+        assert_eq!(
+            VkApiError::LongPoll(crate::longpoll::LongPollError::new(2)).label(),
+            "longpoll"
+        );
+    }
+
+    #[test]
+    fn reject_reserved_params() {
+        let body = |body: Vec<(&'static str, &'static str)>| VkApiBody {
+            v: &Version(5, 131),
+            access_token: Some("token"),
+            body,
+        };
+
+        assert_eq!(
+            body(vec![("user_ids", "1")]).encode().unwrap(),
+            "v=5.131&access_token=token&user_ids=1"
+        );
+        assert_eq!(body(vec![]).encode().unwrap(), "v=5.131&access_token=token");
+        assert!(body(vec![("access_token", "other")]).encode().is_err());
+        assert!(body(vec![("user_ids", "1"), ("v", "5.199")])
+            .encode()
+            .is_err());
+    }
+
+    #[test]
+    fn validate_zstd_params() {
+        assert!(matches!(
+            Compression::zstd(3, 27),
+            Ok(Compression::Zstd {
+                level: 3,
+                window_log_max: 27
+            })
+        ));
+        assert_eq!(
+            Compression::zstd(100, 27).unwrap_err(),
+            CompressionError::ZstdLevel(100)
+        );
+        assert_eq!(
+            Compression::zstd(3, 40).unwrap_err(),
+            CompressionError::ZstdWindowLogMax(40)
+        );
+    }
+
+    #[test]
+    fn raw_response_text() {
+        use crate::vkapi::RawResponse;
+
+        let response = RawResponse {
+            content_type: Some("application/json".into()),
+            body: bytes::Bytes::from_static(br#"{"response":1}"#),
+        };
+        assert_eq!(response.text().unwrap(), r#"{"response":1}"#);
+        assert_eq!(response.content_type(), Some("application/json"));
+
+        let response = RawResponse {
+            content_type: None,
+            body: bytes::Bytes::from_static(&[0xff, 0xfe]),
+        };
+        assert!(response.text().is_err());
+    }
+}
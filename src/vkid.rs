@@ -0,0 +1,400 @@
+//! # VK ID tokens
+//! Exchange of the authorization codes and the silent tokens, and refresh of the access tokens
+//! issued by [VK ID](https://id.vk.com/about/business/go/docs/ru/vkid/latest/vk-id/connection/api-description).
+
+use crate::inner::{create_client, ClientConfig};
+use crate::{BuildError, VkApi, VkApiError, VkApiResult};
+use reqwest::header::ACCEPT;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+/// Token endpoint of VK ID.
+const OAUTH_URL: &str = "https://id.vk.com/oauth2/auth";
+
+/// Tokens are refreshed ahead of the expiration by this margin, so the requests in flight don't fail.
+const EXPIRATION_MARGIN: Duration = Duration::from_secs(30);
+
+/// # VK ID client
+/// Exchanges the codes from the VK ID redirect to the tokens and refreshes them.
+///
+/// ## Usage:
+/// ```rust,no_run
+/// use vkclient::vkid::{CodeExchange, VkId};
+/// use vkclient::VkApi;
+///
+/// let vk_id = VkId::new(12345).unwrap();
+///
+/// async move {
+///     let device_id = "device_id from the redirect";
+///     let tokens = vk_id
+///         .exchange_code(CodeExchange::new(
+///             "code from the redirect",
+///             "code verifier of the PKCE",
+///             device_id,
+///             "https://example.com/callback",
+///         ))
+///         .await
+///         .unwrap();
+///
+///     let client: VkApi = vkclient::VkApiBuilder::new(tokens.access_token.clone())
+///         .with_token_refresh(vk_id, device_id, tokens)
+///         .into();
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct VkId {
+    client: Client,
+    client_id: u64,
+}
+
+impl VkId {
+    /// Creates the client of the application.
+    /// Use [`VkId::with_client`] to pass the built http client.
+    pub fn new(client_id: u64) -> Result<Self, BuildError> {
+        create_client(&ClientConfig::default())
+            .map(|client| Self::with_client(client_id, client))
+            .map_err(BuildError::Client)
+    }
+
+    /// Creates the client of the application with the http client, like the one of [`VkApi::uploader`].
+    #[must_use]
+    pub const fn with_client(client_id: u64, client: Client) -> Self {
+        Self { client, client_id }
+    }
+
+    /// Exchanges the authorization code from the redirect to the tokens.
+    pub async fn exchange_code(&self, exchange: CodeExchange) -> Result<Tokens, VkIdError> {
+        self.send(TokenRequest {
+            grant_type: "authorization_code",
+            client_id: self.client_id,
+            params: exchange,
+        })
+        .await
+    }
+
+    /// Refreshes the tokens. VK ID rotates the refresh tokens, so the passed one becomes invalid
+    /// and the returned one must be stored instead.
+    pub async fn refresh_token(
+        &self,
+        refresh_token: &str,
+        device_id: &str,
+    ) -> Result<Tokens, VkIdError> {
+        self.send(TokenRequest {
+            grant_type: "refresh_token",
+            client_id: self.client_id,
+            params: RefreshParams {
+                refresh_token,
+                device_id,
+            },
+        })
+        .await
+    }
+
+    /// Exchanges the silent token from the VK ID SDK to the access token
+    /// with [auth.exchangeSilentAuthToken](https://dev.vk.com/method/auth.exchangeSilentAuthToken).
+    /// `client` must use the service token of the application.
+    pub async fn exchange_silent_token(
+        &self,
+        client: &VkApi,
+        token: &str,
+        uuid: &str,
+    ) -> VkApiResult<SilentTokenExchange> {
+        client
+            .send_request("auth.exchangeSilentAuthToken", SilentParams { token, uuid })
+            .await
+    }
+
+    async fn send<T: DeserializeOwned, P: Serialize>(&self, params: P) -> Result<T, VkIdError> {
+        let response = self
+            .client
+            .post(OAUTH_URL)
+            .header(ACCEPT, "application/json")
+            .form(&params)
+            .send()
+            .await
+            .map_err(|e| VkIdError::Request(VkApiError::Request(e)))?
+            .bytes()
+            .await
+            .map_err(|e| VkIdError::Request(VkApiError::Request(e)))?;
+
+        match serde_json::from_slice(&response) {
+            Ok(OAuthResponse::Error(error)) => Err(VkIdError::OAuth(error)),
+            Ok(OAuthResponse::Success(response)) => Ok(response),
+            Err(e) => Err(VkIdError::Request(VkApiError::ResponseDeserialize(
+                crate::ResponseDeserialize::Json(e),
+            ))),
+        }
+    }
+}
+
+/// Params of the authorization code exchange.
+#[derive(Debug, Clone, Serialize)]
+pub struct CodeExchange {
+    pub code: String,
+    /// Code verifier of the PKCE, which challenge was passed to the authorization
+    pub code_verifier: String,
+    pub device_id: String,
+    pub redirect_uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+}
+
+impl CodeExchange {
+    /// Creates the params from the redirect.
+    pub fn new<C, V, D, R>(code: C, code_verifier: V, device_id: D, redirect_uri: R) -> Self
+    where
+        C: Into<String>,
+        V: Into<String>,
+        D: Into<String>,
+        R: Into<String>,
+    {
+        Self {
+            code: code.into(),
+            code_verifier: code_verifier.into(),
+            device_id: device_id.into(),
+            redirect_uri: redirect_uri.into(),
+            state: None,
+        }
+    }
+
+    /// Pass the state, which is returned in the response as is.
+    #[must_use]
+    pub fn with_state<S: Into<String>>(mut self, state: S) -> Self {
+        self.state = Some(state.into());
+        self
+    }
+}
+
+/// Tokens issued by VK ID.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Tokens {
+    pub access_token: String,
+    pub refresh_token: String,
+    #[serde(default)]
+    pub id_token: Option<String>,
+    #[serde(default)]
+    pub token_type: Option<String>,
+    /// Lifetime of the access token in seconds.
+    pub expires_in: u64,
+    pub user_id: i64,
+    #[serde(default)]
+    pub state: Option<String>,
+    /// Granted scopes separated by spaces.
+    #[serde(default)]
+    pub scope: Option<String>,
+}
+
+impl std::fmt::Debug for Tokens {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tokens")
+            .field("access_token", &"***")
+            .field("refresh_token", &"***")
+            .field("expires_in", &self.expires_in)
+            .field("user_id", &self.user_id)
+            .field("scope", &self.scope)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Response of [auth.exchangeSilentAuthToken](https://dev.vk.com/method/auth.exchangeSilentAuthToken).
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SilentTokenExchange {
+    pub access_token: String,
+    #[serde(default)]
+    pub access_token_id: Option<String>,
+    pub user_id: i64,
+    #[serde(default)]
+    pub phone: Option<String>,
+    #[serde(default)]
+    pub phone_validated: Option<u8>,
+}
+
+impl std::fmt::Debug for SilentTokenExchange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SilentTokenExchange")
+            .field("access_token", &"***")
+            .field("user_id", &self.user_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Error returned by VK ID, like `invalid_grant` for the used refresh token.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct OAuthError {
+    pub error: String,
+    #[serde(default)]
+    pub error_description: Option<String>,
+    #[serde(default)]
+    pub state: Option<String>,
+}
+
+impl Display for OAuthError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.error_description {
+            Some(description) => write!(f, "vk id error {}: {description}", self.error),
+            None => write!(f, "vk id error {}", self.error),
+        }
+    }
+}
+
+/// Error of the VK ID requests.
+#[derive(Debug)]
+pub enum VkIdError {
+    /// Request failed or the response can't be decoded
+    Request(VkApiError),
+    /// VK ID rejected the request
+    OAuth(OAuthError),
+}
+
+impl Display for VkIdError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Request(e) => Display::fmt(e, f),
+            Self::OAuth(e) => Display::fmt(e, f),
+        }
+    }
+}
+
+impl Error for VkIdError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Request(e) => Some(e),
+            Self::OAuth(_) => None,
+        }
+    }
+}
+
+/// Tokens of the client, which are refreshed when they expire or VK rejects them.
+#[derive(Debug)]
+pub(crate) struct TokenRefresher {
+    vk_id: VkId,
+    device_id: String,
+    session: tokio::sync::Mutex<Session>,
+}
+
+#[derive(Debug)]
+struct Session {
+    tokens: Tokens,
+    expires_at: Instant,
+}
+
+impl Session {
+    fn new(tokens: Tokens) -> Self {
+        Self {
+            expires_at: Instant::now() + Duration::from_secs(tokens.expires_in),
+            tokens,
+        }
+    }
+
+    fn expired(&self) -> bool {
+        Instant::now() + EXPIRATION_MARGIN >= self.expires_at
+    }
+}
+
+impl TokenRefresher {
+    pub(crate) fn new(vk_id: VkId, device_id: String, tokens: Tokens) -> Self {
+        Self {
+            vk_id,
+            device_id,
+            session: tokio::sync::Mutex::new(Session::new(tokens)),
+        }
+    }
+
+    /// Returns the access token, refreshing the expired one.
+    /// The stale token is returned if the refresh fails, so the request reports the authorization error.
+    pub(crate) async fn access_token(&self) -> String {
+        let mut session = self.session.lock().await;
+        if session.expired() {
+            let _ = self.refresh_session(&mut session).await;
+        }
+        session.tokens.access_token.clone()
+    }
+
+    /// Refreshes the token rejected by VK, unless it's already refreshed by the concurrent request.
+    pub(crate) async fn refresh(&self, rejected: &str) -> Result<String, VkIdError> {
+        let mut session = self.session.lock().await;
+        if session.tokens.access_token == rejected {
+            self.refresh_session(&mut session).await?;
+        }
+        Ok(session.tokens.access_token.clone())
+    }
+
+    pub(crate) async fn tokens(&self) -> Tokens {
+        self.session.lock().await.tokens.clone()
+    }
+
+    async fn refresh_session(&self, session: &mut Session) -> Result<(), VkIdError> {
+        let tokens = self
+            .vk_id
+            .refresh_token(&session.tokens.refresh_token, &self.device_id)
+            .await?;
+        *session = Session::new(tokens);
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct TokenRequest<P> {
+    grant_type: &'static str,
+    client_id: u64,
+    #[serde(flatten)]
+    params: P,
+}
+
+#[derive(Serialize)]
+struct RefreshParams<'a> {
+    refresh_token: &'a str,
+    device_id: &'a str,
+}
+
+#[derive(Serialize)]
+struct SilentParams<'a> {
+    token: &'a str,
+    uuid: &'a str,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OAuthResponse<T> {
+    Error(OAuthError),
+    Success(T),
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vkid::{CodeExchange, OAuthResponse, TokenRequest, Tokens};
+
+    #[test]
+    fn serialize_code_exchange() {
+        let request = TokenRequest {
+            grant_type: "authorization_code",
+            client_id: 1,
+            params: CodeExchange::new("code", "verifier", "device", "https://example.com")
+                .with_state("state"),
+        };
+
+        assert_eq!(
+            serde_urlencoded::to_string(request).unwrap(),
+            "grant_type=authorization_code&client_id=1&code=code&code_verifier=verifier&device_id=device&redirect_uri=https%3A%2F%2Fexample.com&state=state"
+        );
+    }
+
+    #[test]
+    fn deserialize_response() {
+        let tokens: OAuthResponse<Tokens> = serde_json::from_str(
+            r#"{"refresh_token":"rt","access_token":"at","id_token":"it","token_type":"Bearer","expires_in":3600,"user_id":1,"state":"s","scope":"email phone"}"#,
+        )
+        .unwrap();
+        assert!(matches!(tokens, OAuthResponse::Success(t) if t.refresh_token == "rt"));
+
+        let error: OAuthResponse<Tokens> = serde_json::from_str(
+            r#"{"error":"invalid_grant","error_description":"refresh token is expired"}"#,
+        )
+        .unwrap();
+        assert!(matches!(error, OAuthResponse::Error(e) if e.error == "invalid_grant"));
+    }
+}
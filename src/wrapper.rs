@@ -1,14 +1,28 @@
 use crate::Version;
 use serde::de::DeserializeOwned;
 
+/// Kind of the access token, which defines the documented rate limit and the methods available to it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum TokenScope {
+    /// User token, 3 requests per second.
+    User,
+    /// Community token, 20 requests per second.
+    Group,
+    /// Service token of the application. VK limits it by the application audience,
+    /// so the conservative user limit is used.
+    Service,
+}
+
 /// # API method description
 /// Wrapper for strong typed API method description.
 ///
 /// Example
-/// ```
+/// ```no_run
 /// use vkclient::{List, Version, VkApi, VkApiWrapper};
 /// use serde::{Serialize, Deserialize};
 ///
+/// # fn main() {
+/// # let access_token = String::new();
 /// let client: VkApi = vkclient::VkApiBuilder::new(access_token).into();
 ///
 /// async {
@@ -16,7 +30,8 @@ use serde::de::DeserializeOwned;
 ///         user_ids: List(vec![1,2]),
 ///         fields: List(vec!["id", "sex"]),
 ///     }).await.expect("vk api error");
-/// }
+/// };
+/// # }
 ///
 /// #[derive(Serialize, Debug)]
 /// struct UsersGetRequest<'a> {
@@ -51,7 +66,22 @@ pub trait VkApiWrapper {
     fn get_method_name() -> &'static str;
 
     /// API version that required for this method
+    #[must_use]
     fn get_version() -> Version {
         Version::default()
     }
+
+    /// Returns false for the open methods, which can be called without an access token.
+    /// Clients without token return [`VkApiError::AuthRequired`](crate::VkApiError::AuthRequired)
+    /// for the methods requiring it.
+    #[must_use]
+    fn requires_auth() -> bool {
+        true
+    }
+
+    /// Returns the kinds of the tokens, which the method accepts. Default is all kinds.
+    #[must_use]
+    fn token_scopes() -> &'static [TokenScope] {
+        &[TokenScope::User, TokenScope::Group, TokenScope::Service]
+    }
 }